@@ -0,0 +1,160 @@
+//! Benchmarks for the expression language, run with `cargo bench --bench expr`.
+//!
+//! Patui is a binary crate so the expression modules are pulled in directly by path rather than
+//! through a library.
+
+#![allow(dead_code, unused_imports)]
+
+#[path = "../src/types"]
+mod types {
+    #[path = "steps/data.rs"]
+    mod data;
+    pub(crate) mod expr;
+
+    pub(crate) use self::{
+        data::{PatuiStepData, PatuiStepDataFlavour},
+        expr::PatuiExpr,
+    };
+}
+
+#[path = "../src/runner/steps/eval.rs"]
+mod eval;
+mod fixtures;
+
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use self::{
+    eval::{eval, EvalResult, StepResults},
+    types::{PatuiExpr, PatuiStepData, PatuiStepDataFlavour},
+};
+
+fn parse(input: &str) -> PatuiExpr {
+    PatuiExpr::try_from(input).unwrap()
+}
+
+/// Results where `steps.foo.out` has received `len` integers and finished.
+fn step_results(len: usize) -> HashMap<PatuiExpr, StepResults> {
+    HashMap::from([(
+        parse("steps.foo.out"),
+        StepResults {
+            data: (0..len as i64)
+                .map(|i| PatuiStepData::new(i.into()))
+                .collect(),
+            is_closed: true,
+            is_item: false,
+        },
+    )])
+}
+
+fn assert_true(res: EvalResult) {
+    assert_eq!(res, EvalResult::Known(PatuiStepDataFlavour::Bool(true)));
+}
+
+fn bench_parse_deep_bin_ops(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_deep_bin_ops");
+
+    for depth in [10, 100, 1_000] {
+        let input = fixtures::deep_bin_ops(depth);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &input, |b, input| {
+            b.iter(|| parse(black_box(input)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_parse_large_list(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_large_list");
+
+    for len in [100, 1_000, 10_000] {
+        let input = fixtures::len_check(len);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &input, |b, input| {
+            b.iter(|| parse(black_box(input)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_parse_map_equality(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_map_equality");
+
+    for len in [10, 100, 1_000] {
+        let input = fixtures::map_equality(len);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &input, |b, input| {
+            b.iter(|| parse(black_box(input)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_parse_step_refs(c: &mut Criterion) {
+    let input = fixtures::step_refs();
+
+    c.bench_function("parse_step_refs", |b| b.iter(|| parse(black_box(&input))));
+}
+
+fn bench_eval_deep_bin_ops(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eval_deep_bin_ops");
+    let results = HashMap::new();
+
+    for depth in [10, 100, 1_000] {
+        let expr = parse(&fixtures::deep_bin_ops(depth));
+        assert_true(eval(&expr, &results).unwrap());
+        group.bench_with_input(BenchmarkId::from_parameter(depth), &expr, |b, expr| {
+            b.iter(|| eval(black_box(expr), &results))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_eval_len(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eval_len");
+
+    for len in [100, 1_000, 10_000] {
+        let expr = parse(&fixtures::step_len_check(len));
+        let results = step_results(len);
+        assert_true(eval(&expr, &results).unwrap());
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &expr, |b, expr| {
+            b.iter(|| eval(black_box(expr), &results))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_eval_map_equality(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eval_map_equality");
+    let results = HashMap::new();
+
+    for len in [10, 100, 1_000] {
+        let expr = parse(&fixtures::map_equality(len));
+        assert_true(eval(&expr, &results).unwrap());
+        group.throughput(Throughput::Elements(len as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(len), &expr, |b, expr| {
+            b.iter(|| eval(black_box(expr), &results))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_parse_deep_bin_ops,
+    bench_parse_large_list,
+    bench_parse_map_equality,
+    bench_parse_step_refs,
+    bench_eval_deep_bin_ops,
+    bench_eval_len,
+    bench_eval_map_equality,
+);
+criterion_main!(benches);
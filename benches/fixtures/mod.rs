@@ -0,0 +1,51 @@
+//! Generators for representative expressions of a given size so the benchmarks can scale the
+//! input without checking in large fixture files.
+
+/// `(1 + 2 + 3 + ...) == n` with `depth` additions. The sum's bracketed as operators don't have
+/// any precedence, `1 + 2 == 3` is `1 + (2 == 3)`.
+pub(crate) fn deep_bin_ops(depth: usize) -> String {
+    let sum = (1..=depth)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(" + ");
+
+    format!("({}) == {}", sum, depth * (depth + 1) / 2)
+}
+
+/// A list literal of `len` integers with a length check on it, e.g. `[0, 1, 2].len() == 3`.
+pub(crate) fn len_check(len: usize) -> String {
+    format!("{}.len() == {}", list(len), len)
+}
+
+/// A length check on everything a step has sent, for evaluating against `len` items of its output.
+pub(crate) fn step_len_check(len: usize) -> String {
+    format!("steps.foo.out.len() == {}", len)
+}
+
+/// Two map literals of `len` entries compared for equality.
+pub(crate) fn map_equality(len: usize) -> String {
+    let map = format!(
+        "{{{}}}",
+        (0..len)
+            .map(|i| format!("\"key{}\": {}", i, i))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    format!("{} == {}", map, map)
+}
+
+/// A typical assertion made against the output of other steps.
+pub(crate) fn step_refs() -> String {
+    "steps.FooTransform.out[0].bar[2] == \"c\" && steps.FooFile.out.len() > 0 && steps.FooPlugin.echo[1] != b\"abc\"".to_string()
+}
+
+fn list(len: usize) -> String {
+    format!(
+        "[{}]",
+        (0..len)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
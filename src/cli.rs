@@ -1,14 +1,25 @@
+mod clone;
+mod db;
 mod describe;
 mod edit;
 mod get;
+mod lint;
 mod new;
+mod rename;
+mod repl;
+mod run;
+mod schema;
+mod steps;
 
-use std::sync::Arc;
+use std::{fmt::Display, sync::Arc, time::Duration};
 
 use clap::Parser;
-use eyre::Result;
+use eyre::{eyre, Result};
 
-use crate::db::Database;
+use crate::{
+    db::{Database, DbError},
+    utils::parse_duration,
+};
 
 const VERSION_MESSAGE: &str = concat!(
     env!("CARGO_PKG_NAME"),
@@ -19,8 +30,66 @@ const VERSION_MESSAGE: &str = concat!(
     ")"
 );
 
+// Exit codes, every subcommand keeps to these so scripts can rely on them.
+
+/// A test run or the checks asked for failed, e.g. a step failed or `lint --deny-warnings` warned,
+/// or the test given isn't valid.
+pub(crate) const EXIT_FAILED: i32 = 1;
+
+/// The command was used wrongly, as well as clap's bad arguments this is asking for something that
+/// doesn't exist or a change that clashes with something that does.
+pub(crate) const EXIT_USAGE: i32 = 2;
+
+/// Anything else going wrong, including panics.
+pub(crate) const EXIT_INTERNAL: i32 = 3;
+
+/// A run was abandoned for taking longer than `--timeout`, matches `timeout(1)`.
+pub(crate) const EXIT_TIMEOUT: i32 = 124;
+
+/// A run was interrupted with Ctrl-C, matches what shells report for SIGINT.
+pub(crate) const EXIT_INTERRUPTED: i32 = 130;
+
+/// Errors in what a command was given rather than anything going wrong running it, reported
+/// without a full report and exiting with the code for what's wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum CliError {
+    /// The command was used wrongly, exits with `EXIT_USAGE`.
+    Usage(String),
+    /// The test or expression given isn't valid, exits with `EXIT_FAILED`.
+    Invalid(String),
+}
+
+impl CliError {
+    pub(crate) fn invalid(e: eyre::Report) -> Self {
+        CliError::Invalid(e.to_string())
+    }
+
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => EXIT_USAGE,
+            CliError::Invalid(_) => EXIT_FAILED,
+        }
+    }
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CliError::Usage(msg) | CliError::Invalid(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
 #[derive(Debug, Parser)]
 pub(crate) enum Command {
+    /// Clone an existing resource
+    Clone(clone::Command),
+
+    /// Back up or restore the whole database
+    Db(db::Command),
+
     /// Describe specific resource
     Describe(describe::Command),
 
@@ -32,20 +101,78 @@ pub(crate) enum Command {
 
     /// Gets generic details about resource requested
     Get(get::Command),
+
+    /// Warn about likely mistakes in a resource
+    Lint(lint::Command),
+
+    /// Rename an existing resource
+    Rename(rename::Command),
+
+    /// Evaluate expressions interactively
+    Repl(repl::Command),
+
+    /// Look at what happened in a test run
+    Run(run::Command),
+
+    /// Print the JSON Schema of the test format
+    Schema(schema::Command),
+
+    /// List the kinds of step available to tests
+    Steps(steps::Command),
 }
 
 impl Command {
-    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
-        if let Err(e) = db.create_tables().await {
-            panic!("Unexpected failure creating tables, aborting\nerror: {}", e);
+    /// Whether the command needs the database, those that don't are given none so it isn't even
+    /// created.
+    pub(crate) fn uses_db(&self) -> bool {
+        match self {
+            Command::Repl(_) | Command::Schema(_) | Command::Steps(_) => false,
+            Command::Run(subcommand) => !subcommand.is_from_stdin(),
+            _ => true,
         }
+    }
 
-        match self {
-            Command::Describe(subcommand) => subcommand.handle(db).await,
-            Command::Edit(subcommand) => subcommand.handle(db).await,
-            Command::Get(subcommand) => subcommand.handle(db).await,
-            Command::New(subcommand) => subcommand.handle(db).await,
+    /// Run the command, `db` has to be given when it `uses_db`.
+    pub(crate) async fn handle(&self, db: Option<Arc<Database>>) -> Result<()> {
+        if let Some(db) = &db {
+            if let Err(e) = db.create_tables().await {
+                panic!("Unexpected failure creating tables, aborting\nerror: {}", e);
+            }
+        }
+
+        let res = match (self, db) {
+            (Command::Repl(subcommand), _) => subcommand.handle().await,
+            (Command::Schema(subcommand), _) => subcommand.handle().await,
+            (Command::Steps(subcommand), _) => subcommand.handle().await,
+            (Command::Run(subcommand), _) if subcommand.is_from_stdin() => {
+                subcommand.run_from_stdin().await
+            }
+            (Command::Clone(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Db(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Describe(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Edit(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Get(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Lint(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::New(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Rename(subcommand), Some(db)) => subcommand.handle(db).await,
+            (Command::Run(subcommand), Some(db)) => subcommand.handle(db).await,
+            (_, None) => Err(eyre!("The database is needed but wasn't opened")),
+        };
+
+        // A missing id, a clashing name or a test that doesn't parse is a user mistake rather
+        // than a bug, so no need for a full report
+        if let Err(e) = &res {
+            if let Some(e) = e.downcast_ref::<DbError>() {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_USAGE);
+            }
+            if let Some(e) = e.downcast_ref::<CliError>() {
+                eprintln!("{}", e);
+                std::process::exit(e.exit_code());
+            }
         }
+
+        res
     }
 }
 
@@ -55,6 +182,20 @@ pub(crate) struct Cli {
     #[clap(short, long)]
     pub(crate) db: Option<String>,
 
+    /// How long the TUI waits for the rest of a multi-key sequence such as `gg`, e.g. `500ms`
+    #[clap(long, value_parser = parse_duration)]
+    pub(crate) key_timeout: Option<Duration>,
+
+    /// Draw the TUI's borders and scrollbars with plain ASCII, done anyway when the locale isn't
+    /// UTF-8
+    #[clap(long)]
+    pub(crate) ascii: bool,
+
+    /// Report panics and errors on a single line without colours or backtraces, as does setting
+    /// `PATUI_PLAIN_PANIC`
+    #[clap(long)]
+    pub(crate) plain_panic: bool,
+
     #[command(subcommand)]
     pub(crate) subcommand: Option<Command>,
 }
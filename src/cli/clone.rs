@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use clap::{Args, Parser};
+use eyre::Result;
+
+use crate::db::Database;
+
+#[derive(Debug, Args)]
+#[command(about = "Clone an existing entity")]
+pub(crate) struct Command {
+    #[command(subcommand)]
+    command: CloneCommand,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        match &self.command {
+            CloneCommand::Test(clone_test) => clone_test.handle(db).await,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) enum CloneCommand {
+    Test(CloneTest),
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Clone an existing test and its steps into a new test")]
+pub(crate) struct CloneTest {
+    #[clap(short, long)]
+    pub(crate) id: i64,
+
+    // Name of the new test, defaults to the original name with " (copy)" appended
+    #[clap(short, long)]
+    pub(crate) name: Option<String>,
+}
+
+impl CloneTest {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        let name = match &self.name {
+            Some(name) => name.clone(),
+            None => format!("{} (copy)", db.get_test(self.id.into()).await?.name),
+        };
+
+        let id = db.clone_test(self.id.into(), name.clone()).await?;
+        eprintln!(
+            "Successfully cloned test ({}) to ({}): {}",
+            self.id, id, name
+        );
+
+        Ok(())
+    }
+}
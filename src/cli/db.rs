@@ -0,0 +1,86 @@
+use std::{
+    io::{BufRead, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use clap::{Args, Parser};
+use eyre::Result;
+
+use crate::db::Database;
+
+#[derive(Debug, Args)]
+#[command(about = "Back up or restore the whole database")]
+pub(crate) struct Command {
+    #[command(subcommand)]
+    command: DbCommand,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        match &self.command {
+            DbCommand::Backup(backup) => backup.handle(db).await,
+            DbCommand::Restore(restore) => restore.handle(db).await,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) enum DbCommand {
+    Backup(DbBackup),
+    Restore(DbRestore),
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Back up every test and run to a single file")]
+pub(crate) struct DbBackup {
+    /// File to write the backup to, it's overwritten if it already exists
+    #[clap(short, long)]
+    pub(crate) file: PathBuf,
+}
+
+impl DbBackup {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        db.backup(&self.file).await?;
+        eprintln!("Successfully backed up to {}", self.file.display());
+
+        Ok(())
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Replace every test and run with those from a backup")]
+pub(crate) struct DbRestore {
+    /// Backup file to restore from
+    #[clap(short, long)]
+    pub(crate) file: PathBuf,
+
+    /// Don't ask for confirmation first
+    #[clap(short, long)]
+    pub(crate) yes: bool,
+}
+
+impl DbRestore {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        if !self.yes {
+            eprint!(
+                "This replaces everything in the database with {}, continue? [y/N] ",
+                self.file.display()
+            );
+            std::io::stderr().flush()?;
+
+            let mut answer = String::new();
+            std::io::stdin().lock().read_line(&mut answer)?;
+
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                eprintln!("Restore cancelled");
+                return Ok(());
+            }
+        }
+
+        db.restore(&self.file).await?;
+        eprintln!("Successfully restored from {}", self.file.display());
+
+        Ok(())
+    }
+}
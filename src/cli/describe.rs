@@ -1,9 +1,11 @@
-use std::{io::Write, sync::Arc};
+use std::{io::Write, path::PathBuf, sync::Arc};
 
 use clap::{Args, Parser};
 use eyre::Result;
 
-use crate::db::Database;
+use crate::{db::Database, runner::find_plugins, utils::get_plugin_dir};
+
+use super::CliError;
 
 #[derive(Debug, Args)]
 #[command(about = "Get an entity")]
@@ -18,6 +20,7 @@ impl Command {
             DescribeCommand::Test(describe_test) | DescribeCommand::Tests(describe_test) => {
                 describe_test.handle(db).await
             }
+            DescribeCommand::Plugin(describe_plugin) => describe_plugin.handle().await,
         }
     }
 }
@@ -27,6 +30,7 @@ pub(crate) enum DescribeCommand {
     Test(DescribeTest),
     // Alias for Test
     Tests(DescribeTest),
+    Plugin(DescribePlugin),
 }
 
 #[derive(Parser, Debug)]
@@ -46,3 +50,40 @@ impl DescribeTest {
         Ok(())
     }
 }
+
+#[derive(Parser, Debug)]
+#[command(about = "Get details of an installed plugin")]
+pub(crate) struct DescribePlugin {
+    #[clap(short, long)]
+    pub(crate) r#type: String,
+
+    // Directory to look for plugins in, defaults to `plugins` in the data directory
+    #[clap(short, long)]
+    pub(crate) dir: Option<PathBuf>,
+}
+
+impl DescribePlugin {
+    pub(crate) async fn handle(&self) -> Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => get_plugin_dir()?,
+        };
+
+        let plugin = find_plugins(&dir)
+            .await?
+            .into_iter()
+            .find(|plugin| plugin.r#type == self.r#type)
+            .ok_or_else(|| {
+                CliError::Usage(format!(
+                    "No plugin of type `{}` found in `{}`",
+                    self.r#type,
+                    dir.display()
+                ))
+            })?;
+
+        std::io::stdout().write_all(&serde_json::to_vec(&plugin)?)?;
+        std::io::stdout().write_all(b"\n")?;
+
+        Ok(())
+    }
+}
@@ -1,9 +1,13 @@
-use std::{io::Write, sync::Arc};
+use std::{io::Write, path::PathBuf, sync::Arc};
 
 use clap::{Args, Parser};
 use eyre::Result;
 
-use crate::db::{Database, PatuiTestMinDisplay};
+use crate::{
+    db::{Database, PatuiTestFilter, PatuiTestMinDisplay, PatuiTestOrder},
+    runner::find_plugins,
+    utils::get_plugin_dir,
+};
 
 #[derive(Debug, Args)]
 #[command(about = "Get an entity")]
@@ -16,6 +20,9 @@ impl Command {
     pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
         match &self.command {
             GetCommand::Test(get_test) | GetCommand::Tests(get_test) => get_test.handle(db).await,
+            GetCommand::Plugin(get_plugins) | GetCommand::Plugins(get_plugins) => {
+                get_plugins.handle().await
+            }
         }
     }
 }
@@ -25,6 +32,44 @@ pub(crate) enum GetCommand {
     Test(GetTest),
     // Alias for Test
     Tests(GetTest),
+    Plugin(GetPlugins),
+    // Alias for Plugin
+    Plugins(GetPlugins),
+}
+
+/// What order tests are listed in, tests that tie are in id order.
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Default)]
+#[clap(rename_all = "lower")]
+pub(crate) enum TestSortBy {
+    #[default]
+    Id,
+    Name,
+    /// When the test was created
+    Created,
+    /// When the test was last changed
+    Updated,
+}
+
+impl From<TestSortBy> for PatuiTestOrder {
+    fn from(value: TestSortBy) -> Self {
+        match value {
+            TestSortBy::Id => PatuiTestOrder::Id,
+            TestSortBy::Name => PatuiTestOrder::Name,
+            TestSortBy::Created => PatuiTestOrder::CreationDate,
+            TestSortBy::Updated => PatuiTestOrder::LastUpdated,
+        }
+    }
+}
+
+/// How tests are printed.
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Default)]
+#[clap(rename_all = "lower")]
+pub(crate) enum TestsFormat {
+    /// A JSON array of the tests
+    #[default]
+    Json,
+    /// A table with a header line and a line per test
+    Table,
 }
 
 #[derive(Parser, Debug)]
@@ -32,6 +77,22 @@ pub(crate) enum GetCommand {
 pub(crate) struct GetTest {
     #[clap(short, long)]
     pub(crate) id: Option<i64>,
+
+    /// Only list tests with this in their name, case sensitive
+    #[arg(long, conflicts_with = "id")]
+    pub(crate) name_contains: Option<String>,
+
+    /// List at most this many tests
+    #[arg(long, conflicts_with = "id")]
+    pub(crate) limit: Option<u32>,
+
+    /// What order to list tests in
+    #[arg(long, value_enum, default_value_t, conflicts_with = "id")]
+    pub(crate) sort_by: TestSortBy,
+
+    /// How to print the tests
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) format: TestsFormat,
 }
 
 impl GetTest {
@@ -39,14 +100,87 @@ impl GetTest {
         let tests: Vec<PatuiTestMinDisplay> = match self.id {
             Some(id) => vec![db.get_test(id.into()).await?.into()],
             None => db
-                .get_tests()
+                .get_tests_filtered(&PatuiTestFilter {
+                    name_contains: self.name_contains.clone(),
+                    limit: self.limit,
+                    order: self.sort_by.into(),
+                })
                 .await?
                 .into_iter()
                 .map(|x| x.into())
                 .collect::<Vec<_>>(),
         };
 
-        std::io::stdout().write_all(&serde_json::to_vec(&tests)?)?;
+        match self.format {
+            TestsFormat::Json => {
+                std::io::stdout().write_all(&serde_json::to_vec(&tests)?)?;
+                std::io::stdout().write_all(b"\n")?;
+            }
+            TestsFormat::Table => std::io::stdout().write_all(tests_table(&tests).as_bytes())?,
+        }
+
+        Ok(())
+    }
+}
+
+/// `tests` as a table lined up in columns, the header's printed even with no tests.
+fn tests_table(tests: &[PatuiTestMinDisplay]) -> String {
+    let rows = tests
+        .iter()
+        .map(|test| {
+            [
+                test.id.to_string(),
+                test.name.clone(),
+                // Keep each test to a line
+                test.description.replace('\n', " "),
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let header = ["ID", "NAME", "DESCRIPTION"].map(String::from);
+    let widths = [0, 1].map(|column| {
+        rows.iter()
+            .chain([&header])
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or_default()
+    });
+
+    [&header]
+        .into_iter()
+        .chain(&rows)
+        .map(|[id, name, description]| {
+            let line = format!(
+                "{:<id_width$}  {:<name_width$}  {}",
+                id,
+                name,
+                description,
+                id_width = widths[0],
+                name_width = widths[1]
+            );
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Get details of the installed plugins")]
+pub(crate) struct GetPlugins {
+    // Directory to look for plugins in, defaults to `plugins` in the data directory
+    #[clap(short, long)]
+    pub(crate) dir: Option<PathBuf>,
+}
+
+impl GetPlugins {
+    pub(crate) async fn handle(&self) -> Result<()> {
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => get_plugin_dir()?,
+        };
+
+        let plugins = find_plugins(&dir).await?;
+
+        std::io::stdout().write_all(&serde_json::to_vec(&plugins)?)?;
         std::io::stdout().write_all(b"\n")?;
 
         Ok(())
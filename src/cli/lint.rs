@@ -0,0 +1,82 @@
+use std::{io::Read, sync::Arc};
+
+use clap::{Args, Parser};
+use eyre::Result;
+
+use super::{CliError, EXIT_FAILED};
+use crate::{db::Database, types::PatuiTestDetails};
+
+#[derive(Debug, Args)]
+#[command(about = "Look for likely mistakes")]
+pub(crate) struct Command {
+    #[command(subcommand)]
+    command: LintCommand,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        match &self.command {
+            LintCommand::Test(lint_test) => lint_test.handle(db).await,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) enum LintCommand {
+    Test(LintTest),
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Warn about likely mistakes in tests that are otherwise valid")]
+pub(crate) struct LintTest {
+    /// Test already in the database to lint
+    #[clap(short, long)]
+    pub(crate) id: Option<i64>,
+
+    /// Exit non-zero when there are any warnings
+    #[clap(long)]
+    pub(crate) deny_warnings: bool,
+
+    // List of files containing yaml for tests, use '-' for stdin
+    pub(crate) files: Vec<String>,
+}
+
+impl LintTest {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        let mut tests = vec![];
+
+        if let Some(id) = self.id {
+            tests.push(PatuiTestDetails::from(db.get_test(id.into()).await?));
+        }
+
+        for file in &self.files {
+            let contents = match file.as_str() {
+                "-" => {
+                    let mut buffer = String::new();
+                    std::io::stdin().read_to_string(&mut buffer)?;
+                    buffer
+                }
+                _ => std::fs::read_to_string(file)?,
+            };
+
+            tests.push(PatuiTestDetails::from_yaml_str(&contents).map_err(CliError::invalid)?);
+        }
+
+        let mut num_warnings = 0;
+        for test in tests {
+            for lint in test.lint()? {
+                println!("warning: test `{}`: {}", test.name, lint);
+                num_warnings += 1;
+            }
+        }
+
+        if num_warnings > 0 {
+            eprintln!("{} warning(s)", num_warnings);
+            if self.deny_warnings {
+                std::process::exit(EXIT_FAILED);
+            }
+        }
+
+        Ok(())
+    }
+}
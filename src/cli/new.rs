@@ -1,14 +1,43 @@
-use std::{io::Read, sync::Arc};
+use std::{
+    io::{Read, Write},
+    sync::Arc,
+    time::Duration,
+};
 
 use clap::{Args, Parser};
 use eyre::Result;
+use serde::Serialize;
+use tokio::{sync::mpsc, task::JoinHandle};
+use tracing::warn;
 
+use super::{CliError, EXIT_FAILED, EXIT_INTERRUPTED, EXIT_TIMEOUT, EXIT_USAGE};
 use crate::{
-    db::Database,
-    runner::TestRunner,
-    types::{PatuiRunDisplay, PatuiTestDetails},
+    db::{Database, PatuiInstance, PatuiRun, PatuiRunId, PatuiRunOutput},
+    runner::{TestRunner, DEFAULT_CAPTURE_LIMIT},
+    types::{PatuiEvent, PatuiRunDisplay, PatuiRunError, PatuiRunStatus, PatuiTestDetails},
+    utils::parse_duration,
 };
 
+/// How a run is reported on stdout.
+#[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq, Default)]
+#[clap(rename_all = "lower")]
+pub(crate) enum RunOutput {
+    /// The finished run as a single JSON object
+    #[default]
+    Json,
+    /// Every event of the run as a line of JSON as it happens, then a summary line of the finished
+    /// run
+    Ndjson,
+}
+
+/// A line of `--output ndjson`, `seq` counts up from 0 over the lines of a run.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum NdjsonLine<'a> {
+    Event { seq: u64, event: &'a PatuiEvent },
+    Summary { seq: u64, run: serde_json::Value },
+}
+
 #[derive(clap::ValueEnum, Debug, Copy, Clone, PartialEq)]
 #[clap(rename_all = "lower")]
 pub(crate) enum Templates {
@@ -83,7 +112,7 @@ impl NewTest {
             };
 
             let test = if self.no_edit {
-                PatuiTestDetails::from_yaml_str(&contents)?
+                PatuiTestDetails::from_yaml_str(&contents).map_err(CliError::invalid)?
             } else {
                 PatuiTestDetails::edit_yaml(contents)?
             };
@@ -108,7 +137,7 @@ impl NewTest {
 
         if pending_tests.is_empty() {
             eprintln!("No tests to create, remove --no-edit or provide valid files");
-            std::process::exit(1);
+            std::process::exit(EXIT_USAGE);
         }
 
         let mut edited_tests = vec![];
@@ -135,26 +164,288 @@ pub(crate) struct NewRun {
     // Test ID to run
     #[arg(short, long)]
     pub(crate) test_id: i64,
+
+    #[command(flatten)]
+    pub(crate) options: RunOptions,
+
+    /// Run the test this many times, each from a fresh start, then report how often it passed to
+    /// catch a test that only fails some of the time
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..), conflicts_with = "output")]
+    pub(crate) repeat: u32,
+
+    /// Save what each step outputs, up to a limit, to report with the run and look at afterwards
+    /// with `run show`
+    #[arg(long)]
+    pub(crate) capture: bool,
+}
+
+/// How a test is run and reported, the same for `new run` and `run --steps-from-stdin`.
+#[derive(Args, Debug)]
+pub(crate) struct RunOptions {
+    /// Abandon the whole run if it takes longer than this, e.g. `30s` or `2m`, instead of the
+    /// test's `default_timeout_ms`
+    #[arg(long, value_parser = parse_duration)]
+    pub(crate) timeout: Option<Duration>,
+
+    /// Cancel the rest of the run as soon as a step fails rather than letting every step finish,
+    /// with `new run --repeat` no more runs are started either
+    #[arg(long)]
+    pub(crate) fail_fast: bool,
+
+    /// Report how the assertion step named evaluated, sub-expression by sub-expression, if it fails
+    #[arg(long)]
+    pub(crate) explain_failure: Option<String>,
+
+    /// How to report the run, `ndjson` streams events as they happen for piping into other tools
+    #[arg(long, value_enum, default_value_t)]
+    pub(crate) output: RunOutput,
+
+    /// Leave steps that didn't fail out of the report, and their events out of `--output ndjson`
+    #[arg(long)]
+    pub(crate) only_failures: bool,
+}
+
+impl RunOptions {
+    /// Set `runner` up to run as asked.
+    pub(crate) fn configure(&self, runner: &mut TestRunner) -> Result<()> {
+        runner.set_fail_fast(self.fail_fast);
+        if let Some(step_name) = &self.explain_failure {
+            runner.set_explain_failure(step_name)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl NewRun {
     pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
         let test = db.get_test(self.test_id.into()).await?;
+        // A timeout given here wins over the one saved with the test
+        let timeout = self
+            .options
+            .timeout
+            .or(test.default_timeout_ms.map(Duration::from_millis));
         let instance = db.get_or_new_instance(test).await?;
+
+        if let Err(e) = db.mark_test_used(self.test_id.into()).await {
+            warn!(
+                "Failed to update last used date for test {}: {}",
+                self.test_id, e
+            );
+        }
+
+        if self.repeat > 1 {
+            return self.repeat_runs(db, instance, timeout).await;
+        }
+
         let run = db.new_run(instance).await?;
+        let mut runner = self.runner(&db, run)?;
+        let events = (self.options.output == RunOutput::Ndjson)
+            .then(|| stream_events(&mut runner, self.options.only_failures));
 
-        let runner = TestRunner::new(run);
+        let run = run_until_interrupted(runner, timeout).await?;
+        let next_seq = match events {
+            Some(events) => Some(events.await??),
+            None => None,
+        };
 
-        let run = runner.run_test().await?;
+        // Now the plugins have been started their versions are known
+        db.save_run_env(run.id, &run.env).await?;
 
-        let res = if let Ok(run_display) = run.clone().try_into() {
-            serde_json::to_string::<PatuiRunDisplay>(&run_display)?
+        let outputs = if self.capture {
+            db.get_run_outputs(run.id).await?
         } else {
-            serde_json::to_string(&run)?
+            vec![]
         };
 
-        println!("{}", res);
+        report_run(run, outputs, next_seq, self.options.only_failures)
+    }
+
+    fn runner(&self, db: &Arc<Database>, run: PatuiRun) -> Result<TestRunner> {
+        let mut runner = TestRunner::new(run);
+        self.options.configure(&mut runner)?;
+        if self.capture {
+            runner.set_capture(db.clone(), DEFAULT_CAPTURE_LIMIT);
+        }
+
+        Ok(runner)
+    }
+
+    /// Run the test `--repeat` times and print a summary of how each run went.
+    async fn repeat_runs(
+        &self,
+        db: Arc<Database>,
+        instance: PatuiInstance,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        let mut runs = vec![];
+
+        for _ in 0..self.repeat {
+            // Every run gets a new runner so nothing carries over from the last one
+            let run = db.new_run(instance.clone()).await?;
+            let runner = self.runner(&db, run)?;
+            let run = run_until_interrupted(runner, timeout).await?;
+            db.save_run_env(run.id, &run.env).await?;
+
+            let is_failed = matches!(run.status, PatuiRunStatus::Error(_));
+            runs.push(RepeatedRun {
+                id: run.id,
+                status: run.status,
+            });
+            if is_failed && self.options.fail_fast {
+                break;
+            }
+        }
+
+        let summary = RepeatSummary::new(runs);
+        println!("{}", serde_json::to_string(&summary)?);
+
+        if let Some(code) = summary.runs.iter().find_map(|run| exit_code(&run.status)) {
+            std::process::exit(code);
+        }
 
         Ok(())
     }
 }
+
+/// How a run of `--repeat` went.
+#[derive(Debug, Serialize)]
+struct RepeatedRun {
+    id: PatuiRunId,
+    status: PatuiRunStatus,
+}
+
+/// What's printed after the runs of `--repeat`, `pass_rate` is the fraction of runs that passed.
+#[derive(Debug, Serialize)]
+struct RepeatSummary {
+    runs: Vec<RepeatedRun>,
+    passed: usize,
+    failed: usize,
+    pass_rate: f64,
+}
+
+impl RepeatSummary {
+    fn new(runs: Vec<RepeatedRun>) -> Self {
+        let passed = runs
+            .iter()
+            .filter(|run| run.status == PatuiRunStatus::Passed)
+            .count();
+        let failed = runs.len() - passed;
+        let pass_rate = if runs.is_empty() {
+            0.0
+        } else {
+            passed as f64 / runs.len() as f64
+        };
+
+        Self {
+            runs,
+            passed,
+            failed,
+            pass_rate,
+        }
+    }
+}
+
+/// The code to exit with for a run that finished with `status`, `None` if it passed.
+fn exit_code(status: &PatuiRunStatus) -> Option<i32> {
+    match status {
+        PatuiRunStatus::Error(PatuiRunError::Timeout(_)) => Some(EXIT_TIMEOUT),
+        PatuiRunStatus::Error(PatuiRunError::StepFailed(_)) => Some(EXIT_FAILED),
+        _ => None,
+    }
+}
+
+/// Run the test of `runner` to the end, or until `timeout` if given. Ctrl-C exits straight away
+/// with `EXIT_INTERRUPTED` rather than leaving the run half reported.
+pub(super) async fn run_until_interrupted(
+    runner: TestRunner,
+    timeout: Option<Duration>,
+) -> Result<PatuiRun> {
+    let run = async move {
+        match timeout {
+            Some(limit) => runner.run_test_with_timeout(limit).await,
+            None => runner.run_test().await,
+        }
+    };
+
+    tokio::select! {
+        run = run => run,
+        _ = tokio::signal::ctrl_c() => {
+            eprintln!("Interrupted");
+            std::process::exit(EXIT_INTERRUPTED);
+        }
+    }
+}
+
+/// Print each event of the run `runner` makes as a line of `--output ndjson`, flushing as we go
+/// so whoever's reading sees events as they happen. With `only_failures` just steps failing are
+/// printed. The task finishes with the run, giving the sequence number of the next line.
+pub(super) fn stream_events(
+    runner: &mut TestRunner,
+    only_failures: bool,
+) -> JoinHandle<Result<u64>> {
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    runner.set_events(events_tx);
+
+    tokio::spawn(async move {
+        let mut seq = 0;
+        while let Some(event) = events_rx.recv().await {
+            if only_failures && !event.is_failure() {
+                continue;
+            }
+
+            let mut stdout = std::io::stdout().lock();
+            serde_json::to_writer(&mut stdout, &NdjsonLine::Event { seq, event: &event })?;
+            writeln!(stdout)?;
+            stdout.flush()?;
+            seq += 1;
+        }
+
+        Ok(seq)
+    })
+}
+
+/// Print the finished `run` as JSON and exit with the code for how it went if it didn't pass.
+/// Each step is shown with what it output if that's in `outputs`, i.e. the run captured outputs.
+/// When streaming with `--output ndjson` `next_seq` is given and the run is printed as the
+/// summary line. With `only_failures` steps that didn't fail are left out, the run's status is
+/// still for every step.
+pub(super) fn report_run(
+    mut run: PatuiRun,
+    mut outputs: Vec<PatuiRunOutput>,
+    next_seq: Option<u64>,
+    only_failures: bool,
+) -> Result<()> {
+    if only_failures {
+        run.step_run_details
+            .retain(|step_run| matches!(step_run.result.status, PatuiRunStatus::Error(_)));
+    }
+
+    let res = if let Ok(mut run_display) = PatuiRunDisplay::try_from(run.clone()) {
+        for step in run_display.step_run_details.iter_mut() {
+            if let Some(idx) = outputs
+                .iter()
+                .position(|output| output.step_name == step.name)
+            {
+                step.output = Some(outputs.swap_remove(idx));
+            }
+        }
+        serde_json::to_value(run_display)?
+    } else {
+        serde_json::to_value(&run)?
+    };
+
+    match next_seq {
+        Some(seq) => println!(
+            "{}",
+            serde_json::to_string(&NdjsonLine::Summary { seq, run: res })?
+        ),
+        None => println!("{}", res),
+    }
+
+    if let Some(code) = exit_code(&run.status) {
+        std::process::exit(code);
+    }
+
+    Ok(())
+}
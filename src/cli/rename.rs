@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use clap::{Args, Parser};
+use eyre::Result;
+
+use crate::db::Database;
+
+#[derive(Debug, Args)]
+#[command(about = "Rename an existing entity")]
+pub(crate) struct Command {
+    #[command(subcommand)]
+    command: RenameCommand,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        match &self.command {
+            RenameCommand::Test(rename_test) => rename_test.handle(db).await,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) enum RenameCommand {
+    Test(RenameTest),
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "Rename an existing test, no other test can already have the new name")]
+pub(crate) struct RenameTest {
+    #[clap(short, long)]
+    pub(crate) id: i64,
+
+    #[clap(short, long)]
+    pub(crate) name: String,
+}
+
+impl RenameTest {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        db.rename_test(self.id.into(), self.name.clone()).await?;
+        eprintln!("Successfully renamed test ({}) to: {}", self.id, self.name);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, IsTerminal, Write},
+};
+
+use clap::Args;
+use eyre::{eyre, Result};
+
+use crate::{
+    runner::{eval, EvalResult},
+    types::{expr::get_step_refs, PatuiExpr},
+};
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Evaluate expressions read from stdin, a line at a time, to try out the expression language"
+)]
+pub(crate) struct Command {
+    /// Print the AST of each expression before its value, `:ast` toggles this
+    #[arg(long)]
+    ast: bool,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self) -> Result<()> {
+        let stdin = std::io::stdin();
+        let interactive = stdin.is_terminal();
+        let mut show_ast = self.ast;
+
+        if interactive {
+            println!(
+                "Enter an expression to evaluate, `:ast` to toggle showing the AST, `:q` to quit"
+            );
+        }
+
+        let mut lines = stdin.lock().lines();
+        loop {
+            if interactive {
+                print!("> ");
+                std::io::stdout().flush()?;
+            }
+
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let line = line?;
+
+            match line.trim() {
+                "" => {}
+                ":ast" => {
+                    show_ast = !show_ast;
+                    println!("AST {}", if show_ast { "on" } else { "off" });
+                }
+                ":q" | ":quit" => break,
+                line => {
+                    // A bad expression shouldn't end the session
+                    if let Err(e) = evaluate(line, show_ast) {
+                        eprintln!("Error: {}", e);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn evaluate(line: &str, show_ast: bool) -> Result<()> {
+    let expr = PatuiExpr::try_from(line)?;
+
+    if show_ast {
+        println!("{:#?}", expr.kind());
+    }
+
+    if !get_step_refs(&expr)?.is_empty() {
+        return Err(eyre!("Step references can only be evaluated during a run"));
+    }
+
+    match eval(&expr, &HashMap::new())? {
+        EvalResult::Known(value) | EvalResult::Predictable(value) => println!("{}", value),
+        EvalResult::Unknown => println!("Unknown"),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::{io::Read, sync::Arc, time::Duration};
+
+use clap::{Args, Parser};
+use eyre::Result;
+
+use crate::{
+    db::{Database, PatuiInstance, PatuiRun, PatuiRunEnv},
+    runner::TestRunner,
+    types::{PatuiRunStatus, PatuiTestDetails},
+    utils::get_current_time_string,
+};
+
+use super::{
+    new::{report_run, run_until_interrupted, stream_events, RunOptions, RunOutput},
+    CliError,
+};
+
+#[derive(Debug, Args)]
+#[command(
+    about = "Inspect test runs",
+    args_conflicts_with_subcommands = true,
+    mut_group("RunOptions", |group| group.requires("steps_from_stdin"))
+)]
+pub(crate) struct Command {
+    #[command(subcommand)]
+    command: Option<RunCommand>,
+
+    /// Run a test read from stdin as YAML or JSON without saving anything to the database,
+    /// reporting the run as `new run` does
+    #[arg(long)]
+    steps_from_stdin: bool,
+
+    #[command(flatten)]
+    options: RunOptions,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        match &self.command {
+            Some(RunCommand::Show(show)) => show.handle(db).await,
+            None if self.steps_from_stdin => self.run_from_stdin().await,
+            None => Err(CliError::Usage(
+                "Expected a subcommand or `--steps-from-stdin`".to_string(),
+            )
+            .into()),
+        }
+    }
+
+    /// Whether this is a run from stdin, which doesn't touch the database.
+    pub(crate) fn is_from_stdin(&self) -> bool {
+        self.command.is_none() && self.steps_from_stdin
+    }
+
+    pub(crate) async fn run_from_stdin(&self) -> Result<()> {
+        let mut contents = String::new();
+        std::io::stdin().read_to_string(&mut contents)?;
+
+        let test = if contents.trim_start().starts_with('{') {
+            PatuiTestDetails::from_json_str(&contents)
+        } else {
+            PatuiTestDetails::from_yaml_str(&contents)
+        }
+        .map_err(CliError::invalid)?;
+
+        let timeout = self
+            .options
+            .timeout
+            .or(test.default_timeout_ms.map(Duration::from_millis));
+
+        let mut runner = TestRunner::new(adhoc_run(test));
+        self.options.configure(&mut runner)?;
+        let events = (self.options.output == RunOutput::Ndjson)
+            .then(|| stream_events(&mut runner, self.options.only_failures));
+
+        let run = run_until_interrupted(runner, timeout).await?;
+        let next_seq = match events {
+            Some(events) => Some(events.await??),
+            None => None,
+        };
+
+        // Nothing's captured without a database to save it in
+        report_run(run, vec![], next_seq, self.options.only_failures)
+    }
+}
+
+/// A run of `test` that only exists in memory, so it has no ids.
+fn adhoc_run(test: PatuiTestDetails) -> PatuiRun {
+    let now = get_current_time_string();
+
+    let instance = PatuiInstance {
+        id: 0.into(),
+        test_id: 0.into(),
+        hash: 0,
+        name: test.name,
+        description: test.description,
+        require_all_steps_produce_output: test.require_all_steps_produce_output,
+        creation_date: test.creation_date,
+        last_updated: now.clone(),
+        steps: test.steps,
+    };
+
+    PatuiRun {
+        id: 0.into(),
+        env: PatuiRunEnv::new(&instance),
+        instance,
+        start_time: now,
+        end_time: None,
+        status: PatuiRunStatus::Pending,
+        step_run_details: vec![],
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) enum RunCommand {
+    Show(RunShow),
+}
+
+#[derive(Parser, Debug)]
+#[command(
+    about = "Show what a step output during a run created with `new run --capture`, or what the run was made with"
+)]
+pub(crate) struct RunShow {
+    /// Run ID to show
+    pub(crate) run_id: i64,
+
+    /// Name of the step to show the output of
+    #[arg(short, long, required_unless_present = "env")]
+    pub(crate) step: Option<String>,
+
+    /// Show the OS, patui version and plugins the run was made with
+    #[arg(long, conflicts_with = "step")]
+    pub(crate) env: bool,
+}
+
+impl RunShow {
+    pub(crate) async fn handle(&self, db: Arc<Database>) -> Result<()> {
+        let res = match &self.step {
+            Some(step) => {
+                serde_json::to_string(&db.get_run_output(self.run_id.into(), step).await?)?
+            }
+            None => serde_json::to_string(&db.get_run_env(self.run_id.into()).await?)?,
+        };
+
+        println!("{}", res);
+
+        Ok(())
+    }
+}
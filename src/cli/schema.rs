@@ -0,0 +1,21 @@
+use std::io::Write;
+
+use clap::Args;
+use eyre::Result;
+
+use crate::types::PatuiTestEditable;
+
+#[derive(Debug, Args)]
+#[command(about = "Print the JSON Schema for test files, for use in editors and docs")]
+pub(crate) struct Command {}
+
+impl Command {
+    pub(crate) async fn handle(&self) -> Result<()> {
+        let schema = schemars::schema_for!(PatuiTestEditable);
+
+        std::io::stdout().write_all(&serde_json::to_vec_pretty(&schema)?)?;
+        std::io::stdout().write_all(b"\n")?;
+
+        Ok(())
+    }
+}
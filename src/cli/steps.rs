@@ -0,0 +1,95 @@
+use std::{io::Write, path::PathBuf};
+
+use clap::{Args, Parser};
+use eyre::{eyre, Result};
+
+use crate::{
+    runner::find_plugins,
+    types::{steps::PatuiStepDetailsEditable, PatuiStepKindDisplay},
+    utils::get_plugin_dir,
+};
+
+#[derive(Debug, Args)]
+#[command(about = "Look at the kinds of step tests can use")]
+pub(crate) struct Command {
+    #[command(subcommand)]
+    command: StepsCommand,
+}
+
+impl Command {
+    pub(crate) async fn handle(&self) -> Result<()> {
+        match &self.command {
+            StepsCommand::List(list_steps) => list_steps.handle().await,
+        }
+    }
+}
+
+#[derive(Parser, Debug)]
+pub(crate) enum StepsCommand {
+    List(ListSteps),
+}
+
+#[derive(Parser, Debug)]
+#[command(about = "List the built-in step kinds and those provided by installed plugins")]
+pub(crate) struct ListSteps {
+    // Directory to look for plugins in, defaults to `plugins` in the data directory
+    #[clap(short, long)]
+    pub(crate) dir: Option<PathBuf>,
+}
+
+impl ListSteps {
+    pub(crate) async fn handle(&self) -> Result<()> {
+        let mut kinds = builtin_step_kinds()?;
+
+        let dir = match &self.dir {
+            Some(dir) => dir.clone(),
+            None => get_plugin_dir()?,
+        };
+        // Nothing's been installed when the default directory doesn't exist yet
+        if self.dir.is_some() || dir.exists() {
+            kinds.extend(
+                find_plugins(&dir)
+                    .await?
+                    .into_iter()
+                    .map(PatuiStepKindDisplay::from),
+            );
+        }
+
+        std::io::stdout().write_all(&serde_json::to_vec(&kinds)?)?;
+        std::io::stdout().write_all(b"\n")?;
+
+        Ok(())
+    }
+}
+
+/// The step kinds tests can use without plugins, with the fields each takes as given by the
+/// schema of the test format.
+fn builtin_step_kinds() -> Result<Vec<PatuiStepKindDisplay>> {
+    let schema = serde_json::to_value(schemars::schema_for!(PatuiStepDetailsEditable))?;
+
+    schema["oneOf"]
+        .as_array()
+        .ok_or_else(|| eyre!("Step kinds missing from schema"))?
+        .iter()
+        .map(|variant| {
+            let kind = variant["required"][0]
+                .as_str()
+                .ok_or_else(|| eyre!("Step kind missing from schema: {}", variant))?;
+
+            let details = match variant["properties"][kind]["$ref"].as_str() {
+                Some(def) => &schema["definitions"][def.trim_start_matches("#/definitions/")],
+                None => &variant["properties"][kind],
+            };
+            let fields = details["properties"]
+                .as_object()
+                .map(|properties| properties.keys().cloned().collect())
+                .unwrap_or_default();
+
+            Ok(PatuiStepKindDisplay {
+                kind: kind.to_string(),
+                fields,
+                plugin: None,
+            })
+        })
+        .collect()
+}
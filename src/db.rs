@@ -3,4 +3,7 @@ mod types;
 
 pub(crate) use sqlite::Database;
 
-pub(crate) use types::{PatuiInstance, PatuiRun, PatuiTestDb, PatuiTestId, PatuiTestMinDisplay};
+pub(crate) use types::{
+    DbError, PatuiInstance, PatuiRun, PatuiRunEnv, PatuiRunId, PatuiRunOutput, PatuiTestDb,
+    PatuiTestFilter, PatuiTestId, PatuiTestMinDisplay, PatuiTestOrder,
+};
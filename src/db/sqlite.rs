@@ -1,12 +1,16 @@
 use std::path::Path;
 
 use eyre::Result;
+use rusqlite::{backup::Progress, DatabaseName, OptionalExtension};
 use tokio_rusqlite::Connection;
 use tracing::{debug, trace};
 
-use super::types::{PatuiInstance, PatuiRun, PatuiTestDb, PatuiTestHashable, PatuiTestId};
+use super::types::{
+    DbError, PatuiInstance, PatuiRun, PatuiRunEnv, PatuiRunId, PatuiRunOutput, PatuiTestDb,
+    PatuiTestFilter, PatuiTestHashable, PatuiTestId,
+};
 use crate::{
-    types::{PatuiRunStatus, PatuiRunStep, PatuiStep, PatuiTest, PatuiTestDetails},
+    types::{PatuiRunStatus, PatuiRunStep, PatuiStep, PatuiStepData, PatuiTest, PatuiTestDetails},
     utils::get_current_time_string,
 };
 
@@ -42,7 +46,9 @@ impl Database {
                         last_updated TEXT NOT NULL,
                         last_used_date TEXT,
                         times_used INTEGER NOT NULL DEFAULT 0,
-                        steps BLOB NOT NULL DEFAULT '[]'
+                        steps BLOB NOT NULL DEFAULT '[]',
+                        require_all_steps_produce_output INTEGER NOT NULL DEFAULT 0,
+                        default_timeout_ms INTEGER
                     );
 
                     -- Holds the audit of the test details when it was ran
@@ -55,6 +61,7 @@ impl Database {
                         creation_date TEXT NOT NULL,
                         last_updated TEXT NOT NULL,
                         steps BLOB NOT NULL DEFAULT '[]',
+                        require_all_steps_produce_output INTEGER NOT NULL DEFAULT 0,
                         FOREIGN KEY (test_id) REFERENCES test(id)
                     );
 
@@ -68,8 +75,42 @@ impl Database {
                         end_time TEXT,
                         status TEXT NOT NULL,
                         step_run_details BLOB NOT NULL DEFAULT '[]',
+                        env BLOB NOT NULL DEFAULT '{}',
                         FOREIGN KEY (instance_id) REFERENCES instance(id)
                     );
+
+                    -- Holds what each step of a run output, when the run captured it
+                    CREATE TABLE IF NOT EXISTS run_outputs (
+                        run_id INTEGER NOT NULL,
+                        step_name TEXT NOT NULL,
+                        outputs BLOB NOT NULL DEFAULT '[]',
+                        dropped INTEGER NOT NULL DEFAULT 0,
+                        PRIMARY KEY (run_id, step_name),
+                        FOREIGN KEY (run_id) REFERENCES run(id)
+                    );
+                    "#,
+                )?;
+
+                // Databases from before these columns existed need them adding
+                for table in ["test", "instance"] {
+                    add_missing_column(
+                        conn,
+                        table,
+                        "require_all_steps_produce_output",
+                        "INTEGER NOT NULL DEFAULT 0",
+                    )?;
+                }
+                add_missing_column(conn, "test", "default_timeout_ms", "INTEGER")?;
+                add_missing_column(conn, "run", "env", "BLOB NOT NULL DEFAULT '{}'")?;
+
+                // Test names are unique, older databases could have the same name more than once
+                // so all but the first of each get their id added to tell them apart
+                conn.execute_batch(
+                    r#"
+                    UPDATE test SET name = name || ' (' || id || ')'
+                        WHERE id NOT IN (SELECT MIN(id) FROM test GROUP BY name);
+
+                    CREATE UNIQUE INDEX IF NOT EXISTS idx_test_name ON test (name);
                     "#,
                 )?;
 
@@ -91,45 +132,68 @@ impl Database {
         let test = self
             .conn
             .call(move |conn| {
-                let mut stmt = conn.prepare("SELECT id, name, desc, creation_date, last_updated, last_used_date, times_used, steps FROM test WHERE id = ?1")?;
-
-                let test = stmt.query_row([i64::from(id)], |row| {
-                    let steps = sql_decode_steps(row.get(7)?)?;
-
-                    Ok(PatuiTestDb {
-                        id,
-                        name: row.get(1)?,
-                        description: row.get(2)?,
-                        creation_date: row.get(3)?,
-                        last_updated: row.get(4)?,
-                        last_used_date: row.get(5)?,
-                        times_used: row.get(6)?,
-                        steps,
+                let mut stmt = conn.prepare("SELECT id, name, desc, creation_date, last_updated, last_used_date, times_used, steps, require_all_steps_produce_output, default_timeout_ms FROM test WHERE id = ?1")?;
+
+                let test = stmt
+                    .query_row([i64::from(id)], |row| {
+                        let steps = sql_decode_steps(row.get(7)?)?;
+
+                        Ok(PatuiTestDb {
+                            id,
+                            name: row.get(1)?,
+                            description: row.get(2)?,
+                            require_all_steps_produce_output: row.get(8)?,
+                            default_timeout_ms: row.get(9)?,
+                            creation_date: row.get(3)?,
+                            last_updated: row.get(4)?,
+                            last_used_date: row.get(5)?,
+                            times_used: row.get(6)?,
+                            steps,
+                        })
                     })
-                })?;
+                    .optional()?;
 
                 Ok(test)
             })
             .await?;
 
-        Ok(test)
+        test.ok_or_else(|| DbError::NotFound(id).into())
     }
 
     pub(crate) async fn get_tests(&self) -> Result<Vec<PatuiTestDb>> {
+        self.get_tests_filtered(&PatuiTestFilter::default()).await
+    }
+
+    /// The tests matching `filter` in the order it asks for.
+    pub(crate) async fn get_tests_filtered(
+        &self,
+        filter: &PatuiTestFilter,
+    ) -> Result<Vec<PatuiTestDb>> {
         debug!("Getting tests...");
+        trace!("Test filter {:?}", filter);
+
+        let sql = format!(
+            "SELECT id, name, desc, creation_date, last_updated, last_used_date, times_used, steps, require_all_steps_produce_output, default_timeout_ms FROM test WHERE (?1 IS NULL OR instr(name, ?1) > 0) {} LIMIT ?2",
+            filter.order.order_by()
+        );
+        let name_contains = filter.name_contains.clone();
+        // A negative limit is no limit at all to SQLite
+        let limit = filter.limit.map_or(-1, i64::from);
 
         let tests = self
             .conn
             .call(move |conn| {
-                let mut stmt = conn.prepare("SELECT id, name, desc, creation_date, last_updated, last_used_date, times_used, steps FROM test")?;
+                let mut stmt = conn.prepare(&sql)?;
                 let tests = stmt
-                    .query_map([], |row| {
+                    .query_map(rusqlite::params![name_contains, limit], |row| {
                         let steps = sql_decode_steps(row.get(7)?)?;
                         let id: i64 = row.get(0)?;
                         Ok(PatuiTestDb {
                             id: id.into(),
                             name: row.get(1)?,
                             description: row.get(2)?,
+                            require_all_steps_produce_output: row.get(8)?,
+                            default_timeout_ms: row.get(9)?,
                             creation_date: row.get(3)?,
                             last_updated: row.get(4)?,
                             last_used_date: row.get(5)?,
@@ -137,7 +201,7 @@ impl Database {
                             steps,
                         })
                     })?
-                .collect::<std::result::Result<Vec<PatuiTestDb>, rusqlite::Error>>()?;
+                    .collect::<std::result::Result<Vec<PatuiTestDb>, rusqlite::Error>>()?;
 
                 Ok(tests)
             })
@@ -146,6 +210,7 @@ impl Database {
         Ok(tests)
     }
 
+    /// Add a new test, fails with `DbError::Conflict` when another test already has its name.
     pub(crate) async fn new_test(&self, details: PatuiTestDetails) -> Result<PatuiTestDb> {
         debug!("New test");
         trace!("New test details {:?}", details);
@@ -154,54 +219,145 @@ impl Database {
 
         let test_id = self.conn
             .call(move |conn| {
-                let mut stmt = conn.prepare("INSERT INTO test (name, desc, creation_date, last_updated, last_used_date, times_used, steps) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+                let mut stmt = conn.prepare("INSERT INTO test (name, desc, creation_date, last_updated, last_used_date, times_used, steps, require_all_steps_produce_output, default_timeout_ms) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)")?;
 
-                let test_id = stmt.insert((
-                    test_clone.name,
+                let res = stmt.insert((
+                    &test_clone.name,
                     test_clone.description,
                     test_clone.creation_date.clone(),
                     test_clone.creation_date,
                     None::<String>,
                     0,
                     sql_encode_steps(&test_clone.steps)?,
-                ))?;
+                    test_clone.require_all_steps_produce_output,
+                    test_clone.default_timeout_ms,
+                ));
 
-                Ok(test_id)
+                Ok(name_conflict(res, test_clone.name)?)
             })
             .await?;
 
-        Ok(PatuiTestDb::new_from_details(test_id.into(), details))
+        Ok(PatuiTestDb::new_from_details(test_id?.into(), details))
     }
 
+    /// Save changes to a test, fails with `DbError::Conflict` when it's been given the name of
+    /// another test.
     pub(crate) async fn edit_test(&self, test: &PatuiTest) -> Result<()> {
         debug!("Edit test");
         trace!("Edit test {:?}", test);
 
         let test_clone = test.clone();
 
-        self.conn
+        let res = self
+            .conn
             .call(move |conn| {
-                let mut stmt = conn.prepare("UPDATE test SET name = ?1, desc = ?2, last_updated = ?3, steps = ?4 WHERE id = ?5")?;
+                let mut stmt = conn.prepare("UPDATE test SET name = ?1, desc = ?2, last_updated = ?3, steps = ?4, require_all_steps_produce_output = ?5, default_timeout_ms = ?6 WHERE id = ?7")?;
 
                 let id: i64 = test_clone.id.into();
 
                 let now = get_current_time_string();
 
-                stmt.execute((
-                    test_clone.name,
+                let res = stmt.execute((
+                    &test_clone.name,
                     test_clone.description,
                     now,
                     sql_encode_steps(&test_clone.steps)?,
+                    test_clone.require_all_steps_produce_output,
+                    test_clone.default_timeout_ms,
                     id,
-                ))?;
+                ));
 
-                Ok(())
+                Ok(name_conflict(res, test_clone.name)?)
             })
             .await?;
 
+        res?;
+
         Ok(())
     }
 
+    /// Copy a test and all its steps into a new test called `new_name`, the copy starts with
+    /// fresh dates and no usage. Fails with `DbError::Conflict` when another test already has
+    /// `new_name`.
+    pub(crate) async fn clone_test(
+        &self,
+        id: PatuiTestId,
+        new_name: String,
+    ) -> Result<PatuiTestId> {
+        debug!("Clone test ({})...", id);
+
+        let test_id = self
+            .conn
+            .call(move |conn| {
+                let mut stmt = conn.prepare("INSERT INTO test (name, desc, creation_date, last_updated, last_used_date, times_used, steps, require_all_steps_produce_output, default_timeout_ms) SELECT ?1, desc, ?2, ?2, NULL, 0, steps, require_all_steps_produce_output, default_timeout_ms FROM test WHERE id = ?3")?;
+
+                let now = get_current_time_string();
+
+                let res = stmt.execute((&new_name, now, i64::from(id)));
+                match name_conflict(res, new_name)? {
+                    Ok(0) => Ok(Err(DbError::NotFound(id))),
+                    Ok(_) => Ok(Ok(conn.last_insert_rowid())),
+                    Err(e) => Ok(Err(e)),
+                }
+            })
+            .await?;
+
+        Ok(test_id?.into())
+    }
+
+    /// Give a test a new name, fails with `DbError::Conflict` when another test already has it.
+    pub(crate) async fn rename_test(&self, id: PatuiTestId, new_name: String) -> Result<()> {
+        debug!("Rename test ({})...", id);
+
+        let res = self
+            .conn
+            .call(move |conn| {
+                let now = get_current_time_string();
+
+                let res = conn.execute(
+                    "UPDATE test SET name = ?1, last_updated = ?2 WHERE id = ?3",
+                    (&new_name, now, i64::from(id)),
+                );
+                match name_conflict(res, new_name)? {
+                    Ok(0) => Ok(Err(DbError::NotFound(id))),
+                    Ok(_) => Ok(Ok(())),
+                    Err(e) => Ok(Err(e)),
+                }
+            })
+            .await?;
+
+        Ok(res?)
+    }
+
+    /// Bump the use count and last used date of a test. In memory and read only databases are
+    /// skipped, returns whether the test was updated.
+    pub(crate) async fn mark_test_used(&self, id: PatuiTestId) -> Result<bool> {
+        debug!("Mark test used ({})...", id);
+
+        let updated = self
+            .conn
+            .call(move |conn| {
+                let is_in_memory = conn.path().is_none_or(|path| path.is_empty());
+                if is_in_memory || conn.is_readonly(DatabaseName::Main)? {
+                    debug!("Database not persistent or writable, skipping test usage update");
+                    return Ok(false);
+                }
+
+                let mut stmt = conn.prepare(
+                    "UPDATE test SET last_used_date = ?1, times_used = times_used + 1 WHERE id = ?2",
+                )?;
+
+                let now = get_current_time_string();
+
+                let rows = stmt.execute((now, i64::from(id)))?;
+
+                Ok(rows > 0)
+            })
+            .await?;
+
+        Ok(updated)
+    }
+
     pub(crate) async fn get_or_new_instance(&self, test: PatuiTestDb) -> Result<PatuiInstance> {
         debug!("Get or new instance");
         trace!("Get or new instance details {:?}", test);
@@ -214,7 +370,7 @@ impl Database {
         }
 
         let instance = self.conn.call(move |conn| {
-            let mut stmt = conn.prepare("INSERT INTO instance (test_id, hash, name, desc, creation_date, last_updated, steps) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+            let mut stmt = conn.prepare("INSERT INTO instance (test_id, hash, name, desc, creation_date, last_updated, steps, require_all_steps_produce_output) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)")?;
 
             let instance_id = stmt.insert((
                 i64::from(test.id),
@@ -224,6 +380,7 @@ impl Database {
                 &test.creation_date,
                 &test.last_updated,
                 sql_encode_steps(&test.steps)?,
+                test.require_all_steps_produce_output,
             ))?;
 
             let instance = PatuiInstance {
@@ -232,6 +389,7 @@ impl Database {
                 hash: instance_hash,
                 name: test.name,
                 description: test.description,
+                require_all_steps_produce_output: test.require_all_steps_produce_output,
                 creation_date: test.creation_date,
                 last_updated: test.last_updated,
                 steps: test.steps,
@@ -245,13 +403,15 @@ impl Database {
 
     pub(crate) async fn new_run(&self, instance: PatuiInstance) -> Result<PatuiRun> {
         let instance_id = instance.id;
+        let env = PatuiRunEnv::new(&instance);
+        let env_clone = env.clone();
 
         let start_time = chrono::Local::now().to_string();
         let start_time_clone = start_time.clone();
 
         let run_id = self.conn
             .call(move |conn| {
-                let mut stmt = conn.prepare("INSERT INTO run (instance_id, start_time, end_time, status, step_run_details) VALUES (?1, ?2, ?3, ?4, ?5)")?;
+                let mut stmt = conn.prepare("INSERT INTO run (instance_id, start_time, end_time, status, step_run_details, env) VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
 
                 let run_id = stmt.insert((
                     i64::from(instance_id),
@@ -259,6 +419,7 @@ impl Database {
                     None::<String>,
                     PatuiRunStatus::Pending,
                     sql_encode_step_runs(&vec![])?,
+                    sql_encode_env(&env_clone)?,
                 ))?;
 
                 Ok(run_id)
@@ -272,12 +433,180 @@ impl Database {
             end_time: None,
             status: PatuiRunStatus::Pending,
             step_run_details: vec![],
+            env,
         })
     }
 
+    /// Replace the environment recorded for a run, e.g. once the plugin versions are known.
+    pub(crate) async fn save_run_env(&self, run_id: PatuiRunId, env: &PatuiRunEnv) -> Result<()> {
+        debug!("Saving environment of run {}...", run_id);
+
+        let env = env.clone();
+
+        let updated = self
+            .conn
+            .call(move |conn| {
+                let mut stmt = conn.prepare("UPDATE run SET env = ?1 WHERE id = ?2")?;
+
+                Ok(stmt.execute((sql_encode_env(&env)?, i64::from(run_id)))?)
+            })
+            .await?;
+
+        if updated == 0 {
+            return Err(DbError::UnknownRun(run_id).into());
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn get_run_env(&self, run_id: PatuiRunId) -> Result<PatuiRunEnv> {
+        debug!("Getting environment of run {}...", run_id);
+
+        let env = self
+            .conn
+            .call(move |conn| {
+                let mut stmt = conn.prepare("SELECT env FROM run WHERE id = ?1")?;
+
+                let env = stmt
+                    .query_row([i64::from(run_id)], |row| sql_decode_env(row.get(0)?))
+                    .optional()?;
+
+                Ok(env)
+            })
+            .await?;
+
+        env.ok_or_else(|| DbError::UnknownRun(run_id).into())
+    }
+
+    pub(crate) async fn save_run_output(&self, output: PatuiRunOutput) -> Result<()> {
+        debug!(
+            "Saving output of step {} for run {}...",
+            output.step_name, output.run_id
+        );
+
+        self.conn
+            .call(move |conn| {
+                let mut stmt = conn.prepare("INSERT OR REPLACE INTO run_outputs (run_id, step_name, outputs, dropped) VALUES (?1, ?2, ?3, ?4)")?;
+
+                stmt.execute((
+                    i64::from(output.run_id),
+                    output.step_name,
+                    sql_encode_outputs(&output.outputs)?,
+                    output.dropped,
+                ))?;
+
+                Ok(())
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn get_run_output(
+        &self,
+        run_id: PatuiRunId,
+        step_name: &str,
+    ) -> Result<PatuiRunOutput> {
+        debug!("Getting output of step {} for run {}...", step_name, run_id);
+
+        let step_name = step_name.to_string();
+        let step_name_clone = step_name.clone();
+
+        let output = self
+            .conn
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT outputs, dropped FROM run_outputs WHERE run_id = ?1 AND step_name = ?2",
+                )?;
+
+                let output = stmt
+                    .query_row((i64::from(run_id), &step_name_clone), |row| {
+                        Ok(PatuiRunOutput {
+                            run_id,
+                            step_name: step_name_clone.clone(),
+                            outputs: sql_decode_outputs(row.get(0)?)?,
+                            dropped: row.get(1)?,
+                        })
+                    })
+                    .optional()?;
+
+                Ok(output)
+            })
+            .await?;
+
+        output.ok_or_else(|| DbError::RunOutputNotFound(run_id, step_name).into())
+    }
+
+    /// Everything captured for `run_id`, one for each step that had an output to capture.
+    pub(crate) async fn get_run_outputs(&self, run_id: PatuiRunId) -> Result<Vec<PatuiRunOutput>> {
+        debug!("Getting outputs of run {}...", run_id);
+
+        let outputs = self
+            .conn
+            .call(move |conn| {
+                let mut stmt = conn.prepare(
+                    "SELECT step_name, outputs, dropped FROM run_outputs WHERE run_id = ?1 ORDER BY rowid",
+                )?;
+
+                let outputs = stmt
+                    .query_map([i64::from(run_id)], |row| {
+                        Ok(PatuiRunOutput {
+                            run_id,
+                            step_name: row.get(0)?,
+                            outputs: sql_decode_outputs(row.get(1)?)?,
+                            dropped: row.get(2)?,
+                        })
+                    })?
+                    .collect::<std::result::Result<Vec<PatuiRunOutput>, rusqlite::Error>>()?;
+
+                Ok(outputs)
+            })
+            .await?;
+
+        Ok(outputs)
+    }
+
+    /// Copy the whole database to `path` with SQLite's online backup, so it's safe to do while
+    /// something else, e.g. the TUI, has the database open.
+    pub(crate) async fn backup(&self, path: &Path) -> Result<()> {
+        debug!("Backing up to {}...", path.display());
+
+        let path = path.to_path_buf();
+
+        self.conn
+            .call(move |conn| {
+                conn.backup(DatabaseName::Main, path, None)?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(())
+    }
+
+    /// Replace everything in the database with the contents of the backup at `path`.
+    pub(crate) async fn restore(&self, path: &Path) -> Result<()> {
+        debug!("Restoring from {}...", path.display());
+
+        // SQLite would happily create an empty database to restore from
+        if !path.is_file() {
+            return Err(DbError::BackupNotFound(path.to_path_buf()).into());
+        }
+
+        let path = path.to_path_buf();
+
+        self.conn
+            .call(move |conn| {
+                conn.restore(DatabaseName::Main, path, None::<fn(Progress)>)?;
+                Ok(())
+            })
+            .await?;
+
+        Ok(())
+    }
+
     async fn get_instance(&self, hash: i64, test: PatuiTest) -> Result<Option<PatuiInstance>> {
         let instance = self.conn.call(move |conn| {
-            let mut stmt = conn.prepare("SELECT id, test_id, name, desc, creation_date, last_updated, steps FROM instance WHERE hash = ?1")?;
+            let mut stmt = conn.prepare("SELECT id, test_id, name, desc, creation_date, last_updated, steps, require_all_steps_produce_output FROM instance WHERE hash = ?1")?;
 
             let mut rows = stmt.query([hash])?;
 
@@ -289,6 +618,8 @@ impl Database {
                     id: test_id.into(),
                     name: row.get(2)?,
                     description: row.get(3)?,
+                    require_all_steps_produce_output: row.get(7)?,
+                    default_timeout_ms: test.default_timeout_ms,
                     steps: steps.clone(),
                 };
 
@@ -301,6 +632,7 @@ impl Database {
                         hash,
                         name: row.get(2)?,
                         description: row.get(3)?,
+                        require_all_steps_produce_output: row.get(7)?,
                         creation_date: row.get(4)?,
                         last_updated: row.get(5)?,
                         steps,
@@ -315,6 +647,43 @@ impl Database {
     }
 }
 
+fn add_missing_column(
+    conn: &rusqlite::Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> std::result::Result<(), rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1",
+        table
+    ))?;
+    if !stmt.exists([column])? {
+        debug!("Adding column {} to {}", column, table);
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            (),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Test names are kept unique by `idx_test_name`, a clash on it is reported as
+/// `DbError::Conflict` while any other error is passed on.
+fn name_conflict<T>(
+    res: std::result::Result<T, rusqlite::Error>,
+    name: String,
+) -> std::result::Result<std::result::Result<T, DbError>, rusqlite::Error> {
+    match res {
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+        {
+            Ok(Err(DbError::Conflict(name)))
+        }
+        res => res.map(Ok),
+    }
+}
+
 // Need a custom hash for the test to be able to do faster database lookups for test details
 fn get_test_hash(test: &PatuiTestDb) -> Result<i64> {
     let hashable_test = <PatuiTestHashable>::from(test);
@@ -351,15 +720,47 @@ fn sql_encode_step_runs(
     Ok(ret)
 }
 
+fn sql_decode_outputs(outputs: String) -> std::result::Result<Vec<PatuiStepData>, rusqlite::Error> {
+    let ret = serde_json::from_str(&outputs)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    Ok(ret)
+}
+
+fn sql_encode_outputs(
+    outputs: &Vec<PatuiStepData>,
+) -> std::result::Result<String, rusqlite::Error> {
+    let ret = serde_json::to_string(outputs)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    Ok(ret)
+}
+
+fn sql_decode_env(env: String) -> std::result::Result<PatuiRunEnv, rusqlite::Error> {
+    let ret = serde_json::from_str(&env)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    Ok(ret)
+}
+
+fn sql_encode_env(env: &PatuiRunEnv) -> std::result::Result<String, rusqlite::Error> {
+    let ret = serde_json::to_string(env)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+    Ok(ret)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
     use assertor::*;
     use rusqlite::Connection;
     use tempfile::tempdir;
 
-    use crate::types::{PatuiStepAssertion, PatuiStepDetails, PatuiStepRead, PatuiTestDetails};
+    use bytes::Bytes;
+
+    use crate::types::{
+        PatuiStepAssertion, PatuiStepDataFlavour, PatuiStepDetails, PatuiStepRead, PatuiTestDetails,
+    };
 
-    use super::*;
+    use super::{super::types::PatuiTestOrder, *};
 
     async fn setup_db() -> (Database, Connection, tempfile::TempDir) {
         let tmpdir = tempdir().unwrap();
@@ -381,6 +782,8 @@ mod tests {
         let test = PatuiTestDetails {
             name: "test name".to_string(),
             description: "test description".to_string(),
+            require_all_steps_produce_output: false,
+            default_timeout_ms: None,
             creation_date: "2021-01-01 00:00:00".to_string(),
             steps: vec![],
         };
@@ -433,12 +836,15 @@ mod tests {
         let test = PatuiTestDetails {
             name: "test name".to_string(),
             description: "test description".to_string(),
+            require_all_steps_produce_output: false,
+            default_timeout_ms: None,
             creation_date: "2021-01-01 00:00:00".to_string(),
             steps: vec![
                 PatuiStep {
                     name: "test step 1".to_string(),
                     when: None,
                     depends_on: vec![],
+                    timeout_ms: None,
                     details: PatuiStepDetails::Read(PatuiStepRead {
                         r#in: "\"dir/file.txt\"".try_into().unwrap(),
                     }),
@@ -447,8 +853,10 @@ mod tests {
                     name: "test step 2".to_string(),
                     when: None,
                     depends_on: vec![],
+                    timeout_ms: None,
                     details: PatuiStepDetails::Assertion(PatuiStepAssertion {
                         expr: "foo == bar".try_into().unwrap(),
+                        sample: None,
                     }),
                 },
             ],
@@ -484,6 +892,7 @@ mod tests {
         assert_that!(steps.get(1).unwrap().details).is_equal_to(&PatuiStepDetails::Assertion(
             PatuiStepAssertion {
                 expr: "foo == bar".try_into().unwrap(),
+                sample: None,
             },
         ));
 
@@ -491,5 +900,548 @@ mod tests {
         assert_that!(row.is_none()).is_true();
     }
 
+    #[tokio::test]
+    async fn test_read_test_stored_by_older_parser() {
+        let (db, db_test, _tmpdir) = setup_db().await;
+
+        // Bytes literals used to keep the whole expression as their source
+        let steps = r#"[{"name":"check","when":null,"depends_on":[],"details":{"Assertion":{"expr":{"raw":"b\"abc\" == b\"abc\"","kind":{"BinOp":["Equal",{"ptr":{"raw":"b\"abc\" == b\"abc\"","kind":{"Lit":{"kind":{"Bytes":[97,98,99]}}}}},{"ptr":{"raw":"b\"abc\" == b\"abc\"","kind":{"Lit":{"kind":{"Bytes":[97,98,99]}}}}}]}}}}}]"#;
+        db_test
+            .execute(
+                "INSERT INTO test (name, desc, creation_date, last_updated, times_used, steps) VALUES ('old test', '', '2021-01-01 00:00:00', '2021-01-01 00:00:00', 0, ?1)",
+                rusqlite::params![steps],
+            )
+            .unwrap();
+
+        let tests = db.get_tests().await.unwrap();
+        assert_that!(tests).has_length(1);
+        let test = db.get_test(tests[0].id).await.unwrap();
+        assert_that!(test.steps).has_length(1);
+        let PatuiStepDetails::Assertion(assertion) = &test.steps[0].details else {
+            panic!("expected an assertion, got {:?}", test.steps[0].details);
+        };
+        assert_that!(assertion.expr.kind()).is_equal_to(
+            crate::types::PatuiExpr::try_from("b\"abc\" == b\"abc\"")
+                .unwrap()
+                .kind(),
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mark_test_used() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test name".to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: "2021-01-01 00:00:00".to_string(),
+                steps: vec![],
+            })
+            .await
+            .unwrap();
+
+        assert_that!(db.mark_test_used(test.id).await.unwrap()).is_true();
+
+        let first_use = db.get_test(test.id).await.unwrap();
+        assert_that!(first_use.last_used_date).is_some();
+        assert_that!(first_use.times_used).is_equal_to(1);
+
+        assert_that!(db.mark_test_used(test.id).await.unwrap()).is_true();
+
+        let second_use = db.get_test(test.id).await.unwrap();
+        assert_that!(second_use.times_used).is_equal_to(2);
+        assert_that!(second_use.last_used_date).is_greater_than(first_use.last_used_date);
+        assert_that!(second_use.last_updated).is_equal_to("2021-01-01 00:00:00".to_string());
+
+        assert_that!(db.mark_test_used(12345.into()).await.unwrap()).is_false();
+    }
+
+    #[tokio::test]
+    async fn test_mark_test_used_in_memory() {
+        let db = Database::new(Path::new(":memory:")).await.unwrap();
+        db.create_tables().await.unwrap();
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test name".to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: "2021-01-01 00:00:00".to_string(),
+                steps: vec![],
+            })
+            .await
+            .unwrap();
+
+        assert_that!(db.mark_test_used(test.id).await.unwrap()).is_false();
+
+        let test = db.get_test(test.id).await.unwrap();
+        assert_that!(test.last_used_date).is_none();
+        assert_that!(test.times_used).is_equal_to(0);
+    }
+
+    #[tokio::test]
+    async fn test_clone_test() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test name".to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: "2021-01-01 00:00:00".to_string(),
+                steps: vec![
+                    PatuiStep {
+                        name: "read".to_string(),
+                        when: None,
+                        depends_on: vec![],
+                        timeout_ms: None,
+                        details: PatuiStepDetails::Read(PatuiStepRead {
+                            r#in: "\"dir/file.txt\"".try_into().unwrap(),
+                        }),
+                    },
+                    PatuiStep {
+                        name: "check".to_string(),
+                        when: Some("steps.read.out.len() > 0".to_string()),
+                        depends_on: vec![],
+                        timeout_ms: None,
+                        details: PatuiStepDetails::Assertion(PatuiStepAssertion {
+                            expr: "steps.read.out[0] == \"foo\"".try_into().unwrap(),
+                            sample: None,
+                        }),
+                    },
+                ],
+            })
+            .await
+            .unwrap();
+        db.mark_test_used(test.id).await.unwrap();
+
+        let clone_id = db
+            .clone_test(test.id, "cloned name".to_string())
+            .await
+            .unwrap();
+        assert_that!(clone_id).is_not_equal_to(test.id);
+
+        let original = db.get_test(test.id).await.unwrap();
+        let clone = db.get_test(clone_id).await.unwrap();
+        assert_that!(clone.name).is_equal_to("cloned name".to_string());
+        assert_that!(clone.description).is_equal_to(original.description);
+        assert_that!(clone.steps).is_equal_to(original.steps);
+        assert_that!(clone.creation_date).is_not_equal_to(original.creation_date);
+        assert_that!(clone.last_used_date).is_none();
+        assert_that!(clone.times_used).is_equal_to(0);
+
+        assert_that!(db.get_tests().await.unwrap()).has_length(2);
+
+        let err = db
+            .clone_test(12345.into(), "missing".to_string())
+            .await
+            .unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>())
+            .is_equal_to(Some(&DbError::NotFound(12345.into())));
+    }
+
+    #[tokio::test]
+    async fn test_rename_test() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let mut tests = vec![];
+        for name in ["first", "second"] {
+            tests.push(
+                db.new_test(PatuiTestDetails {
+                    name: name.to_string(),
+                    description: "test description".to_string(),
+                    require_all_steps_produce_output: false,
+                    default_timeout_ms: None,
+                    creation_date: "2021-01-01 00:00:00".to_string(),
+                    steps: vec![],
+                })
+                .await
+                .unwrap(),
+            );
+        }
+
+        db.rename_test(tests[0].id, "renamed".to_string())
+            .await
+            .unwrap();
+
+        let renamed = db.get_test(tests[0].id).await.unwrap();
+        assert_that!(renamed.name).is_equal_to("renamed".to_string());
+        assert_that!(renamed.last_updated).is_not_equal_to(tests[0].last_updated.clone());
+
+        // Keeping the same name isn't a clash with itself
+        assert_that!(db.rename_test(tests[0].id, "renamed".to_string()).await).is_ok();
+
+        let err = db
+            .rename_test(12345.into(), "missing".to_string())
+            .await
+            .unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>())
+            .is_equal_to(Some(&DbError::NotFound(12345.into())));
+    }
+
+    #[tokio::test]
+    async fn test_rename_test_conflict() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let mut tests = vec![];
+        for name in ["first", "second"] {
+            tests.push(
+                db.new_test(PatuiTestDetails {
+                    name: name.to_string(),
+                    description: "test description".to_string(),
+                    require_all_steps_produce_output: false,
+                    default_timeout_ms: None,
+                    creation_date: "2021-01-01 00:00:00".to_string(),
+                    steps: vec![],
+                })
+                .await
+                .unwrap(),
+            );
+        }
+
+        let err = db
+            .rename_test(tests[1].id, "first".to_string())
+            .await
+            .unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>())
+            .is_equal_to(Some(&DbError::Conflict("first".to_string())));
+        assert_that!(err.to_string())
+            .is_equal_to("a test named `first` already exists".to_string());
+
+        let unchanged = db.get_test(tests[1].id).await.unwrap();
+        assert_that!(unchanged.name).is_equal_to("second".to_string());
+        assert_that!(unchanged.last_updated).is_equal_to(tests[1].last_updated.clone());
+    }
+
+    #[tokio::test]
+    async fn test_name_conflicts() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let details = |name: &str| PatuiTestDetails {
+            name: name.to_string(),
+            description: "test description".to_string(),
+            require_all_steps_produce_output: false,
+            default_timeout_ms: None,
+            creation_date: "2021-01-01 00:00:00".to_string(),
+            steps: vec![],
+        };
+        let first = db.new_test(details("first")).await.unwrap();
+        let second = db.new_test(details("second")).await.unwrap();
+        let conflict = Some(DbError::Conflict("first".to_string()));
+
+        let err = db.new_test(details("first")).await.unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>().cloned()).is_equal_to(conflict.clone());
+
+        let err = db
+            .clone_test(second.id, "first".to_string())
+            .await
+            .unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>().cloned()).is_equal_to(conflict.clone());
+
+        let mut edited: PatuiTest = second.clone().into();
+        edited.name = "first".to_string();
+        edited.description = "edited description".to_string();
+        let err = db.edit_test(&edited).await.unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>().cloned()).is_equal_to(conflict);
+
+        let unchanged = db.get_test(second.id).await.unwrap();
+        assert_that!(unchanged.name).is_equal_to("second".to_string());
+        assert_that!(unchanged.description).is_equal_to("test description".to_string());
+        assert_that!(db.get_tests().await.unwrap()).has_length(2);
+
+        // Editing a test without renaming it isn't a clash with itself
+        let mut edited: PatuiTest = first.into();
+        edited.description = "edited description".to_string();
+        assert_that!(db.edit_test(&edited).await).is_ok();
+    }
+
+    #[tokio::test]
+    async fn test_create_tables_renames_duplicate_names() {
+        let tmpdir = tempdir().unwrap();
+        let db_path = tmpdir.path().join("test.db");
+
+        // Names weren't unique in older databases
+        let db = Database::new(&db_path).await.unwrap();
+        db.create_tables().await.unwrap();
+        let db_test = Connection::open(&db_path).unwrap();
+        db_test
+            .execute_batch(
+                r#"
+                DROP INDEX idx_test_name;
+                INSERT INTO test (name, desc, creation_date, last_updated)
+                    VALUES ('same', 'first', '2021-01-01', '2021-01-01'),
+                        ('same', 'second', '2021-01-01', '2021-01-01'),
+                        ('other', 'third', '2021-01-01', '2021-01-01');
+                "#,
+            )
+            .unwrap();
+
+        assert_that!(db.create_tables().await).is_ok();
+
+        let names = db
+            .get_tests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|test| (test.description, test.name))
+            .collect::<HashMap<_, _>>();
+        assert_that!(names).is_equal_to(HashMap::from(
+            [
+                ("first", "same"),
+                ("second", "same (2)"),
+                ("third", "other"),
+            ]
+            .map(|(desc, name)| (desc.to_string(), name.to_string())),
+        ));
+
+        let err = db
+            .rename_test(3.into(), "same".to_string())
+            .await
+            .unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>())
+            .is_equal_to(Some(&DbError::Conflict("same".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_get_missing_test() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let err = db.get_test(42.into()).await.unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>())
+            .is_equal_to(Some(&DbError::NotFound(42.into())));
+        assert_that!(err.to_string()).is_equal_to("test 42 not found".to_string());
+    }
+
+    #[tokio::test]
+    async fn test_get_tests_filtered() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        for (name, creation_date) in [
+            ("beta", "2021-01-02 00:00:00"),
+            ("alphabet", "2021-01-03 00:00:00"),
+            ("alpha", "2021-01-01 00:00:00"),
+        ] {
+            db.new_test(PatuiTestDetails {
+                name: name.to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: creation_date.to_string(),
+                steps: vec![],
+            })
+            .await
+            .unwrap();
+        }
+
+        let names = |filter: PatuiTestFilter| {
+            let db = db.clone();
+            async move {
+                db.get_tests_filtered(&filter)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|test| test.name)
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_that!(names(PatuiTestFilter::default()).await)
+            .is_equal_to(["beta", "alphabet", "alpha"].map(String::from).to_vec());
+        assert_that!(
+            names(PatuiTestFilter {
+                order: PatuiTestOrder::Name,
+                ..Default::default()
+            })
+            .await
+        )
+        .is_equal_to(["alpha", "alphabet", "beta"].map(String::from).to_vec());
+        assert_that!(
+            names(PatuiTestFilter {
+                order: PatuiTestOrder::CreationDate,
+                ..Default::default()
+            })
+            .await
+        )
+        .is_equal_to(["alpha", "beta", "alphabet"].map(String::from).to_vec());
+        assert_that!(
+            names(PatuiTestFilter {
+                name_contains: Some("alpha".to_string()),
+                limit: Some(1),
+                order: PatuiTestOrder::Name,
+            })
+            .await
+        )
+        .is_equal_to(vec!["alpha".to_string()]);
+        assert_that!(
+            names(PatuiTestFilter {
+                name_contains: Some("Alpha".to_string()),
+                ..Default::default()
+            })
+            .await
+        )
+        .is_empty();
+    }
+
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let (db, db_test, tmpdir) = setup_db().await;
+
+        db.new_test(PatuiTestDetails {
+            name: "test name".to_string(),
+            description: "test description".to_string(),
+            require_all_steps_produce_output: false,
+            default_timeout_ms: None,
+            creation_date: "2021-01-01 00:00:00".to_string(),
+            steps: vec![],
+        })
+        .await
+        .unwrap();
+
+        // Something else, e.g. the TUI, reading from the database part way through a transaction
+        db_test
+            .execute_batch("BEGIN; SELECT COUNT(*) FROM test;")
+            .unwrap();
+
+        let backup_path = tmpdir.path().join("backup.db");
+        assert_that!(db.backup(&backup_path).await).is_ok();
+
+        db_test.execute_batch("COMMIT;").unwrap();
+
+        let restored = Database::new(&tmpdir.path().join("restored.db"))
+            .await
+            .unwrap();
+        assert_that!(restored.restore(&backup_path).await).is_ok();
+
+        let tests = restored.get_tests().await.unwrap();
+        assert_that!(tests).has_length(1);
+        assert_that!(tests[0].name).is_equal_to("test name".to_string());
+
+        let res = restored.restore(&tmpdir.path().join("missing.db")).await;
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().downcast::<DbError>().unwrap())
+            .is_equal_to(DbError::BackupNotFound(tmpdir.path().join("missing.db")));
+        assert_that!(restored.get_tests().await.unwrap()).has_length(1);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_get_run_output() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test name".to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: "2021-01-01 00:00:00".to_string(),
+                steps: vec![],
+            })
+            .await
+            .unwrap();
+        let instance = db.get_or_new_instance(test).await.unwrap();
+        let run = db.new_run(instance).await.unwrap();
+
+        let output = PatuiRunOutput {
+            run_id: run.id,
+            step_name: "FooFile".to_string(),
+            outputs: vec![
+                PatuiStepData::new(PatuiStepDataFlavour::from("foo".to_string())),
+                PatuiStepData::new(PatuiStepDataFlavour::Bytes(Bytes::from("bar"))),
+            ],
+            dropped: 2,
+        };
+        assert_that!(db.save_run_output(output.clone()).await).is_ok();
+
+        let res = db.get_run_output(run.id, "FooFile").await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_equal_to(output.clone());
+
+        let err = db.get_run_output(run.id, "BarFile").await.unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>()).is_equal_to(Some(&DbError::RunOutputNotFound(
+            run.id,
+            "BarFile".to_string(),
+        )));
+        assert_that!(err.to_string()).is_equal_to(format!(
+            "output of step BarFile in run {} not found",
+            run.id
+        ));
+        assert_that!(db.get_run_output(12345.into(), "FooFile").await).is_err();
+
+        let res = db.get_run_outputs(run.id).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_equal_to(vec![output]);
+        assert_that!(db.get_run_outputs(12345.into()).await.unwrap()).is_empty();
+    }
+
+    #[tokio::test]
+    async fn test_create_tables_adds_missing_columns() {
+        let tmpdir = tempdir().unwrap();
+        let db_path = tmpdir.path().join("test.db");
+
+        // A test saved before `require_all_steps_produce_output` existed
+        let db_test = Connection::open(&db_path).unwrap();
+        db_test
+            .execute_batch(
+                r#"
+                CREATE TABLE test (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    desc TEXT NOT NULL,
+                    creation_date TEXT NOT NULL,
+                    last_updated TEXT NOT NULL,
+                    last_used_date TEXT,
+                    times_used INTEGER NOT NULL DEFAULT 0,
+                    steps BLOB NOT NULL DEFAULT '[]'
+                );
+                INSERT INTO test (name, desc, creation_date, last_updated)
+                    VALUES ('old test', 'old description', '2021-01-01', '2021-01-01');
+                "#,
+            )
+            .unwrap();
+
+        let db = Database::new(&db_path).await.unwrap();
+        assert_that!(db.create_tables().await).is_ok();
+        // Running again finds the columns already there
+        assert_that!(db.create_tables().await).is_ok();
+
+        let test = db.get_test(1.into()).await.unwrap();
+        assert_that!(test.name).is_equal_to("old test".to_string());
+        assert_that!(test.require_all_steps_produce_output).is_false();
+        assert_that!(test.default_timeout_ms).is_none();
+    }
+
+    #[tokio::test]
+    async fn test_default_timeout_saved() {
+        let (db, _db_test, _tmpdir) = setup_db().await;
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test name".to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: Some(1500),
+                creation_date: "2021-01-01 00:00:00".to_string(),
+                steps: vec![],
+            })
+            .await
+            .unwrap();
+        assert_that!(db.get_test(test.id).await.unwrap().default_timeout_ms)
+            .is_equal_to(Some(1500));
+
+        let clone_id = db.clone_test(test.id, "clone".to_string()).await.unwrap();
+        assert_that!(db.get_test(clone_id).await.unwrap().default_timeout_ms)
+            .is_equal_to(Some(1500));
+
+        let mut edited: PatuiTest = test.into();
+        edited.default_timeout_ms = None;
+        db.edit_test(&edited).await.unwrap();
+        assert_that!(db.get_test(edited.id).await.unwrap().default_timeout_ms).is_none();
+    }
+
     // TODO: Update test
 }
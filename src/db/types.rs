@@ -4,16 +4,18 @@
 //! consistent.
 
 use std::{
+    collections::BTreeMap,
     fmt::Display,
     ops::{AddAssign, SubAssign},
+    path::PathBuf,
 };
 
 use eyre::Result;
 use serde::{ser::SerializeStruct, Deserialize, Serialize};
 
 use crate::types::{
-    PatuiRunStatus, PatuiRunStep, PatuiRunStepDisplay, PatuiStep, PatuiTestDetails,
-    PatuiTestEditable,
+    PatuiRunStatus, PatuiRunStep, PatuiRunStepDisplay, PatuiStep, PatuiStepData, PatuiStepDetails,
+    PatuiTestDetails, PatuiTestEditable,
 };
 
 // IDs
@@ -135,6 +137,39 @@ impl Display for PatuiId {
     }
 }
 
+// Errors
+
+/// Errors from the database that callers are expected to handle rather than just report.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DbError {
+    /// No test exists with the given id.
+    NotFound(PatuiTestId),
+    /// Nothing was captured for the step in the given run.
+    RunOutputNotFound(PatuiRunId, String),
+    /// No run exists with the given id.
+    UnknownRun(PatuiRunId),
+    /// Another test already has the given name.
+    Conflict(String),
+    /// There's no backup to restore from at the given path.
+    BackupNotFound(PathBuf),
+}
+
+impl Display for DbError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DbError::NotFound(id) => write!(f, "test {} not found", id),
+            DbError::RunOutputNotFound(id, step_name) => {
+                write!(f, "output of step {} in run {} not found", step_name, id)
+            }
+            DbError::UnknownRun(id) => write!(f, "run {} not found", id),
+            DbError::Conflict(name) => write!(f, "a test named `{}` already exists", name),
+            DbError::BackupNotFound(path) => write!(f, "no backup found at {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
 // Test templates
 
 #[derive(Debug, Clone, PartialEq)]
@@ -142,6 +177,8 @@ pub(crate) struct PatuiTestDb {
     pub(crate) id: PatuiTestId,
     pub(crate) name: String,
     pub(crate) description: String,
+    pub(crate) require_all_steps_produce_output: bool,
+    pub(crate) default_timeout_ms: Option<u64>,
     pub(crate) creation_date: String,
     pub(crate) last_updated: String,
     pub(crate) last_used_date: Option<String>,
@@ -154,6 +191,8 @@ impl From<PatuiTestDb> for PatuiTestDetails {
         PatuiTestDetails {
             name: test.name,
             description: test.description,
+            require_all_steps_produce_output: test.require_all_steps_produce_output,
+            default_timeout_ms: test.default_timeout_ms,
             creation_date: test.creation_date,
             steps: test.steps,
         }
@@ -165,6 +204,8 @@ impl From<&PatuiTestDb> for PatuiTestDetails {
         PatuiTestDetails {
             name: test.name.clone(),
             description: test.description.clone(),
+            require_all_steps_produce_output: test.require_all_steps_produce_output,
+            default_timeout_ms: test.default_timeout_ms,
             creation_date: test.creation_date.clone(),
             steps: test.steps.clone(),
         }
@@ -187,6 +228,8 @@ impl PatuiTestDb {
             id,
             name: details.name,
             description: details.description,
+            require_all_steps_produce_output: details.require_all_steps_produce_output,
+            default_timeout_ms: details.default_timeout_ms,
             creation_date: details.creation_date.clone(),
             last_updated: details.creation_date,
             last_used_date: None,
@@ -234,6 +277,9 @@ pub(crate) struct PatuiTestHashable<'a> {
     pub(crate) id: PatuiTestId,
     pub(crate) name: &'a str,
     pub(crate) description: &'a str,
+    // Left out when unset so tests from before this existed keep the same hash
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) require_all_steps_produce_output: bool,
     pub(crate) steps: Vec<&'a PatuiStep>,
 }
 
@@ -243,6 +289,7 @@ impl<'a> From<&'a PatuiTestDb> for PatuiTestHashable<'a> {
             id: test.id,
             name: &test.name,
             description: &test.description,
+            require_all_steps_produce_output: test.require_all_steps_produce_output,
             steps: test.steps.iter().collect(),
         }
     }
@@ -255,6 +302,45 @@ pub(crate) struct PatuiTestMinDisplay {
     pub(crate) description: String,
 }
 
+/// What order `Database::get_tests_filtered` gives tests in, ties are always broken by id.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) enum PatuiTestOrder {
+    #[default]
+    Id,
+    Name,
+    CreationDate,
+    LastUpdated,
+}
+
+impl PatuiTestOrder {
+    fn column(self) -> &'static str {
+        match self {
+            PatuiTestOrder::Id => "id",
+            PatuiTestOrder::Name => "name",
+            PatuiTestOrder::CreationDate => "creation_date",
+            PatuiTestOrder::LastUpdated => "last_updated",
+        }
+    }
+
+    /// The `ORDER BY` clause for this order.
+    pub(crate) fn order_by(self) -> String {
+        match self {
+            PatuiTestOrder::Id => "ORDER BY id".to_string(),
+            _ => format!("ORDER BY {}, id", self.column()),
+        }
+    }
+}
+
+/// Which tests `Database::get_tests_filtered` returns, the default is all of them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct PatuiTestFilter {
+    /// Only tests with this in their name, case sensitive.
+    pub(crate) name_contains: Option<String>,
+    /// At most this many tests.
+    pub(crate) limit: Option<u32>,
+    pub(crate) order: PatuiTestOrder,
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct PatuiTestStatus {
     pub(crate) id: PatuiTestId,
@@ -272,6 +358,8 @@ pub(crate) struct PatuiInstance {
     pub(crate) hash: i64,
     pub(crate) name: String,
     pub(crate) description: String,
+    #[serde(default)]
+    pub(crate) require_all_steps_produce_output: bool,
     pub(crate) creation_date: String,
     pub(crate) last_updated: String,
     pub(crate) steps: Vec<PatuiStep>,
@@ -285,6 +373,61 @@ pub(crate) struct PatuiRun {
     pub(crate) end_time: Option<String>,
     pub(crate) status: PatuiRunStatus,
     pub(crate) step_run_details: Vec<PatuiRunStep>,
+    #[serde(default)]
+    pub(crate) env: PatuiRunEnv,
+}
+
+/// Where and with what a run happened, kept alongside the run so a failure can be matched up
+/// with a change of environment.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiRunEnv {
+    pub(crate) os: String,
+    pub(crate) arch: String,
+    pub(crate) patui_version: String,
+    /// The plugins used by the run, by the name of the step using them.
+    pub(crate) plugins: BTreeMap<String, PatuiRunEnvPlugin>,
+}
+
+impl PatuiRunEnv {
+    /// The environment a run of `instance` would have right now, plugin versions can't be known
+    /// until the plugins are started.
+    pub(crate) fn new(instance: &PatuiInstance) -> Self {
+        let plugins = instance
+            .steps
+            .iter()
+            .filter_map(|step| match &step.details {
+                PatuiStepDetails::Plugin(plugin) => Some((
+                    step.name.clone(),
+                    PatuiRunEnvPlugin {
+                        path: plugin.path.clone(),
+                        version: None,
+                        config: plugin
+                            .config
+                            .iter()
+                            .map(|(k, v)| (k.clone(), v.to_string()))
+                            .collect(),
+                    },
+                )),
+                _ => None,
+            })
+            .collect();
+
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            patui_version: env!("CARGO_PKG_VERSION").to_string(),
+            plugins,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiRunEnvPlugin {
+    pub(crate) path: String,
+    /// As the plugin reported it when started.
+    pub(crate) version: Option<String>,
+    /// The config the plugin was initialised with.
+    pub(crate) config: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -297,6 +440,16 @@ pub(crate) struct PatuiRunDisplay {
     pub(crate) step_run_details: Vec<PatuiRunStepDisplay>,
 }
 
+/// What a step sent on its output channel during a run, kept when the run captures outputs.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiRunOutput {
+    pub(crate) run_id: PatuiRunId,
+    pub(crate) step_name: String,
+    pub(crate) outputs: Vec<PatuiStepData>,
+    /// How many items were left out of `outputs` for going over the capture limit.
+    pub(crate) dropped: usize,
+}
+
 impl TryFrom<PatuiRun> for PatuiRunDisplay {
     type Error = eyre::Report;
 
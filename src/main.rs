@@ -9,10 +9,17 @@ mod tui;
 mod types;
 mod utils;
 
-use std::{env, fs::create_dir_all, sync::Arc};
+use std::{
+    env, fmt,
+    fs::create_dir_all,
+    panic::Location,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 
 use clap::Parser;
-use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
 use eyre::Result;
 use lazy_static::lazy_static;
 use tracing::{error, info};
@@ -20,7 +27,7 @@ use tracing_subscriber::{
     fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry,
 };
 
-use crate::cli::Cli;
+use crate::cli::{Cli, EXIT_INTERNAL};
 
 lazy_static! {
     /// Various constants used in the root application code
@@ -29,8 +36,13 @@ lazy_static! {
     pub(crate) static ref LOG_ENV: String = format!("{}_LOG", PROJECT_NAME.clone());
     /// Various constants used in the root application code
     pub(crate) static ref LOG_FILE_ENV: String = format!("{}_LOG_FILE", PROJECT_NAME.clone());
+    /// Set to anything but `0` for the same as `--plain-panic`
+    pub(crate) static ref PLAIN_PANIC_ENV: String = format!("{}_PLAIN_PANIC", PROJECT_NAME.clone());
 }
 
+/// Whether panics and errors are reported plainly, plugins started are told to do the same.
+pub(crate) static PLAIN_PANIC: AtomicBool = AtomicBool::new(false);
+
 fn initialise_logging() -> Result<()> {
     let now = chrono::offset::Local::now();
     let filter = match env::var("PATUI_LOG") {
@@ -111,46 +123,126 @@ fn initialise_panic_handler(is_tui: bool) -> Result<()> {
                 .create_panic_handler()(panic_info);
         }
 
-        std::process::exit(libc::EXIT_FAILURE);
+        std::process::exit(EXIT_INTERNAL);
     }));
 
     Ok(())
 }
 
-async fn do_main() -> Result<()> {
-    info!("Starting Patui");
+/// Reports errors on a single line with where they were raised, see `initialise_plain_panic_handler`.
+#[derive(Debug, Default)]
+struct PlainEyreHandler {
+    location: Option<&'static Location<'static>>,
+}
 
-    let args = Cli::parse();
-    if args.subcommand.is_some() {
-        initialise_panic_handler(false)?;
-    } else {
-        initialise_panic_handler(true)?;
+impl eyre::EyreHandler for PlainEyreHandler {
+    fn debug(
+        &self,
+        error: &(dyn std::error::Error + 'static),
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}", error)?;
+
+        let mut source = error.source();
+        while let Some(cause) = source {
+            write!(f, ": {}", cause)?;
+            source = cause.source();
+        }
+
+        if let Some(location) = self.location {
+            write!(f, " at {}:{}", location.file(), location.line())?;
+        }
+
+        Ok(())
+    }
+
+    fn track_caller(&mut self, location: &'static Location<'static>) {
+        self.location = Some(location);
     }
+}
+
+/// As `initialise_panic_handler` but panics and errors are a single line with their location and
+/// nothing more, for CI logs and other places nobody's reading colours or backtraces.
+fn initialise_plain_panic_handler(is_tui: bool) -> Result<()> {
+    eyre::set_hook(Box::new(|_| Box::new(PlainEyreHandler::default())))?;
 
-    let strategy = choose_app_strategy(AppStrategyArgs {
-        top_level_domain: "rs".to_string(),
-        author: "strottos".to_string(),
-        app_name: "patui".to_string(),
-    })?;
+    std::panic::set_hook(Box::new(move |panic_info| {
+        if is_tui {
+            if let Err(r) = crate::tui::exit() {
+                error!("Unable to exit Terminal: {:?}", r);
+            }
+        }
 
-    let db_path = match args.db.map(|x| x.into()) {
+        let payload = panic_info.payload();
+        let msg = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(|s| &s[..]))
+            .unwrap_or("Box<dyn Any>");
+        let location = panic_info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_else(|| "unknown location".to_string());
+
+        eprintln!("Panic: {} at {}", msg, location);
+        error!("Panic: {} at {}", msg, location);
+
+        std::process::exit(EXIT_INTERNAL);
+    }));
+
+    Ok(())
+}
+
+/// Open the database at `path`, or `patui.db` in the data directory if not given.
+async fn open_db(path: Option<String>) -> Result<Arc<db::Database>> {
+    let path = match path.map(|x| x.into()) {
         Some(path) => path,
         None => {
-            let mut path = strategy.data_dir();
+            let mut path = utils::get_data_dir()?;
             create_dir_all(&path)?;
             path.push("patui.db");
             path
         }
     };
 
-    let db = Arc::new(db::Database::new(&db_path).await?);
+    Ok(Arc::new(db::Database::new(&path).await?))
+}
+
+async fn do_main() -> Result<()> {
+    info!("Starting Patui");
+
+    let args = Cli::parse();
+    let is_tui = args.subcommand.is_none();
+    let is_plain_panic =
+        args.plain_panic || env::var(&*PLAIN_PANIC_ENV).is_ok_and(|value| value != "0");
+    if is_plain_panic {
+        PLAIN_PANIC.store(true, Ordering::Relaxed);
+        initialise_plain_panic_handler(is_tui)?;
+    } else {
+        initialise_panic_handler(is_tui)?;
+    }
 
     if let Some(subcommand) = args.subcommand {
+        let db = if subcommand.uses_db() {
+            Some(open_db(args.db).await?)
+        } else {
+            None
+        };
         subcommand.handle(db).await?;
     } else {
+        let db = open_db(args.db).await?;
+
         // TUI time
         let mut app = tui::App::new(db)?;
-        app.run().await?;
+        if let Some(key_timeout) = args.key_timeout {
+            app.set_key_timeout(key_timeout);
+        }
+        if args.ascii || !tui::supports_unicode() {
+            app.set_ascii(true);
+        }
+        // Test runs started from the TUI hold on to their steps across awaits so can't be moved
+        // between threads, they're run alongside the app instead
+        tokio::task::LocalSet::new().run_until(app.run()).await?;
     }
 
     Ok(())
@@ -160,5 +252,11 @@ async fn do_main() -> Result<()> {
 async fn main() -> Result<()> {
     initialise_logging()?;
 
-    do_main().await
+    // Reported as returning the error from `main` would be but with our own exit code
+    if let Err(e) = do_main().await {
+        eprintln!("Error: {:?}", e);
+        std::process::exit(EXIT_INTERNAL);
+    }
+
+    Ok(())
 }
@@ -1,20 +1,42 @@
+mod capture;
 mod steps;
 
-use std::sync::{Arc, Mutex};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use crate::{db::PatuiRun, types::PatuiRunStatus};
+use crate::{
+    db::{Database, PatuiRun, PatuiRunOutput},
+    types::{
+        PatuiEvent, PatuiEventKind, PatuiRunError, PatuiRunStatus, PatuiRunStep,
+        PatuiRunStepResult, PatuiStepOutcome, DEFAULT_CHANNEL,
+    },
+    utils::get_current_time_string,
+};
 
-use eyre::Result;
+use eyre::{eyre, Result};
+use futures::{stream::FuturesUnordered, StreamExt};
 use indexmap::IndexMap;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 
-use self::steps::PatuiStepRunner;
+pub(crate) use self::capture::DEFAULT_CAPTURE_LIMIT;
+pub(crate) use self::steps::{eval, find_plugins, EvalResult, PluginPool};
+use self::{capture::OutputCapture, steps::PatuiStepRunner};
 
 pub(crate) struct TestRunner {
     pub(crate) run: PatuiRun,
 
     pub(crate) steps: IndexMap<String, Vec<Arc<Mutex<PatuiStepRunner>>>>,
     // results: Vec<PatuiEvent>,
+    /// Cancel whatever's left of the run as soon as a step fails.
+    fail_fast: bool,
+    /// Where to save what each step outputs and how much of it to keep.
+    capture: Option<(Arc<Database>, usize)>,
+    /// What the steps are outputting while the run's going, if anything needs to know.
+    outputs: Option<OutputCapture>,
+    /// Where to pass on the events of the run as they happen.
+    events: Option<mpsc::UnboundedSender<PatuiEvent>>,
 }
 
 impl TestRunner {
@@ -31,42 +53,286 @@ impl TestRunner {
             run,
             steps,
             // results: vec![],
+            fail_fast: false,
+            capture: None,
+            outputs: None,
+            events: None,
         }
     }
 
-    pub(crate) async fn run_test(mut self) -> Result<PatuiRun> {
-        let (tx, mut rx) = mpsc::channel(100);
+    /// Stop the run at the first step that fails, leaving any steps not yet finished without an
+    /// outcome, rather than waiting for every step to finish.
+    pub(crate) fn set_fail_fast(&mut self, fail_fast: bool) {
+        self.fail_fast = fail_fast;
+    }
 
-        self.init_test().await?;
+    /// Save up to `limit` of what each step outputs to `db` once the run's finished, for looking
+    /// at afterwards.
+    pub(crate) fn set_capture(&mut self, db: Arc<Database>, limit: usize) {
+        self.capture = Some((db, limit));
+    }
 
-        for (_, step_collection) in self.steps.iter() {
-            for step in step_collection {
-                let mut step = step.lock().unwrap();
-                step.run(tx.clone())?;
+    /// Send every event of the run on to `events` as well as it happens, e.g. to follow the run's
+    /// progress.
+    pub(crate) fn set_events(&mut self, events: mpsc::UnboundedSender<PatuiEvent>) {
+        self.events = Some(events);
+    }
+
+    /// Keep how the assertion `step_name` evaluated, sub-expression by sub-expression, should it
+    /// fail.
+    pub(crate) fn set_explain_failure(&mut self, step_name: &str) -> Result<()> {
+        let steps = self
+            .steps
+            .get(step_name)
+            .ok_or_else(|| eyre!("No step `{}` to explain", step_name))?;
+
+        for step in steps {
+            if !step.lock().unwrap().set_explain(true) {
+                return Err(eyre!("Step `{}` isn't an assertion to explain", step_name));
             }
         }
 
-        drop(tx);
+        Ok(())
+    }
+
+    /// As `new` but plugins are taken from and returned to `pool` rather than being launched
+    /// and killed for this run alone.
+    pub(crate) fn new_with_plugin_pool(run: PatuiRun, pool: &PluginPool) -> Self {
+        let runner = Self::new(run);
+
+        for step in runner.steps.values().flatten() {
+            step.lock().unwrap().set_plugin_pool(pool);
+        }
+
+        runner
+    }
+
+    pub(crate) async fn run_test(self) -> Result<PatuiRun> {
+        self.run_test_until(None).await
+    }
+
+    /// Run the test as `run_test` does but give up once `limit` has passed, cancelling any steps
+    /// still going and marking the run as timed out. Steps that finished keep how they went and
+    /// the rest are failed for timing out.
+    pub(crate) async fn run_test_with_timeout(self, limit: Duration) -> Result<PatuiRun> {
+        self.run_test_until(Some(limit)).await
+    }
+
+    async fn run_test_until(mut self, limit: Option<Duration>) -> Result<PatuiRun> {
+        let (tx, mut rx) = mpsc::channel::<PatuiEvent>(100);
+        let (timed_out_tx, mut timed_out_rx) = oneshot::channel::<Duration>();
+        let events = self.events.take();
 
         let receive_task = tokio::spawn(async move {
-            while let Some(res) = rx.recv().await {
-                tracing::trace!("Received result: {:?}", res);
+            let mut step_run_details: Vec<PatuiRunStep> = vec![];
+
+            loop {
+                let res = tokio::select! {
+                    res = rx.recv() => res,
+                    // Steps cancelled for taking too long needn't have let go of their senders
+                    // yet, so what's left is finished off here rather than waiting on them
+                    Ok(limit) = &mut timed_out_rx => {
+                        while let Ok(res) = rx.try_recv() {
+                            record_event(&mut step_run_details, &events, res);
+                        }
+
+                        let unfinished = step_run_details
+                            .iter()
+                            .filter(|step_run| step_run.end_time.is_none())
+                            .map(|step_run| step_run.name.clone())
+                            .collect::<Vec<_>>();
+                        for name in unfinished {
+                            let event = PatuiEvent::step_finished(
+                                name.clone(),
+                                PatuiStepOutcome::Failed(format!(
+                                    "Step `{}` timed out after {:?}",
+                                    name, limit
+                                )),
+                            );
+                            record_event(&mut step_run_details, &events, event);
+                        }
+                        break;
+                    }
+                };
+                let Some(res) = res else {
+                    break;
+                };
+
+                record_event(&mut step_run_details, &events, res);
             }
+
+            step_run_details
         });
 
-        for (_, step_collection) in self.steps.iter() {
+        // Steps are given clones of `tx` so the events don't end before a timeout's been recorded
+        let res = match limit {
+            Some(limit) => match tokio::time::timeout(limit, self.run_steps(tx.clone())).await {
+                Ok(res) => res,
+                Err(_) => {
+                    tracing::warn!("Run {} timed out after {:?}", self.run.id, limit);
+
+                    for step in self.steps.values().flatten() {
+                        step.lock().unwrap().cancel();
+                    }
+                    let _ = timed_out_tx.send(limit);
+
+                    self.run.step_run_details = receive_task.await?;
+                    self.run.end_time = Some(get_current_time_string());
+                    self.run.status = PatuiRunStatus::Error(PatuiRunError::Timeout(limit));
+
+                    return Ok(self.run);
+                }
+            },
+            None => self.run_steps(tx.clone()).await,
+        };
+
+        drop(tx);
+        self.run.step_run_details = receive_task.await?;
+
+        for (name, step_collection) in self.steps.iter() {
             for step in step_collection {
-                step.lock().unwrap().wait().await?;
+                let version = step.lock().unwrap().plugin_version();
+                if let (Some(version), Some(plugin)) = (version, self.run.env.plugins.get_mut(name))
+                {
+                    plugin.version = Some(version);
+                }
+
+                let explanation = step.lock().unwrap().explanation();
+                if let (Some(explanation), Some(step_run)) = (
+                    explanation,
+                    self.run
+                        .step_run_details
+                        .iter_mut()
+                        .find(|step_run| step_run.name == *name),
+                ) {
+                    step_run.result.explanation = Some(explanation);
+                }
             }
         }
 
-        receive_task.await?;
+        if let Some(outputs) = self.outputs.take() {
+            let outputs = outputs.finish(self.run.id).await?;
 
-        self.run.status = PatuiRunStatus::Passed;
+            if self.run.instance.require_all_steps_produce_output {
+                self.fail_steps_without_output(&outputs);
+            }
+
+            if let Some((db, _)) = &self.capture {
+                for output in outputs {
+                    db.save_run_output(output).await?;
+                }
+            }
+        }
+
+        res?;
+
+        // A failed step is a result of the run rather than an error running it
+        self.run.status = self
+            .run
+            .step_run_details
+            .iter()
+            .map(|step_run| &step_run.result.status)
+            .find(|status| matches!(status, PatuiRunStatus::Error(_)))
+            .cloned()
+            .unwrap_or(PatuiRunStatus::Passed);
 
         Ok(self.run)
     }
 
+    /// Initialise, run and wait for all the steps, sending step lifecycle events to `tx` along
+    /// with anything the steps send themselves. Steps failing are reported as events rather than
+    /// returned as errors.
+    async fn run_steps(&mut self, tx: mpsc::Sender<PatuiEvent>) -> Result<()> {
+        self.init_test().await?;
+
+        if self.capture.is_some() || self.run.instance.require_all_steps_produce_output {
+            // Without anything to save the outputs are only counted
+            let limit = self.capture.as_ref().map_or(0, |(_, limit)| *limit);
+            let mut outputs = OutputCapture::new(limit);
+
+            for (name, step_collection) in self.steps.iter() {
+                for step in step_collection {
+                    // Not every step has an output to keep, e.g. assertions
+                    match step.lock().unwrap().subscribe(name, DEFAULT_CHANNEL).await {
+                        Ok(rx) => outputs.capture(name.clone(), rx),
+                        Err(e) => tracing::debug!("Not capturing output of {}: {}", name, e),
+                    }
+                }
+            }
+
+            self.outputs = Some(outputs);
+        }
+
+        for (name, step_collection) in self.steps.iter() {
+            for step in step_collection {
+                tx.send(PatuiEvent::step_started(name.clone())).await?;
+                let mut step = step.lock().unwrap();
+                step.run(tx.clone())?;
+            }
+        }
+
+        // Steps are waited on together and reported as they finish, so a failing step isn't held
+        // up behind others still going
+        let mut waits = self
+            .steps
+            .iter()
+            .flat_map(|(name, step_collection)| {
+                step_collection.iter().map(move |step| (name, step))
+            })
+            .map(|(name, step)| async move {
+                let res = step.lock().unwrap().wait().await;
+                (name, step, res)
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        while let Some((name, step, res)) = waits.next().await {
+            let outcome = match &res {
+                Ok(_) => PatuiStepOutcome::Passed,
+                Err(e) => {
+                    // Drop anything the step left behind so steps reading from it can finish
+                    step.lock().unwrap().cancel();
+                    PatuiStepOutcome::Failed(e.to_string())
+                }
+            };
+            let is_failed = matches!(outcome, PatuiStepOutcome::Failed(_));
+            tx.send(PatuiEvent::step_finished(name.clone(), outcome))
+                .await?;
+
+            if is_failed && self.fail_fast {
+                tracing::debug!("Step {} failed, cancelling the rest of the run", name);
+                // The steps still being waited on are locked until their waits are dropped
+                drop(waits);
+                for step in self.steps.values().flatten() {
+                    step.lock().unwrap().cancel();
+                }
+                return Ok(());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Mark any step that passed without publishing anything as failed.
+    fn fail_steps_without_output(&mut self, outputs: &[PatuiRunOutput]) {
+        for output in outputs {
+            if !output.outputs.is_empty() || output.dropped > 0 {
+                continue;
+            }
+
+            for step_run in self.run.step_run_details.iter_mut() {
+                if step_run.name == output.step_name
+                    && step_run.result.status == PatuiRunStatus::Passed
+                {
+                    step_run.result.status = PatuiStepOutcome::Failed(format!(
+                        "Step `{}` finished without producing any output",
+                        output.step_name
+                    ))
+                    .into();
+                }
+            }
+        }
+    }
+
     async fn init_test(&mut self) -> Result<()> {
         for (name, step_collection) in self.steps.iter() {
             for step in step_collection {
@@ -89,6 +355,43 @@ impl TestRunner {
     }
 }
 
+/// Pass `event` on to `events` and keep track of how each step's going from it in
+/// `step_run_details`.
+fn record_event(
+    step_run_details: &mut Vec<PatuiRunStep>,
+    events: &Option<mpsc::UnboundedSender<PatuiEvent>>,
+    event: PatuiEvent,
+) {
+    tracing::trace!("Received result: {:?}", event);
+
+    // Whoever's following along going away shouldn't stop the run
+    if let Some(events) = events {
+        let _ = events.send(event.clone());
+    }
+
+    match event.value() {
+        PatuiEventKind::StepStarted { name } => step_run_details.push(PatuiRunStep {
+            name: name.clone(),
+            start_time: get_current_time_string(),
+            end_time: None,
+            result: PatuiRunStepResult {
+                status: PatuiRunStatus::Pending,
+                explanation: None,
+            },
+        }),
+        PatuiEventKind::StepFinished { name, outcome } => {
+            if let Some(step_run) = step_run_details
+                .iter_mut()
+                .find(|step_run| step_run.name == *name && step_run.end_time.is_none())
+            {
+                step_run.end_time = Some(get_current_time_string());
+                step_run.result.status = outcome.clone().into();
+            }
+        }
+        PatuiEventKind::Bytes(_) | PatuiEventKind::Diagnostic { .. } => {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
@@ -98,10 +401,11 @@ mod tests {
     use tracing_test::traced_test;
 
     use crate::{
-        db::PatuiInstance,
+        db::{PatuiInstance, PatuiRunEnv},
         types::{
-            PatuiStep, PatuiStepAssertion, PatuiStepDetails, PatuiStepRead,
-            PatuiStepTransformStream, PatuiStepTransformStreamFlavour,
+            PatuiStep, PatuiStepAssertion, PatuiStepDataFlavour, PatuiStepDetails, PatuiStepRead,
+            PatuiStepSender, PatuiStepTransformStream, PatuiStepTransformStreamFlavour,
+            PatuiTestDetails,
         },
     };
 
@@ -120,6 +424,7 @@ mod tests {
                 hash: 123,
                 name: "test".to_string(),
                 description: "test".to_string(),
+                require_all_steps_produce_output: false,
                 creation_date: now.clone(),
                 last_updated: now.clone(),
                 steps: vec![
@@ -127,6 +432,7 @@ mod tests {
                         name: "FooFile".to_string(),
                         when: None,
                         depends_on: vec![],
+                        timeout_ms: None,
                         details: PatuiStepDetails::Read(PatuiStepRead {
                             r#in: "\"tests/data/test.json\"".try_into().unwrap(),
                         }),
@@ -135,6 +441,7 @@ mod tests {
                         name: "FooTransform".to_string(),
                         when: None,
                         depends_on: vec![],
+                        timeout_ms: None,
                         details: PatuiStepDetails::TransformStream(PatuiStepTransformStream {
                             flavour: PatuiStepTransformStreamFlavour::Json,
                             r#in: "steps.FooFile.out".try_into().unwrap(),
@@ -144,8 +451,10 @@ mod tests {
                         name: "FooAssertion".to_string(),
                         when: None,
                         depends_on: vec![],
+                        timeout_ms: None,
                         details: PatuiStepDetails::Assertion(PatuiStepAssertion {
                             expr: "steps.FooTransform.out.len() == 1".try_into().unwrap(),
+                            sample: None,
                         }),
                     },
                     // PatuiStep {
@@ -170,6 +479,7 @@ mod tests {
             end_time: None,
             status: PatuiRunStatus::Pending,
             step_run_details: vec![],
+            env: PatuiRunEnv::default(),
         });
 
         let test_run = timeout(Duration::from_secs(5), test_runner.run_test()).await;
@@ -180,4 +490,456 @@ mod tests {
 
         assert_that!(&test_run.status).is_equal_to(&PatuiRunStatus::Passed);
     }
+
+    /// A pending run of a test made up of `steps`.
+    fn run_of(steps: Vec<PatuiStep>) -> PatuiRun {
+        let now = crate::utils::get_current_time_string();
+
+        PatuiRun {
+            id: 1.into(),
+            instance: PatuiInstance {
+                id: 1.into(),
+                test_id: 1.into(),
+                hash: 123,
+                name: "test".to_string(),
+                description: "test".to_string(),
+                require_all_steps_produce_output: false,
+                creation_date: now.clone(),
+                last_updated: now.clone(),
+                steps,
+            },
+            start_time: now,
+            end_time: None,
+            status: PatuiRunStatus::Pending,
+            step_run_details: vec![],
+            env: PatuiRunEnv::default(),
+        }
+    }
+
+    fn step(name: &str, details: PatuiStepDetails) -> PatuiStep {
+        PatuiStep {
+            name: name.to_string(),
+            when: None,
+            depends_on: vec![],
+            timeout_ms: None,
+            details,
+        }
+    }
+
+    fn assertion_step(name: &str, expr: &str) -> PatuiStep {
+        step(
+            name,
+            PatuiStepDetails::Assertion(PatuiStepAssertion {
+                expr: expr.try_into().unwrap(),
+                sample: None,
+            }),
+        )
+    }
+
+    fn two_step_run() -> PatuiRun {
+        run_of(vec![
+            step(
+                "FooFile",
+                PatuiStepDetails::Read(PatuiStepRead {
+                    r#in: "\"tests/data/test.json\"".try_into().unwrap(),
+                }),
+            ),
+            step(
+                "FooTransform",
+                PatuiStepDetails::TransformStream(PatuiStepTransformStream {
+                    flavour: PatuiStepTransformStreamFlavour::Json,
+                    r#in: "steps.FooFile.out".try_into().unwrap(),
+                }),
+            ),
+        ])
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_indexed_assertion() {
+        let mut run = two_step_run();
+        run.instance.steps.push(assertion_step(
+            "FooAssertion",
+            "steps.FooTransform.out[0].baz[2] == 3",
+        ));
+
+        let test_run = timeout(Duration::from_secs(5), TestRunner::new(run).run_test()).await;
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap().unwrap();
+
+        assert_that!(&test_run.status).is_equal_to(&PatuiRunStatus::Passed);
+    }
+
+    #[cfg(unix)]
+    #[traced_test]
+    #[tokio::test]
+    async fn run_assertion_settled_before_its_source_finishes() {
+        use tokio::io::AsyncWriteExt;
+
+        // Read from a pipe so the rest of what's read comes after the assertion's settled by the
+        // first chunk and stopped listening
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("pipe");
+        let status = std::process::Command::new("mkfifo")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert_that!(status.success()).is_true();
+
+        let run = run_of(vec![
+            step(
+                "Pipe",
+                PatuiStepDetails::Read(PatuiStepRead {
+                    r#in: format!("\"{}\"", path.display())
+                        .as_str()
+                        .try_into()
+                        .unwrap(),
+                }),
+            ),
+            assertion_step("FirstChunk", "steps.Pipe.out[0] == b\"first\""),
+        ]);
+
+        let write = async {
+            let mut pipe = tokio::fs::OpenOptions::new()
+                .write(true)
+                .open(&path)
+                .await
+                .unwrap();
+            pipe.write_all(b"first").await.unwrap();
+            pipe.flush().await.unwrap();
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            for _ in 0..3 {
+                pipe.write_all(b"more").await.unwrap();
+                pipe.flush().await.unwrap();
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        };
+
+        let test_run = timeout(Duration::from_secs(5), async {
+            tokio::join!(TestRunner::new(run).run_test(), write).0
+        })
+        .await;
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap().unwrap();
+
+        // Both steps ran to the end without error
+        assert_that!(test_run.step_run_details.len()).is_equal_to(2);
+        assert_that!(&test_run.status).is_equal_to(&PatuiRunStatus::Passed);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_lifecycle_events() {
+        let mut test_runner = TestRunner::new(two_step_run());
+
+        let (tx, mut rx) = mpsc::channel(100);
+        let res = timeout(Duration::from_secs(5), test_runner.run_steps(tx)).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+
+        let mut lifecycle_events = vec![];
+        while let Some(event) = rx.recv().await {
+            if !matches!(event.value(), PatuiEventKind::Bytes(_)) {
+                lifecycle_events.push(event.value().clone());
+            }
+        }
+
+        assert_that!(lifecycle_events).is_equal_to(vec![
+            PatuiEventKind::StepStarted {
+                name: "FooFile".to_string(),
+            },
+            PatuiEventKind::StepStarted {
+                name: "FooTransform".to_string(),
+            },
+            PatuiEventKind::StepFinished {
+                name: "FooFile".to_string(),
+                outcome: PatuiStepOutcome::Passed,
+            },
+            PatuiEventKind::StepFinished {
+                name: "FooTransform".to_string(),
+                outcome: PatuiStepOutcome::Passed,
+            },
+        ]);
+
+        let test_run = timeout(
+            Duration::from_secs(5),
+            TestRunner::new(two_step_run()).run_test(),
+        )
+        .await;
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap().unwrap();
+
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .map(|step_run| (&step_run.name[..], &step_run.result.status))
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![
+            ("FooFile", &PatuiRunStatus::Passed),
+            ("FooTransform", &PatuiRunStatus::Passed),
+        ]);
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .all(|step_run| step_run.end_time.is_some()))
+        .is_true();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_capture_outputs() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::new(&tmpdir.path().join("test.db")).await.unwrap());
+        db.create_tables().await.unwrap();
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test".to_string(),
+                description: "test".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: crate::utils::get_current_time_string(),
+                steps: two_step_run().instance.steps,
+            })
+            .await
+            .unwrap();
+        let instance = db.get_or_new_instance(test).await.unwrap();
+
+        let run = db.new_run(instance.clone()).await.unwrap();
+        let run_id = run.id;
+        let mut test_runner = TestRunner::new(run);
+        test_runner.set_capture(db.clone(), DEFAULT_CAPTURE_LIMIT);
+        let test_run = timeout(Duration::from_secs(5), test_runner.run_test()).await;
+        assert_that!(test_run).is_ok();
+        assert_that!(test_run.unwrap()).is_ok();
+
+        let file_output = db.get_run_output(run_id, "FooFile").await.unwrap();
+        let file_bytes = file_output
+            .outputs
+            .iter()
+            .flat_map(|output| output.data().as_bytes().unwrap().to_vec())
+            .collect::<Vec<_>>();
+        assert_that!(file_bytes).is_equal_to(std::fs::read("tests/data/test.json").unwrap());
+        assert_that!(file_output.dropped).is_equal_to(0);
+
+        let transform_output = db.get_run_output(run_id, "FooTransform").await.unwrap();
+        assert_that!(transform_output.outputs.len()).is_equal_to(1);
+        assert_that!(matches!(
+            transform_output.outputs[0].data(),
+            PatuiStepDataFlavour::Map(_)
+        ))
+        .is_true();
+
+        // Only what fits in the limit is kept
+        let run = db.new_run(instance).await.unwrap();
+        let run_id = run.id;
+        let mut test_runner = TestRunner::new(run);
+        test_runner.set_capture(db.clone(), 0);
+        let test_run = timeout(Duration::from_secs(5), test_runner.run_test()).await;
+        assert_that!(test_run.unwrap()).is_ok();
+
+        let transform_output = db.get_run_output(run_id, "FooTransform").await.unwrap();
+        assert_that!(transform_output.outputs.len()).is_equal_to(0);
+        assert_that!(transform_output.dropped).is_equal_to(1);
+    }
+
+    fn read_run(path: &str, require_all_steps_produce_output: bool) -> PatuiRun {
+        let mut run = run_of(vec![step(
+            "FooFile",
+            PatuiStepDetails::Read(PatuiStepRead {
+                r#in: format!("{:?}", path).as_str().try_into().unwrap(),
+            }),
+        )]);
+        run.instance.require_all_steps_produce_output = require_all_steps_produce_output;
+        run
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_require_all_steps_produce_output() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let empty_path = tmpdir.path().join("empty.txt");
+        std::fs::write(&empty_path, "").unwrap();
+        let empty_path = empty_path.to_str().unwrap();
+
+        for (path, require_output, expected) in [
+            ("tests/data/test.json", true, PatuiRunStatus::Passed),
+            (empty_path, false, PatuiRunStatus::Passed),
+            (
+                empty_path,
+                true,
+                PatuiRunStatus::Error(PatuiRunError::StepFailed(
+                    "Step `FooFile` finished without producing any output".to_string(),
+                )),
+            ),
+        ] {
+            let test_runner = TestRunner::new(read_run(path, require_output));
+            let test_run = timeout(Duration::from_secs(5), test_runner.run_test()).await;
+            assert_that!(test_run).is_ok();
+            let test_run = test_run.unwrap().unwrap();
+
+            assert_that!(test_run.status).is_equal_to(expected.clone());
+            assert_that!(test_run.step_run_details[0].result.status).is_equal_to(expected);
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_with_timeout() {
+        // Each step waits on the other so this run never finishes by itself
+        let mut test_runner = TestRunner::new(run_of(vec![
+            step(
+                "Ping",
+                PatuiStepDetails::Read(PatuiStepRead {
+                    r#in: "steps.Pong.out".try_into().unwrap(),
+                }),
+            ),
+            step(
+                "Pong",
+                PatuiStepDetails::Read(PatuiStepRead {
+                    r#in: "steps.Ping.out".try_into().unwrap(),
+                }),
+            ),
+            assertion_step("Quick", "1 == 1"),
+        ]));
+        let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+        test_runner.set_events(events_tx);
+
+        let start = std::time::Instant::now();
+        let test_run = timeout(
+            Duration::from_secs(5),
+            test_runner.run_test_with_timeout(Duration::from_millis(200)),
+        )
+        .await;
+        let elapsed = start.elapsed();
+
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap();
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap();
+
+        assert_that!(&test_run.status).is_equal_to(&PatuiRunStatus::Error(PatuiRunError::Timeout(
+            Duration::from_millis(200),
+        )));
+        assert_that!(test_run.end_time).is_some();
+        assert_that!(elapsed).is_at_least(Duration::from_millis(200));
+        assert_that!(elapsed).is_less_than(Duration::from_secs(2));
+
+        // The step that finished in time keeps how it went
+        let timed_out = |name| {
+            PatuiRunStatus::Error(PatuiRunError::StepFailed(format!(
+                "Step `{}` timed out after 200ms",
+                name
+            )))
+        };
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .map(|step_run| (step_run.name.as_str(), step_run.result.status.clone()))
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![
+            ("Ping", timed_out("Ping")),
+            ("Pong", timed_out("Pong")),
+            ("Quick", PatuiRunStatus::Passed),
+        ]);
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .all(|step_run| step_run.end_time.is_some()))
+        .is_true();
+
+        let mut failed = vec![];
+        while let Ok(event) = events_rx.try_recv() {
+            if event.is_failure() {
+                failed.push(event.step_name().to_string());
+            }
+        }
+        assert_that!(failed).is_equal_to(vec!["Ping".to_string(), "Pong".to_string()]);
+    }
+
+    fn two_failing_assertions_run() -> PatuiRun {
+        run_of(
+            ["First", "Second"]
+                .into_iter()
+                .map(|name| assertion_step(name, "\"a\" == \"b\""))
+                .collect(),
+        )
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_fail_fast_past_a_step_still_going() {
+        // Sending takes at least 10s, far longer than the assertion after it takes to fail
+        let slow = std::iter::repeat_n("b\"x\"", 10_000)
+            .collect::<Vec<_>>()
+            .join(", ");
+        let run = run_of(vec![
+            step(
+                "Slow",
+                PatuiStepDetails::Sender(PatuiStepSender {
+                    expr: format!("[{}]", slow).as_str().try_into().unwrap(),
+                }),
+            ),
+            assertion_step("Failing", "\"a\" == \"b\""),
+        ]);
+
+        let mut test_runner = TestRunner::new(run);
+        test_runner.set_fail_fast(true);
+        let test_run = timeout(Duration::from_secs(5), test_runner.run_test()).await;
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap().unwrap();
+
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .map(|step_run| step_run.result.status.clone())
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![
+            PatuiRunStatus::Pending,
+            PatuiRunStatus::Error(PatuiRunError::StepFailed(
+                "Assertion `Failing` failed: \"a\" == \"b\"".to_string(),
+            )),
+        ]);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn run_failing_steps() {
+        let failed = |name: &str| {
+            PatuiRunStatus::Error(PatuiRunError::StepFailed(format!(
+                "Assertion `{}` failed: \"a\" == \"b\"",
+                name
+            )))
+        };
+
+        let test_run = timeout(
+            Duration::from_secs(5),
+            TestRunner::new(two_failing_assertions_run()).run_test(),
+        )
+        .await;
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap().unwrap();
+
+        assert_that!(&test_run.status).is_equal_to(&failed("First"));
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .map(|step_run| step_run.result.status.clone())
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![failed("First"), failed("Second")]);
+
+        let mut test_runner = TestRunner::new(two_failing_assertions_run());
+        test_runner.set_fail_fast(true);
+        let test_run = timeout(Duration::from_secs(5), test_runner.run_test()).await;
+        assert_that!(test_run).is_ok();
+        let test_run = test_run.unwrap().unwrap();
+
+        assert_that!(&test_run.status).is_equal_to(&failed("First"));
+        assert_that!(test_run
+            .step_run_details
+            .iter()
+            .map(|step_run| step_run.result.status.clone())
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![failed("First"), PatuiRunStatus::Pending]);
+    }
 }
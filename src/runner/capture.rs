@@ -0,0 +1,148 @@
+use eyre::Result;
+use indexmap::IndexMap;
+use tokio::{
+    sync::{
+        broadcast::{
+            self,
+            error::{RecvError, TryRecvError},
+        },
+        oneshot,
+    },
+    task::JoinHandle,
+};
+
+use crate::{
+    db::{PatuiRunId, PatuiRunOutput},
+    types::PatuiStepData,
+};
+
+/// How much of each step's output is kept by default, in terms of
+/// `PatuiStepDataFlavour::estimated_size`.
+pub(crate) const DEFAULT_CAPTURE_LIMIT: usize = 1024 * 1024;
+
+/// Collects what steps send on their output channels during a run. Anything a step sends past
+/// `limit` is counted but not kept, so a limit of 0 just counts.
+#[derive(Debug)]
+pub(crate) struct OutputCapture {
+    limit: usize,
+    tasks: Vec<(String, oneshot::Sender<()>, JoinHandle<CapturedOutput>)>,
+}
+
+impl OutputCapture {
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            tasks: vec![],
+        }
+    }
+
+    /// Start keeping what's received on `rx` as output of `step_name`.
+    pub(crate) fn capture(
+        &mut self,
+        step_name: String,
+        mut rx: broadcast::Receiver<PatuiStepData>,
+    ) {
+        let (done_tx, mut done_rx) = oneshot::channel::<()>();
+        let limit = self.limit;
+
+        let task = tokio::spawn(async move {
+            let mut captured = CapturedOutput::default();
+
+            loop {
+                tokio::select! {
+                    res = rx.recv() => match res {
+                        Ok(data) => captured.push(data, limit),
+                        Err(RecvError::Lagged(count)) => captured.dropped += count as usize,
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = &mut done_rx => {
+                        // The step's finished so anything it sent is already waiting here
+                        loop {
+                            match rx.try_recv() {
+                                Ok(data) => captured.push(data, limit),
+                                Err(TryRecvError::Lagged(count)) => {
+                                    captured.dropped += count as usize
+                                }
+                                Err(_) => break,
+                            }
+                        }
+                        break;
+                    }
+                }
+            }
+
+            captured
+        });
+
+        self.tasks.push((step_name, done_tx, task));
+    }
+
+    /// Stop capturing and return everything captured as output of `run_id`, steps sharing a name
+    /// are put together.
+    pub(crate) async fn finish(self, run_id: PatuiRunId) -> Result<Vec<PatuiRunOutput>> {
+        let mut outputs: IndexMap<String, PatuiRunOutput> = IndexMap::new();
+
+        for (step_name, done_tx, task) in self.tasks {
+            let _ = done_tx.send(());
+            let captured = task.await?;
+
+            let output = outputs
+                .entry(step_name.clone())
+                .or_insert_with(|| PatuiRunOutput {
+                    run_id,
+                    step_name,
+                    outputs: vec![],
+                    dropped: 0,
+                });
+            output.outputs.extend(captured.outputs);
+            output.dropped += captured.dropped;
+        }
+
+        Ok(outputs.into_values().collect())
+    }
+}
+
+#[derive(Debug, Default)]
+struct CapturedOutput {
+    outputs: Vec<PatuiStepData>,
+    size: usize,
+    dropped: usize,
+}
+
+impl CapturedOutput {
+    fn push(&mut self, data: PatuiStepData, limit: usize) {
+        let size = data.data.estimated_size();
+
+        if self.size + size > limit {
+            self.dropped += 1;
+        } else {
+            self.size += size;
+            self.outputs.push(data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use tracing_test::traced_test;
+
+    use crate::types::PatuiStepDataFlavour;
+
+    use super::*;
+
+    #[traced_test]
+    #[test]
+    fn captured_output_limit() {
+        let data = PatuiStepData::new(PatuiStepDataFlavour::from("hello".to_string()));
+        let size = data.data.estimated_size();
+        let mut captured = CapturedOutput::default();
+
+        for _ in 0..5 {
+            captured.push(data.clone(), size * 3);
+        }
+
+        assert_that!(captured.outputs.len()).is_equal_to(3);
+        assert_that!(captured.dropped).is_equal_to(2);
+    }
+}
@@ -1,34 +1,53 @@
 mod assertion;
+mod eval;
+mod file_sink;
 mod plugin;
 mod reader;
 mod sender;
+mod transform;
 mod transform_stream;
 mod writer;
 
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
 use eyre::{eyre, Result};
-use tokio::sync::{broadcast, mpsc};
+use tokio::{
+    sync::{broadcast, mpsc},
+    time::Instant,
+};
 
 use self::{
-    assertion::PatuiStepRunnerAssertion, plugin::PatuiStepRunnerPlugin,
-    reader::PatuiStepRunnerRead, sender::PatuiStepRunnerSender,
-    transform_stream::PatuiStepRunnerTransformStream, writer::PatuiStepRunnerWrite,
+    assertion::PatuiStepRunnerAssertion, file_sink::PatuiStepRunnerFileSink,
+    plugin::PatuiStepRunnerPlugin, reader::PatuiStepRunnerRead, sender::PatuiStepRunnerSender,
+    transform::PatuiStepRunnerTransform, transform_stream::PatuiStepRunnerTransformStream,
+    writer::PatuiStepRunnerWrite,
+};
+pub(crate) use self::{
+    eval::{eval, EvalResult},
+    plugin::{find_plugins, PluginPool},
 };
 use crate::types::{
-    expr::{ast::ExprKind, get_all_idents},
-    PatuiEvent, PatuiExpr, PatuiStep, PatuiStepData, PatuiStepDetails,
+    expr::{get_step_refs, StepRef},
+    PatuiEvalTrace, PatuiEvent, PatuiExpr, PatuiStep, PatuiStepData, PatuiStepDetails,
 };
 
+/// How many items a step's output channel holds for each subscriber. Every subscriber sees every
+/// item, one that falls further behind than this misses the oldest ones.
+// TODO: Tune this parameter, configurable maybe? Probably should perf test.
+pub(crate) const OUT_CHANNEL_CAPACITY: usize = 32;
+
 #[derive(Debug)]
 pub(crate) enum PatuiStepRunnerFlavour {
     Read(PatuiStepRunnerRead),
     Write(PatuiStepRunnerWrite),
+    FileSink(PatuiStepRunnerFileSink),
     Sender(PatuiStepRunnerSender),
     TransformStream(PatuiStepRunnerTransformStream),
+    Transform(PatuiStepRunnerTransform),
     Assertion(PatuiStepRunnerAssertion),
     Plugin(PatuiStepRunnerPlugin),
 }
@@ -36,6 +55,11 @@ pub(crate) enum PatuiStepRunnerFlavour {
 #[derive(Debug)]
 pub(crate) struct PatuiStepRunner {
     flavour: PatuiStepRunnerFlavour,
+    channels: Option<Vec<String>>,
+    /// How long the step can go on for once it's started.
+    timeout: Option<Duration>,
+    /// When the step has to be finished by, set once it's started if it has a timeout.
+    deadline: Option<Instant>,
 }
 
 impl PatuiStepRunner {
@@ -47,12 +71,18 @@ impl PatuiStepRunner {
                     patui_step_transform_strema,
                 ))
             }
+            PatuiStepDetails::Transform(patui_step_transform) => PatuiStepRunnerFlavour::Transform(
+                PatuiStepRunnerTransform::new(step.name.clone(), patui_step_transform),
+            ),
             PatuiStepDetails::Read(patui_step_read) => PatuiStepRunnerFlavour::Read(
                 PatuiStepRunnerRead::new(step.name.clone(), patui_step_read),
             ),
             PatuiStepDetails::Write(patui_step_write) => {
                 PatuiStepRunnerFlavour::Write(PatuiStepRunnerWrite::new(patui_step_write))
             }
+            PatuiStepDetails::FileSink(patui_step_file_sink) => PatuiStepRunnerFlavour::FileSink(
+                PatuiStepRunnerFileSink::new(step.name.clone(), patui_step_file_sink),
+            ),
             PatuiStepDetails::Assertion(patui_step_assertion) => PatuiStepRunnerFlavour::Assertion(
                 PatuiStepRunnerAssertion::new(step.name.clone(), patui_step_assertion),
             ),
@@ -64,7 +94,12 @@ impl PatuiStepRunner {
             ),
         };
 
-        Self { flavour }
+        Self {
+            flavour,
+            channels: step.details.channels(),
+            timeout: step.timeout_ms.map(Duration::from_millis),
+            deadline: None,
+        }
     }
 
     pub(crate) async fn init(
@@ -78,12 +113,18 @@ impl PatuiStepRunner {
             PatuiStepRunnerFlavour::TransformStream(runner) => {
                 runner.init(current_step_name, step_runners).await
             }
+            PatuiStepRunnerFlavour::Transform(runner) => {
+                runner.init(current_step_name, step_runners).await
+            }
             PatuiStepRunnerFlavour::Read(runner) => {
                 runner.init(current_step_name, step_runners).await
             }
             PatuiStepRunnerFlavour::Write(runner) => {
                 runner.init(current_step_name, step_runners).await
             }
+            PatuiStepRunnerFlavour::FileSink(runner) => {
+                runner.init(current_step_name, step_runners).await
+            }
             PatuiStepRunnerFlavour::Assertion(runner) => {
                 runner.init(current_step_name, step_runners).await
             }
@@ -97,30 +138,139 @@ impl PatuiStepRunner {
     }
 
     pub(crate) fn run(&mut self, tx: mpsc::Sender<PatuiEvent>) -> Result<()> {
+        self.deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+
         match &mut self.flavour {
             PatuiStepRunnerFlavour::TransformStream(runner) => runner.run(tx),
+            PatuiStepRunnerFlavour::Transform(runner) => runner.run(tx),
             PatuiStepRunnerFlavour::Read(runner) => runner.run(tx),
             PatuiStepRunnerFlavour::Write(runner) => runner.run(tx),
+            PatuiStepRunnerFlavour::FileSink(runner) => runner.run(tx),
             PatuiStepRunnerFlavour::Assertion(runner) => runner.run(tx),
             PatuiStepRunnerFlavour::Sender(runner) => runner.run(tx),
             PatuiStepRunnerFlavour::Plugin(runner) => runner.run(tx),
         }
     }
 
+    /// Wait for the step to finish, if it has a timeout and it's not finished in time the step is
+    /// cancelled and this fails.
     pub(crate) async fn wait(&mut self) -> Result<()> {
+        let Some(deadline) = self.deadline else {
+            return self.wait_flavour().await;
+        };
+
+        match tokio::time::timeout_at(deadline, self.wait_flavour()).await {
+            Ok(res) => res,
+            Err(_) => {
+                self.cancel();
+                Err(eyre!(
+                    "Step timed out after {}ms",
+                    self.timeout.unwrap_or_default().as_millis()
+                ))
+            }
+        }
+    }
+
+    async fn wait_flavour(&mut self) -> Result<()> {
         match &mut self.flavour {
             PatuiStepRunnerFlavour::TransformStream(runner) => runner.wait().await,
+            PatuiStepRunnerFlavour::Transform(runner) => runner.wait().await,
             PatuiStepRunnerFlavour::Read(runner) => runner.wait().await,
             PatuiStepRunnerFlavour::Write(runner) => runner.wait().await,
+            PatuiStepRunnerFlavour::FileSink(runner) => runner.wait().await,
             PatuiStepRunnerFlavour::Assertion(runner) => runner.wait().await,
             PatuiStepRunnerFlavour::Sender(runner) => runner.wait().await,
             PatuiStepRunnerFlavour::Plugin(runner) => runner.wait().await,
         }
     }
 
+    /// Have plugin steps reuse processes from `pool`, does nothing for other steps.
+    pub(crate) fn set_plugin_pool(&mut self, pool: &PluginPool) {
+        if let PatuiStepRunnerFlavour::Plugin(runner) = &mut self.flavour {
+            runner.set_pool(pool.clone());
+        }
+    }
+
+    /// Keep how an assertion evaluated should it fail, returns whether the step's an assertion.
+    pub(crate) fn set_explain(&mut self, explain: bool) -> bool {
+        match &mut self.flavour {
+            PatuiStepRunnerFlavour::Assertion(runner) => {
+                runner.set_explain(explain);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// How a failed assertion evaluated when asked to explain it, `None` otherwise.
+    pub(crate) fn explanation(&self) -> Option<PatuiEvalTrace> {
+        match &self.flavour {
+            PatuiStepRunnerFlavour::Assertion(runner) => runner.explanation(),
+            _ => None,
+        }
+    }
+
+    /// The version of the plugin a plugin step started, `None` for other steps.
+    pub(crate) fn plugin_version(&self) -> Option<String> {
+        match &self.flavour {
+            PatuiStepRunnerFlavour::Plugin(runner) => runner.version().map(str::to_string),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn cancel(&mut self) {
+        match &mut self.flavour {
+            PatuiStepRunnerFlavour::TransformStream(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::Transform(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::Read(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::Write(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::FileSink(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::Assertion(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::Sender(runner) => runner.cancel(),
+            PatuiStepRunnerFlavour::Plugin(runner) => runner.cancel(),
+        }
+    }
+
+    #[cfg(test)]
     fn flavour_mut(&mut self) -> &mut PatuiStepRunnerFlavour {
         &mut self.flavour
     }
+
+    /// Subscribe to `channel` of this step, `step_name` being what this step is called.
+    pub(crate) async fn subscribe(
+        &mut self,
+        step_name: &str,
+        channel: &str,
+    ) -> Result<broadcast::Receiver<PatuiStepData>> {
+        self.check_channel(step_name, channel)?;
+
+        match &mut self.flavour {
+            PatuiStepRunnerFlavour::TransformStream(runner) => runner.subscribe(channel).await,
+            PatuiStepRunnerFlavour::Transform(runner) => runner.subscribe(channel).await,
+            PatuiStepRunnerFlavour::Read(runner) => runner.subscribe(channel).await,
+            PatuiStepRunnerFlavour::Sender(runner) => runner.subscribe(channel).await,
+            PatuiStepRunnerFlavour::Plugin(runner) => runner.subscribe(channel).await,
+            PatuiStepRunnerFlavour::Write(_)
+            | PatuiStepRunnerFlavour::FileSink(_)
+            | PatuiStepRunnerFlavour::Assertion(_) => {
+                Err(eyre!("Step `{}` can't be subscribed to", step_name))
+            }
+        }
+    }
+
+    /// Checks that `channel` is one this step has declared, steps that don't declare their
+    /// channels accept anything and leave it to the subscription to fail.
+    fn check_channel(&self, step_name: &str, channel: &str) -> Result<()> {
+        match &self.channels {
+            Some(channels) if !channels.iter().any(|c| c == channel) => Err(eyre!(
+                "Step `{}` has no channel `{}`, available channels: [{}]",
+                step_name,
+                channel,
+                channels.join(", ")
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 pub(crate) trait PatuiStepRunnerTrait {
@@ -144,6 +294,10 @@ pub(crate) trait PatuiStepRunnerTrait {
         Ok(())
     }
 
+    /// Stop anything the step still has running, called when a run is abandoned part way through
+    /// so `wait` must leave anything it hasn't finished waiting on in place for this.
+    fn cancel(&mut self) {}
+
     // fn check(&mut self, _action: &str) -> Result<PatuiStepData> {
     //     Err(eyre!("Checking not supported"))
     // }
@@ -165,58 +319,25 @@ async fn init_subscribe_steps(
 ) -> Result<HashMap<PatuiExpr, broadcast::Receiver<PatuiStepData>>> {
     let mut receivers = HashMap::new();
 
-    for ident in get_all_idents(expr)?.iter() {
-        tracing::trace!("Checking ident for subscribing: {:?}", ident.kind());
-        let (ref_step, field) = match ident.kind() {
-            ExprKind::Ident(_) => continue,
-            ExprKind::Field(root_expr, field_ident) => match root_expr.kind() {
-                ExprKind::Field(root_expr, sub_expr) => match root_expr.kind() {
-                    ExprKind::Ident(root_ident) => {
-                        if root_ident.value == "steps".to_string() {
-                            (&sub_expr.value, &field_ident.value)
-                        } else {
-                            continue;
-                        }
-                    }
-                    _ => continue,
-                },
-                _ => continue,
-            },
-            ExprKind::Index(_, _) => continue,
-            ExprKind::Call(_, _) => continue,
-            _ => {
-                return Err(eyre::eyre!("Unrecognised ident kind: {}", ident));
-            }
-        };
+    for StepRef {
+        expr: ident,
+        step: ref_step,
+        channel: field,
+    } in get_step_refs(expr)?
+    {
+        // The same reference used twice in one expression reads from the one subscription
+        if receivers.contains_key(&ident) {
+            continue;
+        }
 
-        if let Some(step_runners) = other_step_runners.get(ref_step) {
+        if let Some(step_runners) = other_step_runners.get(&ref_step) {
             tracing::debug!("Subscription: {current_step_name} -> {ref_step}");
             tracing::trace!("Step Runners: {:?}", step_runners);
 
             for step_runner in step_runners {
                 let mut step_runner = step_runner.lock().unwrap();
-                match step_runner.flavour_mut() {
-                    PatuiStepRunnerFlavour::TransformStream(patui_step_runner_transform_stream) => {
-                        receivers.insert(
-                            ident.clone(),
-                            patui_step_runner_transform_stream.subscribe(&field).await?,
-                        );
-                    }
-                    PatuiStepRunnerFlavour::Read(patui_step_runner_read) => {
-                        receivers.insert(
-                            ident.clone(),
-                            patui_step_runner_read.subscribe(&field).await?,
-                        );
-                    }
-                    PatuiStepRunnerFlavour::Write(_) => todo!(),
-                    PatuiStepRunnerFlavour::Assertion(_) => {
-                        todo!()
-                    }
-                    PatuiStepRunnerFlavour::Sender(_) => {}
-                    PatuiStepRunnerFlavour::Plugin(_) => {
-                        todo!()
-                    }
-                }
+                let receiver = step_runner.subscribe(&ref_step, &field).await?;
+                receivers.insert(ident.clone(), receiver);
             }
         } else {
             return Err(eyre!("No step found for referenced step: `{}`", ref_step));
@@ -225,3 +346,177 @@ async fn init_subscribe_steps(
 
     Ok(receivers)
 }
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use tracing_test::traced_test;
+
+    use crate::types::{steps::PatuiStepPlugin, PatuiStepRead};
+
+    use super::*;
+
+    fn step_runners(steps: Vec<PatuiStep>) -> HashMap<String, Vec<Arc<Mutex<PatuiStepRunner>>>> {
+        steps
+            .iter()
+            .map(|step| {
+                (
+                    step.name.clone(),
+                    vec![Arc::new(Mutex::new(PatuiStepRunner::new(step)))],
+                )
+            })
+            .collect()
+    }
+
+    fn read_step() -> PatuiStep {
+        PatuiStep {
+            name: "FooFile".to_string(),
+            when: None,
+            depends_on: vec![],
+            timeout_ms: None,
+            details: PatuiStepDetails::Read(PatuiStepRead {
+                r#in: "\"tests/data/test.json\"".try_into().unwrap(),
+            }),
+        }
+    }
+
+    fn plugin_step(channels: Vec<String>) -> PatuiStep {
+        PatuiStep {
+            name: "FooPlugin".to_string(),
+            when: None,
+            depends_on: vec![],
+            timeout_ms: None,
+            details: PatuiStepDetails::Plugin(PatuiStepPlugin {
+                path: "plugin".to_string(),
+                config: HashMap::new(),
+                r#in: HashMap::new(),
+                channels,
+                shutdown_grace_ms: None,
+                transport: None,
+            }),
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn subscribe_default_channel() {
+        let step_runners = step_runners(vec![read_step()]);
+
+        let receivers = init_subscribe_steps(
+            &"steps.FooFile.out.len() == 1".try_into().unwrap(),
+            "main",
+            &step_runners,
+        )
+        .await;
+
+        assert_that!(receivers).is_ok();
+        let receivers = receivers.unwrap();
+        assert_that!(receivers.len()).is_equal_to(1);
+        assert_that!(receivers.contains_key(&"steps.FooFile.out".try_into().unwrap())).is_true();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn subscribe_same_reference_twice() {
+        let step_runners = step_runners(vec![read_step()]);
+
+        let receivers = init_subscribe_steps(
+            &"steps.FooFile.out == steps.FooFile.out".try_into().unwrap(),
+            "main",
+            &step_runners,
+        )
+        .await;
+
+        assert_that!(receivers).is_ok();
+        assert_that!(receivers.unwrap().len()).is_equal_to(1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn subscribers_receive_everything() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("data.bin");
+        let contents = (0..64 * 1024).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        std::fs::write(&path, &contents).unwrap();
+
+        let step = PatuiStep {
+            name: "FooFile".to_string(),
+            when: None,
+            depends_on: vec![],
+            timeout_ms: None,
+            details: PatuiStepDetails::Read(PatuiStepRead {
+                r#in: format!("\"{}\"", path.display()).try_into().unwrap(),
+            }),
+        };
+        let step_runners = step_runners(vec![step]);
+
+        let mut all_receivers = vec![];
+        for step_name in ["first", "second"] {
+            let receivers = init_subscribe_steps(
+                &"steps.FooFile.out.len() > 0".try_into().unwrap(),
+                step_name,
+                &step_runners,
+            )
+            .await
+            .unwrap();
+            all_receivers.extend(receivers.into_values());
+        }
+        assert_that!(all_receivers.len()).is_equal_to(2);
+
+        let (tx, mut rx) = mpsc::channel(1);
+        let events = tokio::spawn(async move { while rx.recv().await.is_some() {} });
+
+        let step_runner = step_runners.into_values().next().unwrap().remove(0);
+        let mut step_runner = Arc::try_unwrap(step_runner).unwrap().into_inner().unwrap();
+        step_runner.run(tx).unwrap();
+        step_runner.wait().await.unwrap();
+        events.await.unwrap();
+
+        // Neither subscriber was reading while the file was sent, both should still see all of it
+        for mut receiver in all_receivers {
+            let mut received = vec![];
+            loop {
+                match receiver.recv().await {
+                    Ok(data) => received.extend(data.data.as_bytes().unwrap().iter()),
+                    Err(broadcast::error::RecvError::Closed) => break,
+                    Err(e) => panic!("Subscriber missed items: {}", e),
+                }
+            }
+            assert_that!(received).is_equal_to(contents.clone());
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn subscribe_undeclared_channel() {
+        let step_runners = step_runners(vec![read_step()]);
+
+        let receivers = init_subscribe_steps(
+            &"steps.FooFile.echo".try_into().unwrap(),
+            "main",
+            &step_runners,
+        )
+        .await;
+
+        assert_that!(receivers).is_err();
+        assert_that!(receivers.unwrap_err().to_string()).is_equal_to(
+            "Step `FooFile` has no channel `echo`, available channels: [out]".to_string(),
+        );
+    }
+
+    #[traced_test]
+    #[test]
+    fn check_declared_channels() {
+        let runner =
+            PatuiStepRunner::new(&plugin_step(vec!["out".to_string(), "echo".to_string()]));
+
+        assert_that!(runner.check_channel("FooPlugin", "echo")).is_ok();
+        assert_that!(runner.check_channel("FooPlugin", "out")).is_ok();
+        assert_that!(runner.check_channel("FooPlugin", "stdout")).is_err();
+
+        let runner = PatuiStepRunner::new(&plugin_step(vec![]));
+
+        assert_that!(runner.check_channel("FooPlugin", "echo")).is_ok();
+        assert_that!(runner.check_channel("FooPlugin", "anything")).is_ok();
+    }
+}
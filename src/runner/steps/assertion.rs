@@ -1,30 +1,72 @@
 use std::{
     collections::HashMap,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
-use eyre::Result;
-use tokio::sync::broadcast;
+use eyre::{eyre, Result};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    task::JoinHandle,
+};
 
-use super::{init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait};
-use crate::types::{PatuiExpr, PatuiStepAssertion, PatuiStepData};
+use super::{
+    eval::{equal, eval, EvalResult, StepResults},
+    init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait,
+};
+use crate::types::{
+    expr::ast::{integer_value, BinOp, ExprKind, Ident},
+    PatuiEvalState, PatuiEvalTrace, PatuiEvent, PatuiExpr, PatuiStepAssertion,
+    PatuiStepAssertionSample, PatuiStepData, PatuiStepDataFlavour,
+};
 
 #[derive(Debug)]
 pub(crate) struct PatuiStepRunnerAssertion {
+    step_name: String,
     step: PatuiStepAssertion,
 
     receivers: Option<HashMap<PatuiExpr, broadcast::Receiver<PatuiStepData>>>,
-    // tasks: Vec<JoinHandle<()>>,
+    /// One task per subscription, each forwards what it receives on to the evaluator.
+    tasks: Vec<JoinHandle<()>>,
+    evaluator: Option<JoinHandle<Result<()>>>,
+    num_evaluations: Arc<AtomicUsize>,
+    /// Where the evaluator keeps how the expression evaluated if it fails, when asked to explain.
+    explanation: Option<Arc<Mutex<Option<PatuiEvalTrace>>>>,
 }
 
 impl PatuiStepRunnerAssertion {
-    pub(crate) fn new(_step_name: String, step: &PatuiStepAssertion) -> Self {
+    pub(crate) fn new(step_name: String, step: &PatuiStepAssertion) -> Self {
         Self {
+            step_name,
             step: step.clone(),
             receivers: None,
-            // tasks: vec![],
+            tasks: vec![],
+            evaluator: None,
+            num_evaluations: Arc::new(AtomicUsize::new(0)),
+            explanation: None,
         }
     }
+
+    pub(crate) fn set_explain(&mut self, explain: bool) {
+        self.explanation = explain.then(|| Arc::new(Mutex::new(None)));
+    }
+
+    pub(crate) fn explanation(&self) -> Option<PatuiEvalTrace> {
+        self.explanation
+            .as_ref()
+            .and_then(|explanation| explanation.lock().unwrap().clone())
+    }
+
+    #[cfg(test)]
+    fn num_evaluations(&self) -> usize {
+        self.num_evaluations.load(Ordering::Relaxed)
+    }
 }
 
 impl PatuiStepRunnerTrait for PatuiStepRunnerAssertion {
@@ -39,4 +81,972 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerAssertion {
 
         Ok(())
     }
+
+    fn run(&mut self, _tx: mpsc::Sender<PatuiEvent>) -> Result<()> {
+        let receivers = self.receivers.take().unwrap_or_default();
+
+        // Receivers never share anything, each owns its subscription and hands items to the
+        // evaluator over a channel so a busy subscription can't hold up the others.
+        let (item_tx, item_rx) = mpsc::unbounded_channel();
+        let mut exprs = Vec::with_capacity(receivers.len());
+
+        for (idx, (expr, mut receiver)) in receivers.into_iter().enumerate() {
+            let item_tx = item_tx.clone();
+            exprs.push(expr);
+
+            self.tasks.push(tokio::spawn(async move {
+                loop {
+                    let item = match receiver.recv().await {
+                        Ok(data) => ReceivedItem::Data(idx, data),
+                        Err(RecvError::Lagged(count)) => ReceivedItem::Lagged(idx, count),
+                        Err(RecvError::Closed) => ReceivedItem::Closed(idx),
+                    };
+                    let is_last =
+                        matches!(item, ReceivedItem::Lagged(_, _) | ReceivedItem::Closed(_));
+
+                    if item_tx.send(item).is_err() || is_last {
+                        break;
+                    }
+                }
+            }));
+        }
+
+        self.evaluator = Some(tokio::spawn(evaluate(
+            self.step_name.clone(),
+            self.step.expr.clone(),
+            exprs,
+            item_rx,
+            Sampler::new(self.step.sample.clone()),
+            self.num_evaluations.clone(),
+            self.explanation.clone(),
+        )));
+
+        Ok(())
+    }
+
+    async fn wait(&mut self) -> Result<()> {
+        tracing::trace!("Waiting");
+        let res = match self.evaluator.as_mut() {
+            Some(evaluator) => evaluator.await?,
+            None => Ok(()),
+        };
+        self.evaluator = None;
+
+        // The evaluator can finish before the subscriptions do when the result is known early
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
+        res
+    }
+
+    fn cancel(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
+        if let Some(evaluator) = self.evaluator.take() {
+            evaluator.abort();
+        }
+    }
+
+    #[cfg(test)]
+    fn test_set_receiver(
+        &mut self,
+        sub_ref: &str,
+        rx: broadcast::Receiver<PatuiStepData>,
+    ) -> Result<()> {
+        self.receivers
+            .get_or_insert_with(HashMap::new)
+            .insert(sub_ref.try_into().unwrap(), rx);
+
+        Ok(())
+    }
+}
+
+/// What a subscription task passes on to the evaluator, tagged with the index of the
+/// subscription's expression.
+#[derive(Debug)]
+enum ReceivedItem {
+    Data(usize, PatuiStepData),
+    Lagged(usize, u64),
+    /// The step has finished, nothing more will be received from the subscription.
+    Closed(usize),
+}
+
+/// Decides when an assertion with a `sample` option is due to be evaluated again.
+#[derive(Debug)]
+struct Sampler {
+    sample: Option<PatuiStepAssertionSample>,
+    num_pending: u64,
+    last_evaluated: Option<Instant>,
+}
+
+impl Sampler {
+    fn new(sample: Option<PatuiStepAssertionSample>) -> Self {
+        Self {
+            sample,
+            num_pending: 0,
+            last_evaluated: None,
+        }
+    }
+
+    /// Record `num_items` more items arriving at `now`, returns whether to evaluate.
+    fn is_due(&mut self, num_items: u64, now: Instant) -> bool {
+        self.num_pending += num_items;
+
+        let is_due = match &self.sample {
+            None => true,
+            Some(PatuiStepAssertionSample::EveryN(n)) => self.num_pending >= *n,
+            Some(PatuiStepAssertionSample::EveryMs(ms)) => self
+                .last_evaluated
+                .is_none_or(|last| now >= last + Duration::from_millis(*ms)),
+        };
+
+        if is_due {
+            self.num_pending = 0;
+            self.last_evaluated = Some(now);
+        }
+
+        is_due
+    }
+}
+
+/// Keeps up with the parts of an assertion over a subscription that only need a little state as
+/// items arrive, e.g. `steps.foo.out.len()`, `steps.foo.out[2]` or `3 in steps.foo.out`, so each
+/// evaluation doesn't go over everything received so far.
+#[derive(Debug)]
+struct Incremental {
+    tracked: Vec<Tracked>,
+    /// What each tracked expression evaluates to so far, `eval` takes these in place of the
+    /// references they're over.
+    results: HashMap<PatuiExpr, StepResults>,
+}
+
+#[derive(Debug)]
+struct Tracked {
+    expr: PatuiExpr,
+    /// The index of the subscription it's over.
+    sub: usize,
+    kind: TrackedKind,
+    is_closed: bool,
+}
+
+#[derive(Debug)]
+enum TrackedKind {
+    Len(usize),
+    Index {
+        idx: usize,
+        num_received: usize,
+        item: Option<PatuiStepDataFlavour>,
+    },
+    Contains {
+        item: PatuiStepDataFlavour,
+        is_contains: bool,
+        is_found: bool,
+    },
+}
+
+impl Incremental {
+    /// Track `expr` where `exprs` are the references subscribed to, `None` when a reference is
+    /// used in any other way, e.g. `steps.foo.out == [1, 2]`, which needs a full evaluation.
+    fn new(expr: &PatuiExpr, exprs: &[PatuiExpr]) -> Option<Self> {
+        let mut incremental = Self {
+            tracked: vec![],
+            results: HashMap::new(),
+        };
+
+        incremental.track(expr, exprs).then_some(incremental)
+    }
+
+    fn track(&mut self, expr: &PatuiExpr, exprs: &[PatuiExpr]) -> bool {
+        if exprs.contains(expr) {
+            return false;
+        }
+
+        let Some((sub, kind)) = trackable(expr, exprs) else {
+            return sub_exprs(expr)
+                .into_iter()
+                .all(|sub_expr| self.track(sub_expr, exprs));
+        };
+
+        if !self.results.contains_key(expr) {
+            let tracked = Tracked {
+                expr: expr.clone(),
+                sub,
+                kind,
+                is_closed: false,
+            };
+            self.results.insert(expr.clone(), tracked.results());
+            self.tracked.push(tracked);
+        }
+
+        true
+    }
+
+    fn push(&mut self, sub: usize, data: &PatuiStepData) {
+        for tracked in self.tracked.iter_mut().filter(|tracked| tracked.sub == sub) {
+            match &mut tracked.kind {
+                TrackedKind::Len(len) => *len += 1,
+                TrackedKind::Index {
+                    idx,
+                    num_received,
+                    item,
+                } => {
+                    if num_received == idx {
+                        *item = Some(data.data.clone());
+                    }
+                    *num_received += 1;
+                }
+                TrackedKind::Contains { item, is_found, .. } => {
+                    *is_found = *is_found || equal(&data.data, item);
+                }
+            }
+            self.results.insert(tracked.expr.clone(), tracked.results());
+        }
+    }
+
+    /// Returns whether what's tracked still stands once `sub` has closed. An index that never
+    /// arrived is out of range, which is left to a full evaluation to report.
+    fn close(&mut self, sub: usize) -> bool {
+        for tracked in self.tracked.iter_mut().filter(|tracked| tracked.sub == sub) {
+            if matches!(tracked.kind, TrackedKind::Index { item: None, .. }) {
+                return false;
+            }
+            tracked.is_closed = true;
+            self.results.insert(tracked.expr.clone(), tracked.results());
+        }
+
+        true
+    }
+}
+
+impl Tracked {
+    /// The expression's value so far, evaluated the same as it would be over everything received.
+    fn results(&self) -> StepResults {
+        let (value, is_closed) = match &self.kind {
+            TrackedKind::Len(len) => (
+                Some(PatuiStepDataFlavour::Integer(len.to_string())),
+                self.is_closed,
+            ),
+            // Items already received never change
+            TrackedKind::Index { item, .. } => (item.clone(), true),
+            TrackedKind::Contains {
+                is_contains,
+                is_found,
+                ..
+            } => (
+                Some(PatuiStepDataFlavour::Bool(is_found == is_contains)),
+                self.is_closed,
+            ),
+        };
+
+        StepResults {
+            data: value.into_iter().map(PatuiStepData::new).collect(),
+            is_closed,
+            is_item: true,
+        }
+    }
+}
+
+/// The subscription in `exprs` that `expr` is over and how to keep track of it, if it's the
+/// length of one, a fixed index into one or whether one contains a fixed value.
+fn trackable(expr: &PatuiExpr, exprs: &[PatuiExpr]) -> Option<(usize, TrackedKind)> {
+    let sub = |expr: &PatuiExpr| exprs.iter().position(|sub_expr| sub_expr == expr);
+    let fixed = |expr: &PatuiExpr| match eval(expr, &HashMap::new()) {
+        Ok(EvalResult::Known(value)) => Some(value),
+        _ => None,
+    };
+
+    match expr.kind() {
+        ExprKind::Call(func, args) if args.is_empty() => match func.kind() {
+            ExprKind::Field(receiver, Ident { value }) if value == "len" => {
+                Some((sub(receiver)?, TrackedKind::Len(0)))
+            }
+            _ => None,
+        },
+        ExprKind::Index(receiver, index) => {
+            let PatuiStepDataFlavour::Integer(idx) = fixed(index)? else {
+                return None;
+            };
+            Some((
+                sub(receiver)?,
+                TrackedKind::Index {
+                    idx: usize::try_from(integer_value(&idx)?).ok()?,
+                    num_received: 0,
+                    item: None,
+                },
+            ))
+        }
+        ExprKind::BinOp(op @ (BinOp::Contains | BinOp::NotContains), item, collection) => Some((
+            sub(collection)?,
+            TrackedKind::Contains {
+                item: fixed(item)?,
+                is_contains: matches!(op, BinOp::Contains),
+                is_found: false,
+            },
+        )),
+        _ => None,
+    }
+}
+
+/// Evaluate `expr` as items arrive on `item_rx`, finishing as soon as the result is known or
+/// otherwise once every subscription has closed.
+async fn evaluate(
+    step_name: String,
+    expr: PatuiExpr,
+    exprs: Vec<PatuiExpr>,
+    mut item_rx: mpsc::UnboundedReceiver<ReceivedItem>,
+    mut sampler: Sampler,
+    num_evaluations: Arc<AtomicUsize>,
+    explanation: Option<Arc<Mutex<Option<PatuiEvalTrace>>>>,
+) -> Result<()> {
+    let mut results: HashMap<PatuiExpr, StepResults> = exprs
+        .iter()
+        .map(|expr| (expr.clone(), StepResults::default()))
+        .collect();
+    // Everything received is still kept to explain a failure or fall back on
+    let mut incremental = Incremental::new(&expr, &exprs);
+
+    let explain = |results: &HashMap<PatuiExpr, StepResults>| {
+        if let Some(explanation) = &explanation {
+            *explanation.lock().unwrap() = Some(eval_trace(&expr, results));
+        }
+    };
+
+    while let Some(item) = item_rx.recv().await {
+        // Take everything that's already waiting so a burst of items costs one evaluation
+        let mut num_items = 0;
+        let mut is_closing = false;
+        let mut lagged = None;
+        let mut item = Some(item);
+        while let Some(next) = item {
+            num_items += 1;
+            match next {
+                ReceivedItem::Data(idx, data) => {
+                    if let Some(incremental) = incremental.as_mut() {
+                        incremental.push(idx, &data);
+                    }
+                    results.get_mut(&exprs[idx]).unwrap().data.push(data);
+                }
+                ReceivedItem::Closed(idx) => {
+                    if incremental
+                        .as_mut()
+                        .is_some_and(|incremental| !incremental.close(idx))
+                    {
+                        incremental = None;
+                    }
+                    results.get_mut(&exprs[idx]).unwrap().is_closed = true;
+                    is_closing = true;
+                }
+                ReceivedItem::Lagged(idx, count) => {
+                    lagged = Some((idx, count));
+                    break;
+                }
+            }
+            item = item_rx.try_recv().ok();
+        }
+
+        // A subscription closing can settle the result so it's worth a look whatever the sampling,
+        // as can what arrived before one lagged
+        if !sampler.is_due(num_items, Instant::now()) && !is_closing && lagged.is_none() {
+            continue;
+        }
+
+        num_evaluations.fetch_add(1, Ordering::Relaxed);
+        // A predictable result could still change with more data, e.g. `steps.foo.out.len() == 2`
+        // once two items have arrived, so it's only settled once the subscriptions it uses close
+        let current = incremental
+            .as_ref()
+            .map_or(&results, |incremental| &incremental.results);
+        if let EvalResult::Known(value) = eval(&expr, current).inspect_err(|_| explain(&results))? {
+            return check_result(&step_name, &expr, value).inspect_err(|_| explain(&results));
+        }
+
+        if let Some((idx, count)) = lagged {
+            return Err(eyre!(
+                "Assertion `{}` missed {} items from `{}`",
+                step_name,
+                count,
+                exprs[idx]
+            ));
+        }
+    }
+
+    // Everything's arrived, whatever the sampling this is the final say
+    num_evaluations.fetch_add(1, Ordering::Relaxed);
+    let current = incremental
+        .as_ref()
+        .map_or(&results, |incremental| &incremental.results);
+    let res = match eval(&expr, current) {
+        Ok(EvalResult::Known(value) | EvalResult::Predictable(value)) => {
+            check_result(&step_name, &expr, value)
+        }
+        Ok(EvalResult::Unknown) => Err(eyre!(
+            "Assertion `{}` couldn't be evaluated from what was received: {}",
+            step_name,
+            expr
+        )),
+        Err(e) => Err(e),
+    };
+    if res.is_err() {
+        explain(&results);
+    }
+
+    res
+}
+
+fn check_result(step_name: &str, expr: &PatuiExpr, value: PatuiStepDataFlavour) -> Result<()> {
+    match value {
+        PatuiStepDataFlavour::Bool(true) => Ok(()),
+        PatuiStepDataFlavour::Bool(false) => {
+            Err(eyre!("Assertion `{}` failed: {}", step_name, expr))
+        }
+        _ => Err(eyre!(
+            "Assertion `{}` should evaluate to a bool, got {:?}: {}",
+            step_name,
+            value,
+            expr
+        )),
+    }
+}
+
+/// Evaluate `expr` as `eval` does, keeping what each sub-expression evaluated to along the way
+/// to show how the result came about.
+pub(crate) fn eval_trace(
+    expr: &PatuiExpr,
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> PatuiEvalTrace {
+    let (state, value, error) = match eval(expr, results) {
+        Ok(EvalResult::Known(value)) => (PatuiEvalState::Known, Some((&value).into()), None),
+        Ok(EvalResult::Predictable(value)) => {
+            (PatuiEvalState::Predictable, Some((&value).into()), None)
+        }
+        Ok(EvalResult::Unknown) => (PatuiEvalState::Unknown, None, None),
+        Err(e) => (PatuiEvalState::Error, None, Some(e.to_string())),
+    };
+
+    // What a step sent is taken as a whole, there's nothing further down to show
+    let children = if results.contains_key(expr) {
+        vec![]
+    } else {
+        sub_exprs(expr)
+            .into_iter()
+            .map(|sub_expr| eval_trace(sub_expr, results))
+            .collect()
+    };
+
+    PatuiEvalTrace {
+        expr: expr.to_string(),
+        state,
+        value,
+        error,
+        children,
+    }
+}
+
+/// The sub-expressions `eval` evaluates on the way to evaluating `expr`.
+fn sub_exprs(expr: &PatuiExpr) -> Vec<&PatuiExpr> {
+    match expr.kind() {
+        ExprKind::Lit(_) | ExprKind::Ident(_) => vec![],
+        ExprKind::Field(receiver, _) | ExprKind::UnOp(_, receiver) => vec![receiver],
+        ExprKind::Index(receiver, index) => vec![receiver, index],
+        ExprKind::Slice(receiver, from, to) => std::iter::once(&**receiver)
+            .chain(from.iter().chain(to).map(|bound| &**bound))
+            .collect(),
+        // A method's evaluated on its receiver, the method itself is never evaluated
+        ExprKind::Call(func, args) => match func.kind() {
+            ExprKind::Field(receiver, _) => std::iter::once(&**receiver)
+                .chain(args.iter().map(|arg| &**arg))
+                .collect(),
+            _ => args.iter().map(|arg| &**arg).collect(),
+        },
+        ExprKind::List(elems) | ExprKind::Set(elems) => elems.iter().map(|elem| &**elem).collect(),
+        ExprKind::Map(entries) => entries.iter().map(|entry| &entry.1).collect(),
+        ExprKind::BinOp(_, lhs, rhs) => vec![lhs, rhs],
+        ExprKind::If(cond, then, otherwise) => vec![cond, then, otherwise],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assertor::*;
+    use tokio::time::timeout;
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn foo_out() -> Vec<PatuiExpr> {
+        vec!["steps.foo.out".try_into().unwrap()]
+    }
+
+    #[traced_test]
+    #[test]
+    fn incremental_matches_full_eval() {
+        let exprs = foo_out();
+
+        for expr in [
+            "steps.foo.out.len() == 3",
+            "steps.foo.out.len() > 1",
+            "steps.foo.out[1] == 2",
+            "steps.foo.out[0x2] + 1",
+            "2 in steps.foo.out",
+            "5 not in steps.foo.out",
+            "(steps.foo.out.len() == 3) && (steps.foo.out[0] == 1)",
+            "(3 in steps.foo.out) || (steps.foo.out.len() > 5)",
+            "\"a\" in steps.foo.out",
+        ] {
+            let expr = PatuiExpr::try_from(expr).unwrap();
+            let mut incremental = Incremental::new(&expr, &exprs).unwrap();
+            let mut results = HashMap::from([(exprs[0].clone(), StepResults::default())]);
+
+            let check = |incremental: &Incremental, results: &HashMap<PatuiExpr, StepResults>| {
+                let verdict = |results| eval(&expr, results).map_err(|e| e.to_string());
+                assert_that!(verdict(&incremental.results)).is_equal_to(verdict(results));
+            };
+
+            check(&incremental, &results);
+            for i in 1..=3 {
+                let data = PatuiStepData::new(PatuiStepDataFlavour::from(i));
+                incremental.push(0, &data);
+                results.get_mut(&exprs[0]).unwrap().data.push(data);
+                check(&incremental, &results);
+            }
+
+            assert_that!(incremental.close(0)).is_true();
+            results.get_mut(&exprs[0]).unwrap().is_closed = true;
+            check(&incremental, &results);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn incremental_falls_back() {
+        let exprs = foo_out();
+
+        for expr in [
+            "steps.foo.out == [1, 2]",
+            "steps.foo.out[0] in steps.foo.out",
+            "steps.foo.out[steps.foo.out.len() - 1] == 2",
+            "steps.foo.out[1:].len() == 2",
+        ] {
+            assert_that!(Incremental::new(&expr.try_into().unwrap(), &exprs).is_none()).is_true();
+        }
+
+        // An index that never arrives is an error, left to a full evaluation to report
+        let mut incremental =
+            Incremental::new(&"steps.foo.out[3] == 1".try_into().unwrap(), &exprs).unwrap();
+        incremental.push(0, &PatuiStepData::new(PatuiStepDataFlavour::from(1)));
+        assert_that!(incremental.close(0)).is_false();
+    }
+
+    #[traced_test]
+    #[test]
+    fn incremental_eval_over_tracked_values() {
+        let exprs = foo_out();
+        let expr =
+            PatuiExpr::try_from("(steps.foo.out.len() == 1000) && (999 in steps.foo.out)").unwrap();
+        let mut incremental = Incremental::new(&expr, &exprs).unwrap();
+        let mut results = HashMap::from([(exprs[0].clone(), StepResults::default())]);
+
+        for i in 0..1000 {
+            let data = PatuiStepData::new(PatuiStepDataFlavour::from(i));
+            incremental.push(0, &data);
+            results.get_mut(&exprs[0]).unwrap().data.push(data);
+        }
+
+        // Each evaluation goes over one value per tracked expression rather than every item
+        let num_items = |results: &HashMap<PatuiExpr, StepResults>| {
+            results
+                .values()
+                .map(|step_results| step_results.data.len())
+                .sum::<usize>()
+        };
+        assert_that!(num_items(&incremental.results)).is_equal_to(2);
+        assert_that!(num_items(&results)).is_equal_to(1000);
+
+        assert_that!(eval(&expr, &incremental.results).unwrap())
+            .is_equal_to(EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)));
+    }
+
+    fn assertion_runner(expr: &str) -> PatuiStepRunnerAssertion {
+        PatuiStepRunnerAssertion::new(
+            "check".to_string(),
+            &PatuiStepAssertion {
+                expr: expr.try_into().unwrap(),
+                sample: None,
+            },
+        )
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_without_subscriptions() {
+        let mut runner = assertion_runner("\"a\".upper() == \"A\"");
+        assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+        assert_that!(runner.wait().await).is_ok();
+
+        let mut runner = assertion_runner("\"a\" == \"b\"");
+        assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+        let res = runner.wait().await;
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .is_equal_to("Assertion `check` failed: \"a\" == \"b\"".to_string());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_finishes_once_known() {
+        let mut runner = assertion_runner("steps.foo.out[0] == \"ready\"");
+
+        let (input_tx, input_rx) = broadcast::channel(8);
+        assert_that!(runner.test_set_receiver("steps.foo.out", input_rx)).is_ok();
+        assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+
+        input_tx
+            .send(PatuiStepData::new(PatuiStepDataFlavour::String(
+                "ready".to_string(),
+            )))
+            .unwrap();
+
+        // The sender is still open, the first item is all that's needed
+        let res = timeout(Duration::from_millis(500), runner.wait()).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_settled_before_lagging() {
+        for (expr, expected) in [
+            // The first item settles it, missing those after doesn't matter
+            ("steps.foo.out[0] == \"ready\"", None),
+            (
+                "steps.foo.out[1] == \"ready\"",
+                Some("Assertion `check` missed 3 items from `steps.foo.out`"),
+            ),
+        ] {
+            let (item_tx, item_rx) = mpsc::unbounded_channel();
+            item_tx
+                .send(ReceivedItem::Data(
+                    0,
+                    PatuiStepData::new(PatuiStepDataFlavour::String("ready".to_string())),
+                ))
+                .unwrap();
+            item_tx.send(ReceivedItem::Lagged(0, 3)).unwrap();
+
+            let res = evaluate(
+                "check".to_string(),
+                expr.try_into().unwrap(),
+                foo_out(),
+                item_rx,
+                Sampler::new(None),
+                Arc::new(AtomicUsize::new(0)),
+                None,
+            )
+            .await;
+
+            match expected {
+                None => assert_that!(res).is_ok(),
+                Some(expected) => {
+                    assert_that!(res).is_err();
+                    assert_that!(res.unwrap_err().to_string()).is_equal_to(expected.to_string());
+                }
+            }
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_out_of_range_at_close() {
+        let mut runner = assertion_runner("steps.foo.out[1] == \"ready\"");
+
+        let (input_tx, input_rx) = broadcast::channel(8);
+        assert_that!(runner.test_set_receiver("steps.foo.out", input_rx)).is_ok();
+        assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+
+        input_tx
+            .send(PatuiStepData::new(PatuiStepDataFlavour::String(
+                "ready".to_string(),
+            )))
+            .unwrap();
+        drop(input_tx);
+
+        let res = timeout(Duration::from_millis(500), runner.wait()).await;
+        assert_that!(res).is_ok();
+        let res = res.unwrap();
+        assert_that!(res).is_err();
+        // Once the step's finished there's never going to be a second item
+        assert_that!(res.unwrap_err().to_string())
+            .starts_with("Index 1 out of range for `steps.foo.out`");
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_explain_failure() {
+        for (item, is_ok) in [("Ready", true), ("Busy", false)] {
+            let mut runner = assertion_runner(
+                "(steps.foo.out.len() == 1) && (steps.foo.out[0].lower() == \"ready\")",
+            );
+            runner.set_explain(true);
+
+            let (input_tx, input_rx) = broadcast::channel(8);
+            assert_that!(runner.test_set_receiver("steps.foo.out", input_rx)).is_ok();
+            assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+
+            input_tx
+                .send(PatuiStepData::new(PatuiStepDataFlavour::String(
+                    item.to_string(),
+                )))
+                .unwrap();
+            // A failing item settles it with the step still open, passing needs the length final
+            if is_ok {
+                drop(input_tx);
+            }
+
+            let res = timeout(Duration::from_millis(500), runner.wait()).await;
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap().is_ok()).is_equal_to(is_ok);
+
+            if is_ok {
+                assert_that!(runner.explanation()).is_none();
+                continue;
+            }
+
+            let explanation = serde_json::to_value(runner.explanation().unwrap()).unwrap();
+            assert_that!(explanation["value"]).is_equal_to(serde_json::json!(false));
+
+            // The length holds, it's the comparison of the item that decides the result
+            let [len_check, item_check] = &explanation["children"].as_array().unwrap()[..] else {
+                panic!("Expected two sides of `&&`, got {}", explanation);
+            };
+            assert_that!(len_check["value"]).is_equal_to(serde_json::json!(true));
+            assert_that!(item_check["expr"])
+                .is_equal_to(serde_json::json!("steps.foo.out[0].lower() == \"ready\""));
+            assert_that!(item_check["value"]).is_equal_to(serde_json::json!(false));
+            assert_that!(item_check["children"][0]).is_equal_to(serde_json::json!({
+                "expr": "steps.foo.out[0].lower()",
+                "state": "known",
+                "value": "busy",
+                "children": [{
+                    "expr": "steps.foo.out[0]",
+                    "state": "known",
+                    "value": "Busy",
+                    "children": [
+                        {"expr": "steps.foo.out", "state": "predictable", "value": ["Busy"]},
+                        {"expr": "0", "state": "known", "value": 0},
+                    ],
+                }],
+            }));
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_predictable_settled_at_close() {
+        for (num_items, is_ok) in [(2, true), (3, false)] {
+            let mut runner = assertion_runner("steps.foo.out.len() == 2");
+
+            let (input_tx, input_rx) = broadcast::channel(8);
+            assert_that!(runner.test_set_receiver("steps.foo.out", input_rx)).is_ok();
+            assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+
+            for i in 0..2 {
+                input_tx
+                    .send(PatuiStepData::new(PatuiStepDataFlavour::Integer(
+                        i.to_string(),
+                    )))
+                    .unwrap();
+            }
+
+            // Two items makes the assertion true for now but more could still arrive
+            let res = timeout(Duration::from_millis(100), runner.wait()).await;
+            assert_that!(res).is_err();
+
+            for i in 2..num_items {
+                input_tx
+                    .send(PatuiStepData::new(PatuiStepDataFlavour::Integer(
+                        i.to_string(),
+                    )))
+                    .unwrap();
+            }
+            drop(input_tx);
+
+            let res = timeout(Duration::from_millis(500), runner.wait()).await;
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap().is_ok()).is_equal_to(is_ok);
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_settled_when_one_step_closes() {
+        let mut runner =
+            assertion_runner("(steps.foo.out.len() == 2) && (steps.bar.out.len() == 0)");
+
+        let (foo_tx, foo_rx) = broadcast::channel(8);
+        let (_bar_tx, bar_rx) = broadcast::channel(8);
+        assert_that!(runner.test_set_receiver("steps.foo.out", foo_rx)).is_ok();
+        assert_that!(runner.test_set_receiver("steps.bar.out", bar_rx)).is_ok();
+        assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+
+        for i in 0..3 {
+            foo_tx
+                .send(PatuiStepData::new(PatuiStepDataFlavour::Integer(
+                    i.to_string(),
+                )))
+                .unwrap();
+        }
+
+        // Three items is only too many for now until `steps.foo` finishes
+        let res = timeout(Duration::from_millis(100), runner.wait()).await;
+        assert_that!(res).is_err();
+
+        // `steps.bar` is still open but `steps.foo` finishing is enough to fail the `&&`
+        drop(foo_tx);
+
+        let res = timeout(Duration::from_millis(500), runner.wait()).await;
+        assert_that!(res).is_ok();
+        let res = res.unwrap();
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with("Assertion `check` failed");
+    }
+
+    /// Lots of subscriptions all sending as fast as they can, every item should be seen in order
+    /// and nothing should be starved out.
+    async fn stress_assertion(bad_sub: Option<usize>) -> Result<()> {
+        const NUM_SUBS: usize = 32;
+        const NUM_ITEMS: usize = 500;
+
+        let expected = (0..NUM_ITEMS)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let expr = format!(
+            "[{}] == [{}]",
+            (0..NUM_SUBS)
+                .map(|i| format!("steps.s{}.out", i))
+                .collect::<Vec<_>>()
+                .join(", "),
+            vec![format!("[{}]", expected); NUM_SUBS].join(", ")
+        );
+        let mut runner = assertion_runner(&expr);
+
+        let mut senders = Vec::with_capacity(NUM_SUBS);
+        for i in 0..NUM_SUBS {
+            let (input_tx, input_rx) = broadcast::channel(NUM_ITEMS);
+            runner.test_set_receiver(&format!("steps.s{}.out", i), input_rx)?;
+            senders.push(input_tx);
+        }
+        runner.run(mpsc::channel(1).0)?;
+
+        let send_tasks = senders
+            .into_iter()
+            .enumerate()
+            .map(|(sub, input_tx)| {
+                tokio::spawn(async move {
+                    for i in 0..NUM_ITEMS {
+                        let value = if Some(sub) == bad_sub && i == NUM_ITEMS / 2 {
+                            i + 1
+                        } else {
+                            i
+                        };
+                        input_tx
+                            .send(PatuiStepData::new(PatuiStepDataFlavour::Integer(
+                                value.to_string(),
+                            )))
+                            .unwrap();
+                        if i % 50 == 0 {
+                            tokio::task::yield_now().await;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for task in send_tasks {
+            task.await?;
+        }
+
+        timeout(Duration::from_secs(10), runner.wait()).await?
+    }
+
+    #[traced_test]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn assertion_many_subscriptions() {
+        assert_that!(stress_assertion(None).await).is_ok();
+
+        let res = stress_assertion(Some(7)).await;
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with("Assertion `check` failed");
+    }
+
+    #[traced_test]
+    #[test]
+    fn sampler_cadence() {
+        let start = Instant::now();
+
+        let mut sampler = Sampler::new(None);
+        assert_that!(sampler.is_due(1, start)).is_true();
+        assert_that!(sampler.is_due(1, start)).is_true();
+
+        let mut sampler = Sampler::new(Some(PatuiStepAssertionSample::EveryN(10)));
+        let due = (0..35)
+            .filter(|_| sampler.is_due(1, start))
+            .collect::<Vec<_>>();
+        assert_that!(due.len()).is_equal_to(3);
+        assert_that!(sampler.is_due(4, start)).is_false();
+        assert_that!(sampler.is_due(1, start)).is_true();
+
+        let mut sampler = Sampler::new(Some(PatuiStepAssertionSample::EveryMs(100)));
+        assert_that!(sampler.is_due(1, start)).is_true();
+        assert_that!(sampler.is_due(1, start + Duration::from_millis(50))).is_false();
+        assert_that!(sampler.is_due(1, start + Duration::from_millis(100))).is_true();
+        assert_that!(sampler.is_due(1, start + Duration::from_millis(150))).is_false();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn assertion_sampled() {
+        const NUM_ITEMS: usize = 1000;
+
+        let expected = (0..NUM_ITEMS)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut runner = PatuiStepRunnerAssertion::new(
+            "check".to_string(),
+            &PatuiStepAssertion {
+                expr: PatuiExpr::try_from(&format!("steps.foo.out == [{}]", expected)[..]).unwrap(),
+                sample: Some(PatuiStepAssertionSample::EveryN(100)),
+            },
+        );
+
+        let (input_tx, input_rx) = broadcast::channel(NUM_ITEMS);
+        assert_that!(runner.test_set_receiver("steps.foo.out", input_rx)).is_ok();
+        assert_that!(runner.run(mpsc::channel(1).0)).is_ok();
+
+        for i in 0..NUM_ITEMS {
+            input_tx
+                .send(PatuiStepData::new(PatuiStepDataFlavour::Integer(
+                    i.to_string(),
+                )))
+                .unwrap();
+            // Give the evaluator the chance to see items as they come rather than all at once
+            if i % 10 == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+        drop(input_tx);
+
+        let res = timeout(Duration::from_secs(5), runner.wait()).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+
+        // At most one for every 100 items plus the final evaluation with everything
+        assert_that!(runner.num_evaluations()).is_at_least(1);
+        assert_that!(runner.num_evaluations()).is_at_most(NUM_ITEMS / 100 + 1);
+    }
 }
@@ -0,0 +1,1714 @@
+//! Evaluating expressions against the step data received so far. The benchmarks pull this in by
+//! path so it mustn't refer to anything in the crate beyond the expression and step data types.
+
+use std::{cmp, collections::HashMap, fmt};
+
+use eyre::{eyre, Result};
+
+use crate::types::{
+    expr::ast::{integer_value, BinOp, ExprKind, Ident, Lit, LitKind, UnOp, P},
+    PatuiExpr, PatuiStepData, PatuiStepDataFlavour,
+};
+
+/// Everything received so far from one subscription, e.g. `steps.foo.out`.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct StepResults {
+    pub(crate) data: Vec<PatuiStepData>,
+    /// Whether the step has finished sending, after which what's been received is final.
+    pub(crate) is_closed: bool,
+    /// Whether this stands for a single item rather than everything received, e.g. the item a
+    /// transform is working on, in which case the reference evaluates to that item itself. The
+    /// item is only final once closed.
+    pub(crate) is_item: bool,
+}
+
+impl StepResults {
+    /// A reference that evaluates to just `data`.
+    pub(crate) fn item(data: PatuiStepData) -> Self {
+        Self {
+            data: vec![data],
+            is_closed: true,
+            is_item: true,
+        }
+    }
+}
+
+/// The result of evaluating an expression against the step data received so far.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EvalResult {
+    /// The value is final, nothing received later can change it.
+    Known(PatuiStepDataFlavour),
+    /// The value given what's been received so far, this can change as more data arrives.
+    Predictable(PatuiStepDataFlavour),
+    /// Not enough has been received to give a value yet.
+    Unknown,
+}
+
+impl EvalResult {
+    /// Apply `f` to the values of `inputs`, the result is only as certain as the least certain
+    /// of the inputs.
+    fn combine<F>(inputs: Vec<EvalResult>, f: F) -> Result<EvalResult>
+    where
+        F: FnOnce(Vec<PatuiStepDataFlavour>) -> Result<PatuiStepDataFlavour>,
+    {
+        let mut is_known = true;
+        let mut values = Vec::with_capacity(inputs.len());
+
+        for input in inputs {
+            match input {
+                EvalResult::Known(value) => values.push(value),
+                EvalResult::Predictable(value) => {
+                    is_known = false;
+                    values.push(value);
+                }
+                EvalResult::Unknown => return Ok(EvalResult::Unknown),
+            }
+        }
+
+        let value = f(values)?;
+
+        Ok(if is_known {
+            EvalResult::Known(value)
+        } else {
+            EvalResult::Predictable(value)
+        })
+    }
+}
+
+/// Evaluate `expr` where `results` holds everything received so far for each step reference
+/// subscribed to, e.g. `steps.foo.out`.
+pub(crate) fn eval(
+    expr: &PatuiExpr,
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> Result<EvalResult> {
+    if let Some(step_results) = results.get(expr) {
+        if step_results.is_item {
+            return Ok(step_results
+                .data
+                .first()
+                .map_or(EvalResult::Unknown, |item| {
+                    if step_results.is_closed {
+                        EvalResult::Known(item.data.clone())
+                    } else {
+                        EvalResult::Predictable(item.data.clone())
+                    }
+                }));
+        }
+
+        let data = PatuiStepDataFlavour::Array(
+            step_results
+                .data
+                .iter()
+                .map(|item| item.data.clone())
+                .collect(),
+        );
+        return Ok(if step_results.is_closed {
+            EvalResult::Known(data)
+        } else {
+            EvalResult::Predictable(data)
+        });
+    }
+
+    match expr.kind() {
+        ExprKind::Lit(lit) => Ok(EvalResult::Known(eval_lit(lit)?)),
+        ExprKind::Ident(ident) => Err(eyre!("Unknown identifier `{}`", ident.value)),
+        ExprKind::Field(receiver, field) => {
+            EvalResult::combine(vec![eval(receiver, results)?], |mut values| {
+                match values.pop().unwrap() {
+                    PatuiStepDataFlavour::Map(mut map) => map
+                        .remove(&field.value)
+                        .ok_or_else(|| eyre!("No field `{}` in `{}`", field.value, **receiver)),
+                    _ => Err(eyre!(
+                        "Can't get field `{}` of `{}`",
+                        field.value,
+                        **receiver
+                    )),
+                }
+            })
+        }
+        ExprKind::Index(receiver, index) => eval_index(receiver, index, results),
+        ExprKind::Slice(receiver, from, to) => {
+            eval_slice(receiver, from.as_deref(), to.as_deref(), results)
+        }
+        ExprKind::Call(func, args) => eval_call(func, args, results),
+        ExprKind::List(elems) => EvalResult::combine(eval_all(elems, results)?, |values| {
+            Ok(PatuiStepDataFlavour::Array(values))
+        }),
+        ExprKind::Set(elems) => EvalResult::combine(eval_all(elems, results)?, |values| {
+            Ok(PatuiStepDataFlavour::Set(values))
+        }),
+        ExprKind::Map(entries) => {
+            let mut keys = Vec::with_capacity(entries.len());
+            let mut values = Vec::with_capacity(entries.len());
+
+            for entry in entries {
+                let (key, value) = &**entry;
+                let ExprKind::Lit(Lit {
+                    kind: LitKind::Str(key),
+                }) = key.kind()
+                else {
+                    return Err(eyre!("Map keys must be strings, got `{}`", key));
+                };
+                keys.push(key.clone());
+                values.push(eval(value, results)?);
+            }
+
+            EvalResult::combine(values, |values| {
+                Ok(PatuiStepDataFlavour::Map(
+                    keys.into_iter().zip(values).collect(),
+                ))
+            })
+        }
+        ExprKind::BinOp(op @ (BinOp::Equal | BinOp::NotEqual), lhs, rhs) => {
+            // A list literal's order matters and a set literal's doesn't, so there's no saying
+            // which was meant
+            if matches!(
+                (lhs.kind(), rhs.kind()),
+                (ExprKind::List(_), ExprKind::Set(_)) | (ExprKind::Set(_), ExprKind::List(_))
+            ) {
+                return Err(eyre!(
+                    "`{}` compares a list with a set, make both sides lists if order matters or sets if it doesn't",
+                    expr
+                ));
+            }
+
+            let is_equal = matches!(op, BinOp::Equal);
+            EvalResult::combine(vec![eval(lhs, results)?, eval(rhs, results)?], |values| {
+                Ok(PatuiStepDataFlavour::Bool(
+                    equal(&values[0], &values[1]) == is_equal,
+                ))
+            })
+        }
+        ExprKind::BinOp(op @ (BinOp::Contains | BinOp::NotContains), item, collection) => {
+            let is_contains = matches!(op, BinOp::Contains);
+            EvalResult::combine(
+                vec![eval(item, results)?, eval(collection, results)?],
+                |values| {
+                    Ok(PatuiStepDataFlavour::Bool(
+                        contains(&values[1], &values[0], collection)? == is_contains,
+                    ))
+                },
+            )
+        }
+        ExprKind::BinOp(
+            op @ (BinOp::LessThan
+            | BinOp::LessThanEqual
+            | BinOp::GreaterThan
+            | BinOp::GreaterThanEqual),
+            lhs,
+            rhs,
+        ) => EvalResult::combine(vec![eval(lhs, results)?, eval(rhs, results)?], |values| {
+            let ordering = compare(&values[0], &values[1], expr)?;
+            Ok(PatuiStepDataFlavour::Bool(match op {
+                BinOp::LessThan => ordering.is_lt(),
+                BinOp::LessThanEqual => ordering.is_le(),
+                BinOp::GreaterThan => ordering.is_gt(),
+                BinOp::GreaterThanEqual => ordering.is_ge(),
+                _ => unreachable!(),
+            }))
+        }),
+        ExprKind::BinOp(
+            op @ (BinOp::Add | BinOp::Subtract | BinOp::Multiply | BinOp::Divide | BinOp::Modulo),
+            lhs,
+            rhs,
+        ) => EvalResult::combine(
+            vec![eval(lhs, results)?, eval(rhs, results)?],
+            |mut values| {
+                let rhs = values.pop().unwrap();
+                let lhs = values.pop().unwrap();
+                arithmetic(op, lhs, rhs, expr)
+            },
+        ),
+        ExprKind::BinOp(op @ (BinOp::And | BinOp::Or), lhs, rhs) => {
+            let is_and = matches!(op, BinOp::And);
+
+            // A final false for `&&` or true for `||` settles it whatever the other side is, so
+            // one failed half doesn't wait on a stream that hasn't sent anything yet
+            let settled = EvalResult::Known(PatuiStepDataFlavour::Bool(!is_and));
+            let lhs = eval(lhs, results)?;
+            if lhs == settled {
+                return Ok(settled);
+            }
+            let rhs = eval(rhs, results)?;
+            if rhs == settled
+                && matches!(
+                    lhs,
+                    EvalResult::Unknown
+                        | EvalResult::Known(PatuiStepDataFlavour::Bool(_))
+                        | EvalResult::Predictable(PatuiStepDataFlavour::Bool(_))
+                )
+            {
+                return Ok(settled);
+            }
+
+            EvalResult::combine(vec![lhs, rhs], |values| match (&values[0], &values[1]) {
+                (PatuiStepDataFlavour::Bool(a), PatuiStepDataFlavour::Bool(b)) => {
+                    Ok(PatuiStepDataFlavour::Bool(if is_and {
+                        *a && *b
+                    } else {
+                        *a || *b
+                    }))
+                }
+                _ => Err(eyre!(
+                    "Both sides of `{}` should be bools, got {:?}",
+                    expr,
+                    values
+                )),
+            })
+        }
+        // Negating keeps the certainty of what's negated, so `!(a == b)` is the same as `a != b`
+        ExprKind::UnOp(UnOp::Not, inner) => {
+            EvalResult::combine(vec![eval(inner, results)?], |mut values| {
+                match values.pop().unwrap() {
+                    PatuiStepDataFlavour::Bool(b) => Ok(PatuiStepDataFlavour::Bool(!b)),
+                    value => Err(eyre!(
+                        "`{}` should be a bool to negate, got {:?}",
+                        **inner,
+                        value
+                    )),
+                }
+            })
+        }
+        ExprKind::UnOp(UnOp::Neg, inner) => {
+            EvalResult::combine(vec![eval(inner, results)?], |mut values| {
+                match values.pop().unwrap() {
+                    PatuiStepDataFlavour::Integer(value) => Ok(PatuiStepDataFlavour::Integer(
+                        parse_integer(&value)?
+                            .checked_neg()
+                            .ok_or_else(|| eyre!("`{}` overflowed", expr))?
+                            .to_string(),
+                    )),
+                    PatuiStepDataFlavour::Float(value) => Ok(PatuiStepDataFlavour::Float(format!(
+                        "{:?}",
+                        -parse_float(&value)?
+                    ))),
+                    value => Err(eyre!(
+                        "`{}` should be a number to negate, got {:?}",
+                        **inner,
+                        value
+                    )),
+                }
+            })
+        }
+        ExprKind::If(_, _, _) => Err(eyre!("If expressions aren't supported yet")),
+    }
+}
+
+fn eval_all(
+    exprs: &[P<PatuiExpr>],
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> Result<Vec<EvalResult>> {
+    exprs.iter().map(|expr| eval(expr, results)).collect()
+}
+
+fn eval_lit(lit: &Lit) -> Result<PatuiStepDataFlavour> {
+    match &lit.kind {
+        LitKind::Bool(b) => Ok(PatuiStepDataFlavour::Bool(*b)),
+        LitKind::Bytes(bytes) => Ok(PatuiStepDataFlavour::Bytes(bytes.clone())),
+        LitKind::Integer(int) => Ok(PatuiStepDataFlavour::Integer(int.clone())),
+        LitKind::Decimal(dec) => Ok(PatuiStepDataFlavour::Float(dec.clone())),
+        LitKind::Str(s) => Ok(PatuiStepDataFlavour::String(s.clone())),
+        LitKind::Token(token) => Err(eyre!("Can't evaluate `{}` on its own", token)),
+    }
+}
+
+fn eval_index(
+    receiver: &PatuiExpr,
+    index: &PatuiExpr,
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> Result<EvalResult> {
+    let receiver_res = eval(receiver, results)?;
+    let index_res = eval(index, results)?;
+
+    let (EvalResult::Known(index_value) | EvalResult::Predictable(index_value)) = &index_res else {
+        return Ok(EvalResult::Unknown);
+    };
+
+    match (&receiver_res, index_value) {
+        (
+            EvalResult::Known(PatuiStepDataFlavour::Array(items))
+            | EvalResult::Predictable(PatuiStepDataFlavour::Array(items)),
+            PatuiStepDataFlavour::Integer(idx),
+        ) => {
+            let idx = parse_index(idx)?;
+            match items.get(idx) {
+                // Items already received never change, only how many there are
+                Some(item) if matches!(index_res, EvalResult::Known(_)) => {
+                    Ok(EvalResult::Known(item.clone()))
+                }
+                Some(item) => Ok(EvalResult::Predictable(item.clone())),
+                None if matches!(receiver_res, EvalResult::Predictable(_)) => {
+                    Ok(EvalResult::Unknown)
+                }
+                None => Err(eyre!("Index {} out of range for `{}`", idx, receiver)),
+            }
+        }
+        (
+            EvalResult::Known(PatuiStepDataFlavour::Bytes(bytes))
+            | EvalResult::Predictable(PatuiStepDataFlavour::Bytes(bytes)),
+            PatuiStepDataFlavour::Integer(idx),
+        ) => {
+            let idx = parse_index(idx)?;
+            match bytes.get(idx) {
+                // A single byte is its value as a number
+                Some(&byte) => EvalResult::combine(vec![receiver_res.clone(), index_res], |_| {
+                    Ok(i64::from(byte).into())
+                }),
+                None if matches!(receiver_res, EvalResult::Predictable(_)) => {
+                    Ok(EvalResult::Unknown)
+                }
+                None => Err(eyre!("Index {} out of range for `{}`", idx, receiver)),
+            }
+        }
+        (EvalResult::Unknown, _) => Ok(EvalResult::Unknown),
+        _ => EvalResult::combine(vec![receiver_res, index_res], |mut values| {
+            let index_value = values.pop().unwrap();
+            match (values.pop().unwrap(), index_value) {
+                (PatuiStepDataFlavour::Map(mut map), PatuiStepDataFlavour::String(key)) => map
+                    .remove(&key)
+                    .ok_or_else(|| eyre!("No key `{}` in `{}`", key, receiver)),
+                _ => Err(eyre!("Can't index `{}` with `{}`", receiver, index)),
+            }
+        }),
+    }
+}
+
+/// Evaluate `receiver[from:to]` for bytes and lists, `from` defaults to the start and `to` to the
+/// end.
+fn eval_slice(
+    receiver: &PatuiExpr,
+    from: Option<&PatuiExpr>,
+    to: Option<&PatuiExpr>,
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> Result<EvalResult> {
+    let receiver_res = eval(receiver, results)?;
+    let (EvalResult::Known(value) | EvalResult::Predictable(value)) = &receiver_res else {
+        return Ok(EvalResult::Unknown);
+    };
+    let is_open = matches!(receiver_res, EvalResult::Predictable(_));
+
+    let mut is_known = true;
+    let mut bounds = [None, None];
+    for (bound, expr) in bounds.iter_mut().zip([from, to]) {
+        let Some(expr) = expr else {
+            continue;
+        };
+        match eval(expr, results)? {
+            EvalResult::Known(PatuiStepDataFlavour::Integer(idx)) => {
+                *bound = Some(parse_index(&idx)?)
+            }
+            EvalResult::Predictable(PatuiStepDataFlavour::Integer(idx)) => {
+                is_known = false;
+                *bound = Some(parse_index(&idx)?);
+            }
+            EvalResult::Unknown => return Ok(EvalResult::Unknown),
+            _ => return Err(eyre!("Can't slice `{}` with `{}`", receiver, expr)),
+        }
+    }
+    let [from, to] = bounds;
+    // Items already received never change but a slice to the end grows as more arrive
+    let is_known = is_known && (!is_open || to.is_some());
+
+    let range = |len: usize| {
+        let range = from.unwrap_or(0)..to.unwrap_or(len);
+        if range.start <= range.end && range.end <= len {
+            Ok(Some(range))
+        } else if is_open && (range.start <= range.end || to.is_none()) {
+            // Not enough items have arrived yet, only an explicitly reversed slice is an error
+            Ok(None)
+        } else {
+            Err(eyre!(
+                "Slice {}..{} out of range for `{}` of length {}",
+                range.start,
+                range.end,
+                receiver,
+                len
+            ))
+        }
+    };
+    let value = match value {
+        PatuiStepDataFlavour::Bytes(bytes) => match range(bytes.len())? {
+            Some(range) => PatuiStepDataFlavour::Bytes(bytes.slice(range)),
+            None => return Ok(EvalResult::Unknown),
+        },
+        PatuiStepDataFlavour::Array(items) => match range(items.len())? {
+            Some(range) => PatuiStepDataFlavour::Array(items[range].to_vec()),
+            None => return Ok(EvalResult::Unknown),
+        },
+        _ => {
+            return Err(eyre!(
+                "Can't slice `{}`, only bytes and lists can be",
+                receiver
+            ))
+        }
+    };
+
+    Ok(if is_known {
+        EvalResult::Known(value)
+    } else {
+        EvalResult::Predictable(value)
+    })
+}
+
+fn eval_call(
+    func: &PatuiExpr,
+    args: &[P<PatuiExpr>],
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> Result<EvalResult> {
+    let ExprKind::Field(receiver, Ident { value: name }) = func.kind() else {
+        return eval_function(func, args, results);
+    };
+
+    match name.as_str() {
+        "trim" | "lower" | "upper" => {
+            if !args.is_empty() {
+                return Err(eyre!("`{}()` takes no arguments", name));
+            }
+
+            EvalResult::combine(vec![eval(receiver, results)?], |mut values| {
+                let value = values.pop().unwrap();
+                let value = as_str(&value, name)?;
+
+                Ok(PatuiStepDataFlavour::String(match name.as_str() {
+                    "trim" => value.trim().to_string(),
+                    "lower" => value.to_lowercase(),
+                    _ => value.to_uppercase(),
+                }))
+            })
+        }
+        "to_string" => {
+            if !args.is_empty() {
+                return Err(eyre!("`{}()` takes no arguments", name));
+            }
+
+            EvalResult::combine(vec![eval(receiver, results)?], |mut values| {
+                Ok(PatuiStepDataFlavour::String(match values.pop().unwrap() {
+                    PatuiStepDataFlavour::String(s) => s,
+                    PatuiStepDataFlavour::Integer(value) => parse_integer(&value)?.to_string(),
+                    PatuiStepDataFlavour::Float(value) => value,
+                    PatuiStepDataFlavour::Bool(value) => value.to_string(),
+                    value => {
+                        return Err(eyre!(
+                            "`to_string()` expects a string, number or bool, got {:?}",
+                            value
+                        ))
+                    }
+                }))
+            })
+        }
+        "len" => {
+            if !args.is_empty() {
+                return Err(eyre!("`{}()` takes no arguments", name));
+            }
+
+            EvalResult::combine(vec![eval(receiver, results)?], |mut values| {
+                let len = match values.pop().unwrap() {
+                    PatuiStepDataFlavour::String(s) => s.chars().count(),
+                    PatuiStepDataFlavour::Bytes(bytes) => bytes.len(),
+                    PatuiStepDataFlavour::Array(items) | PatuiStepDataFlavour::Set(items) => {
+                        items.len()
+                    }
+                    PatuiStepDataFlavour::Map(map) => map.len(),
+                    value => {
+                        return Err(eyre!(
+                            "`len()` expects a string, bytes or a collection, got {:?}",
+                            value
+                        ))
+                    }
+                };
+
+                Ok(PatuiStepDataFlavour::Integer(len.to_string()))
+            })
+        }
+        // Both ordered by key so the same map always gives the same array
+        "keys" | "values" => {
+            if !args.is_empty() {
+                return Err(eyre!("`{}()` takes no arguments", name));
+            }
+
+            EvalResult::combine(vec![eval(receiver, results)?], |mut values| {
+                let PatuiStepDataFlavour::Map(map) = values.pop().unwrap() else {
+                    return Err(eyre!("`{}()` expects a map, got `{}`", name, **receiver));
+                };
+
+                let mut entries = map.into_iter().collect::<Vec<_>>();
+                entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+                Ok(PatuiStepDataFlavour::Array(
+                    entries
+                        .into_iter()
+                        .map(|(key, value)| match name.as_str() {
+                            "keys" => PatuiStepDataFlavour::String(key),
+                            _ => value,
+                        })
+                        .collect(),
+                ))
+            })
+        }
+        _ => Err(eyre!("Unknown method `{}` on `{}`", name, **receiver)),
+    }
+}
+
+/// Evaluate a call to a function that isn't a method on some value, e.g. `zip(a, b)`.
+fn eval_function(
+    func: &PatuiExpr,
+    args: &[P<PatuiExpr>],
+    results: &HashMap<PatuiExpr, StepResults>,
+) -> Result<EvalResult> {
+    let ExprKind::Ident(Ident { value: name }) = func.kind() else {
+        return Err(eyre!("Unknown function `{}`", func));
+    };
+
+    match name.as_str() {
+        // Pairs up items by the order they arrived in, stopping at the end of the shorter, so
+        // while a step's still sending the pairs so far are predictable and indexing an existing
+        // pair is known
+        "zip" => {
+            if args.len() != 2 {
+                return Err(eyre!("`zip()` takes two arguments, got {}", args.len()));
+            }
+
+            EvalResult::combine(eval_all(args, results)?, |values| {
+                let mut arrays = values.into_iter().map(|value| match value {
+                    PatuiStepDataFlavour::Array(items) => Ok(items),
+                    value => Err(eyre!("`zip()` expects arrays, got {:?}", value)),
+                });
+                let lhs = arrays.next().unwrap()?;
+                let rhs = arrays.next().unwrap()?;
+
+                Ok(PatuiStepDataFlavour::Array(
+                    lhs.into_iter()
+                        .zip(rhs)
+                        .map(|(a, b)| PatuiStepDataFlavour::Array(vec![a, b]))
+                        .collect(),
+                ))
+            })
+        }
+        _ => Err(eyre!("Unknown function `{}`", func)),
+    }
+}
+
+/// Errors evaluating an expression that callers may want to tell apart from a badly written
+/// expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum EvalError {
+    /// The expression given divided by zero, or took the remainder of dividing by zero.
+    DivisionByZero(String),
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvalError::DivisionByZero(expr) => write!(f, "Division by zero in `{}`", expr),
+        }
+    }
+}
+
+impl std::error::Error for EvalError {}
+
+/// Apply `+`, `-`, `*`, `/` or `%` to two numbers, or `+` to two strings or two bytes, which
+/// concatenates them. Integers stay integers, so division truncates and the remainder takes the
+/// sign of the left hand side, anything involving a float gives a float.
+fn arithmetic(
+    op: &BinOp,
+    lhs: PatuiStepDataFlavour,
+    rhs: PatuiStepDataFlavour,
+    expr: &PatuiExpr,
+) -> Result<PatuiStepDataFlavour> {
+    match (lhs, rhs) {
+        (PatuiStepDataFlavour::Integer(lhs), PatuiStepDataFlavour::Integer(rhs)) => {
+            let lhs = parse_integer(&lhs)?;
+            let rhs = parse_integer(&rhs)?;
+
+            if matches!(op, BinOp::Divide | BinOp::Modulo) && rhs == 0 {
+                return Err(EvalError::DivisionByZero(expr.to_string()).into());
+            }
+
+            let value = match op {
+                BinOp::Add => lhs.checked_add(rhs),
+                BinOp::Subtract => lhs.checked_sub(rhs),
+                BinOp::Multiply => lhs.checked_mul(rhs),
+                BinOp::Divide => lhs.checked_div(rhs),
+                BinOp::Modulo => lhs.checked_rem(rhs),
+                _ => unreachable!(),
+            }
+            .ok_or_else(|| eyre!("`{}` overflowed", expr))?;
+
+            Ok(PatuiStepDataFlavour::Integer(value.to_string()))
+        }
+        (
+            PatuiStepDataFlavour::Integer(lhs) | PatuiStepDataFlavour::Float(lhs),
+            PatuiStepDataFlavour::Integer(rhs) | PatuiStepDataFlavour::Float(rhs),
+        ) => {
+            let lhs = parse_float(&lhs)?;
+            let rhs = parse_float(&rhs)?;
+
+            if matches!(op, BinOp::Divide | BinOp::Modulo) && rhs == 0.0 {
+                return Err(EvalError::DivisionByZero(expr.to_string()).into());
+            }
+
+            let value = match op {
+                BinOp::Add => lhs + rhs,
+                BinOp::Subtract => lhs - rhs,
+                BinOp::Multiply => lhs * rhs,
+                BinOp::Divide => lhs / rhs,
+                BinOp::Modulo => lhs % rhs,
+                _ => unreachable!(),
+            };
+
+            // Debug formatting keeps the `.0` on whole numbers so the result still reads as a float
+            Ok(PatuiStepDataFlavour::Float(format!("{:?}", value)))
+        }
+        (PatuiStepDataFlavour::String(lhs), PatuiStepDataFlavour::String(rhs))
+            if matches!(op, BinOp::Add) =>
+        {
+            Ok(PatuiStepDataFlavour::String(lhs + &rhs))
+        }
+        (PatuiStepDataFlavour::Bytes(lhs), PatuiStepDataFlavour::Bytes(rhs))
+            if matches!(op, BinOp::Add) =>
+        {
+            Ok(PatuiStepDataFlavour::Bytes([lhs, rhs].concat().into()))
+        }
+        (
+            PatuiStepDataFlavour::String(_),
+            PatuiStepDataFlavour::Integer(_) | PatuiStepDataFlavour::Float(_),
+        )
+        | (
+            PatuiStepDataFlavour::Integer(_) | PatuiStepDataFlavour::Float(_),
+            PatuiStepDataFlavour::String(_),
+        ) if matches!(op, BinOp::Add) => Err(eyre!(
+            "Can't add a string and a number in `{}`, use `to_string()` on the number first",
+            expr
+        )),
+        (lhs, rhs) => Err(eyre!(
+            "Can't evaluate `{}` with {:?} and {:?}",
+            expr,
+            lhs,
+            rhs
+        )),
+    }
+}
+
+/// Order two numbers, or two strings lexicographically. An integer and a float are compared as
+/// floats, anything else can't be ordered.
+fn compare(
+    lhs: &PatuiStepDataFlavour,
+    rhs: &PatuiStepDataFlavour,
+    expr: &PatuiExpr,
+) -> Result<cmp::Ordering> {
+    match (lhs, rhs) {
+        (PatuiStepDataFlavour::Integer(lhs), PatuiStepDataFlavour::Integer(rhs)) => {
+            Ok(parse_integer(lhs)?.cmp(&parse_integer(rhs)?))
+        }
+        (
+            PatuiStepDataFlavour::Integer(lhs) | PatuiStepDataFlavour::Float(lhs),
+            PatuiStepDataFlavour::Integer(rhs) | PatuiStepDataFlavour::Float(rhs),
+        ) => parse_float(lhs)?
+            .partial_cmp(&parse_float(rhs)?)
+            .ok_or_else(|| eyre!("Can't order NaN in `{}`", expr)),
+        (PatuiStepDataFlavour::String(lhs), PatuiStepDataFlavour::String(rhs)) => Ok(lhs.cmp(rhs)),
+        (lhs, rhs) => Err(eyre!("Can't compare {:?} and {:?} in `{}`", lhs, rhs, expr)),
+    }
+}
+
+fn parse_integer(value: &str) -> Result<i128> {
+    integer_value(value).ok_or_else(|| eyre!("`{}` isn't a valid integer", value))
+}
+
+fn parse_index(value: &str) -> Result<usize> {
+    usize::try_from(parse_integer(value)?).map_err(|_| eyre!("`{}` isn't a valid index", value))
+}
+
+fn parse_float(value: &str) -> Result<f64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0b"))
+    {
+        Some(_) => Ok(parse_integer(value)? as f64),
+        None => value
+            .parse()
+            .map_err(|e| eyre!("`{}` isn't a valid number: {}", value, e)),
+    }
+}
+
+/// Whether `lhs` and `rhs` are equal. Lists are equal when they have equal elements in the same
+/// order, whereas a set is equal to a set or list, such as a step's output, with the same elements
+/// in any order, each as many times.
+pub(crate) fn equal(lhs: &PatuiStepDataFlavour, rhs: &PatuiStepDataFlavour) -> bool {
+    match (lhs, rhs) {
+        (PatuiStepDataFlavour::Array(lhs), PatuiStepDataFlavour::Array(rhs)) => {
+            lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| equal(lhs, rhs))
+        }
+        (
+            PatuiStepDataFlavour::Set(lhs),
+            PatuiStepDataFlavour::Set(rhs) | PatuiStepDataFlavour::Array(rhs),
+        )
+        | (PatuiStepDataFlavour::Array(lhs), PatuiStepDataFlavour::Set(rhs)) => {
+            PatuiStepDataFlavour::same_items(lhs, rhs, equal)
+        }
+        (PatuiStepDataFlavour::Map(lhs), PatuiStepDataFlavour::Map(rhs)) => {
+            lhs.len() == rhs.len()
+                && lhs
+                    .iter()
+                    .all(|(key, lhs)| rhs.get(key).is_some_and(|rhs| equal(lhs, rhs)))
+        }
+        _ => lhs == rhs,
+    }
+}
+
+/// Whether `item` is in `collection`, i.e. an element of a list or set, a key of a map or part of
+/// a string or bytes. A single byte can also be looked for by its value, as indexing bytes gives.
+fn contains(
+    collection: &PatuiStepDataFlavour,
+    item: &PatuiStepDataFlavour,
+    collection_expr: &PatuiExpr,
+) -> Result<bool> {
+    match (collection, item) {
+        (PatuiStepDataFlavour::Array(items) | PatuiStepDataFlavour::Set(items), _) => {
+            Ok(items.iter().any(|elem| equal(elem, item)))
+        }
+        (PatuiStepDataFlavour::Map(map), PatuiStepDataFlavour::String(key)) => {
+            Ok(map.contains_key(key))
+        }
+        (PatuiStepDataFlavour::String(s), PatuiStepDataFlavour::String(sub)) => {
+            Ok(s.contains(sub.as_str()))
+        }
+        (PatuiStepDataFlavour::Bytes(bytes), PatuiStepDataFlavour::Bytes(sub)) => {
+            Ok(sub.is_empty() || bytes.windows(sub.len()).any(|window| window == &sub[..]))
+        }
+        (PatuiStepDataFlavour::Bytes(bytes), PatuiStepDataFlavour::Integer(value)) => {
+            let value = parse_integer(value)?;
+            Ok(bytes.iter().any(|byte| i128::from(*byte) == value))
+        }
+        _ => Err(eyre!("Can't look for {:?} in `{}`", item, collection_expr)),
+    }
+}
+
+/// Strings are taken as they are and bytes are accepted as long as they're valid UTF-8.
+fn as_str<'a>(value: &'a PatuiStepDataFlavour, func_name: &str) -> Result<&'a str> {
+    match value {
+        PatuiStepDataFlavour::String(s) => Ok(s),
+        PatuiStepDataFlavour::Bytes(bytes) => std::str::from_utf8(bytes)
+            .map_err(|e| eyre!("`{}()` needs bytes to be valid UTF-8: {}", func_name, e)),
+        _ => Err(eyre!("`{}()` expects a string, got {:?}", func_name, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use bytes::Bytes;
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn eval_str(expr: &str, results: &HashMap<PatuiExpr, StepResults>) -> Result<EvalResult> {
+        eval(&PatuiExpr::try_from(expr).unwrap(), results)
+    }
+
+    fn step_results(data: Vec<PatuiStepDataFlavour>) -> HashMap<PatuiExpr, StepResults> {
+        HashMap::from([(
+            "steps.foo.out".try_into().unwrap(),
+            StepResults {
+                data: data.into_iter().map(PatuiStepData::new).collect(),
+                ..Default::default()
+            },
+        )])
+    }
+
+    /// Results for several steps that haven't finished yet.
+    fn open_step_results<const N: usize>(
+        steps: [(PatuiExpr, Vec<PatuiStepData>); N],
+    ) -> HashMap<PatuiExpr, StepResults> {
+        steps
+            .into_iter()
+            .map(|(expr, data)| {
+                (
+                    expr,
+                    StepResults {
+                        data,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn closed_step_results(data: Vec<PatuiStepDataFlavour>) -> HashMap<PatuiExpr, StepResults> {
+        let mut results = step_results(data);
+        for step_results in results.values_mut() {
+            step_results.is_closed = true;
+        }
+        results
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_lits() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [
+            ("123", PatuiStepDataFlavour::Integer("123".to_string())),
+            ("1.5", PatuiStepDataFlavour::Float("1.5".to_string())),
+            ("true", PatuiStepDataFlavour::Bool(true)),
+            ("\"abc\"", PatuiStepDataFlavour::String("abc".to_string())),
+            (
+                "[1, \"a\"]",
+                PatuiStepDataFlavour::Array(vec![
+                    PatuiStepDataFlavour::Integer("1".to_string()),
+                    PatuiStepDataFlavour::String("a".to_string()),
+                ]),
+            ),
+            (
+                "{\"a\": 1}[\"a\"]",
+                PatuiStepDataFlavour::Integer("1".to_string()),
+            ),
+            ("+5", PatuiStepDataFlavour::Integer("5".to_string())),
+            ("1 == 1", PatuiStepDataFlavour::Bool(true)),
+            ("\"a\" == \"b\"", PatuiStepDataFlavour::Bool(false)),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_arithmetic() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [
+            ("1 + 2", PatuiStepDataFlavour::Integer("3".to_string())),
+            ("0x10 - 1", PatuiStepDataFlavour::Integer("15".to_string())),
+            ("-3 * 4", PatuiStepDataFlavour::Integer("-12".to_string())),
+            ("2.5 * 2", PatuiStepDataFlavour::Float("5.0".to_string())),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+
+        for (expr, error) in [
+            ("\"a\" * 2", "Can't evaluate `\"a\" * 2`"),
+            (
+                "170141183460469231731687303715884105727 + 1",
+                "`170141183460469231731687303715884105727 + 1` overflowed",
+            ),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).starts_with(error);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_logic() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [("true && false", false), ("true || false", true)] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap())
+                .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(expected)));
+        }
+
+        let res = eval_str("1 && true", &results);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .starts_with("Both sides of `1 && true` should be bools");
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_concatenation() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [
+            (
+                "\"foo\" + \"bar\"",
+                PatuiStepDataFlavour::String("foobar".to_string()),
+            ),
+            (
+                "b\"ab\" + b\"c\"",
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(b"abc")),
+            ),
+            (
+                "\"id-\" + 0x10.to_string()",
+                PatuiStepDataFlavour::String("id-16".to_string()),
+            ),
+            (
+                "(\"foo\" + \"bar\") == \"foobar\"",
+                PatuiStepDataFlavour::Bool(true),
+            ),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+
+        for (expr, error) in [
+            (
+                "\"id-\" + 1",
+                "Can't add a string and a number in `\"id-\" + 1`, use `to_string()`",
+            ),
+            ("1.5 + \"a\"", "Can't add a string and a number"),
+            ("\"a\" + b\"a\"", "Can't evaluate `\"a\" + b\"a\"`"),
+            ("\"a\" - \"b\"", "Can't evaluate `\"a\" - \"b\"`"),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).starts_with(error);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_bytes_slicing() {
+        let results = step_results(vec![PatuiStepDataFlavour::Bytes(Bytes::from_static(&[
+            0x00, 0x01, 0xfe, 0xff, 0x10, 0x20,
+        ]))]);
+
+        for (expr, expected) in [
+            (
+                "steps.foo.out[0][2:5]",
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(&[0xfe, 0xff, 0x10])),
+            ),
+            (
+                "steps.foo.out[0][:2]",
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(&[0x00, 0x01])),
+            ),
+            (
+                "steps.foo.out[0][4:]",
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(&[0x10, 0x20])),
+            ),
+            (
+                "steps.foo.out[0][3:3]",
+                PatuiStepDataFlavour::Bytes(Bytes::new()),
+            ),
+            (
+                "steps.foo.out[0][6:]",
+                PatuiStepDataFlavour::Bytes(Bytes::new()),
+            ),
+            ("steps.foo.out[0][3]", PatuiStepDataFlavour::from(255)),
+            ("steps.foo.out[0][4 / 2]", PatuiStepDataFlavour::from(254)),
+            // Indices are read the same whichever way the integer is written
+            ("steps.foo.out[0][0x3]", PatuiStepDataFlavour::from(255)),
+            (
+                "steps.foo.out[0][0b10:0x4]",
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(&[0xfe, 0xff])),
+            ),
+            (
+                "steps.foo.out[0][4:] == steps.foo.out[0][4:6]",
+                PatuiStepDataFlavour::Bool(true),
+            ),
+            ("[1, 2, 3][1:] == [2, 3]", PatuiStepDataFlavour::Bool(true)),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+
+        for (expr, error) in [
+            (
+                "steps.foo.out[0][6]",
+                "Index 6 out of range for `steps.foo.out[0]`",
+            ),
+            (
+                "steps.foo.out[0][4:7]",
+                "Slice 4..7 out of range for `steps.foo.out[0]` of length 6",
+            ),
+            (
+                "steps.foo.out[0][\"a\":]",
+                "Can't slice `steps.foo.out[0]` with `\"a\"`",
+            ),
+            ("\"abc\"[1:]", "Can't slice `\"abc\"`"),
+            ("steps.foo.out[0][-1]", "`-1` isn't a valid index"),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).starts_with(error);
+        }
+
+        // Reversed literal bounds are already rejected when parsing
+        assert_that!(PatuiExpr::try_from("steps.foo.out[0][4:2]")).is_err();
+
+        // More items can still arrive on the stream so past the end isn't known yet
+        assert_that!(eval_str("steps.foo.out[0:2]", &results).unwrap())
+            .is_equal_to(EvalResult::Unknown);
+        assert_that!(eval_str("steps.foo.out[3:]", &results).unwrap())
+            .is_equal_to(EvalResult::Unknown);
+        assert_that!(eval_str("steps.foo.out[:1]", &results).unwrap()).is_equal_to(
+            EvalResult::Known(PatuiStepDataFlavour::Array(vec![
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(&[
+                    0x00, 0x01, 0xfe, 0xff, 0x10, 0x20,
+                ])),
+            ])),
+        );
+
+        // Whereas once the step's finished there's nothing more to come
+        let closed = closed_step_results(vec![PatuiStepDataFlavour::Bytes(Bytes::new())]);
+        let res = eval_str("steps.foo.out[3:]", &closed);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .starts_with("Slice 3..1 out of range for `steps.foo.out` of length 1");
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_division() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [
+            ("7 / 2", PatuiStepDataFlavour::Integer("3".to_string())),
+            ("-7 / 2", PatuiStepDataFlavour::Integer("-3".to_string())),
+            ("7.0 / 2", PatuiStepDataFlavour::Float("3.5".to_string())),
+            ("7 / 2.0", PatuiStepDataFlavour::Float("3.5".to_string())),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+
+        for expr in ["1 / 0", "1.5 / 0.0", "1 % 0", "1.5 % 0.0"] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().downcast_ref::<EvalError>())
+                .is_equal_to(Some(&EvalError::DivisionByZero(expr.to_string())));
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_comparisons() {
+        let results = step_results(vec![PatuiStepDataFlavour::Integer("3".to_string())]);
+
+        for (expr, expected) in [
+            ("1 < 2", true),
+            ("2 < 1", false),
+            ("3 < 3", false),
+            ("3 <= 3", true),
+            ("4 <= 3", false),
+            ("2 > 1", true),
+            ("3 > 3", false),
+            ("3 >= 3", true),
+            ("2 >= 3", false),
+            ("1 != 2", true),
+            ("3 != 3", false),
+            ("-1 < 0x10", true),
+            ("2.5 < 3", true),
+            ("3 <= 3.0", true),
+            ("1.5 >= 1.25", true),
+            ("\"abc\" < \"abd\"", true),
+            ("\"b\" > \"abc\"", true),
+            ("\"abc\" >= \"abc\"", true),
+            ("steps.foo.out[0] >= 3", true),
+            ("steps.foo.out[0] > 3", false),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap())
+                .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(expected)));
+        }
+
+        assert_that!(eval_str("steps.foo.out[1] < 3", &results).unwrap())
+            .is_equal_to(EvalResult::Unknown);
+
+        for expr in ["\"a\" < 1", "1 >= \"a\"", "true < false", "[1] < [2]"] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).starts_with("Can't compare");
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_modulo() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [
+            ("10 % 3", PatuiStepDataFlavour::Integer("1".to_string())),
+            ("-10 % 3", PatuiStepDataFlavour::Integer("-1".to_string())),
+            ("0x10 % 5", PatuiStepDataFlavour::Integer("1".to_string())),
+            ("7.5 % 2", PatuiStepDataFlavour::Float("1.5".to_string())),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+
+        assert_that!(eval_str("\"a\" % 2", &results)).is_err();
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_step_arithmetic() {
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Integer("10".to_string()),
+            PatuiStepDataFlavour::Float("2.5".to_string()),
+        ]);
+
+        for expr in [
+            "(steps.foo.out[0] + 2) == 12",
+            "(steps.foo.out[0] - 12) == -2",
+            "(steps.foo.out[0] % 3) == 1",
+            "(steps.foo.out[0] / 4) == 2",
+            "(steps.foo.out[1] * 2) == 5.0",
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap())
+                .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(true)));
+        }
+
+        // Nothing received yet so the sum isn't known either
+        assert_that!(eval_str("(steps.foo.out[0] + 1) == 2", &step_results(vec![])).unwrap())
+            .is_equal_to(EvalResult::Unknown);
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_step_refs() {
+        let results = step_results(vec![]);
+        assert_that!(eval_str("steps.foo.out[0] == \"ready\"", &results).unwrap())
+            .is_equal_to(EvalResult::Unknown);
+
+        let results = step_results(vec![PatuiStepDataFlavour::String("ready".to_string())]);
+        assert_that!(eval_str("steps.foo.out[0] == \"ready\"", &results).unwrap())
+            .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(true)));
+        assert_that!(eval_str("steps.foo.out == [\"ready\"]", &results).unwrap())
+            .is_equal_to(EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)));
+
+        assert_that!(eval_str("steps.bar.out[0]", &results)).is_err();
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_string_builtins() {
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Bytes(Bytes::from(" READY\n")),
+            PatuiStepDataFlavour::String("Mixed Case".to_string()),
+        ]);
+
+        for (expr, expected) in [
+            ("\"  padded \t\".trim()", "padded"),
+            ("\"Hello\".lower()", "hello"),
+            ("\"Hello\".upper()", "HELLO"),
+            ("steps.foo.out[0].trim()", "READY"),
+            ("steps.foo.out[1].upper()", "MIXED CASE"),
+            ("b\" abc \".trim().upper()", "ABC"),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(
+                PatuiStepDataFlavour::String(expected.to_string()),
+            ));
+        }
+
+        assert_that!(eval_str("steps.foo.out[0].trim().lower() == \"ready\"", &results).unwrap())
+            .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(true)));
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_string_builtins_errors() {
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Integer("1".to_string()),
+            PatuiStepDataFlavour::Bytes(Bytes::from(vec![0xff, 0xfe])),
+        ]);
+
+        for (expr, expected_err) in [
+            ("steps.foo.out[0].trim()", "`trim()` expects a string"),
+            ("[\"a\"].lower()", "`lower()` expects a string"),
+            (
+                "steps.foo.out[1].upper()",
+                "`upper()` needs bytes to be valid UTF-8",
+            ),
+            ("\"a\".trim(1)", "`trim()` takes no arguments"),
+            ("\"a\".reverse()", "Unknown method `reverse`"),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).starts_with(expected_err);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_len() {
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Bytes(Bytes::from("abc")),
+            PatuiStepDataFlavour::Integer("1".to_string()),
+        ]);
+
+        for (expr, expected) in [
+            (
+                "\"héllo\".len()",
+                EvalResult::Known(PatuiStepDataFlavour::Integer("5".to_string())),
+            ),
+            (
+                "[1, 2, 3].len()",
+                EvalResult::Known(PatuiStepDataFlavour::Integer("3".to_string())),
+            ),
+            (
+                "steps.foo.out[0].len()",
+                EvalResult::Known(PatuiStepDataFlavour::Integer("3".to_string())),
+            ),
+            (
+                "steps.foo.out.len()",
+                EvalResult::Predictable(PatuiStepDataFlavour::Integer("2".to_string())),
+            ),
+        ] {
+            assert_that!(eval_str(expr, &results).unwrap()).is_equal_to(expected);
+        }
+
+        let res = eval_str("steps.foo.out[1].len()", &results);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with("`len()` expects a string");
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_closed_step() {
+        let items = vec![
+            PatuiStepDataFlavour::Integer("1".to_string()),
+            PatuiStepDataFlavour::Integer("2".to_string()),
+        ];
+        let open = step_results(items.clone());
+        let closed = closed_step_results(items);
+
+        for (expr, open_expected, closed_expected) in [
+            (
+                "steps.foo.out.len() == 2",
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)),
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "steps.foo.out == [1, 2]",
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)),
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "3 in steps.foo.out",
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(false)),
+                EvalResult::Known(PatuiStepDataFlavour::Bool(false)),
+            ),
+        ] {
+            assert_that!(eval_str(expr, &open).unwrap()).is_equal_to(open_expected);
+            assert_that!(eval_str(expr, &closed).unwrap()).is_equal_to(closed_expected);
+        }
+
+        // A third item could still arrive while open, once closed there's never going to be one
+        assert_that!(eval_str("steps.foo.out[2]", &open).unwrap()).is_equal_to(EvalResult::Unknown);
+        let res = eval_str("steps.foo.out[2]", &closed);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .starts_with("Index 2 out of range for `steps.foo.out`");
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_zip() {
+        let results = open_step_results([
+            (
+                "steps.foo.out".try_into().unwrap(),
+                vec![
+                    PatuiStepData::new(PatuiStepDataFlavour::Integer("1".to_string())),
+                    PatuiStepData::new(PatuiStepDataFlavour::Integer("2".to_string())),
+                ],
+            ),
+            (
+                "steps.bar.out".try_into().unwrap(),
+                vec![
+                    PatuiStepData::new(PatuiStepDataFlavour::String("x".to_string())),
+                    PatuiStepData::new(PatuiStepDataFlavour::String("y".to_string())),
+                ],
+            ),
+        ]);
+
+        let pair = |a: &str, b: &str| {
+            PatuiStepDataFlavour::Array(vec![
+                PatuiStepDataFlavour::Integer(a.to_string()),
+                PatuiStepDataFlavour::String(b.to_string()),
+            ])
+        };
+
+        for (expr, expected) in [
+            (
+                "zip(steps.foo.out, steps.bar.out)",
+                EvalResult::Predictable(PatuiStepDataFlavour::Array(vec![
+                    pair("1", "x"),
+                    pair("2", "y"),
+                ])),
+            ),
+            (
+                "zip(steps.foo.out, steps.bar.out)[0] == [1, \"x\"]",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "zip([1, 2], [\"x\", \"y\"])",
+                EvalResult::Known(PatuiStepDataFlavour::Array(vec![
+                    pair("1", "x"),
+                    pair("2", "y"),
+                ])),
+            ),
+        ] {
+            assert_that!(eval_str(expr, &results).unwrap()).is_equal_to(expected);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_zip_mismatched_lengths() {
+        let results = open_step_results([
+            (
+                "steps.foo.out".try_into().unwrap(),
+                vec![
+                    PatuiStepData::new(PatuiStepDataFlavour::Integer("1".to_string())),
+                    PatuiStepData::new(PatuiStepDataFlavour::Integer("2".to_string())),
+                    PatuiStepData::new(PatuiStepDataFlavour::Integer("3".to_string())),
+                ],
+            ),
+            (
+                "steps.bar.out".try_into().unwrap(),
+                vec![PatuiStepData::new(PatuiStepDataFlavour::String(
+                    "x".to_string(),
+                ))],
+            ),
+        ]);
+
+        for (expr, expected) in [
+            (
+                "zip(steps.foo.out, steps.bar.out).len()",
+                EvalResult::Predictable(PatuiStepDataFlavour::Integer("1".to_string())),
+            ),
+            (
+                "zip(steps.foo.out, steps.bar.out)[0][1]",
+                EvalResult::Known(PatuiStepDataFlavour::String("x".to_string())),
+            ),
+            // `steps.bar` may still send its second item
+            ("zip(steps.foo.out, steps.bar.out)[1]", EvalResult::Unknown),
+            (
+                "zip([1, 2, 3], [\"x\"]).len()",
+                EvalResult::Known(PatuiStepDataFlavour::Integer("1".to_string())),
+            ),
+        ] {
+            assert_that!(eval_str(expr, &results).unwrap()).is_equal_to(expected);
+        }
+
+        for (expr, expected_err) in [
+            ("zip([1])", "`zip()` takes two arguments, got 1"),
+            ("zip([1], 2)", "`zip()` expects arrays"),
+            ("unzip([1], [2])", "Unknown function `unzip`"),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).starts_with(expected_err);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_keys_and_values() {
+        let results = step_results(vec![PatuiStepDataFlavour::Map(HashMap::from([
+            (
+                "b".to_string(),
+                PatuiStepDataFlavour::Integer("42".to_string()),
+            ),
+            ("a".to_string(), PatuiStepDataFlavour::Null),
+        ]))]);
+
+        for (expr, expected) in [
+            (
+                "{\"c\": 3, \"a\": 1, \"b\": 2}.keys()",
+                EvalResult::Known(PatuiStepDataFlavour::Array(vec![
+                    PatuiStepDataFlavour::String("a".to_string()),
+                    PatuiStepDataFlavour::String("b".to_string()),
+                    PatuiStepDataFlavour::String("c".to_string()),
+                ])),
+            ),
+            (
+                "{\"c\": 3, \"a\": 1, \"b\": 2}.values()",
+                EvalResult::Known(PatuiStepDataFlavour::Array(vec![
+                    PatuiStepDataFlavour::Integer("1".to_string()),
+                    PatuiStepDataFlavour::Integer("2".to_string()),
+                    PatuiStepDataFlavour::Integer("3".to_string()),
+                ])),
+            ),
+            (
+                "42 in steps.foo.out[0].values()",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "\"b\" in steps.foo.out[0].keys()",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "\"b\" in steps.foo.out[0].values()",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(false)),
+            ),
+        ] {
+            assert_that!(eval_str(expr, &results).unwrap()).is_equal_to(expected);
+        }
+
+        let res = eval_str("[1, 2].values()", &results);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .is_equal_to("`values()` expects a map, got `[1, 2]`".to_string());
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_set_equality() {
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Integer("1".to_string()),
+            PatuiStepDataFlavour::Integer("2".to_string()),
+        ]);
+
+        for (expr, expected) in [
+            (
+                "{1, 2} == {2, 1}",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "{1, 2} != {2, 1}",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(false)),
+            ),
+            (
+                "{1, 2} == {1, 3}",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(false)),
+            ),
+            (
+                "[1, 2] == [2, 1]",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(false)),
+            ),
+            (
+                "[{1, 2}] == [{2, 1}]",
+                EvalResult::Known(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "steps.foo.out == {2, 1}",
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "steps.foo.out == [2, 1]",
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(false)),
+            ),
+        ] {
+            assert_that!(eval_str(expr, &results).unwrap()).is_equal_to(expected);
+        }
+
+        // Each item is paired off with a different element of the set, as two sets are compared
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Integer("1".to_string()),
+            PatuiStepDataFlavour::Integer("1".to_string()),
+            PatuiStepDataFlavour::Integer("2".to_string()),
+        ]);
+        assert_that!(eval_str("steps.foo.out == {1, 2}", &results).unwrap())
+            .is_equal_to(EvalResult::Predictable(PatuiStepDataFlavour::Bool(false)));
+        assert_that!(equal(
+            &PatuiStepDataFlavour::Set(vec![
+                PatuiStepDataFlavour::Integer("1".to_string()),
+                PatuiStepDataFlavour::Integer("1".to_string()),
+                PatuiStepDataFlavour::Integer("2".to_string()),
+            ]),
+            &PatuiStepDataFlavour::Set(vec![
+                PatuiStepDataFlavour::Integer("1".to_string()),
+                PatuiStepDataFlavour::Integer("2".to_string()),
+                PatuiStepDataFlavour::Integer("2".to_string()),
+            ]),
+        ))
+        .is_false();
+
+        // Whether order matters can't be told when a list literal is compared with a set literal
+        let res = eval_str("[1, 2] == {2, 1}", &results);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).is_equal_to(
+            "`[1, 2] == {2, 1}` compares a list with a set, make both sides lists if order matters or sets if it doesn't"
+                .to_string(),
+        );
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_contains() {
+        let results = HashMap::new();
+
+        for (expr, expected) in [
+            ("1 in [1, 2]", true),
+            ("3 in [1, 2]", false),
+            ("\"a\" in {\"a\", \"b\"}", true),
+            ("\"a\" in {\"a\": 1}", true),
+            ("\"b\" in {\"a\": 1}", false),
+            ("\"ell\" in \"hello\"", true),
+            ("b\"ell\" in b\"hello\"", true),
+            ("b\"elo\" in b\"hello\"", false),
+            ("0x65 in b\"hello\"", true),
+            ("256 in b[0, 255]", false),
+            ("b\"hello\"[1] in b\"el\"", true),
+            ("3 not in [1, 2]", true),
+            ("1 != 2", true),
+            ("!(1 == 1)", false),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap())
+                .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(expected)));
+        }
+
+        let res = eval_str("1 in 2", &results);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with("Can't look for");
+
+        let res = eval_str("!1", &results);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with("`1` should be a bool to negate");
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_negative_numbers() {
+        let results = step_results(vec![
+            PatuiStepDataFlavour::Integer("5".to_string()),
+            PatuiStepDataFlavour::Float("-2.5".to_string()),
+        ]);
+
+        for (expr, expected) in [
+            ("-(2)", PatuiStepDataFlavour::Integer("-2".to_string())),
+            ("-(-2)", PatuiStepDataFlavour::Integer("2".to_string())),
+            ("-(0x10)", PatuiStepDataFlavour::Integer("-16".to_string())),
+            ("-(1.5)", PatuiStepDataFlavour::Float("-1.5".to_string())),
+            ("-(2 * 3)", PatuiStepDataFlavour::Integer("-6".to_string())),
+            (
+                "-steps.foo.out[0]",
+                PatuiStepDataFlavour::Integer("-5".to_string()),
+            ),
+            (
+                "-steps.foo.out[1]",
+                PatuiStepDataFlavour::Float("2.5".to_string()),
+            ),
+            (
+                "(-steps.foo.out[0]) == -5",
+                PatuiStepDataFlavour::Bool(true),
+            ),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(EvalResult::Known(expected));
+        }
+
+        assert_that!(eval_str("-steps.foo.out[2]", &results).unwrap())
+            .is_equal_to(EvalResult::Unknown);
+
+        for expr in ["-\"a\"", "-true", "-[1]"] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).contains("should be a number to negate");
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_short_circuit() {
+        // Nothing received yet, so either comparison on its own is unknown
+        let results = step_results(vec![]);
+
+        for (expr, expected) in [
+            ("false && (steps.foo.out[0] == 1)", false),
+            ("(steps.foo.out[0] == 1) && false", false),
+            ("true || (steps.foo.out[0] == 1)", true),
+            ("(steps.foo.out[0] == 1) || true", true),
+            // The right hand side is never looked at so the missing step doesn't matter
+            ("false && (steps.bar.out[0] == 1)", false),
+        ] {
+            let res = eval_str(expr, &results);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap())
+                .is_equal_to(EvalResult::Known(PatuiStepDataFlavour::Bool(expected)));
+        }
+
+        for expr in [
+            "true && (steps.foo.out[0] == 1)",
+            "(steps.foo.out[0] == 1) || false",
+        ] {
+            assert_that!(eval_str(expr, &results).unwrap()).is_equal_to(EvalResult::Unknown);
+        }
+
+        // Only a final value settles it, more data could still make the length 2
+        assert_that!(eval_str(
+            "(steps.foo.out.len() == 2) || (steps.foo.out[0] == 1)",
+            &results
+        )
+        .unwrap())
+        .is_equal_to(EvalResult::Unknown);
+
+        assert_that!(eval_str("1 && false", &results)).is_err();
+    }
+
+    #[traced_test]
+    #[test]
+    fn eval_negation_equivalence() {
+        // Nothing received yet, so indexing into the step's output is unknown
+        let unknown = step_results(vec![]);
+        let predictable = step_results(vec![
+            PatuiStepDataFlavour::String("a".to_string()),
+            PatuiStepDataFlavour::String("b".to_string()),
+        ]);
+
+        for (negated, inverse) in [
+            ("!(\"a\" in steps.foo.out)", "\"a\" not in steps.foo.out"),
+            ("!(\"c\" in steps.foo.out)", "\"c\" not in steps.foo.out"),
+            (
+                "!(steps.foo.out[0] in [\"a\"])",
+                "steps.foo.out[0] not in [\"a\"]",
+            ),
+            (
+                "!(steps.foo.out[1] in \"abc\")",
+                "steps.foo.out[1] not in \"abc\"",
+            ),
+            ("!(\"a\" in [\"a\"])", "\"a\" not in [\"a\"]"),
+            ("!(steps.foo.out[0] == \"a\")", "steps.foo.out[0] != \"a\""),
+            ("!(steps.foo.out == [\"a\"])", "steps.foo.out != [\"a\"]"),
+            ("!(1 == 2)", "1 != 2"),
+            ("!!(1 == 2)", "1 == 2"),
+        ] {
+            for results in [&unknown, &predictable] {
+                let negated_res = eval_str(negated, results);
+                assert_that!(negated_res).is_ok();
+                assert_that!(negated_res.unwrap()).is_equal_to(eval_str(inverse, results).unwrap());
+            }
+        }
+
+        // Negating doesn't change how certain a result is
+        for (expr, results, expected) in [
+            (
+                "!(\"a\" in steps.foo.out)",
+                &unknown,
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "!(steps.foo.out[0] == \"a\")",
+                &unknown,
+                EvalResult::Unknown,
+            ),
+            (
+                "!(\"a\" in steps.foo.out)",
+                &predictable,
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(false)),
+            ),
+            (
+                "!(steps.foo.out == [\"a\"])",
+                &predictable,
+                EvalResult::Predictable(PatuiStepDataFlavour::Bool(true)),
+            ),
+            (
+                "!(steps.foo.out[0] == \"a\")",
+                &predictable,
+                EvalResult::Known(PatuiStepDataFlavour::Bool(false)),
+            ),
+        ] {
+            assert_that!(eval_str(expr, results).unwrap()).is_equal_to(expected);
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use eyre::{eyre, Result};
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    task::JoinHandle,
+};
+
+use super::{init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait};
+use crate::types::{
+    PatuiEvent, PatuiExpr, PatuiStepData, PatuiStepDataFlavour, PatuiStepFileSink,
+    PatuiStepFileSinkFormat,
+};
+
+/// How long written data can sit in the buffer before it's flushed out to the file.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug)]
+pub(crate) struct PatuiStepRunnerFileSink {
+    step_name: String,
+    step: PatuiStepFileSink,
+
+    receivers: Option<HashMap<PatuiExpr, broadcast::Receiver<PatuiStepData>>>,
+
+    task: Option<JoinHandle<Result<()>>>,
+}
+
+impl PatuiStepRunnerFileSink {
+    pub(crate) fn new(step_name: String, step: &PatuiStepFileSink) -> Self {
+        Self {
+            step_name,
+            step: step.clone(),
+            receivers: None,
+            task: None,
+        }
+    }
+}
+
+impl PatuiStepRunnerTrait for PatuiStepRunnerFileSink {
+    async fn init(
+        &mut self,
+        current_step_name: &str,
+        step_runners: HashMap<String, Vec<Arc<Mutex<PatuiStepRunner>>>>,
+    ) -> Result<()> {
+        let receivers =
+            init_subscribe_steps(&self.step.input, current_step_name, &step_runners).await?;
+        self.receivers = Some(receivers);
+
+        Ok(())
+    }
+
+    fn run(&mut self, _tx: mpsc::Sender<PatuiEvent>) -> Result<()> {
+        let step = self.step.clone();
+        let step_name = self.step_name.clone();
+
+        let mut receiver = self
+            .receivers
+            .take()
+            .and_then(|mut receivers| receivers.remove(&step.input))
+            .ok_or_else(|| {
+                eyre!(
+                    "Step `{}` can only write out a step's channel, got `{}`",
+                    step_name,
+                    step.input
+                )
+            })?;
+
+        self.task = Some(tokio::spawn(async move {
+            let mut file = BufWriter::new(File::create(&step.path).await.map_err(|e| {
+                eyre!(
+                    "Step `{}` couldn't create `{}`: {}",
+                    step_name,
+                    step.path,
+                    e
+                )
+            })?);
+            let mut flush = tokio::time::interval(FLUSH_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    res = receiver.recv() => match res {
+                        Ok(data) => file.write_all(&encode(&data.data, &step.format)?).await?,
+                        Err(RecvError::Lagged(count)) => {
+                            tracing::warn!("Step `{}` missed {} items", step_name, count);
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    _ = flush.tick() => file.flush().await?,
+                }
+            }
+
+            file.flush().await?;
+
+            Ok(())
+        }));
+
+        Ok(())
+    }
+
+    async fn wait(&mut self) -> Result<()> {
+        tracing::trace!("Waiting");
+        let res = match self.task.as_mut() {
+            Some(task) => task.await?,
+            None => Ok(()),
+        };
+        self.task = None;
+
+        res
+    }
+
+    fn cancel(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+
+    #[cfg(test)]
+    fn test_set_receiver(
+        &mut self,
+        sub_ref: &str,
+        rx: broadcast::Receiver<PatuiStepData>,
+    ) -> Result<()> {
+        let receivers = HashMap::from([(sub_ref.try_into().unwrap(), rx)]);
+        self.receivers = Some(receivers);
+
+        Ok(())
+    }
+}
+
+/// What's written to the file for `data`.
+fn encode(data: &PatuiStepDataFlavour, format: &PatuiStepFileSinkFormat) -> Result<Vec<u8>> {
+    Ok(match format {
+        PatuiStepFileSinkFormat::Raw => match data {
+            PatuiStepDataFlavour::Bytes(bytes) => bytes.to_vec(),
+            PatuiStepDataFlavour::String(value) => value.as_bytes().to_vec(),
+            _ => data.to_string().into_bytes(),
+        },
+        PatuiStepFileSinkFormat::JsonLines => {
+            let mut line = serde_json::to_vec(&serde_json::Value::from(data))?;
+            line.push(b'\n');
+            line
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use bytes::Bytes;
+    use tempfile::tempdir;
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    async fn sink(format: PatuiStepFileSinkFormat, items: Vec<PatuiStepDataFlavour>) -> String {
+        let tmpdir = tempdir().unwrap();
+        let path = tmpdir.path().join("sink.out");
+
+        let mut step = PatuiStepRunnerFileSink::new(
+            "sink".to_string(),
+            &PatuiStepFileSink {
+                input: "steps.test_input.out".try_into().unwrap(),
+                path: path.to_str().unwrap().to_string(),
+                format,
+            },
+        );
+
+        let (input_tx, input_rx) = broadcast::channel(32);
+        assert_that!(step.test_set_receiver("steps.test_input.out", input_rx)).is_ok();
+
+        let (res_tx, _) = mpsc::channel(1);
+        assert_that!(step.run(res_tx)).is_ok();
+
+        for item in items {
+            input_tx.send(PatuiStepData::new(item)).unwrap();
+        }
+        drop(input_tx);
+
+        assert_that!(step.wait().await).is_ok();
+
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn file_sink_raw() {
+        let contents = sink(
+            PatuiStepFileSinkFormat::Raw,
+            vec![
+                PatuiStepDataFlavour::Bytes(Bytes::from("Hello, ")),
+                PatuiStepDataFlavour::String("World!".to_string()),
+                PatuiStepDataFlavour::Integer("42".to_string()),
+            ],
+        )
+        .await;
+
+        assert_that!(contents).is_equal_to("Hello, World!42".to_string());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn file_sink_json_lines() {
+        let contents = sink(
+            PatuiStepFileSinkFormat::JsonLines,
+            vec![
+                PatuiStepDataFlavour::String("hello".to_string()),
+                PatuiStepDataFlavour::Map(HashMap::from([
+                    (
+                        "b".to_string(),
+                        PatuiStepDataFlavour::Float("1.5".to_string()),
+                    ),
+                    (
+                        "a".to_string(),
+                        PatuiStepDataFlavour::Array(vec![
+                            PatuiStepDataFlavour::Integer("1".to_string()),
+                            PatuiStepDataFlavour::Null,
+                        ]),
+                    ),
+                ])),
+                PatuiStepDataFlavour::Bytes(Bytes::from_static(&[0xff, 0x00])),
+            ],
+        )
+        .await;
+
+        assert_that!(contents)
+            .is_equal_to("\"hello\"\n{\"a\":[1,null],\"b\":1.5}\n[255,0]\n".to_string());
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn file_sink_bad_path() {
+        let mut step = PatuiStepRunnerFileSink::new(
+            "sink".to_string(),
+            &PatuiStepFileSink {
+                input: "steps.test_input.out".try_into().unwrap(),
+                path: "/no/such/dir/sink.out".to_string(),
+                format: PatuiStepFileSinkFormat::Raw,
+            },
+        );
+
+        let (_input_tx, input_rx) = broadcast::channel(32);
+        assert_that!(step.test_set_receiver("steps.test_input.out", input_rx)).is_ok();
+
+        let (res_tx, _) = mpsc::channel(1);
+        assert_that!(step.run(res_tx)).is_ok();
+
+        let res = step.wait().await;
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .starts_with("Step `sink` couldn't create `/no/such/dir/sink.out`");
+    }
+}
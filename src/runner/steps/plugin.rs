@@ -1,21 +1,52 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    fmt,
+    net::{Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::Path,
+    process::Stdio,
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
 
 use crate::{
-    runner::steps::init_subscribe_steps, types::steps::PatuiStepPlugin,
-    utils::get_unused_localhost_port,
+    runner::steps::init_subscribe_steps,
+    types::{
+        steps::{PatuiStepPlugin, PatuiStepPluginTransport},
+        PatuiDiagnosticSeverity, PatuiEvent, PatuiPluginDisplay,
+    },
 };
 
 use eyre::{eyre, Result};
+use semver::{Version, VersionReq};
 use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt, BufReader},
     process::{Child, Command},
-    sync::{broadcast, oneshot, Mutex},
+    sync::{broadcast, mpsc, oneshot, Mutex},
     task::JoinHandle,
 };
-use tonic::{transport::Channel, Request};
+use tonic::{transport::Channel, Code, Request};
+
+use crate::types::ptplugin::{
+    self, diagnostic::Severity, get_info, health, init, plugin_service_client::PluginServiceClient,
+    Diagnostic,
+};
 
-use crate::types::ptplugin::{self, get_info, plugin_service_client::PluginServiceClient};
+use super::{
+    PatuiExpr, PatuiStepData, PatuiStepRunner, PatuiStepRunnerTrait, OUT_CHANNEL_CAPACITY,
+};
 
-use super::{PatuiExpr, PatuiStepData, PatuiStepRunner, PatuiStepRunnerTrait};
+/// The plugin versions this version of Patui knows how to work with.
+const SUPPORTED_PLUGIN_VERSIONS: &str = "^0.1";
+
+/// How many times to try connecting to a plugin that's just been started before giving up.
+const CONNECT_ATTEMPTS: u32 = 5;
+/// How long to wait before the first retry connecting to a plugin, doubling for each after.
+const CONNECT_BACKOFF: Duration = Duration::from_millis(50);
+/// How long a plugin listening on TCP has to report the port it's chosen once started.
+const REPORT_PORT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a plugin has to exit by itself once it's finished, unless its step says otherwise.
+const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(2);
 
 #[derive(Debug)]
 pub(crate) struct PatuiStepRunnerPlugin {
@@ -27,6 +58,15 @@ pub(crate) struct PatuiStepRunnerPlugin {
 
     plugin_process: Option<Arc<Mutex<Child>>>,
     client_socket: Option<PluginServiceClient<Channel>>,
+    address: Option<PluginAddress>,
+    pool: Option<PluginPool>,
+    version: Option<String>,
+
+    /// Diagnostics the plugin reports, held until the step's run and they can be sent on as
+    /// events.
+    diagnostics_tx: Option<mpsc::UnboundedSender<Diagnostic>>,
+    diagnostics_rx: Option<mpsc::UnboundedReceiver<Diagnostic>>,
+    diagnostics_task: Option<JoinHandle<()>>,
 
     run_tx: Option<oneshot::Sender<()>>,
     run_rx: Option<oneshot::Receiver<()>>,
@@ -35,6 +75,7 @@ pub(crate) struct PatuiStepRunnerPlugin {
 impl PatuiStepRunnerPlugin {
     pub(crate) fn new(step_name: String, step: &PatuiStepPlugin) -> Self {
         let (run_tx, run_rx) = oneshot::channel();
+        let (diagnostics_tx, diagnostics_rx) = mpsc::unbounded_channel();
 
         Self {
             step_name,
@@ -45,37 +86,577 @@ impl PatuiStepRunnerPlugin {
 
             plugin_process: None,
             client_socket: None,
+            address: None,
+            pool: None,
+            version: None,
+
+            diagnostics_tx: Some(diagnostics_tx),
+            diagnostics_rx: Some(diagnostics_rx),
+            diagnostics_task: None,
 
             run_tx: Some(run_tx),
             run_rx: Some(run_rx),
         }
     }
 
+    /// Take the plugin from `pool` if there's one idle there and return it to the pool once
+    /// done with rather than killing it.
+    pub(crate) fn set_pool(&mut self, pool: PluginPool) {
+        self.pool = Some(pool);
+    }
+
+    /// The version the plugin reported, once it's been started.
+    pub(crate) fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
     async fn run_process(&mut self) -> Result<()> {
-        let mut cmd = Command::new(&self.step.path);
-        let port = get_unused_localhost_port().await?;
-        cmd.args(["--port", &format!("{}", port)]);
+        if let Some((process, client, address)) =
+            self.pool.as_ref().and_then(|x| x.take(&self.step.path))
+        {
+            tracing::debug!("Reusing pooled plugin `{}`", self.step.path);
+            self.version = client
+                .clone()
+                .get_info(Request::new(get_info::Request {}))
+                .await
+                .ok()
+                .and_then(|response| response.into_inner().step_runner)
+                .map(|step_runner| step_runner.version);
+            self.plugin_process = Some(process);
+            self.client_socket = Some(client);
+            self.address = Some(address);
+            return Ok(());
+        }
 
-        self.plugin_process = Some(Arc::new(Mutex::new(cmd.spawn()?)));
+        let (process, client, address, info) =
+            start_plugin(&self.step.path, self.step.transport.unwrap_or_default()).await?;
+        if let Some(pool) = &self.pool {
+            pool.record_spawn();
+        }
 
-        // TODO: This is a hack to wait for the plugin to start up, rework as polling at some point
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        tracing::debug!("Plugin info: {:?}", info);
+        self.version = info.step_runner.map(|step_runner| step_runner.version);
 
-        let addr = format!("http://[::1]:{}", port);
-        let mut client = PluginServiceClient::connect(addr).await?;
+        self.plugin_process = Some(Arc::new(Mutex::new(process)));
+        self.client_socket = Some(client);
+        self.address = Some(address);
 
-        let request = Request::new(get_info::Request {});
+        Ok(())
+    }
 
-        let response = client.get_info(request).await?;
+    /// Initialise the plugin and check it's ready to be run.
+    async fn init_plugin(&mut self) -> Result<()> {
+        let Some(client_socket) = self.client_socket.as_ref() else {
+            return Err(eyre!(
+                "Plugin `{}` must be started before init",
+                self.step_name
+            ));
+        };
+        let mut client_socket = client_socket.clone();
+
+        let request = Request::new(init::Request {
+            step_name: self.step_name.clone(),
+            step_runners: HashMap::new(),
+            config: self
+                .step
+                .config
+                .iter()
+                .map(|(k, v)| (k.clone(), v.to_string()))
+                .collect(),
+            keep_alive: self.pool.is_some(),
+        });
 
-        tracing::debug!("Plugin info: {:?}", response);
+        let response = client_socket.init(request).await?.into_inner();
+        self.report_diagnostics("initialise", response.diagnostics)?;
 
-        self.client_socket = Some(client);
+        match client_socket.health(Request::new(health::Request {})).await {
+            Ok(response) => {
+                let response = response.into_inner();
+                self.report_diagnostics("become healthy", response.diagnostics)?;
+                if !response.ready {
+                    return Err(eyre!("Plugin `{}` isn't ready to run", self.step_name));
+                }
+            }
+            // Plugins don't have to support health checks, a successful init is good enough
+            Err(status) if status.code() == Code::Unimplemented => {}
+            Err(status) => {
+                return Err(eyre!(
+                    "Plugin `{}` health check failed: {}",
+                    self.step_name,
+                    status.message()
+                ))
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fail with the plugin's error diagnostics from trying to `action` if there are any, the
+    /// rest are sent on as events.
+    fn report_diagnostics(&self, action: &str, diagnostics: Vec<Diagnostic>) -> Result<()> {
+        let diagnostics = check_diagnostics(&self.step_name, action, diagnostics)?;
+
+        if let Some(diagnostics_tx) = &self.diagnostics_tx {
+            for diagnostic in diagnostics {
+                let _ = diagnostics_tx.send(diagnostic);
+            }
+        }
 
         Ok(())
     }
 }
 
+/// Fail unless the plugin reported a version within `SUPPORTED_PLUGIN_VERSIONS`.
+fn check_version(step_name: &str, version: Option<&str>) -> Result<()> {
+    let Some(version) = version else {
+        return Err(eyre!("Plugin `{}` didn't report its version", step_name));
+    };
+
+    let supported = VersionReq::parse(SUPPORTED_PLUGIN_VERSIONS)?;
+    let parsed = Version::parse(version).map_err(|e| {
+        eyre!(
+            "Plugin `{}` reported an invalid version `{}`: {}",
+            step_name,
+            version,
+            e
+        )
+    })?;
+
+    if !supported.matches(&parsed) {
+        return Err(eyre!(
+            "Plugin `{}` has version {} which isn't supported, expected {}",
+            step_name,
+            version,
+            SUPPORTED_PLUGIN_VERSIONS
+        ));
+    }
+
+    Ok(())
+}
+
+/// Fail with the plugin's error diagnostics if there are any, returning those less severe.
+fn check_diagnostics(
+    step_name: &str,
+    action: &str,
+    diagnostics: Vec<Diagnostic>,
+) -> Result<Vec<Diagnostic>> {
+    let (errors, rest): (Vec<_>, Vec<_>) = diagnostics
+        .into_iter()
+        .partition(|diagnostic| diagnostic.severity() == Severity::Error);
+
+    if errors.is_empty() {
+        Ok(rest)
+    } else {
+        let errors = errors
+            .iter()
+            .map(|diagnostic| format!("{}: {}", diagnostic.summary, diagnostic.detail))
+            .collect::<Vec<_>>();
+        Err(eyre!(
+            "Plugin `{}` failed to {}: {}",
+            step_name,
+            action,
+            errors.join(", ")
+        ))
+    }
+}
+
+/// The event for a diagnostic reported by the plugin of step `step_name`.
+fn diagnostic_event(step_name: &str, diagnostic: Diagnostic) -> PatuiEvent {
+    let severity = match diagnostic.severity() {
+        Severity::Error => PatuiDiagnosticSeverity::Error,
+        Severity::Warning => PatuiDiagnosticSeverity::Warning,
+        Severity::Info => PatuiDiagnosticSeverity::Info,
+    };
+
+    PatuiEvent::diagnostic(
+        step_name.to_string(),
+        severity,
+        diagnostic.summary,
+        diagnostic.detail,
+    )
+}
+
+/// Give a plugin that's been told it's finished `grace` to exit by itself, killing it if it
+/// hasn't by then so it's not left running after the run.
+async fn stop_plugin(step_name: &str, process: &mut Child, grace: Duration) -> Result<()> {
+    tracing::trace!("Awaiting process completion");
+    match tokio::time::timeout(grace, process.wait()).await {
+        Ok(res) => {
+            res?;
+        }
+        Err(_) => {
+            tracing::warn!(
+                "Plugin `{}` didn't exit within {:?}, killing it",
+                step_name,
+                grace
+            );
+            process.kill().await?;
+        }
+    }
+    tracing::trace!("Process complete");
+
+    Ok(())
+}
+
+/// Plugin processes kept running between test runs so they needn't be relaunched for every run.
+/// Clones share the same processes.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PluginPool {
+    inner: Arc<std::sync::Mutex<PluginPoolInner>>,
+}
+
+type PooledPlugin = (
+    Arc<Mutex<Child>>,
+    PluginServiceClient<Channel>,
+    PluginAddress,
+);
+
+#[derive(Debug, Default)]
+struct PluginPoolInner {
+    idle: HashMap<String, Vec<PooledPlugin>>,
+    num_spawned: usize,
+}
+
+impl PluginPool {
+    /// Take an idle plugin launched from `path` if there is one still running.
+    fn take(&self, path: &str) -> Option<PooledPlugin> {
+        let mut inner = self.inner.lock().unwrap();
+        let idle = inner.idle.get_mut(path)?;
+
+        while let Some((process, client, address)) = idle.pop() {
+            let is_running = process
+                .try_lock()
+                .is_ok_and(|mut process| matches!(process.try_wait(), Ok(None)));
+            if is_running {
+                return Some((process, client, address));
+            }
+            address.remove_socket();
+        }
+
+        None
+    }
+
+    fn put(&self, path: &str, plugin: PooledPlugin) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.idle.entry(path.to_string()).or_default().push(plugin);
+    }
+
+    fn record_spawn(&self) {
+        self.inner.lock().unwrap().num_spawned += 1;
+    }
+
+    #[cfg(test)]
+    pub(crate) fn num_spawned(&self) -> usize {
+        self.inner.lock().unwrap().num_spawned
+    }
+
+    /// Kill all the idle plugins, any still in use are killed as their runs finish.
+    pub(crate) async fn shutdown(&self) {
+        let idle = std::mem::take(&mut self.inner.lock().unwrap().idle);
+
+        for (path, plugins) in idle {
+            for (process, _, address) in plugins {
+                if let Err(e) = process.lock().await.kill().await {
+                    tracing::warn!("Failed to kill pooled plugin `{}`: {}", path, e);
+                }
+                address.remove_socket();
+            }
+        }
+    }
+}
+
+/// Where a plugin's been asked to listen for Patui to connect to it. A TCP port of 0 leaves the
+/// plugin to choose a free port itself and report it back, nothing can take the port in between.
+#[derive(Debug, Clone, PartialEq)]
+enum PluginAddress {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+impl PluginAddress {
+    /// Somewhere nothing else is listening to give a plugin connected to over `transport`.
+    fn new(transport: PatuiStepPluginTransport) -> Result<Self> {
+        Ok(match transport {
+            PatuiStepPluginTransport::Ipv4 => PluginAddress::Tcp((Ipv4Addr::LOCALHOST, 0).into()),
+            PatuiStepPluginTransport::Ipv6 => PluginAddress::Tcp((Ipv6Addr::LOCALHOST, 0).into()),
+            #[cfg(unix)]
+            PatuiStepPluginTransport::Unix => {
+                use std::sync::atomic::{AtomicUsize, Ordering};
+
+                static NEXT_SOCKET: AtomicUsize = AtomicUsize::new(0);
+
+                PluginAddress::Unix(std::env::temp_dir().join(format!(
+                    "patui-plugin-{}-{}.sock",
+                    std::process::id(),
+                    NEXT_SOCKET.fetch_add(1, Ordering::Relaxed)
+                )))
+            }
+            #[cfg(not(unix))]
+            PatuiStepPluginTransport::Unix => {
+                return Err(eyre!(
+                    "Plugins can't be connected to over a Unix domain socket on this platform"
+                ))
+            }
+        })
+    }
+
+    /// The arguments telling a plugin to listen here.
+    fn args(&self) -> Vec<String> {
+        match self {
+            PluginAddress::Tcp(addr) => {
+                let mut args = vec!["--port".to_string(), addr.port().to_string()];
+                if addr.is_ipv6() {
+                    args.push("--ipv6".to_string());
+                }
+                args
+            }
+            #[cfg(unix)]
+            PluginAddress::Unix(path) => {
+                vec!["--socket".to_string(), path.to_string_lossy().to_string()]
+            }
+        }
+    }
+
+    async fn connect(
+        &self,
+    ) -> std::result::Result<PluginServiceClient<Channel>, tonic::transport::Error> {
+        match self {
+            PluginAddress::Tcp(addr) => {
+                PluginServiceClient::connect(format!("http://{}", addr)).await
+            }
+            #[cfg(unix)]
+            PluginAddress::Unix(path) => {
+                use hyper_util::rt::TokioIo;
+                use tokio::net::UnixStream;
+                use tonic::transport::{Endpoint, Uri};
+                use tower::service_fn;
+
+                let path = path.clone();
+                // The connector always dials the socket whatever the endpoint's URI
+                let channel = Endpoint::from_static("http://[::1]:50051")
+                    .connect_with_connector(service_fn(move |_: Uri| {
+                        let path = path.clone();
+                        async move {
+                            Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(path).await?))
+                        }
+                    }))
+                    .await?;
+                Ok(PluginServiceClient::new(channel))
+            }
+        }
+    }
+
+    /// Remove the socket made for the plugin to listen on once it's finished with, a plugin
+    /// that's exited cleanly may well have removed it already.
+    fn remove_socket(&self) {
+        #[cfg(unix)]
+        if let PluginAddress::Unix(path) = self {
+            if let Err(e) = std::fs::remove_file(path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    tracing::warn!("Failed to remove plugin socket {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for PluginAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PluginAddress::Tcp(addr) => write!(f, "{}", addr),
+            #[cfg(unix)]
+            PluginAddress::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Launch the plugin at `path` and connect to it over `transport`, returning the process, a
+/// client, where it's listening and the info the plugin reports about itself.
+async fn start_plugin(
+    path: &str,
+    transport: PatuiStepPluginTransport,
+) -> Result<(
+    Child,
+    PluginServiceClient<Channel>,
+    PluginAddress,
+    get_info::Response,
+)> {
+    let mut cmd = Command::new(path);
+    let mut address = PluginAddress::new(transport)?;
+    cmd.args(address.args());
+    cmd.stdout(Stdio::piped());
+    cmd.kill_on_drop(true);
+    // So the plugin's output reads the same as ours
+    if crate::PLAIN_PANIC.load(Ordering::Relaxed) {
+        cmd.env(&*crate::PLAIN_PANIC_ENV, "1");
+    }
+
+    let mut process = cmd.spawn()?;
+
+    let mut stdout = BufReader::new(
+        process
+            .stdout
+            .take()
+            .ok_or_else(|| eyre!("Plugin `{}` has no stdout", path))?,
+    );
+    if let PluginAddress::Tcp(addr) = &mut address {
+        addr.set_port(read_reported_port(path, &mut stdout).await?);
+    }
+
+    // Anything else the plugin writes is only of interest when debugging it, it's read as it
+    // arrives so the plugin's never blocked on a full pipe
+    let log_path = path.to_string();
+    tokio::spawn(async move {
+        let mut lines = stdout.lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::debug!("Plugin `{}`: {}", log_path, line);
+        }
+    });
+
+    match get_info_from(path, &address).await {
+        Ok((client, response)) => Ok((process, client, address, response)),
+        Err(e) => {
+            address.remove_socket();
+            Err(e)
+        }
+    }
+}
+
+/// Connect to the plugin at `path` listening on `address` and ask it about itself.
+async fn get_info_from(
+    path: &str,
+    address: &PluginAddress,
+) -> Result<(PluginServiceClient<Channel>, get_info::Response)> {
+    let mut client = connect_plugin(path, address).await?;
+
+    let request = Request::new(get_info::Request {});
+
+    let response = client.get_info(request).await?.into_inner();
+
+    Ok((client, response))
+}
+
+/// The port the plugin at `path` reports it's listening on, the first line it writes to `stdout`.
+async fn read_reported_port(path: &str, stdout: &mut (impl AsyncBufRead + Unpin)) -> Result<u16> {
+    let mut line = String::new();
+    let read = tokio::time::timeout(REPORT_PORT_TIMEOUT, stdout.read_line(&mut line))
+        .await
+        .map_err(|_| {
+            eyre!(
+                "Plugin `{}` didn't report its port within {:?}",
+                path,
+                REPORT_PORT_TIMEOUT
+            )
+        })??;
+
+    if read == 0 {
+        return Err(eyre!("Plugin `{}` exited before reporting its port", path));
+    }
+
+    match line.trim().parse() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(eyre!(
+            "Plugin `{}` reported `{}` rather than the port it's listening on",
+            path,
+            line.trim()
+        )),
+    }
+}
+
+/// Connect to the plugin at `path` listening on `address`, retrying with backoff while it starts
+/// up.
+async fn connect_plugin(
+    path: &str,
+    address: &PluginAddress,
+) -> Result<PluginServiceClient<Channel>> {
+    let mut backoff = CONNECT_BACKOFF;
+
+    for attempt in 1.. {
+        match address.connect().await {
+            Ok(client) => return Ok(client),
+            Err(e) if attempt < CONNECT_ATTEMPTS => {
+                tracing::trace!("Plugin `{}` not ready at {}: {}", path, address, e);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(e) => {
+                return Err(eyre!(
+                    "Unable to connect to plugin `{}` at {} after {} attempts: {}",
+                    path,
+                    address,
+                    CONNECT_ATTEMPTS,
+                    e
+                ))
+            }
+        }
+    }
+
+    unreachable!()
+}
+
+/// Briefly launch the plugin at `path` to find out what it is.
+async fn get_plugin_info(path: &Path) -> Result<PatuiPluginDisplay> {
+    let path = path.to_string_lossy().to_string();
+
+    let (mut process, client, address, response) =
+        start_plugin(&path, PatuiStepPluginTransport::default()).await?;
+
+    drop(client);
+    process.kill().await?;
+    address.remove_socket();
+
+    let Some(step_runner) = response.step_runner else {
+        return Err(eyre!("Plugin `{}` didn't report any info", path));
+    };
+
+    Ok(PatuiPluginDisplay::new(path, step_runner))
+}
+
+/// Whether `path` is a file that can be run, going by its permissions.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+/// Whether `path` is a file that can be run, there's no executable bit so this goes by its
+/// extension.
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+        && path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exe"))
+}
+
+/// Find the plugins in `dir`, i.e. any executable file in it that answers as a plugin. Files that
+/// can't be run as plugins are skipped with a warning.
+pub(crate) async fn find_plugins(dir: &Path) -> Result<Vec<PatuiPluginDisplay>> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| eyre!("Unable to read plugin directory `{}`: {}", dir.display(), e))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut plugins = vec![];
+
+    for path in paths {
+        if !is_executable(&path) {
+            continue;
+        }
+
+        match get_plugin_info(&path).await {
+            Ok(plugin) => plugins.push(plugin),
+            Err(e) => tracing::warn!("Skipping `{}`, not a plugin: {}", path.display(), e),
+        }
+    }
+
+    Ok(plugins)
+}
+
 impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
     async fn init(
         &mut self,
@@ -92,12 +673,32 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
 
         self.run_process().await?;
 
+        if let Err(e) = check_version(&self.step_name, self.version.as_deref()) {
+            self.cancel();
+            return Err(e);
+        }
+
+        if let Err(e) = self.init_plugin().await {
+            self.cancel();
+            return Err(e);
+        }
+
         Ok(())
     }
 
-    fn run(&mut self, _tx: tokio::sync::mpsc::Sender<super::PatuiEvent>) -> Result<()> {
+    fn run(&mut self, tx: tokio::sync::mpsc::Sender<super::PatuiEvent>) -> Result<()> {
         let client_socket = self.client_socket.as_ref().unwrap().clone();
 
+        if let Some(mut diagnostics_rx) = self.diagnostics_rx.take() {
+            let step_name = self.step_name.clone();
+            self.diagnostics_task = Some(tokio::spawn(async move {
+                while let Some(diagnostic) = diagnostics_rx.recv().await {
+                    let _ = tx.send(diagnostic_event(&step_name, diagnostic)).await;
+                }
+            }));
+        }
+        let diagnostics_tx = self.diagnostics_tx.clone();
+
         let run_tx = self.run_tx.take().unwrap();
         let receivers = self.receivers.take();
         let step = self.step.clone();
@@ -121,6 +722,7 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
 
             for (r#in, receiver) in receivers.into_iter() {
                 let client_socket = client_socket.clone();
+                let diagnostics_tx = diagnostics_tx.clone();
                 let name = step
                     .r#in
                     .iter()
@@ -153,6 +755,11 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
                         panic!("No response");
                     };
                     tracing::trace!("RESP = {:?}", resp);
+                    if let Some(diagnostics_tx) = &diagnostics_tx {
+                        for diagnostic in resp.diagnostics {
+                            let _ = diagnostics_tx.send(diagnostic);
+                        }
+                    }
                 }));
             }
 
@@ -174,14 +781,22 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
             name: sub.to_string(),
         });
 
-        let (tx, rx) = broadcast::channel(32); // TODO: Make this configurable
+        let (tx, rx) = broadcast::channel(OUT_CHANNEL_CAPACITY);
 
-        let mut client_socket = self.client_socket.as_ref().unwrap().clone();
+        let Some(client_socket) = self.client_socket.as_ref() else {
+            return Err(eyre!(
+                "Plugin `{}` must be started before subscribing to `{}`",
+                self.step_name,
+                sub
+            ));
+        };
+        let mut client_socket = client_socket.clone();
         let mut stream = client_socket.subscribe(request).await?.into_inner();
 
         drop(client_socket);
 
         let sub = sub.to_string();
+        let diagnostics_tx = self.diagnostics_tx.clone();
 
         self.tasks.push(tokio::spawn(async move {
             let sub = sub;
@@ -191,7 +806,15 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
                     &sub,
                     response
                 );
-                tx.send(response.data.unwrap().try_into().unwrap()).unwrap();
+                if let Some(diagnostics_tx) = &diagnostics_tx {
+                    for diagnostic in response.diagnostics {
+                        let _ = diagnostics_tx.send(diagnostic);
+                    }
+                }
+                if let Some(data) = response.data {
+                    // Nobody listening is fine, e.g. an assertion that's already settled
+                    let _ = tx.send(data.try_into().unwrap());
+                }
             }
         }));
 
@@ -208,33 +831,77 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
         let mut client_socket = self.client_socket.as_ref().unwrap().clone();
         let response = client_socket.wait(request).await?.into_inner();
         tracing::trace!("Plugin wait response: {:?}", response);
-        if !response.diagnostics.is_empty() {
-            tracing::error!("Diagnostics: {:?}", response.diagnostics);
-            todo!();
+        if let Err(e) = self.report_diagnostics("finish", response.diagnostics) {
+            self.cancel();
+            return Err(e);
         }
 
         let Some(plugin_process) = self.plugin_process.take() else {
             return Err(eyre!("Plugin process not found"));
         };
 
-        plugin_process.lock().await.kill().await.unwrap();
-
-        tracing::trace!("Awaiting process completion");
-        plugin_process.lock().await.wait().await.unwrap();
-        tracing::trace!("Process complete");
+        if self.pool.is_none() {
+            let grace = self
+                .step
+                .shutdown_grace_ms
+                .map_or(DEFAULT_SHUTDOWN_GRACE, Duration::from_millis);
+            let res = stop_plugin(&self.step_name, &mut *plugin_process.lock().await, grace).await;
+            if let Some(address) = self.address.take() {
+                address.remove_socket();
+            }
+            res?;
+        }
 
-        drop(client_socket);
         self.client_socket = None;
 
-        for task in self.tasks.drain(..) {
+        for task in self.tasks.iter_mut() {
             task.await?;
         }
+        self.tasks.clear();
+
+        // Everything that could report a diagnostic has finished so they've all been sent once
+        // the channel's drained
+        self.diagnostics_tx = None;
+        if let Some(diagnostics_task) = self.diagnostics_task.take() {
+            diagnostics_task.await?;
+        }
+
+        // Only a plugin that finished its run cleanly goes back in the pool, anything else could
+        // carry state over into the next run.
+        if let (Some(pool), Some(address)) = (&self.pool, self.address.take()) {
+            pool.put(&self.step.path, (plugin_process, client_socket, address));
+        }
 
         tracing::debug!("Plugin complete {}", self.step_name);
 
         Ok(())
     }
 
+    fn cancel(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        if let Some(diagnostics_task) = self.diagnostics_task.take() {
+            diagnostics_task.abort();
+        }
+
+        if let Some(plugin_process) = self.plugin_process.take() {
+            match plugin_process.try_lock() {
+                Ok(mut plugin_process) => {
+                    if let Err(e) = plugin_process.start_kill() {
+                        tracing::warn!("Failed to kill plugin `{}`: {}", self.step_name, e);
+                    }
+                }
+                Err(_) => tracing::warn!("Plugin `{}` busy, unable to kill it", self.step_name),
+            }
+        }
+        if let Some(address) = self.address.take() {
+            address.remove_socket();
+        }
+
+        self.client_socket = None;
+    }
+
     #[cfg(test)]
     fn test_set_receiver(
         &mut self,
@@ -250,6 +917,8 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerPlugin {
 
 #[cfg(test)]
 mod tests {
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
     use std::{process::Command, time::Duration};
 
     use assertor::*;
@@ -257,7 +926,10 @@ mod tests {
     use tokio::{sync::mpsc, time::timeout};
     use tracing_test::traced_test;
 
-    use crate::types::PatuiStepDataFlavour;
+    use crate::{
+        runner::steps::PatuiStepRunnerFlavour,
+        types::{PatuiEventKind, PatuiStep, PatuiStepDataFlavour, PatuiStepDetails},
+    };
 
     use super::*;
 
@@ -296,6 +968,9 @@ mod tests {
                 path: "./test_progs/test_plugin/target/debug/test_patui_plugin".to_string(),
                 config: HashMap::new(),
                 r#in: HashMap::new(),
+                channels: vec![],
+                shutdown_grace_ms: None,
+                transport: None,
             },
         );
 
@@ -357,6 +1032,536 @@ mod tests {
         assert_that!(task.await).is_ok();
     }
 
+    #[traced_test]
+    #[tokio::test]
+    async fn test_pooled_plugin() {
+        compile_program();
+
+        let pool = PluginPool::default();
+
+        for _ in 0..2 {
+            let mut main_step = PatuiStepRunnerPlugin::new(
+                "main".to_string(),
+                &PatuiStepPlugin {
+                    path: "./test_progs/test_plugin/target/debug/test_patui_plugin".to_string(),
+                    config: HashMap::new(),
+                    r#in: HashMap::new(),
+                    channels: vec![],
+                    shutdown_grace_ms: None,
+                    transport: None,
+                },
+            );
+            main_step.set_pool(pool.clone());
+
+            let res = timeout(
+                Duration::from_secs(2),
+                main_step.init("main", HashMap::new()),
+            )
+            .await;
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_ok();
+
+            let output_res = timeout(Duration::from_secs(5), main_step.subscribe("out")).await;
+            assert_that!(output_res).is_ok();
+            let mut output_rx = output_res.unwrap().unwrap();
+
+            let (res_tx, _res_rx) = mpsc::channel(1);
+            assert_that!(main_step.run(res_tx)).is_ok();
+
+            let recv = timeout(Duration::from_secs(10), output_rx.recv()).await;
+            assert_that!(recv).is_ok();
+            assert_that!(recv.unwrap().unwrap().data).is_equal_to(PatuiStepDataFlavour::Null);
+
+            drop(output_rx);
+
+            let res = timeout(Duration::from_secs(2), main_step.wait()).await;
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_ok();
+        }
+
+        assert_that!(pool.num_spawned()).is_equal_to(1);
+
+        pool.shutdown().await;
+        assert_that!(pool.take("./test_progs/test_plugin/target/debug/test_patui_plugin"))
+            .is_none();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_stop_plugin() {
+        // Exits by itself well within the grace period
+        let mut process = tokio::process::Command::new("true").spawn().unwrap();
+        let start = std::time::Instant::now();
+        assert_that!(stop_plugin("main", &mut process, Duration::from_secs(5)).await).is_ok();
+        assert_that!(start.elapsed()).is_less_than(Duration::from_secs(5));
+        assert_that!(logs_contain("killing it")).is_false();
+
+        // Never exits by itself so has to be killed once the grace period is up
+        let mut process = tokio::process::Command::new("sleep")
+            .arg("30")
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let start = std::time::Instant::now();
+        assert_that!(stop_plugin("main", &mut process, Duration::from_millis(200)).await).is_ok();
+        let elapsed = start.elapsed();
+        assert_that!(elapsed).is_at_least(Duration::from_millis(200));
+        assert_that!(elapsed).is_less_than(Duration::from_secs(5));
+        assert_that!(process.try_wait().unwrap()).is_some();
+        assert_that!(logs_contain(
+            "Plugin `main` didn't exit within 200ms, killing it"
+        ))
+        .is_true();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_plugin_ignoring_shutdown() {
+        compile_program();
+
+        let mut main_step = PatuiStepRunnerPlugin::new(
+            "main".to_string(),
+            &PatuiStepPlugin {
+                path: "./test_progs/test_plugin/target/debug/test_patui_plugin".to_string(),
+                config: HashMap::from([(
+                    "ignore_shutdown".to_string(),
+                    "true".try_into().unwrap(),
+                )]),
+                r#in: HashMap::new(),
+                channels: vec![],
+                shutdown_grace_ms: Some(300),
+                transport: None,
+            },
+        );
+
+        let res = timeout(
+            Duration::from_secs(2),
+            main_step.init("main", HashMap::new()),
+        )
+        .await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+        let plugin_process = main_step.plugin_process.clone().unwrap();
+
+        let (res_tx, _res_rx) = mpsc::channel(1);
+        assert_that!(main_step.run(res_tx)).is_ok();
+
+        let start = std::time::Instant::now();
+        let res = timeout(Duration::from_secs(5), main_step.wait()).await;
+        let elapsed = start.elapsed();
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+
+        assert_that!(elapsed).is_at_least(Duration::from_millis(300));
+        assert_that!(elapsed).is_less_than(Duration::from_secs(2));
+        assert_that!(plugin_process.lock().await.try_wait().unwrap()).is_some();
+    }
+
+    /// An address on a port nothing's listening on, for as long as nothing else takes it.
+    fn unused_tcp_address() -> PluginAddress {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        PluginAddress::Tcp(listener.local_addr().unwrap())
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_connect_plugin_retries() {
+        let address = unused_tcp_address();
+        let PluginAddress::Tcp(addr) = address else {
+            panic!("Expected a TCP address, got {}", address);
+        };
+
+        // The plugin's only listening a while after we start trying to connect
+        let listen_task = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+            let (_socket, _) = listener.accept().await.unwrap();
+            // Hold on to the connection until the test's done with it
+            std::future::pending::<()>().await;
+        });
+
+        let res = timeout(Duration::from_secs(5), connect_plugin("plugin", &address)).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+        assert_that!(logs_contain("not ready at 127.0.0.1:")).is_true();
+
+        listen_task.abort();
+    }
+
+    #[cfg(unix)]
+    #[traced_test]
+    #[tokio::test]
+    async fn test_connect_plugin_unix_socket() {
+        let address = PluginAddress::new(PatuiStepPluginTransport::Unix).unwrap();
+        let PluginAddress::Unix(path) = address.clone() else {
+            panic!("Expected a Unix socket, got {}", address);
+        };
+        assert_that!(address.args()).is_equal_to(vec![
+            "--socket".to_string(),
+            path.to_string_lossy().to_string(),
+        ]);
+
+        let listen_path = path.clone();
+        let listen_task = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            let listener = tokio::net::UnixListener::bind(listen_path).unwrap();
+            let (_socket, _) = listener.accept().await.unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let res = timeout(Duration::from_secs(5), connect_plugin("plugin", &address)).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+
+        listen_task.abort();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_plugin_address_args() {
+        // The plugin's left to choose its own port
+        assert_that!(PluginAddress::new(PatuiStepPluginTransport::Ipv4)
+            .unwrap()
+            .args())
+        .is_equal_to(vec!["--port".to_string(), "0".to_string()]);
+        assert_that!(PluginAddress::new(PatuiStepPluginTransport::Ipv6)
+            .unwrap()
+            .args())
+        .is_equal_to(vec![
+            "--port".to_string(),
+            "0".to_string(),
+            "--ipv6".to_string(),
+        ]);
+        assert_that!(PluginAddress::Tcp((Ipv4Addr::LOCALHOST, 1234).into()).args())
+            .is_equal_to(vec!["--port".to_string(), "1234".to_string()]);
+        assert_that!(PluginAddress::Tcp((Ipv6Addr::LOCALHOST, 1234).into()).args()).is_equal_to(
+            vec![
+                "--port".to_string(),
+                "1234".to_string(),
+                "--ipv6".to_string(),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn test_read_reported_port() {
+        assert_that!(read_reported_port("plugin", &mut &b"4321\nlistening\n"[..])
+            .await
+            .unwrap())
+        .is_equal_to(4321);
+
+        for (stdout, expected) in [
+            (&b""[..], "Plugin `plugin` exited before reporting its port"),
+            (
+                &b"listening\n"[..],
+                "Plugin `plugin` reported `listening` rather than the port it's listening on",
+            ),
+            (
+                &b"0\n"[..],
+                "Plugin `plugin` reported `0` rather than the port it's listening on",
+            ),
+        ] {
+            let res = read_reported_port("plugin", &mut &stdout[..]).await;
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).is_equal_to(expected.to_string());
+        }
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_connect_plugin_gives_up() {
+        let address = unused_tcp_address();
+
+        let start = std::time::Instant::now();
+        let res = timeout(Duration::from_secs(5), connect_plugin("plugin", &address)).await;
+        let elapsed = start.elapsed();
+
+        assert_that!(res).is_ok();
+        let res = res.unwrap();
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with(format!(
+            "Unable to connect to plugin `plugin` at {} after 5 attempts",
+            address
+        ));
+        // Waited 50ms, 100ms, 200ms then 400ms between the attempts
+        assert_that!(elapsed).is_at_least(Duration::from_millis(750));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_start_plugins_concurrently() {
+        compile_program();
+
+        let path = "./test_progs/test_plugin/target/debug/test_patui_plugin";
+        let (first, second) = timeout(Duration::from_secs(5), async {
+            tokio::join!(
+                start_plugin(path, PatuiStepPluginTransport::Ipv4),
+                start_plugin(path, PatuiStepPluginTransport::Ipv4)
+            )
+        })
+        .await
+        .unwrap();
+
+        // Each is given a port of its own so both answer
+        let (mut first_process, mut first_client, first_address, _) = first.unwrap();
+        let (mut second_process, mut second_client, second_address, _) = second.unwrap();
+        assert_that!(first_process.id()).is_not_equal_to(second_process.id());
+        assert_that!(first_address).is_not_equal_to(second_address);
+        for client in [&mut first_client, &mut second_client] {
+            let res = client.get_info(Request::new(get_info::Request {})).await;
+            assert_that!(res).is_ok();
+        }
+
+        first_process.kill().await.unwrap();
+        second_process.kill().await.unwrap();
+    }
+
+    #[cfg(unix)]
+    #[traced_test]
+    #[tokio::test]
+    async fn test_start_plugin_unix_socket() {
+        compile_program();
+
+        let path = "./test_progs/test_plugin/target/debug/test_patui_plugin";
+        let res = timeout(
+            Duration::from_secs(5),
+            start_plugin(path, PatuiStepPluginTransport::Unix),
+        )
+        .await
+        .unwrap();
+
+        let (mut process, mut client, address, _) = res.unwrap();
+        let PluginAddress::Unix(socket) = address.clone() else {
+            panic!("Expected a Unix socket, got {}", address);
+        };
+        let res = client.get_info(Request::new(get_info::Request {})).await;
+        assert_that!(res).is_ok();
+
+        // Killed it's left no chance to remove its socket itself
+        process.kill().await.unwrap();
+        assert_that!(socket.exists()).is_true();
+        address.remove_socket();
+        assert_that!(socket.exists()).is_false();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_socket() {
+        let address = PluginAddress::new(PatuiStepPluginTransport::Unix).unwrap();
+        let PluginAddress::Unix(socket) = address.clone() else {
+            panic!("Expected a Unix socket, got {}", address);
+        };
+        std::fs::write(&socket, "").unwrap();
+
+        address.remove_socket();
+        assert_that!(socket.exists()).is_false();
+
+        // Already gone is fine, the plugin may have removed it
+        address.remove_socket();
+        assert_that!(socket.exists()).is_false();
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert_that!(check_version("main", Some("0.1.0"))).is_ok();
+        assert_that!(check_version("main", Some("0.1.7"))).is_ok();
+
+        for (version, expected) in [
+            (None, "Plugin `main` didn't report its version"),
+            (
+                Some("0.2.0"),
+                "Plugin `main` has version 0.2.0 which isn't supported, expected ^0.1",
+            ),
+            (
+                Some("1.0.0"),
+                "Plugin `main` has version 1.0.0 which isn't supported, expected ^0.1",
+            ),
+        ] {
+            let res = check_version("main", version);
+            assert_that!(res).is_err();
+            assert_that!(res.unwrap_err().to_string()).is_equal_to(expected.to_string());
+        }
+
+        let res = check_version("main", Some("latest"));
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .starts_with("Plugin `main` reported an invalid version `latest`");
+    }
+
+    // Wraps the plugin in a shell script
+    #[cfg(unix)]
+    #[traced_test]
+    #[tokio::test]
+    async fn test_plugin_unsupported_version() {
+        compile_program();
+
+        // Have the plugin report a version that's out of range without touching the environment
+        // of the tests running alongside
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("old_plugin");
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\nTEST_PATUI_PLUGIN_VERSION=2.0.0 exec {} \"$@\"\n",
+                std::fs::canonicalize("./test_progs/test_plugin/target/debug/test_patui_plugin")
+                    .unwrap()
+                    .display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut main_step = PatuiStepRunnerPlugin::new(
+            "main".to_string(),
+            &PatuiStepPlugin {
+                path: path.to_string_lossy().to_string(),
+                config: HashMap::new(),
+                r#in: HashMap::new(),
+                channels: vec![],
+                shutdown_grace_ms: None,
+                transport: None,
+            },
+        );
+
+        let res = timeout(
+            Duration::from_secs(2),
+            main_step.init("main", HashMap::new()),
+        )
+        .await;
+        assert_that!(res).is_ok();
+        let res = res.unwrap();
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).is_equal_to(
+            "Plugin `main` has version 2.0.0 which isn't supported, expected ^0.1".to_string(),
+        );
+
+        // Rejected before it's run, nothing's left of it to run
+        assert_that!(main_step.plugin_process).is_none();
+        assert_that!(main_step.client_socket).is_none();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_plugin_init_error() {
+        compile_program();
+
+        let mut main_step = PatuiStepRunnerPlugin::new(
+            "main".to_string(),
+            &PatuiStepPlugin {
+                path: "./test_progs/test_plugin/target/debug/test_patui_plugin".to_string(),
+                config: HashMap::from([("fail_init".to_string(), "true".try_into().unwrap())]),
+                r#in: HashMap::new(),
+                channels: vec![],
+                shutdown_grace_ms: None,
+                transport: None,
+            },
+        );
+
+        let res = timeout(
+            Duration::from_secs(2),
+            main_step.init("main", HashMap::new()),
+        )
+        .await;
+        assert_that!(res).is_ok();
+        let res = res.unwrap();
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).contains(
+            "Plugin `main` failed to initialise: Init failed: Asked to fail by `fail_init`",
+        );
+
+        assert_that!(main_step.plugin_process).is_none();
+        assert_that!(main_step.client_socket).is_none();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_plugin_init_diagnostic() {
+        compile_program();
+
+        let mut main_step = PatuiStepRunnerPlugin::new(
+            "main".to_string(),
+            &PatuiStepPlugin {
+                path: "./test_progs/test_plugin/target/debug/test_patui_plugin".to_string(),
+                config: HashMap::from([("warn_init".to_string(), "true".try_into().unwrap())]),
+                r#in: HashMap::new(),
+                channels: vec![],
+                shutdown_grace_ms: None,
+                transport: None,
+            },
+        );
+
+        let res = timeout(
+            Duration::from_secs(2),
+            main_step.init("main", HashMap::new()),
+        )
+        .await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+
+        let (res_tx, mut res_rx) = mpsc::channel(10);
+        assert_that!(main_step.run(res_tx)).is_ok();
+
+        let res = timeout(Duration::from_secs(2), res_rx.recv()).await;
+        assert_that!(res).is_ok();
+        assert_that!(*res.unwrap().unwrap().value()).is_equal_to(PatuiEventKind::Diagnostic {
+            severity: PatuiDiagnosticSeverity::Warning,
+            summary: "Init warning".to_string(),
+            detail: "Asked to warn by `warn_init`".to_string(),
+        });
+
+        let res = timeout(Duration::from_secs(5), main_step.wait()).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+    }
+
+    #[test]
+    fn test_check_diagnostics() {
+        let diagnostic = |severity: Severity, summary: &str| Diagnostic {
+            severity: severity.into(),
+            summary: summary.to_string(),
+            detail: "detail".to_string(),
+        };
+
+        let res = check_diagnostics(
+            "main",
+            "initialise",
+            vec![
+                diagnostic(Severity::Info, "info"),
+                diagnostic(Severity::Warning, "warning"),
+            ],
+        );
+        assert_that!(res).is_ok();
+        let events = res
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| diagnostic_event("main", diagnostic).value().clone())
+            .collect::<Vec<_>>();
+        assert_that!(events).is_equal_to(vec![
+            PatuiEventKind::Diagnostic {
+                severity: PatuiDiagnosticSeverity::Info,
+                summary: "info".to_string(),
+                detail: "detail".to_string(),
+            },
+            PatuiEventKind::Diagnostic {
+                severity: PatuiDiagnosticSeverity::Warning,
+                summary: "warning".to_string(),
+                detail: "detail".to_string(),
+            },
+        ]);
+
+        let res = check_diagnostics(
+            "main",
+            "initialise",
+            vec![
+                diagnostic(Severity::Warning, "warning"),
+                diagnostic(Severity::Error, "error"),
+            ],
+        );
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string())
+            .is_equal_to("Plugin `main` failed to initialise: error: detail".to_string());
+    }
+
     #[traced_test]
     #[tokio::test]
     async fn test_echo_plugin() {
@@ -371,6 +1576,9 @@ mod tests {
                     "echo".to_string(),
                     "steps.test_input.out".try_into().unwrap(),
                 )]),
+                channels: vec![],
+                shutdown_grace_ms: None,
+                transport: None,
             },
         );
 
@@ -428,4 +1636,92 @@ mod tests {
 
         assert_that!(task.await).is_ok();
     }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn test_subscribe_declared_echo_channel() {
+        compile_program();
+
+        let plugin_step = PatuiStep {
+            name: "plugin".to_string(),
+            when: None,
+            depends_on: vec![],
+            timeout_ms: None,
+            details: PatuiStepDetails::Plugin(PatuiStepPlugin {
+                path: "./test_progs/test_plugin/target/debug/test_patui_plugin".to_string(),
+                config: HashMap::new(),
+                r#in: HashMap::new(),
+                channels: vec!["out".to_string(), "echo".to_string()],
+                shutdown_grace_ms: None,
+                transport: None,
+            }),
+        };
+        let mut plugin_runner = PatuiStepRunner::new(&plugin_step);
+        let PatuiStepRunnerFlavour::Plugin(runner) = plugin_runner.flavour_mut() else {
+            panic!("Expected plugin runner");
+        };
+        let res = timeout(Duration::from_secs(2), runner.run_process()).await;
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap()).is_ok();
+        let plugin_runner = Arc::new(std::sync::Mutex::new(plugin_runner));
+
+        let step_runners = HashMap::from([("plugin".to_string(), vec![plugin_runner.clone()])]);
+
+        let receivers = init_subscribe_steps(
+            &"steps.plugin.echo".try_into().unwrap(),
+            "main",
+            &step_runners,
+        )
+        .await;
+        assert_that!(receivers).is_ok();
+        assert_that!(receivers
+            .unwrap()
+            .keys()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>())
+        .is_equal_to(vec!["steps.plugin.echo".to_string()]);
+
+        let receivers = init_subscribe_steps(
+            &"steps.plugin.stdout".try_into().unwrap(),
+            "main",
+            &step_runners,
+        )
+        .await;
+        assert_that!(receivers).is_err();
+
+        let plugin_process = {
+            let mut runner = plugin_runner.lock().unwrap();
+            let PatuiStepRunnerFlavour::Plugin(runner) = runner.flavour_mut() else {
+                panic!("Expected plugin runner");
+            };
+            runner.plugin_process.take().unwrap()
+        };
+        plugin_process.lock().await.kill().await.unwrap();
+    }
+
+    #[traced_test]
+    #[test]
+    fn executable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("notes.txt");
+        std::fs::write(&text, "").unwrap();
+
+        #[cfg(unix)]
+        let plugin = {
+            let path = dir.path().join("plugin");
+            std::fs::write(&path, "").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+            path
+        };
+        #[cfg(not(unix))]
+        let plugin = {
+            let path = dir.path().join("plugin.exe");
+            std::fs::write(&path, "").unwrap();
+            path
+        };
+
+        assert_that!(is_executable(&plugin)).is_true();
+        assert_that!(is_executable(&text)).is_false();
+        assert_that!(is_executable(dir.path())).is_false();
+    }
 }
@@ -120,11 +120,9 @@ impl PatuiStepRunnerProcess {
                 match chunk {
                     Ok(chunk) => {
                         tracing::trace!("Read chunk: {:?}", chunk);
-                        if let Err(e) =
-                            stdout_tx.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(chunk)))
-                        {
-                            panic!("Error sending chunk: {:?}", e);
-                        }
+                        // Nobody listening is fine, e.g. an assertion that's already settled
+                        let _ =
+                            stdout_tx.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(chunk)));
                     }
                     // TODO: Handle properly
                     Err(e) => panic!("Error reading chunk: {:?}", e),
@@ -139,11 +137,8 @@ impl PatuiStepRunnerProcess {
                 match chunk {
                     Ok(chunk) => {
                         tracing::trace!("Read chunk: {:?}", chunk);
-                        if let Err(e) =
-                            stderr_tx.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(chunk)))
-                        {
-                            panic!("Error sending chunk: {:?}", e);
-                        }
+                        let _ =
+                            stderr_tx.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(chunk)));
                     }
                     // TODO: Handle properly
                     Err(e) => panic!("Error reading chunk: {:?}", e),
@@ -13,10 +13,10 @@ use tokio::{
 };
 use tokio_util::io::ReaderStream;
 
-use super::{init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait};
+use super::{init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait, OUT_CHANNEL_CAPACITY};
 use crate::types::{
     expr::ast::{ExprKind, LitKind},
-    PatuiEvent, PatuiExpr, PatuiStepData, PatuiStepDataFlavour, PatuiStepRead,
+    PatuiEvent, PatuiExpr, PatuiStepData, PatuiStepDataFlavour, PatuiStepRead, DEFAULT_CHANNEL,
 };
 
 #[derive(Debug)]
@@ -38,7 +38,7 @@ impl PatuiStepRunnerRead {
         Self {
             step_name,
             step: step.clone(),
-            out: Some(broadcast::channel(1)),
+            out: Some(broadcast::channel(OUT_CHANNEL_CAPACITY)),
             receivers: None,
             tasks: vec![],
         }
@@ -73,14 +73,17 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerRead {
                 };
                 let receiver = receivers.get_mut(&step.r#in).unwrap();
 
-                let binding = receiver.recv().await.unwrap();
+                // The step read from can finish without sending anything, e.g. it timed out
+                let Ok(binding) = receiver.recv().await else {
+                    tracing::debug!("Nothing to read from: {}", step.r#in);
+                    return;
+                };
                 let data = binding.data.as_bytes().unwrap();
 
-                out_sender
-                    .send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
-                        data.clone(),
-                    )))
-                    .unwrap();
+                // Nobody listening is fine, e.g. an assertion that's already settled
+                let _ = out_sender.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
+                    data.clone(),
+                )));
 
                 tx.send(PatuiEvent::send_bytes(data.clone(), step_name))
                     .await
@@ -101,11 +104,9 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerRead {
 
                     let data = data.unwrap();
 
-                    out_sender
-                        .send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
-                            data.clone(),
-                        )))
-                        .unwrap();
+                    let _ = out_sender.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
+                        data.clone(),
+                    )));
 
                     tx.send(PatuiEvent::send_bytes(data, step_name.clone()))
                         .await
@@ -123,22 +124,31 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerRead {
 
     async fn subscribe(&mut self, sub: &str) -> Result<broadcast::Receiver<PatuiStepData>> {
         match sub {
-            "out" => Ok(self.out.as_ref().unwrap().0.subscribe()),
+            DEFAULT_CHANNEL => Ok(self.out.as_ref().unwrap().0.subscribe()),
             _ => Err(eyre!("Invalid subscription {}", sub)),
         }
     }
 
     async fn wait(&mut self) -> Result<()> {
         tracing::trace!("Waiting");
-        for task in self.tasks.drain(..) {
+        for task in self.tasks.iter_mut() {
             task.await?;
         }
+        self.tasks.clear();
 
         self.out = None;
 
         Ok(())
     }
 
+    fn cancel(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
+        self.out = None;
+    }
+
     #[cfg(test)]
     fn test_set_receiver(
         &mut self,
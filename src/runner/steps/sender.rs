@@ -5,10 +5,10 @@ use tokio::{
     task::JoinHandle,
 };
 
-use super::PatuiStepRunnerTrait;
+use super::{PatuiStepRunnerTrait, OUT_CHANNEL_CAPACITY};
 use crate::types::{
     expr::ast::{ExprKind, LitKind},
-    PatuiEvent, PatuiStepData, PatuiStepDataFlavour, PatuiStepSender,
+    PatuiEvent, PatuiStepData, PatuiStepDataFlavour, PatuiStepSender, DEFAULT_CHANNEL,
 };
 
 #[derive(Debug)]
@@ -27,8 +27,7 @@ impl PatuiStepRunnerSender {
         Self {
             step_name: "sender".to_string(),
             step: step.clone(),
-            // TODO: Tune this parameter, configurable maybe? Probably should perf test.
-            out: Some(broadcast::channel(32)),
+            out: Some(broadcast::channel(OUT_CHANNEL_CAPACITY)),
             tasks: vec![],
         }
     }
@@ -49,11 +48,11 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerSender {
                             LitKind::Bool(_) => todo!(),
                             LitKind::Bytes(bytes) => {
                                 tracing::trace!("Sending bytes: {:?}", bytes);
-                                out_sender
-                                    .send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
-                                        bytes.clone(),
-                                    )))
-                                    .unwrap();
+                                // Nobody listening is fine, e.g. an assertion that's already
+                                // settled
+                                let _ = out_sender.send(PatuiStepData::new(
+                                    PatuiStepDataFlavour::Bytes(bytes.clone()),
+                                ));
 
                                 tx.send(PatuiEvent::send_bytes(bytes.clone(), step_name.clone()))
                                     .await
@@ -75,11 +74,9 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerSender {
                 match &lit.kind {
                     LitKind::Bool(_) => todo!(),
                     LitKind::Bytes(bytes) => {
-                        out_sender
-                            .send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
-                                bytes.clone(),
-                            )))
-                            .unwrap();
+                        let _ = out_sender.send(PatuiStepData::new(PatuiStepDataFlavour::Bytes(
+                            bytes.clone(),
+                        )));
 
                         tx.send(PatuiEvent::send_bytes(bytes.clone(), step_name))
                             .await
@@ -88,11 +85,9 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerSender {
                     LitKind::Integer(_) => todo!(),
                     LitKind::Decimal(_) => todo!(),
                     LitKind::Str(string) => {
-                        out_sender
-                            .send(PatuiStepData::new(PatuiStepDataFlavour::String(
-                                string.clone(),
-                            )))
-                            .unwrap();
+                        let _ = out_sender.send(PatuiStepData::new(PatuiStepDataFlavour::String(
+                            string.clone(),
+                        )));
 
                         tx.send(PatuiEvent::send_bytes(
                             Bytes::from(string.clone()),
@@ -118,7 +113,7 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerSender {
 
     async fn subscribe(&mut self, sub: &str) -> Result<broadcast::Receiver<PatuiStepData>> {
         match sub {
-            "out" => Ok(self.out.as_ref().unwrap().0.subscribe()),
+            DEFAULT_CHANNEL => Ok(self.out.as_ref().unwrap().0.subscribe()),
             _ => Err(eyre!("Invalid subscription {}", sub)),
         }
     }
@@ -126,13 +121,22 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerSender {
     async fn wait(&mut self) -> Result<()> {
         tracing::trace!("Waiting");
 
-        for task in self.tasks.drain(..) {
+        for task in self.tasks.iter_mut() {
             task.await?;
         }
+        self.tasks.clear();
         self.out = None;
 
         Ok(())
     }
+
+    fn cancel(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
+        self.out = None;
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,279 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use eyre::{eyre, Result};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc,
+    },
+    task::JoinHandle,
+};
+
+use super::{
+    eval::{eval, EvalResult, StepResults},
+    init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait, OUT_CHANNEL_CAPACITY,
+};
+use crate::types::{
+    expr::get_step_refs, PatuiEvent, PatuiExpr, PatuiStepData, PatuiStepTransform, DEFAULT_CHANNEL,
+};
+
+#[derive(Debug)]
+pub(crate) struct PatuiStepRunnerTransform {
+    step_name: String,
+    step: PatuiStepTransform,
+
+    out: Option<(
+        broadcast::Sender<PatuiStepData>,
+        broadcast::Receiver<PatuiStepData>,
+    )>,
+    receivers: Option<HashMap<PatuiExpr, broadcast::Receiver<PatuiStepData>>>,
+
+    task: Option<JoinHandle<Result<()>>>,
+}
+
+impl PatuiStepRunnerTransform {
+    pub(crate) fn new(step_name: String, step: &PatuiStepTransform) -> Self {
+        Self {
+            step_name,
+            step: step.clone(),
+            out: Some(broadcast::channel(OUT_CHANNEL_CAPACITY)),
+            receivers: None,
+            task: None,
+        }
+    }
+}
+
+impl PatuiStepRunnerTrait for PatuiStepRunnerTransform {
+    async fn init(
+        &mut self,
+        current_step_name: &str,
+        step_runners: HashMap<String, Vec<Arc<Mutex<PatuiStepRunner>>>>,
+    ) -> Result<()> {
+        // The expression only ever sees the one item so it can't use anything but the input
+        if let Some(step_ref) = get_step_refs(&self.step.expr)?
+            .into_iter()
+            .find(|step_ref| step_ref.expr != self.step.input)
+        {
+            return Err(eyre!(
+                "Step `{}` can only refer to its input `{}` in its expression, got `{}`",
+                self.step_name,
+                self.step.input,
+                step_ref.expr
+            ));
+        }
+
+        let receivers =
+            init_subscribe_steps(&self.step.input, current_step_name, &step_runners).await?;
+        self.receivers = Some(receivers);
+
+        Ok(())
+    }
+
+    fn run(&mut self, _tx: mpsc::Sender<PatuiEvent>) -> Result<()> {
+        let step = self.step.clone();
+        let step_name = self.step_name.clone();
+
+        let out_sender = self.out.as_ref().unwrap().0.clone();
+        let mut receiver = self
+            .receivers
+            .take()
+            .and_then(|mut receivers| receivers.remove(&step.input))
+            .ok_or_else(|| {
+                eyre!(
+                    "Step `{}` can only transform a step's channel, got `{}`",
+                    step_name,
+                    step.input
+                )
+            })?;
+
+        self.task = Some(tokio::spawn(async move {
+            loop {
+                let data = match receiver.recv().await {
+                    Ok(data) => data,
+                    Err(RecvError::Lagged(count)) => {
+                        tracing::warn!("Step `{}` missed {} items", step_name, count);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+
+                let results = HashMap::from([(step.input.clone(), StepResults::item(data))]);
+                let value = match eval(&step.expr, &results)? {
+                    EvalResult::Known(value) | EvalResult::Predictable(value) => value,
+                    EvalResult::Unknown => {
+                        return Err(eyre!(
+                            "Step `{}` couldn't evaluate `{}` for an item",
+                            step_name,
+                            step.expr
+                        ))
+                    }
+                };
+
+                // Nobody listening is fine, the data just goes nowhere
+                let _ = out_sender.send(PatuiStepData::new(value));
+            }
+
+            Ok(())
+        }));
+
+        Ok(())
+    }
+
+    async fn subscribe(&mut self, sub: &str) -> Result<broadcast::Receiver<PatuiStepData>> {
+        match sub {
+            DEFAULT_CHANNEL => Ok(self
+                .out
+                .as_ref()
+                .ok_or_else(|| eyre!("Step `{}` has finished", self.step_name))?
+                .0
+                .subscribe()),
+            _ => Err(eyre!("Invalid subscription")),
+        }
+    }
+
+    async fn wait(&mut self) -> Result<()> {
+        tracing::trace!("Waiting");
+        let res = match self.task.as_mut() {
+            Some(task) => task.await?,
+            None => Ok(()),
+        };
+        self.task = None;
+
+        self.out = None;
+
+        res
+    }
+
+    fn cancel(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+
+        self.out = None;
+    }
+
+    #[cfg(test)]
+    fn test_set_receiver(
+        &mut self,
+        sub_ref: &str,
+        rx: broadcast::Receiver<PatuiStepData>,
+    ) -> Result<()> {
+        let receivers = HashMap::from([(sub_ref.try_into().unwrap(), rx)]);
+        self.receivers = Some(receivers);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assertor::*;
+    use tokio::time::timeout;
+    use tracing_test::traced_test;
+
+    use crate::types::PatuiStepDataFlavour;
+
+    use super::*;
+
+    fn transform(expr: &str) -> PatuiStepRunnerTransform {
+        PatuiStepRunnerTransform::new(
+            "main".to_string(),
+            &PatuiStepTransform {
+                input: "steps.test_input.out".try_into().unwrap(),
+                expr: expr.try_into().unwrap(),
+            },
+        )
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn step_transform_doubles_integers() {
+        let mut main_step = transform("steps.test_input.out * 2");
+
+        let mut output_rx = main_step.subscribe("out").await.unwrap();
+
+        let (input_tx, input_rx) = broadcast::channel(32);
+        assert_that!(main_step.test_set_receiver("steps.test_input.out", input_rx)).is_ok();
+
+        for i in ["1", "2", "3"] {
+            input_tx
+                .send(PatuiStepData::new(PatuiStepDataFlavour::Integer(
+                    i.to_string(),
+                )))
+                .unwrap();
+        }
+        drop(input_tx);
+
+        let (res_tx, _res_rx) = mpsc::channel(1);
+        assert_that!(main_step.run(res_tx)).is_ok();
+
+        let mut received = vec![];
+        for _ in 0..3 {
+            let recv = timeout(Duration::from_millis(50), output_rx.recv()).await;
+            received.push(recv.unwrap().unwrap().data().clone());
+        }
+        assert_that!(received).is_equal_to(vec![
+            PatuiStepDataFlavour::Integer("2".to_string()),
+            PatuiStepDataFlavour::Integer("4".to_string()),
+            PatuiStepDataFlavour::Integer("6".to_string()),
+        ]);
+
+        assert_that!(main_step.wait().await).is_ok();
+        assert_that!(output_rx.recv().await).is_equal_to(Err(RecvError::Closed));
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn step_transform_extracts_field() {
+        let mut main_step = transform("steps.test_input.out.name");
+
+        let mut output_rx = main_step.subscribe("out").await.unwrap();
+
+        let (input_tx, input_rx) = broadcast::channel(32);
+        assert_that!(main_step.test_set_receiver("steps.test_input.out", input_rx)).is_ok();
+
+        input_tx
+            .send(PatuiStepData::new(PatuiStepDataFlavour::Map(
+                HashMap::from([(
+                    "name".to_string(),
+                    PatuiStepDataFlavour::String("foo".to_string()),
+                )]),
+            )))
+            .unwrap();
+        drop(input_tx);
+
+        let (res_tx, _res_rx) = mpsc::channel(1);
+        assert_that!(main_step.run(res_tx)).is_ok();
+
+        let recv = timeout(Duration::from_millis(50), output_rx.recv()).await;
+        assert_that!(*recv.unwrap().unwrap().data())
+            .is_equal_to(PatuiStepDataFlavour::String("foo".to_string()));
+        assert_that!(main_step.wait().await).is_ok();
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn step_transform_fails_on_bad_item() {
+        let mut main_step = transform("steps.test_input.out * 2");
+
+        let (input_tx, input_rx) = broadcast::channel(32);
+        assert_that!(main_step.test_set_receiver("steps.test_input.out", input_rx)).is_ok();
+
+        input_tx
+            .send(PatuiStepData::new(PatuiStepDataFlavour::String(
+                "foo".to_string(),
+            )))
+            .unwrap();
+        drop(input_tx);
+
+        let (res_tx, _res_rx) = mpsc::channel(1);
+        assert_that!(main_step.run(res_tx)).is_ok();
+
+        assert_that!(main_step.wait().await).is_err();
+    }
+}
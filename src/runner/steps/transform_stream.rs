@@ -12,10 +12,10 @@ use tokio::{
 
 use crate::types::{
     expr::ast::ExprKind, PatuiEvent, PatuiExpr, PatuiStepData, PatuiStepDataFlavour,
-    PatuiStepTransformStream,
+    PatuiStepTransformStream, DEFAULT_CHANNEL,
 };
 
-use super::{init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait};
+use super::{init_subscribe_steps, PatuiStepRunner, PatuiStepRunnerTrait, OUT_CHANNEL_CAPACITY};
 
 #[derive(Debug)]
 pub(crate) struct PatuiStepRunnerTransformStream {
@@ -36,7 +36,7 @@ impl PatuiStepRunnerTransformStream {
         Self {
             step: step.clone(),
             step_name,
-            out: Some(broadcast::channel(1)),
+            out: Some(broadcast::channel(OUT_CHANNEL_CAPACITY)),
             receivers: None,
             tasks: vec![],
         }
@@ -96,7 +96,8 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerTransformStream {
                         _ => todo!(),
                     };
 
-                    out_sender.send(data.clone()).unwrap();
+                    // Nobody listening is fine, e.g. an assertion that's already settled
+                    let _ = out_sender.send(data.clone());
 
                     tx.send(PatuiEvent::send_bytes(
                         Bytes::from("Sent JSON"),
@@ -120,22 +121,31 @@ impl PatuiStepRunnerTrait for PatuiStepRunnerTransformStream {
 
     async fn subscribe(&mut self, sub: &str) -> Result<broadcast::Receiver<PatuiStepData>> {
         match sub {
-            "out" => Ok(self.out.as_ref().unwrap().0.subscribe()),
+            DEFAULT_CHANNEL => Ok(self.out.as_ref().unwrap().0.subscribe()),
             _ => Err(eyre!("Invalid subscription")),
         }
     }
 
     async fn wait(&mut self) -> Result<()> {
         tracing::trace!("Waiting");
-        for task in self.tasks.drain(..) {
+        for task in self.tasks.iter_mut() {
             task.await?;
         }
+        self.tasks.clear();
 
         self.out = None;
 
         Ok(())
     }
 
+    fn cancel(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
+        self.out = None;
+    }
+
     #[cfg(test)]
     fn test_set_receiver(
         &mut self,
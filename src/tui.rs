@@ -2,6 +2,7 @@ mod app;
 mod bottom_bar;
 mod editor;
 mod error;
+mod keys;
 mod panes;
 mod popups;
 mod terminal;
@@ -10,6 +11,7 @@ mod types;
 mod widgets;
 
 pub(crate) use app::App;
+pub(crate) use terminal::supports_unicode;
 
 use eyre::Result;
 
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use eyre::Result;
@@ -7,26 +11,41 @@ use ratatui::{
     widgets::Clear,
     Frame,
 };
-use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::{
+    sync::mpsc::{self, UnboundedSender},
+    task::JoinHandle,
+};
 use tracing::{debug, trace};
 
 use super::{
     bottom_bar::BottomBar,
     error::{ErrorType, PatuiError},
-    panes::{Pane, TestDetailsPane, TestListPane},
-    popups::{ErrorComponent, HelpComponent, PopupComponent, TestEditComponent},
+    keys::{KeyBuffer, DEFAULT_KEY_TIMEOUT},
+    panes::{ConsolePane, Pane, TestDetailsPane, TestListPane},
+    popups::{
+        DiagnosticsComponent, DiffComponent, ErrorComponent, HelpComponent, PopupComponent,
+        RenameComponent, TestEditComponent,
+    },
     terminal::{Event, Tui},
     top_bar::TopBar,
 };
-use crate::db::{Database, PatuiTestId};
+use crate::{
+    db::{Database, DbError, PatuiTestId},
+    runner::{PluginPool, TestRunner},
+};
 
 pub(crate) use super::types::*;
 
 #[derive(Debug)]
 pub(crate) struct App {
     should_quit: bool,
-    last_key_events: Vec<KeyEvent>,
+    key_buffer: KeyBuffer,
     db: Arc<Database>,
+    plugin_pool: PluginPool,
+    /// Where actions are sent from outside the main loop, set once the app's running.
+    action_tx: Option<UnboundedSender<Action>>,
+    /// The test run going on in the background, if any.
+    run_task: Option<JoinHandle<()>>,
 
     selected_test_id: Option<PatuiTestId>,
 
@@ -37,20 +56,27 @@ pub(crate) struct App {
     popups: Vec<Popup>,
     bottom_bar: BottomBar,
 
+    is_compact: bool,
+    is_ascii: bool,
+    show_console: bool,
     redraw: bool,
 }
 
 impl App {
     pub(crate) fn new(db: Arc<Database>) -> Result<Self> {
-        let last_key_events = vec![];
-
         let top_bar = TopBar::new(vec!["Tests".to_string()]);
         let bottom_bar = BottomBar::new();
 
-        let mut panes = HashMap::from([(
-            PaneType::TestList,
-            Box::new(TestListPane::new()) as Box<dyn Pane>,
-        )]);
+        let mut panes = HashMap::from([
+            (
+                PaneType::TestList,
+                Box::new(TestListPane::new()) as Box<dyn Pane>,
+            ),
+            (
+                PaneType::Console,
+                Box::new(ConsolePane::new()) as Box<dyn Pane>,
+            ),
+        ]);
 
         panes.get_mut(&PaneType::TestList).unwrap().set_focus(true);
 
@@ -58,8 +84,11 @@ impl App {
 
         Ok(Self {
             should_quit: false,
-            last_key_events,
+            key_buffer: KeyBuffer::new(DEFAULT_KEY_TIMEOUT),
             db,
+            plugin_pool: PluginPool::default(),
+            action_tx: None,
+            run_task: None,
 
             selected_test_id: None,
 
@@ -70,15 +99,32 @@ impl App {
             top_bar,
             bottom_bar,
 
+            is_compact: false,
+            is_ascii: false,
+            show_console: false,
             redraw: true,
         })
     }
 
+    /// How long to wait for the rest of a multi-key sequence before dropping the keys so far.
+    pub(crate) fn set_key_timeout(&mut self, timeout: Duration) {
+        self.key_buffer.set_timeout(timeout);
+    }
+
+    /// Draw with plain ASCII for terminals that can't show unicode.
+    pub(crate) fn set_ascii(&mut self, is_ascii: bool) {
+        self.is_ascii = is_ascii;
+        for pane in self.panes.values_mut() {
+            pane.set_ascii(is_ascii);
+        }
+    }
+
     pub(crate) async fn run(&mut self) -> Result<()> {
         let db = self.db.clone();
 
         let (action_tx, mut action_rx) = mpsc::unbounded_channel();
         let action_tx_clone = action_tx.clone();
+        self.action_tx = Some(action_tx.clone());
 
         tokio::spawn(async move {
             let action_tx = action_tx_clone;
@@ -140,34 +186,27 @@ impl App {
 
         tui.exit()?;
 
+        if let Some(run_task) = self.run_task.take() {
+            run_task.abort();
+        }
+        self.plugin_pool.shutdown().await;
+
         Ok(())
     }
 
     fn handle_keys(&mut self, key: KeyEvent, action_tx: &UnboundedSender<Action>) -> Result<()> {
         trace!("Pressed key: {:?}", key);
-        trace!("Last key events: {:?}", self.last_key_events);
+        trace!("Last key events: {:?}", self.key_buffer.keys());
 
-        self.last_key_events.push(key);
-
-        // TODO: This needs sorting out to enable multiple keys to be sent to things
-        // and to clearout after timeouts, etc.
+        self.key_buffer.push(key, Instant::now());
+        let keys = self.key_buffer.keys();
 
         // Always have a fallback to check for double ctrl-c and quit, can't be
         // overridden
-        if self.last_key_events[self.last_key_events.len().saturating_sub(2)..]
-            == vec![
-                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
-                KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL),
-            ]
-        {
+        if keys.ends_with(&[KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL); 2]) {
             debug!("Got double ctrl-c, quitting");
             action_tx.send(Action::Quit)?;
-            self.last_key_events.clear();
-        } else if self.last_key_events[self.last_key_events.len().saturating_sub(1)..]
-            == vec![KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE)]
-        {
-            self.last_key_events.clear();
-            self.last_key_events.push(key);
+            self.key_buffer.clear();
         } else {
             let crumb_last_pane = &PaneType::TestList;
             if let Some(popup) = self.popups.last_mut() {
@@ -184,7 +223,7 @@ impl App {
                 let Some(selected_pane) = self.panes.get_mut(&self.selected_pane) else {
                     panic!("Selected pane not found");
                 };
-                for action in selected_pane.input(&key)?.into_iter() {
+                for action in selected_pane.input(self.key_buffer.keys())?.into_iter() {
                     action_tx.send(action)?;
                 }
             }
@@ -207,9 +246,17 @@ impl App {
                 }
                 self.redraw = false;
             }
-            Action::Tick => {}
+            Action::Tick => {
+                self.key_buffer.expire(Instant::now());
+            }
             Action::Resize(w, h) => {
                 tui.resize(Rect::new(0, 0, *w, *h))?;
+                // Panes only learn how much fits as they're rendered, so lay them out at the new
+                // size before asking them to put the selection back in view
+                tui.draw(|f| self.render(f))?;
+                for pane in self.panes.values_mut() {
+                    pane.relayout();
+                }
                 self.redraw = true;
             }
             Action::Quit => self.should_quit = true,
@@ -236,6 +283,54 @@ impl App {
                 self.popups.pop();
                 self.redraw = true;
             }
+            Action::ToggleCompact => {
+                self.is_compact = !self.is_compact;
+                for pane in self.panes.values_mut() {
+                    pane.set_compact(self.is_compact);
+                }
+                self.redraw = true;
+            }
+            Action::ToggleConsole => {
+                self.show_console = !self.show_console;
+                extra_actions.push(Action::PaneChange(if self.show_console {
+                    PaneType::Console
+                } else {
+                    PaneType::TestList
+                }));
+            }
+            Action::PatuiEvent(_) => {
+                // The console and the test's details see every event, the selected pane is
+                // updated below anyway
+                for pane_type in [PaneType::Console, PaneType::TestDetail] {
+                    if self.selected_pane != pane_type {
+                        if let Some(pane) = self.panes.get_mut(&pane_type) {
+                            extra_actions.extend(pane.update(action)?);
+                        }
+                    }
+                }
+                self.redraw = self.redraw
+                    || self.show_console
+                    || self.panes.contains_key(&PaneType::TestDetail);
+            }
+            Action::RunTest(id) => {
+                if self
+                    .run_task
+                    .as_ref()
+                    .is_some_and(|task| !task.is_finished())
+                {
+                    extra_actions.push(Action::Error(PatuiError::new(
+                        ErrorType::Info,
+                        "A test is already running, wait for it to finish first".to_string(),
+                    )));
+                } else if let Some(action_tx) = &self.action_tx {
+                    self.run_task = Some(
+                        start_run(self.db.clone(), &self.plugin_pool, *id, action_tx.clone())
+                            .await?,
+                    );
+                    self.show_console = true;
+                }
+                self.redraw = true;
+            }
             Action::EditorMode(editor_mode) => {
                 self.handle_editor_mode(editor_mode, tui, &mut extra_actions)
                     .await;
@@ -259,12 +354,29 @@ impl App {
             Action::DbCreate(ref db_change) => {
                 tracing::trace!("Got db change: {:?}", db_change);
                 match db_change.clone() {
-                    DbCreate::Test(details) => {
-                        let test = self.db.new_test(details).await?;
-                        extra_actions.push(Action::UpdateData(UpdateData::Tests(
-                            self.db.get_tests().await?,
-                        )));
-                        extra_actions.push(Action::UpdateData(UpdateData::TestDetail(test.into())));
+                    DbCreate::Test(details) => match self.db.new_test(details).await {
+                        Ok(test) => {
+                            extra_actions.push(Action::UpdateData(UpdateData::Tests(
+                                self.db.get_tests().await?,
+                            )));
+                            extra_actions
+                                .push(Action::UpdateData(UpdateData::TestDetail(test.into())));
+                        }
+                        Err(e) => extra_actions.push(name_conflict(e, "create test")?),
+                    },
+                    DbCreate::CloneTest(id) => {
+                        let name = format!("{} (copy)", self.db.get_test(id).await?.name);
+                        match self.db.clone_test(id, name).await {
+                            Ok(clone_id) => {
+                                extra_actions.push(Action::UpdateData(UpdateData::Tests(
+                                    self.db.get_tests().await?,
+                                )));
+                                extra_actions.push(Action::UpdateData(UpdateData::TestDetail(
+                                    self.db.get_test(clone_id).await?.into(),
+                                )));
+                            }
+                            Err(e) => extra_actions.push(name_conflict(e, "clone test")?),
+                        }
                     }
                 };
                 self.redraw = true;
@@ -272,12 +384,25 @@ impl App {
             Action::DbUpdate(ref db_change) => {
                 tracing::trace!("Got db change: {:?}", db_change);
                 match db_change.clone() {
-                    DbUpdate::Test(test) => {
-                        self.db.edit_test(&test).await?;
+                    DbUpdate::Test(test) => match self.db.edit_test(&test).await {
+                        Ok(()) => {
+                            extra_actions.push(Action::UpdateData(UpdateData::Tests(
+                                self.db.get_tests().await?,
+                            )));
+                            extra_actions.push(Action::UpdateData(UpdateData::TestDetail(test)));
+                        }
+                        Err(e) => extra_actions.push(name_conflict(e, "save test")?),
+                    },
+                    DbUpdate::RenameTest(id, name) => {
+                        if let Err(e) = self.db.rename_test(id, name).await {
+                            extra_actions.push(name_conflict(e, "rename test")?);
+                        }
                         extra_actions.push(Action::UpdateData(UpdateData::Tests(
                             self.db.get_tests().await?,
                         )));
-                        extra_actions.push(Action::UpdateData(UpdateData::TestDetail(test)));
+                        extra_actions.push(Action::UpdateData(UpdateData::TestDetail(
+                            self.db.get_test(id).await?.into(),
+                        )));
                     }
                 };
                 self.redraw = true;
@@ -293,7 +418,7 @@ impl App {
                 }
                 self.redraw = true;
             }
-            Action::ClearKeys => self.last_key_events.clear(),
+            Action::ClearKeys => self.key_buffer.clear(),
             Action::UpdateData(_) => {
                 self.redraw = true;
             }
@@ -356,6 +481,20 @@ impl App {
     }
 
     fn render_centre(&self, f: &mut Frame, r: Rect) {
+        let r = if self.show_console {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+                .split(r);
+            let Some(console_pane) = self.panes.get(&PaneType::Console) else {
+                panic!("Console pane not found");
+            };
+            console_pane.render(f, chunks[1]);
+            chunks[0]
+        } else {
+            r
+        };
+
         match self.mode {
             Mode::TestList => {
                 let pane = self.panes.get(&PaneType::TestList).unwrap();
@@ -380,6 +519,15 @@ impl App {
     }
 
     fn render_create_popup(&self, f: &mut Frame, r: Rect, popup: &Popup) {
+        let title = popup.mode.title();
+
+        // Diffs get the whole screen, they need all the room they can get
+        if let PopupMode::Diff { .. } = popup.mode {
+            f.render_widget(Clear, r);
+            popup.component.render(f, r, title);
+            return;
+        }
+
         let popup_layout = Layout::vertical([
             Constraint::Percentage(20),
             Constraint::Percentage(60),
@@ -396,8 +544,6 @@ impl App {
 
         f.render_widget(Clear, area);
 
-        let title = popup.mode.title();
-
         popup.component.render(f, area, title);
     }
 
@@ -447,6 +593,13 @@ impl App {
             }
         };
 
+        // Panes may have just been created so make sure they match the current display mode
+        for (pane_type, pane) in self.panes.iter_mut() {
+            pane.set_compact(self.is_compact);
+            pane.set_ascii(self.is_ascii);
+            pane.set_focus(*pane_type == self.selected_pane);
+        }
+
         // TODO:
         // let panes_len = self.panes.len();
         // let effective_panes_len = cmp::min(panes_len, self.selected_pane + 1);
@@ -522,11 +675,192 @@ impl App {
             // Box::new(TestEditComponent::new_update(
             //     self.db.get_test(*id).await?.details,
             // )?),
+            PopupMode::RenameTest(id) => {
+                Box::new(RenameComponent::new(*id, self.db.get_test(*id).await?.name))
+            }
             PopupMode::Help => Box::new(HelpComponent::new(self.get_help())),
             PopupMode::Error => unreachable!(), // Handled elsewhere, use Action::Error
+            PopupMode::Diff { expected, actual } => Box::new(DiffComponent::new(expected, actual)),
+            PopupMode::Diagnostics(diagnostics) => Box::new(DiagnosticsComponent::new(diagnostics)),
         };
         self.popups.push(Popup::new(popup_mode.clone(), component));
 
         Ok(())
     }
 }
+
+/// Start a run of test `id` in the background, passing its events on to `action_tx` as they
+/// happen and reading the test again once it's done so what's shown is up to date. The run is
+/// abandoned after the test's default timeout if it has one. Must be called from within a
+/// `LocalSet`, see `main`.
+async fn start_run(
+    db: Arc<Database>,
+    plugin_pool: &PluginPool,
+    id: PatuiTestId,
+    action_tx: UnboundedSender<Action>,
+) -> Result<JoinHandle<()>> {
+    let test = db.get_test(id).await?;
+    let timeout = test.default_timeout_ms.map(Duration::from_millis);
+    let instance = db.get_or_new_instance(test).await?;
+    let run = db.new_run(instance).await?;
+
+    if let Err(e) = db.mark_test_used(id).await {
+        tracing::warn!("Failed to update last used date for test {}: {}", id, e);
+    }
+
+    let mut runner = TestRunner::new_with_plugin_pool(run, plugin_pool);
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    runner.set_events(events_tx);
+
+    Ok(tokio::task::spawn_local(async move {
+        let forward_tx = action_tx.clone();
+        let forward = tokio::spawn(async move {
+            while let Some(event) = events_rx.recv().await {
+                let _ = forward_tx.send(Action::PatuiEvent(event));
+            }
+        });
+
+        let res = match timeout {
+            Some(limit) => runner.run_test_with_timeout(limit).await,
+            None => runner.run_test().await,
+        };
+        let res = match res {
+            Ok(run) => db.save_run_env(run.id, &run.env).await,
+            Err(e) => Err(e),
+        };
+        let _ = forward.await;
+
+        if let Err(e) = res {
+            let _ = action_tx.send(Action::Error(PatuiError::new(
+                ErrorType::Error,
+                format!("Failed to run test {}: {}", id, e),
+            )));
+        }
+        let _ = action_tx.send(Action::DbRead(DbRead::TestDetail(id)));
+    }))
+}
+
+/// Picking a name that's taken is easily fixed so it's just said, any other error is passed on.
+fn name_conflict(e: eyre::Report, doing: &str) -> Result<Action> {
+    match e.downcast_ref::<DbError>() {
+        Some(conflict @ DbError::Conflict(_)) => Ok(Action::Error(PatuiError::new(
+            ErrorType::Info,
+            format!("Can't {}, {}", doing, conflict),
+        ))),
+        _ => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assertor::*;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tokio::time::timeout;
+    use tracing_test::traced_test;
+
+    use crate::types::{
+        PatuiEventKind, PatuiStep, PatuiStepDetails, PatuiStepRead, PatuiTestDetails,
+    };
+
+    use super::*;
+
+    #[traced_test]
+    #[tokio::test]
+    async fn start_run_for_test() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::new(&tmpdir.path().join("test.db")).await.unwrap());
+        db.create_tables().await.unwrap();
+
+        let test = db
+            .new_test(PatuiTestDetails {
+                name: "test".to_string(),
+                description: "test".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: crate::utils::get_current_time_string(),
+                steps: vec![PatuiStep {
+                    name: "FooFile".to_string(),
+                    when: None,
+                    depends_on: vec![],
+                    timeout_ms: None,
+                    details: PatuiStepDetails::Read(PatuiStepRead {
+                        r#in: "\"tests/data/test.json\"".try_into().unwrap(),
+                    }),
+                }],
+            })
+            .await
+            .unwrap();
+
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let task = start_run(db.clone(), &PluginPool::default(), test.id, action_tx)
+                    .await
+                    .unwrap();
+                assert_that!(timeout(Duration::from_secs(5), task).await).is_ok();
+            })
+            .await;
+
+        let mut actions = vec![];
+        while let Ok(action) = action_rx.try_recv() {
+            actions.push(action);
+        }
+
+        assert_that!(matches!(
+            actions.first(),
+            Some(Action::PatuiEvent(event))
+                if matches!(event.value(), PatuiEventKind::StepStarted { name } if name == "FooFile")
+        ))
+        .is_true();
+        assert_that!(actions.last())
+            .is_equal_to(Some(&Action::DbRead(DbRead::TestDetail(test.id))));
+        assert_that!(db.get_test(test.id).await.unwrap().times_used).is_equal_to(1);
+    }
+
+    #[traced_test]
+    #[tokio::test]
+    async fn hint_when_no_tests() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Database::new(&tmpdir.path().join("test.db")).await.unwrap());
+        db.create_tables().await.unwrap();
+
+        // Opened again, the tables already exist so there's no welcome
+        let db = Arc::new(Database::new(&tmpdir.path().join("test.db")).await.unwrap());
+        assert_that!(db.create_tables().await.unwrap()).is_false();
+
+        let mut app = App::new(db.clone()).unwrap();
+        let tests = db.get_tests().await.unwrap();
+        app.panes
+            .get_mut(&PaneType::TestList)
+            .unwrap()
+            .update(&Action::UpdateData(UpdateData::Tests(tests)))
+            .unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let content = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+
+        assert_that!(content).contains("No tests yet — press 'n' to create one");
+    }
+
+    #[test]
+    fn name_conflict_only_says_so() {
+        let action = name_conflict(DbError::Conflict("test".to_string()).into(), "clone test");
+        assert_that!(action.unwrap()).is_equal_to(Action::Error(PatuiError::new(
+            ErrorType::Info,
+            "Can't clone test, a test named `test` already exists".to_string(),
+        )));
+
+        let err = name_conflict(DbError::NotFound(42.into()).into(), "clone test").unwrap_err();
+        assert_that!(err.downcast_ref::<DbError>())
+            .is_equal_to(Some(&DbError::NotFound(42.into())));
+    }
+}
@@ -34,11 +34,25 @@ impl BottomBar {
             | (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
                 Ok(std::vec![Action::PopupCreate(PopupMode::Help)])
             }
+            (KeyCode::Char('t'), KeyModifiers::CONTROL) => Ok(std::vec![Action::ToggleCompact]),
+            (KeyCode::Char('o'), KeyModifiers::CONTROL) => Ok(std::vec![Action::ToggleConsole]),
             _ => Ok(std::vec![]),
         }
     }
 
     pub(crate) fn keys(&self, _mode: &PaneType) -> Vec<HelpItem> {
-        std::vec![HelpItem::new("C-? | C-h", "Help Popup", "Help Popup")]
+        std::vec![
+            HelpItem::new("C-? | C-h", "Help Popup", "Help Popup"),
+            HelpItem::new(
+                "C-t",
+                "Toggle Borders",
+                "Toggle compact display without borders or padding",
+            ),
+            HelpItem::new(
+                "C-o",
+                "Toggle Console",
+                "Show or hide the console of events from test runs",
+            ),
+        ]
     }
 }
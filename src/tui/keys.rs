@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::KeyEvent;
+
+/// How long a partly typed key sequence, e.g. the first `g` of `gg`, waits for its next key.
+pub(crate) const DEFAULT_KEY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The keys pressed so far that haven't yet been acted on. Whoever acts on them clears the
+/// buffer, anything left once `timeout` has passed since the last key is dropped by `expire`.
+#[derive(Debug)]
+pub(crate) struct KeyBuffer {
+    keys: Vec<KeyEvent>,
+    timeout: Duration,
+    deadline: Option<Instant>,
+}
+
+impl KeyBuffer {
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            keys: vec![],
+            timeout,
+            deadline: None,
+        }
+    }
+
+    pub(crate) fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// The pending keys, oldest first.
+    pub(crate) fn keys(&self) -> &[KeyEvent] {
+        &self.keys
+    }
+
+    pub(crate) fn push(&mut self, key: KeyEvent, now: Instant) {
+        self.keys.push(key);
+        self.deadline = Some(now + self.timeout);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.keys.clear();
+        self.deadline = None;
+    }
+
+    /// Drop the pending keys if the timeout has passed by `now`, returns whether anything was
+    /// dropped.
+    pub(crate) fn expire(&mut self, now: Instant) -> bool {
+        match self.deadline {
+            Some(deadline) if now >= deadline => {
+                tracing::trace!("Dropping stale keys: {:?}", self.keys);
+                self.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use assertor::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tracing_test::traced_test;
+
+    use super::KeyBuffer;
+
+    #[traced_test]
+    #[test]
+    fn key_buffer_expire() {
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+        let key = KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE);
+
+        assert_that!(buffer.expire(start)).is_false();
+
+        buffer.push(key, start);
+        buffer.push(key, start + Duration::from_millis(400));
+        assert_that!(buffer.keys().len()).is_equal_to(2);
+
+        // The deadline moves on with each key pressed
+        assert_that!(buffer.expire(start + Duration::from_millis(600))).is_false();
+        assert_that!(buffer.keys().len()).is_equal_to(2);
+
+        assert_that!(buffer.expire(start + Duration::from_millis(900))).is_true();
+        assert_that!(buffer.keys().len()).is_equal_to(0);
+        assert_that!(buffer.expire(start + Duration::from_secs(10))).is_false();
+    }
+}
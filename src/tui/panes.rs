@@ -4,15 +4,18 @@ use ratatui::{layout::Rect, Frame};
 
 use super::app::{Action, HelpItem};
 
+mod console;
 mod test_details;
 mod test_list;
 
+pub(crate) use console::ConsolePane;
 pub(crate) use test_details::TestDetailsPane;
 pub(crate) use test_list::TestListPane;
 
 pub(crate) trait Pane: std::fmt::Debug {
-    /// Take input for the component and optionally send back an action to perform
-    fn input(&mut self, _key: &KeyEvent) -> Result<Vec<Action>> {
+    /// Take input for the component and optionally send back an action to perform, `keys` holds
+    /// the keys pressed that haven't been acted on yet with the newest last
+    fn input(&mut self, _keys: &[KeyEvent]) -> Result<Vec<Action>> {
         Ok(vec![])
     }
 
@@ -28,6 +31,16 @@ pub(crate) trait Pane: std::fmt::Debug {
 
     fn set_focus(&mut self, _focus: bool);
 
+    /// Switch between the normal and compact, borderless rendering
+    fn set_compact(&mut self, _is_compact: bool) {}
+
+    /// Draw borders and scrollbars with plain ASCII rather than unicode
+    fn set_ascii(&mut self, _is_ascii: bool) {}
+
+    /// Called once the pane has been rendered at a new size so it can bring anything the resize
+    /// pushed out of view, such as the selection, back into view
+    fn relayout(&mut self) {}
+
     /// Render the component into the rect given
     fn render(&self, f: &mut Frame, rect: Rect);
 
@@ -0,0 +1,453 @@
+use std::{cell::Cell, collections::VecDeque};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Paragraph, ScrollbarState},
+    Frame,
+};
+
+use crate::{
+    tui::{
+        app::{Action, HelpItem, PaneType, PopupMode},
+        widgets::{scrollbar, scrollbar_area, titled_block},
+    },
+    types::{PatuiDiagnosticSeverity, PatuiEvent, PatuiEventKind, PatuiStepOutcome},
+};
+
+use super::Pane;
+
+/// How many events the console holds on to, the oldest are dropped to make room for new ones.
+const CONSOLE_CAPACITY: usize = 1000;
+
+/// How much an event matters, the console can be limited to events at or above a severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Severity {
+    /// Data sent between steps.
+    Debug,
+    /// Steps starting and passing.
+    Info,
+    /// Plugins warning of something that doesn't stop their step.
+    Warning,
+    /// Steps failing.
+    Error,
+}
+
+impl Severity {
+    fn of(event: &PatuiEvent) -> Self {
+        match event.value() {
+            PatuiEventKind::Bytes(_) => Severity::Debug,
+            PatuiEventKind::StepStarted { .. } => Severity::Info,
+            PatuiEventKind::StepFinished { outcome, .. } => match outcome {
+                PatuiStepOutcome::Passed => Severity::Info,
+                PatuiStepOutcome::Failed(_) => Severity::Error,
+            },
+            PatuiEventKind::Diagnostic { severity, .. } => match severity {
+                PatuiDiagnosticSeverity::Info => Severity::Info,
+                PatuiDiagnosticSeverity::Warning => Severity::Warning,
+                PatuiDiagnosticSeverity::Error => Severity::Error,
+            },
+        }
+    }
+
+    fn colour(self) -> Color {
+        match self {
+            Severity::Debug => Color::DarkGray,
+            Severity::Info => Color::White,
+            Severity::Warning => Color::Yellow,
+            Severity::Error => Color::Red,
+        }
+    }
+
+    /// The severity after this one, wrapping back round to the lowest.
+    fn next(self) -> Self {
+        match self {
+            Severity::Debug => Severity::Info,
+            Severity::Info => Severity::Warning,
+            Severity::Warning => Severity::Error,
+            Severity::Error => Severity::Debug,
+        }
+    }
+}
+
+/// Tails the events from test runs, following the newest unless scrolled back.
+#[derive(Debug)]
+pub(crate) struct ConsolePane {
+    events: VecDeque<(Severity, String)>,
+    min_severity: Severity,
+    /// Show just steps failing whatever `min_severity` is.
+    is_only_failures: bool,
+    /// How many lines up from the newest the view is, 0 follows new events as they arrive.
+    scroll_back: usize,
+    /// Just the diagnostics plugins reported, for showing by themselves.
+    diagnostics: VecDeque<String>,
+
+    is_focussed: bool,
+    is_compact: bool,
+    is_ascii: bool,
+    num_display_lines: Cell<usize>,
+}
+
+impl ConsolePane {
+    pub(crate) fn new() -> Self {
+        Self {
+            events: VecDeque::with_capacity(CONSOLE_CAPACITY),
+            min_severity: Severity::Debug,
+            is_only_failures: false,
+            scroll_back: 0,
+            diagnostics: VecDeque::new(),
+
+            is_focussed: false,
+            is_compact: false,
+            is_ascii: false,
+            num_display_lines: Cell::new(10),
+        }
+    }
+
+    pub(crate) fn push(&mut self, event: &PatuiEvent) {
+        let severity = Severity::of(event);
+
+        let message = match event.value() {
+            PatuiEventKind::Bytes(bytes) => format!("sent {:?}", bytes),
+            PatuiEventKind::StepStarted { .. } => "started".to_string(),
+            PatuiEventKind::StepFinished { outcome, .. } => match outcome {
+                PatuiStepOutcome::Passed => "passed".to_string(),
+                PatuiStepOutcome::Failed(e) => format!("failed: {}", e),
+            },
+            PatuiEventKind::Diagnostic {
+                severity,
+                summary,
+                detail,
+            } => format!("{:?}: {}: {}", severity, summary, detail),
+        };
+        let message = format!("{}: {}", event.step_name(), message);
+
+        if let PatuiEventKind::Diagnostic { .. } = event.value() {
+            if self.diagnostics.len() == CONSOLE_CAPACITY {
+                self.diagnostics.pop_front();
+            }
+            self.diagnostics.push_back(message.clone());
+        }
+
+        if self.events.len() == CONSOLE_CAPACITY {
+            self.events.pop_front();
+        }
+        self.events.push_back((severity, message));
+
+        // Keep what's being looked at in place when scrolled back
+        if self.scroll_back > 0 && severity >= self.shown_severity() {
+            self.scroll_back += 1;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+        self.diagnostics.clear();
+        self.scroll_back = 0;
+    }
+
+    pub(crate) fn set_min_severity(&mut self, min_severity: Severity) {
+        self.min_severity = min_severity;
+        self.scroll_back = 0;
+    }
+
+    pub(crate) fn toggle_only_failures(&mut self) {
+        self.is_only_failures = !self.is_only_failures;
+        self.scroll_back = 0;
+    }
+
+    /// The lowest severity of event shown.
+    fn shown_severity(&self) -> Severity {
+        if self.is_only_failures {
+            Severity::Error
+        } else {
+            self.min_severity
+        }
+    }
+
+    /// The events shown with the current filter, oldest first.
+    fn visible(&self) -> impl Iterator<Item = &(Severity, String)> {
+        let shown_severity = self.shown_severity();
+
+        self.events
+            .iter()
+            .filter(move |(severity, _)| *severity >= shown_severity)
+    }
+
+    fn max_scroll_back(&self) -> usize {
+        self.visible()
+            .count()
+            .saturating_sub(self.num_display_lines.get())
+    }
+
+    fn scroll(&mut self, count: isize) {
+        self.scroll_back = self
+            .scroll_back
+            .saturating_add_signed(-count)
+            .min(self.max_scroll_back());
+    }
+}
+
+impl Pane for ConsolePane {
+    fn render(&self, f: &mut Frame, rect: Rect) {
+        let style = if self.is_focussed {
+            Style::default().fg(Color::White).bg(Color::Black)
+        } else {
+            Style::default().fg(Color::DarkGray).bg(Color::Black)
+        };
+
+        let display_height = if self.is_compact {
+            rect.height.saturating_sub(1)
+        } else {
+            rect.height.saturating_sub(4)
+        } as usize;
+        self.num_display_lines.set(display_height);
+
+        let num_visible = self.visible().count();
+        let scroll_back = self
+            .scroll_back
+            .min(num_visible.saturating_sub(display_height));
+        let first_row = num_visible.saturating_sub(display_height + scroll_back);
+
+        let lines = self
+            .visible()
+            .skip(first_row)
+            .take(display_height)
+            .map(|(severity, text)| Line::from(text.as_str()).style(style.fg(severity.colour())))
+            .collect::<Vec<_>>();
+
+        let title = if self.is_only_failures {
+            "Console (failures only)".to_string()
+        } else {
+            format!("Console ({:?} and above)", self.min_severity)
+        };
+        f.render_widget(
+            Paragraph::new(lines).block(titled_block(title, style, self.is_compact, self.is_ascii)),
+            rect,
+        );
+
+        let scrollbar = scrollbar(self.is_ascii);
+        let mut scrollbar_state =
+            ScrollbarState::new(num_visible.saturating_sub(display_height)).position(first_row);
+        f.render_stateful_widget(
+            scrollbar,
+            scrollbar_area(rect, self.is_compact),
+            &mut scrollbar_state,
+        );
+    }
+
+    fn update(&mut self, action: &Action) -> Result<Vec<Action>> {
+        if let Action::PatuiEvent(event) = action {
+            self.push(event);
+        }
+
+        Ok(vec![])
+    }
+
+    fn input(&mut self, keys: &[KeyEvent]) -> Result<Vec<Action>> {
+        let mut actions = vec![];
+        let Some(key) = keys.last() else {
+            return Ok(actions);
+        };
+
+        match (key.code, key.modifiers) {
+            (KeyCode::Up, KeyModifiers::NONE) | (KeyCode::Char('k'), KeyModifiers::NONE) => {
+                self.scroll(-1);
+            }
+            (KeyCode::Down, KeyModifiers::NONE) | (KeyCode::Char('j'), KeyModifiers::NONE) => {
+                self.scroll(1);
+            }
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                self.scroll(-(self.num_display_lines.get() as isize / 2));
+            }
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                self.scroll(self.num_display_lines.get() as isize / 2);
+            }
+            (KeyCode::Char('G'), KeyModifiers::SHIFT) => self.scroll_back = 0,
+            (KeyCode::Char('s'), KeyModifiers::NONE) => {
+                self.set_min_severity(self.min_severity.next())
+            }
+            (KeyCode::Char('f'), KeyModifiers::NONE) => self.toggle_only_failures(),
+            (KeyCode::Char('x'), KeyModifiers::NONE) => self.clear(),
+            (KeyCode::Char('d'), KeyModifiers::NONE) => {
+                actions.push(Action::PopupCreate(PopupMode::Diagnostics(
+                    self.diagnostics.iter().cloned().collect(),
+                )));
+            }
+            (KeyCode::Esc, KeyModifiers::NONE) => {
+                actions.push(Action::PaneChange(PaneType::TestList));
+            }
+            _ => {}
+        }
+
+        actions.push(Action::ClearKeys);
+        actions.push(Action::ForceRedraw);
+
+        Ok(actions)
+    }
+
+    fn keys(&self) -> Vec<HelpItem> {
+        vec![
+            HelpItem::new("↑ | ↓ | j | k", "Scroll", "Scroll through the events"),
+            HelpItem::new("G", "Follow", "Go back to following the newest events"),
+            HelpItem::new("s", "Severity", "Change the lowest severity of event shown"),
+            HelpItem::new("f", "Failures", "Toggle showing only steps that failed"),
+            HelpItem::new("d", "Diagnostics", "Show the diagnostics plugins reported"),
+            HelpItem::new("x", "Clear", "Clear the console"),
+            HelpItem::new("<Esc>", "Back", "Go back to the test list"),
+        ]
+    }
+
+    fn set_focus(&mut self, focus: bool) {
+        self.is_focussed = focus;
+    }
+
+    fn set_compact(&mut self, is_compact: bool) {
+        self.is_compact = is_compact;
+    }
+
+    fn set_ascii(&mut self, is_ascii: bool) {
+        self.is_ascii = is_ascii;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use bytes::Bytes;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tracing_test::traced_test;
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::{
+        tui::{
+            app::{Action, PopupMode},
+            panes::Pane,
+        },
+        types::{PatuiDiagnosticSeverity, PatuiEvent, PatuiStepOutcome},
+    };
+
+    use super::{ConsolePane, Severity, CONSOLE_CAPACITY};
+
+    fn feed(console: &mut ConsolePane) {
+        for event in [
+            PatuiEvent::step_started("reader".to_string()),
+            PatuiEvent::send_bytes(Bytes::from("hello"), "reader".to_string()),
+            PatuiEvent::step_finished("reader".to_string(), PatuiStepOutcome::Passed),
+            PatuiEvent::step_started("check".to_string()),
+            PatuiEvent::step_finished(
+                "check".to_string(),
+                PatuiStepOutcome::Failed("Assertion `check` failed".to_string()),
+            ),
+        ] {
+            console.update(&Action::PatuiEvent(event)).unwrap();
+        }
+    }
+
+    fn render(console: &ConsolePane) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(50, 9)).unwrap();
+        terminal.draw(|f| console.render(f, f.area())).unwrap();
+
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(50)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[traced_test]
+    #[test]
+    fn console_events() {
+        let mut console = ConsolePane::new();
+        feed(&mut console);
+
+        let mut terminal = Terminal::new(TestBackend::new(50, 9)).unwrap();
+        terminal.draw(|f| console.render(f, f.area())).unwrap();
+        insta::assert_debug_snapshot!(terminal.backend().buffer());
+
+        console.set_min_severity(Severity::Error);
+        let rendered = render(&console);
+        assert_that!(rendered).contains("check: failed: Assertion `check` failed");
+        assert_that!(rendered).does_not_contain("started");
+
+        console.set_min_severity(Severity::Debug);
+        console.clear();
+        assert_that!(render(&console)).does_not_contain("reader");
+    }
+
+    #[traced_test]
+    #[test]
+    fn console_scroll_back() {
+        let mut console = ConsolePane::new();
+        for _ in 0..CONSOLE_CAPACITY + 10 {
+            feed(&mut console);
+        }
+        assert_that!(console.events.len()).is_equal_to(CONSOLE_CAPACITY);
+
+        // 5 lines fit so the newest 5 events are shown
+        let rendered = render(&console);
+        assert_that!(rendered).contains("check: failed");
+        assert_that!(rendered).contains("reader: started");
+
+        // Scrolled back the view stays where it is as events arrive
+        console.scroll(-3);
+        let rendered = render(&console);
+        feed(&mut console);
+        assert_that!(render(&console)).is_equal_to(rendered);
+
+        console.scroll(-(CONSOLE_CAPACITY as isize * 2));
+        assert_that!(console.scroll_back).is_equal_to(CONSOLE_CAPACITY - 5);
+    }
+
+    #[traced_test]
+    #[test]
+    fn console_only_failures() {
+        let mut console = ConsolePane::new();
+        feed(&mut console);
+
+        console.toggle_only_failures();
+        let rendered = render(&console);
+        assert_that!(rendered).contains("Console (failures only)");
+        assert_that!(rendered).contains("check: failed: Assertion `check` failed");
+        assert_that!(rendered).does_not_contain("reader");
+        assert_that!(rendered).does_not_contain("passed");
+
+        // The severity chosen comes back once toggled off again
+        console.toggle_only_failures();
+        assert_that!(render(&console)).contains("reader: passed");
+    }
+
+    #[traced_test]
+    #[test]
+    fn console_diagnostics() {
+        let mut console = ConsolePane::new();
+        feed(&mut console);
+        console
+            .update(&Action::PatuiEvent(PatuiEvent::diagnostic(
+                "plugin".to_string(),
+                PatuiDiagnosticSeverity::Warning,
+                "Slow start".to_string(),
+                "took 3s".to_string(),
+            )))
+            .unwrap();
+
+        console.set_min_severity(Severity::Warning);
+        let rendered = render(&console);
+        assert_that!(rendered).contains("plugin: Warning: Slow start: took 3s");
+        assert_that!(rendered).does_not_contain("reader");
+
+        let actions = console
+            .input(&[KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE)])
+            .unwrap();
+        assert_that!(actions).contains(Action::PopupCreate(PopupMode::Diagnostics(vec![
+            "plugin: Warning: Slow start: took 3s".to_string(),
+        ])));
+    }
+}
@@ -2,44 +2,56 @@ use crate::{
     db::PatuiTestDb,
     tui::{
         app::{Action, HelpItem, StatusChange},
-        widgets::{Text, TextDisplay},
+        widgets::{StepGraph, Tabs, Text, TextDisplay},
     },
+    types::PatuiEventKind,
 };
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use eyre::Result;
-use ratatui::prelude::{Frame, Rect};
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    prelude::{Frame, Rect},
+};
 
 use super::Pane;
 
+const TAB_TITLES: [&str; 3] = ["Overview", "Steps", "Results"];
+
+/// The tab the step graph is shown above.
+const STEPS_TAB: usize = 1;
+
 #[derive(Debug)]
 pub(crate) struct TestDetailsPane {
     // test: PatuiTestDb,
-    text_display: TextDisplay,
+    tabs: Tabs,
+    views: Vec<TextDisplay>,
+    graph: StepGraph,
 }
 
 impl TestDetailsPane {
     pub(crate) fn new(test: PatuiTestDb) -> Self {
-        let mut text = vec![];
-
-        text.push(Text::new(
+        let overview = vec![Text::new(
             format!(
-                "Id: {}\nName: {}\nDescription: {}\nSteps:{}",
-                test.id,
-                test.name,
-                test.description,
-                if test.steps.is_empty() { " []" } else { "" }
+                "Id: {}\nName: {}\nDescription: {}\nCreation Date: {}\nLast Updated: {}",
+                test.id, test.name, test.description, test.creation_date, test.last_updated,
             ),
             false,
-        ));
+        )];
+
+        let mut steps = vec![];
+
+        if test.steps.is_empty() {
+            steps.push(Text::new("Steps: []".to_string(), false));
+        }
 
         for (idx, step) in test.steps.iter().enumerate() {
             match step.details.get_display_yaml() {
                 Ok(yaml) => {
-                    text.push(Text::new(yaml, true));
+                    steps.push(Text::new(yaml, true));
                 }
                 Err(err) => {
-                    text.push(Text::new(
+                    steps.push(Text::new(
                         format!(
                             "Err reading PatuiStep into yaml from step {}: {:?}\n\tErr: {}",
                             idx, step, err
@@ -50,34 +62,101 @@ impl TestDetailsPane {
             }
         }
 
-        let text_display = TextDisplay::new_with_text(text, Some("Test Details".to_string()), true);
+        let results = vec![Text::new(
+            format!(
+                "Last Used: {}\nTimes Used: {}",
+                test.last_used_date.as_deref().unwrap_or("Never"),
+                test.times_used,
+            ),
+            false,
+        )];
+
+        let views = [overview, steps, results]
+            .into_iter()
+            .zip(TAB_TITLES)
+            .map(|(text, title)| TextDisplay::new_with_text(text, Some(title.to_string()), true))
+            .collect();
+
+        let tabs = Tabs::new(TAB_TITLES.iter().map(|title| title.to_string()).collect());
+
+        let graph = StepGraph::new(&test.steps);
 
         Self {
             // test,
-            text_display,
+            tabs,
+            views,
+            graph,
         }
     }
+
+    fn current_view(&self) -> &TextDisplay {
+        &self.views[self.tabs.selected_idx()]
+    }
+
+    fn current_view_mut(&mut self) -> &mut TextDisplay {
+        &mut self.views[self.tabs.selected_idx()]
+    }
+
+    fn change_tab(&mut self, changed: bool, actions: &mut Vec<Action>) {
+        if changed {
+            actions.push(Action::ForceRedraw);
+        }
+        actions.push(Action::ClearKeys);
+    }
 }
 
 impl Pane for TestDetailsPane {
     fn render(&self, f: &mut Frame, rect: Rect) {
-        f.render_widget(&self.text_display, rect);
+        // Leave the steps themselves at least half the room
+        let graph_height = if self.tabs.selected_idx() == STEPS_TAB {
+            self.graph.height().min(rect.height / 2)
+        } else {
+            0
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(graph_height),
+                Constraint::Min(5),
+            ])
+            .split(rect);
+
+        f.render_widget(&self.tabs, chunks[0]);
+        f.render_widget(&self.graph, chunks[1]);
+        f.render_widget(self.current_view(), chunks[2]);
     }
 
-    fn input(&mut self, key: &KeyEvent) -> Result<Vec<Action>> {
+    fn input(&mut self, keys: &[KeyEvent]) -> Result<Vec<Action>> {
         let mut actions = vec![];
+        let Some(key) = keys.last() else {
+            return Ok(actions);
+        };
 
         match (key.code, key.modifiers) {
             (KeyCode::Down, KeyModifiers::NONE) | (KeyCode::Char('j'), KeyModifiers::NONE) => {
-                self.text_display.navigate(1);
+                self.current_view_mut().navigate(1);
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Up, KeyModifiers::NONE) | (KeyCode::Char('k'), KeyModifiers::NONE) => {
-                self.text_display.navigate(-1);
+                self.current_view_mut().navigate(-1);
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
+            (KeyCode::Right, KeyModifiers::NONE) | (KeyCode::Char('l'), KeyModifiers::NONE) => {
+                let changed = self.tabs.navigate(1);
+                self.change_tab(changed, &mut actions);
+            }
+            (KeyCode::Left, KeyModifiers::NONE) | (KeyCode::Char('h'), KeyModifiers::NONE) => {
+                let changed = self.tabs.navigate(-1);
+                self.change_tab(changed, &mut actions);
+            }
+            (KeyCode::Char(c @ '1'..='9'), KeyModifiers::NONE) => {
+                let changed = self.tabs.select(c as usize - '1' as usize);
+                self.change_tab(changed, &mut actions);
+            }
             // (KeyCode::Char('e'), KeyModifiers::NONE) => {
             //     if let Some(selected_step) = self.selected_step {
             //         actions.push(Action::EditorMode(EditorMode::UpdateTestStep(
@@ -87,10 +166,10 @@ impl Pane for TestDetailsPane {
             //     }
             // }
             (KeyCode::Esc, KeyModifiers::NONE) => {
-                if !self.text_display.is_selected() {
+                if !self.current_view().is_selected() {
                     actions.push(Action::StatusChange(StatusChange::ModeChangeTestList));
                 } else {
-                    self.text_display.set_unselected();
+                    self.current_view_mut().set_unselected();
                 }
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
@@ -98,20 +177,140 @@ impl Pane for TestDetailsPane {
             _ => {}
         }
 
+        self.graph
+            .set_selected_idx(self.views[STEPS_TAB].selected_idx());
+
         Ok(actions)
     }
 
+    fn update(&mut self, action: &Action) -> Result<Vec<Action>> {
+        if let Action::PatuiEvent(event) = action {
+            if let PatuiEventKind::StepFinished { name, outcome } = event.value() {
+                self.graph.set_outcome(name, outcome.clone());
+            }
+        }
+
+        Ok(vec![])
+    }
+
     fn keys(&self) -> Vec<HelpItem> {
         vec![
             HelpItem::new("n", "New Test", "New Test"),
             HelpItem::new("u", "Update Test", "Update Test"),
             HelpItem::new("d", "Delete Test", "Delete Test"),
             HelpItem::new("↑ | ↓", "Navigate", "Navigate"),
+            HelpItem::new(
+                "← | → | 1-3",
+                "Switch Tab",
+                "Switch between the detail tabs",
+            ),
             HelpItem::new("<Enter>", "Select Test", "Select Test"),
         ]
     }
 
     fn set_focus(&mut self, is_focussed: bool) {
-        self.text_display.set_focus(is_focussed);
+        self.tabs.set_focus(is_focussed);
+        for view in self.views.iter_mut() {
+            view.set_focus(is_focussed);
+        }
+    }
+
+    fn set_compact(&mut self, is_compact: bool) {
+        for view in self.views.iter_mut() {
+            view.set_compact(is_compact);
+        }
+    }
+
+    fn set_ascii(&mut self, is_ascii: bool) {
+        for view in self.views.iter_mut() {
+            view.set_ascii(is_ascii);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, Terminal};
+    use tracing_test::traced_test;
+
+    use crate::{
+        db::PatuiTestDb,
+        tui::panes::Pane,
+        types::{PatuiStep, PatuiStepDetails, PatuiStepRead},
+    };
+
+    use super::TestDetailsPane;
+
+    fn get_pane() -> TestDetailsPane {
+        TestDetailsPane::new(PatuiTestDb {
+            id: 1.into(),
+            name: "test name".to_string(),
+            description: "test description".to_string(),
+            require_all_steps_produce_output: false,
+            default_timeout_ms: None,
+            creation_date: "2024-08-31 11:00:00".to_string(),
+            last_updated: "2024-08-31 11:00:00".to_string(),
+            last_used_date: None,
+            times_used: 0,
+            steps: vec![PatuiStep {
+                name: "FooFile".to_string(),
+                when: None,
+                depends_on: vec![],
+                timeout_ms: None,
+                details: PatuiStepDetails::Read(PatuiStepRead {
+                    r#in: "\"dir/file.txt\"".try_into().unwrap(),
+                }),
+            }],
+        })
+    }
+
+    fn render_to_string(pane: &TestDetailsPane) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| pane.render(f, f.area())).unwrap();
+
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect()
+    }
+
+    #[traced_test]
+    #[test]
+    fn switch_tabs() {
+        let mut pane = get_pane();
+
+        assert_that!(pane.tabs.selected_idx()).is_equal_to(0);
+        let content = render_to_string(&pane);
+        assert_that!(content).contains("Name: test name");
+        assert_that!(content).does_not_contain("dir/file.txt");
+
+        let actions = pane
+            .input(&[KeyEvent::new(KeyCode::Right, KeyModifiers::NONE)])
+            .unwrap();
+        assert_that!(actions).is_not_empty();
+
+        assert_that!(pane.tabs.selected_idx()).is_equal_to(1);
+        let content = render_to_string(&pane);
+        assert_that!(content).contains("dir/file.txt");
+        assert_that!(content).does_not_contain("Name: test name");
+
+        pane.input(&[KeyEvent::new(KeyCode::Char('3'), KeyModifiers::NONE)])
+            .unwrap();
+
+        assert_that!(pane.tabs.selected_idx()).is_equal_to(2);
+        let content = render_to_string(&pane);
+        assert_that!(content).contains("Times Used: 0");
+
+        pane.input(&[KeyEvent::new(KeyCode::Right, KeyModifiers::NONE)])
+            .unwrap();
+
+        assert_that!(pane.tabs.selected_idx()).is_equal_to(0);
+        let content = render_to_string(&pane);
+        assert_that!(content).contains("Name: test name");
     }
 }
@@ -1,8 +1,12 @@
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use eyre::Result;
 use ratatui::{
-    layout::{Constraint, Rect},
+    layout::{Alignment, Constraint, Flex, Layout, Margin, Rect},
     text::Text,
+    widgets::Paragraph,
     Frame,
 };
 
@@ -10,14 +14,53 @@ use crate::{
     db::{PatuiTestDb, PatuiTestId},
     tui::{
         app::{
-            Action, DbRead, EditorMode, HelpItem, PaneType, PopupMode, StatusChange, UpdateData,
+            Action, DbCreate, DbRead, EditorMode, HelpItem, PaneType, PopupMode, StatusChange,
+            UpdateData,
         },
-        widgets::{ScrollType, Table, TableHeader},
+        widgets::{ScrollType, SortValue, Table, TableHeader},
     },
 };
 
 use super::Pane;
 
+/// How long the selection has to stay put before the selected test's details are loaded.
+const PREVIEW_DELAY: Duration = Duration::from_millis(150);
+
+/// Shown in place of the tests while there aren't any.
+const NO_TESTS_HINT: &str = "No tests yet — press 'n' to create one";
+
+/// The columns `s` goes through sorting by, the name, creation date, last used date and times
+/// used.
+const SORT_COLUMNS: [usize; 4] = [0, 2, 3, 4];
+
+/// The characters bound to something as the first key typed, any other starts finding a test by
+/// name.
+const BOUND_CHARS: &str = "nucerRspSgGHMLhjklN/:";
+
+/// Typed before a name to find a test by it, needed when the name starts with a bound character.
+const FIND_KEY: char = '\'';
+
+/// Whether `key` starts typing the name of a test to go to.
+fn starts_type_ahead(key: &KeyEvent) -> bool {
+    match (key.code, key.modifiers) {
+        (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+            c == FIND_KEY || !BOUND_CHARS.contains(c)
+        }
+        _ => false,
+    }
+}
+
+/// Dates are kept as the local time they were written at, sorting by the time they are rather
+/// than how they read keeps them in order across changes of timezone.
+fn date_sort_value(date: &str) -> SortValue {
+    if let Ok(date) = date.parse::<DateTime<FixedOffset>>() {
+        return SortValue::Date(date.naive_utc());
+    }
+
+    NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S")
+        .map_or_else(|_| SortValue::Text(date.to_string()), SortValue::Date)
+}
+
 #[derive(Debug)]
 pub(crate) struct TestListPane<'a> {
     initialized: bool,
@@ -26,6 +69,15 @@ pub(crate) struct TestListPane<'a> {
     tests: Vec<PatuiTestDb>,
 
     table: Table<'a>,
+    is_compact: bool,
+
+    /// Show the details of whichever test is selected as the selection moves.
+    preview: bool,
+    /// The test to show the details of once the time given has passed.
+    pending_preview: Option<(PatuiTestId, Instant)>,
+
+    /// The search being typed after `/`, the tests are filtered by it as it's typed.
+    search: Option<String>,
 }
 
 impl<'a> TestListPane<'a> {
@@ -46,27 +98,73 @@ impl<'a> TestListPane<'a> {
             tests: vec![],
 
             table,
+            is_compact: false,
+
+            preview: true,
+            pending_preview: None,
+
+            search: None,
         }
     }
 
     pub(crate) fn update_tests(&mut self, tests: Vec<PatuiTestDb>) {
+        let selected_id = self.get_selected_test_id();
         self.tests = tests;
         self.loading = false;
+
+        let elements = self
+            .tests
+            .iter()
+            .map(|test| {
+                vec![
+                    Text::from(test.name.clone()),
+                    Text::from(test.description.clone()),
+                    Text::from(test.creation_date.clone()),
+                    Text::from(
+                        test.last_used_date
+                            .clone()
+                            .unwrap_or_else(|| "Never".to_string()),
+                    ),
+                    Text::from(test.times_used.to_string()),
+                ]
+            })
+            .collect::<Vec<Vec<Text>>>();
+        let sort_values = self
+            .tests
+            .iter()
+            .map(|test| {
+                vec![
+                    SortValue::Text(test.name.to_lowercase()),
+                    SortValue::Text(test.description.to_lowercase()),
+                    date_sort_value(&test.creation_date),
+                    test.last_used_date
+                        .as_deref()
+                        .map_or(SortValue::Missing, date_sort_value),
+                    SortValue::Number(test.times_used.into()),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        // Refreshed tests keep the selection, scroll position and sort rather than starting over.
+        // The selection follows the selected test if it's moved, e.g. a test was added before it,
+        // or stays where it was if the test's gone.
+        if self.initialized {
+            self.table.set_sort_values(sort_values);
+            self.table.set_elements(elements);
+            if let Some(idx) =
+                selected_id.and_then(|id| self.tests.iter().position(|test| test.id == id))
+            {
+                self.table.select_element(idx);
+            }
+            return;
+        }
         self.initialized = true;
 
         let is_focussed = self.table.is_focussed();
+        let filter = self.table.filter().to_string();
 
         self.table = Table::new_with_elements(
-            self.tests
-                .iter()
-                .map(|test| {
-                    vec![
-                        Text::from(test.name.clone()),
-                        Text::from(test.description.clone()),
-                        Text::from(test.creation_date.clone()),
-                    ]
-                })
-                .collect::<Vec<Vec<Text>>>(),
+            elements,
             vec![
                 TableHeader::new("Name".into(), 0, Constraint::Min(12)),
                 TableHeader::new("Creation Date".into(), 2, Constraint::Max(19)),
@@ -76,13 +174,19 @@ impl<'a> TestListPane<'a> {
                 TableHeader::new("Description".into(), 1, Constraint::Min(5)),
                 TableHeader::new("Creation Date".into(), 2, Constraint::Max(19)),
                 TableHeader::new("Last Used Date".into(), 3, Constraint::Max(19)),
-                TableHeader::new("Times Used".into(), 4, Constraint::Max(10)),
+                // Room for the sort arrow
+                TableHeader::new("Times Used".into(), 4, Constraint::Max(12)),
             ],
             Some("Tests List"),
             true,
         );
 
         self.table.set_focus(is_focussed);
+        self.table.set_compact(self.is_compact);
+        self.table.set_wrap(true);
+        self.table.set_sort_values(sort_values);
+        self.table.set_filter_columns(vec![0, 1]);
+        self.table.set_filter(&filter);
     }
 
     fn get_selected_test_id(&self) -> Option<PatuiTestId> {
@@ -91,19 +195,117 @@ impl<'a> TestListPane<'a> {
             .map(|idx| self.tests[idx].id.into())
     }
 
-    fn change_test_detail(&self) -> Vec<Action> {
-        let Some(id) = self.get_selected_test_id() else {
-            panic!("No test selected");
-        };
-        vec![Action::StatusChange(
-            StatusChange::ModeChangeTestListWithDetails(id),
-        )]
+    /// Jump to the first test whose name starts with `prefix`, ignoring case.
+    fn find_by_prefix(&mut self, prefix: &str) -> Vec<Action> {
+        let prefix = prefix.to_lowercase();
+        let found = self
+            .table
+            .select_by_predicate(|row| row[0].to_string().to_lowercase().starts_with(&prefix));
+
+        match found {
+            Some(_) => {
+                self.schedule_preview();
+                vec![Action::ForceRedraw]
+            }
+            None => vec![],
+        }
+    }
+
+    /// Show only the tests whose name or description contains `filter`, the first of them is
+    /// selected if nothing is already.
+    fn filter(&mut self, filter: &str) {
+        self.table.set_filter(filter);
+        if !filter.is_empty() && self.table.selected_idx().is_none() {
+            self.table.go_to(0);
+        }
+        self.schedule_preview();
+    }
+
+    /// Keys typed after `/` make up the search, <Enter> keeps the tests it filters to and <Esc>
+    /// shows them all again.
+    ///
+    /// Returns `None` for keys that act as usual while searching, e.g. the arrows to move between
+    /// the tests found.
+    fn search_input(&mut self, key: &KeyEvent) -> Option<Vec<Action>> {
+        let search = self.search.as_mut()?;
+
+        match (key.code, key.modifiers) {
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => search.push(c),
+            (KeyCode::Backspace, KeyModifiers::NONE) => {
+                search.pop();
+            }
+            (KeyCode::Enter, KeyModifiers::NONE) => {
+                self.search = None;
+                return Some(vec![Action::ClearKeys, Action::ForceRedraw]);
+            }
+            (KeyCode::Esc, KeyModifiers::NONE) => {
+                self.search = None;
+                self.filter("");
+                return Some(vec![Action::ClearKeys, Action::ForceRedraw]);
+            }
+            _ => return None,
+        }
+
+        let search = search.clone();
+        self.filter(&search);
+
+        Some(vec![Action::ClearKeys, Action::ForceRedraw])
+    }
+
+    /// Load the details of the selected test once the selection's settled, so moving quickly
+    /// through the list doesn't read every test passed over from the database.
+    fn schedule_preview(&mut self) {
+        if !self.preview {
+            return;
+        }
+
+        if let Some(id) = self.get_selected_test_id() {
+            self.pending_preview = Some((id, Instant::now() + PREVIEW_DELAY));
+        }
+    }
+
+    fn poll_preview(&mut self, now: Instant) -> Vec<Action> {
+        match self.pending_preview {
+            Some((id, deadline)) if now >= deadline => {
+                self.pending_preview = None;
+                vec![Action::StatusChange(
+                    StatusChange::ModeChangeTestListWithDetails(id),
+                )]
+            }
+            _ => vec![],
+        }
     }
 }
 
 impl<'a> Pane for TestListPane<'a> {
     fn render(&self, f: &mut Frame, rect: Rect) {
-        f.render_widget(&self.table, rect);
+        let filter = self.search.as_deref().unwrap_or(self.table.filter());
+        if self.search.is_some() || !filter.is_empty() {
+            let [table_rect, search_rect] =
+                Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(rect);
+            f.render_widget(&self.table, table_rect);
+            f.render_widget(
+                Paragraph::new(format!(
+                    "/{}  ({} of {} tests)",
+                    filter,
+                    self.table.num_elements(),
+                    self.tests.len()
+                )),
+                search_rect,
+            );
+        } else {
+            f.render_widget(&self.table, rect);
+        }
+
+        if self.initialized && self.tests.is_empty() {
+            let [hint_rect] = Layout::vertical([Constraint::Length(1)])
+                .flex(Flex::Center)
+                .areas(rect.inner(Margin::new(1, 1)));
+            f.render_widget(
+                Paragraph::new(NO_TESTS_HINT).alignment(Alignment::Center),
+                hint_rect,
+            );
+        }
     }
 
     fn update(&mut self, action: &Action) -> Result<Vec<Action>> {
@@ -115,6 +317,7 @@ impl<'a> Pane for TestListPane<'a> {
                     self.loading = true;
                     ret.push(Action::DbRead(DbRead::Test));
                 }
+                ret.extend(self.poll_preview(Instant::now()));
             }
             Action::UpdateData(UpdateData::Tests(tests)) => self.update_tests(tests.clone()),
             Action::StatusChange(StatusChange::Reset) => self.table.reset(),
@@ -124,10 +327,100 @@ impl<'a> Pane for TestListPane<'a> {
         Ok(ret)
     }
 
-    fn input(&mut self, key: &KeyEvent) -> Result<Vec<Action>> {
+    fn input(&mut self, keys: &[KeyEvent]) -> Result<Vec<Action>> {
         let mut actions = vec![];
+        let Some(key) = keys.last() else {
+            return Ok(actions);
+        };
+
+        if let Some(actions) = self.search_input(key) {
+            return Ok(actions);
+        }
+
+        // Typing a name goes to the first test starting with it, the keys stay pending so the
+        // prefix builds up until no key's been typed for a while. Once started any character is
+        // part of the name, even those bound to something else.
+        if starts_type_ahead(&keys[0]) {
+            match (key.code, key.modifiers) {
+                (KeyCode::Char(_), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                    let typed = match keys[0].code {
+                        KeyCode::Char(FIND_KEY) => &keys[1..],
+                        _ => keys,
+                    };
+                    let prefix = typed
+                        .iter()
+                        .filter_map(|key| match key.code {
+                            KeyCode::Char(c) => Some(c),
+                            _ => None,
+                        })
+                        .collect::<String>();
+                    if prefix.is_empty() {
+                        return Ok(actions);
+                    }
+                    return Ok(self.find_by_prefix(&prefix));
+                }
+                (KeyCode::Esc, KeyModifiers::NONE) => return Ok(vec![Action::ClearKeys]),
+                // Anything else acts as usual, e.g. <Enter> opens the test found
+                _ => {}
+            }
+        }
+
+        // `:` followed by a row number and <Enter> goes to that row, as in an editor rows count
+        // from 1
+        if keys.len() > 1 && keys[0].code == KeyCode::Char(':') {
+            match (key.code, key.modifiers) {
+                (KeyCode::Char(c), KeyModifiers::NONE) if c.is_ascii_digit() => {
+                    return Ok(actions);
+                }
+                (KeyCode::Enter, KeyModifiers::NONE) => {
+                    let row = keys[1..keys.len() - 1]
+                        .iter()
+                        .filter_map(|key| match key.code {
+                            KeyCode::Char(c) => c.to_digit(10),
+                            _ => None,
+                        })
+                        .fold(0_usize, |row, digit| {
+                            row.saturating_mul(10).saturating_add(digit as usize)
+                        });
+                    if self.table.go_to(row.saturating_sub(1)).is_some() {
+                        self.schedule_preview();
+                        actions.push(Action::ForceRedraw);
+                    }
+                }
+                _ => {}
+            }
+            actions.push(Action::ClearKeys);
+            return Ok(actions);
+        }
 
         match (key.code, key.modifiers) {
+            (KeyCode::Char('/'), KeyModifiers::NONE) => {
+                self.search = Some(String::new());
+                self.filter("");
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
+            // Only once there's something searched for, otherwise `n` creates a test
+            (KeyCode::Char('n'), KeyModifiers::NONE)
+            | (KeyCode::Char('N'), KeyModifiers::SHIFT)
+                if !self.table.filter().is_empty() =>
+            {
+                let count = if key.code == KeyCode::Char('n') {
+                    1
+                } else {
+                    -1
+                };
+                if self.table.next_match(count).is_some() {
+                    self.schedule_preview();
+                    actions.push(Action::ForceRedraw);
+                }
+                actions.push(Action::ClearKeys);
+            }
+            (KeyCode::Esc, KeyModifiers::NONE) if !self.table.filter().is_empty() => {
+                self.filter("");
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
             (KeyCode::Char('n'), KeyModifiers::NONE) => {
                 actions.push(Action::PopupCreate(PopupMode::CreateTest));
                 actions.push(Action::ClearKeys);
@@ -144,18 +437,42 @@ impl<'a> Pane for TestListPane<'a> {
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
+            (KeyCode::Char('c'), KeyModifiers::NONE) => {
+                if let Some(selected_test_id) = self.get_selected_test_id() {
+                    actions.push(Action::DbCreate(DbCreate::CloneTest(selected_test_id)));
+                }
+                actions.push(Action::ClearKeys);
+            }
+            (KeyCode::Char('R'), KeyModifiers::SHIFT) => {
+                if let Some(selected_test_id) = self.get_selected_test_id() {
+                    actions.push(Action::PopupCreate(PopupMode::RenameTest(selected_test_id)));
+                }
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
             (KeyCode::Char('e'), KeyModifiers::NONE) => {
                 if let Some(selected_test_id) = self.get_selected_test_id() {
                     actions.push(Action::EditorMode(EditorMode::UpdateTest(selected_test_id)));
                 }
                 actions.push(Action::ClearKeys);
             }
+            (KeyCode::Char('r'), KeyModifiers::NONE) => {
+                if let Some(selected_test_id) = self.get_selected_test_id() {
+                    actions.push(Action::StatusChange(
+                        StatusChange::ModeChangeTestListWithDetails(selected_test_id),
+                    ));
+                    actions.push(Action::RunTest(selected_test_id));
+                }
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
             (KeyCode::Esc, KeyModifiers::NONE) => {
                 actions.push(Action::StatusChange(StatusChange::Reset));
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Enter, KeyModifiers::NONE) => {
+                self.pending_preview = None;
                 if let Some(id) = self.get_selected_test_id() {
                     actions.push(Action::StatusChange(
                         StatusChange::ModeChangeTestListWithDetails(id),
@@ -167,48 +484,87 @@ impl<'a> Pane for TestListPane<'a> {
             }
             (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
                 self.table.scroll(ScrollType::FullPageDown);
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
                 self.table.scroll(ScrollType::FullPageUp);
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
                 self.table.scroll(ScrollType::HalfPageDown);
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
                 self.table.scroll(ScrollType::HalfPageUp);
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
                 self.table.scroll(ScrollType::Single(1));
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
                 self.table.scroll(ScrollType::Single(-1));
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
+            (KeyCode::Char('s'), KeyModifiers::NONE) => {
+                let column = match self.table.sort() {
+                    Some((column, _)) => {
+                        let next = SORT_COLUMNS.iter().position(|c| *c == column).unwrap_or(0) + 1;
+                        SORT_COLUMNS[next % SORT_COLUMNS.len()]
+                    }
+                    None => SORT_COLUMNS[0],
+                };
+                self.table.sort_by(column, true);
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
+            (KeyCode::Char('S'), KeyModifiers::SHIFT) => {
+                let (column, ascending) = self.table.sort().unwrap_or((SORT_COLUMNS[0], true));
+                self.table.sort_by(column, !ascending);
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
+            (KeyCode::Char('p'), KeyModifiers::NONE) => {
+                self.preview = !self.preview;
+                if !self.preview {
+                    self.pending_preview = None;
+                }
+                actions.push(Action::ClearKeys);
+            }
+            // Wait for the row to go to
+            (KeyCode::Char(':'), KeyModifiers::NONE | KeyModifiers::SHIFT) => {}
+            // Leave the first `g` pending until the second arrives
             (KeyCode::Char('g'), KeyModifiers::NONE)
-            | (KeyCode::Char('G'), KeyModifiers::SHIFT)
-            | (KeyCode::Char('H'), KeyModifiers::SHIFT)
+                if !keys.ends_with(&[KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE); 2]) => {
+            }
+            (KeyCode::Char('g'), KeyModifiers::NONE) => {
+                self.table.scroll(ScrollType::Top);
+                self.schedule_preview();
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
+            (KeyCode::Char('G'), KeyModifiers::SHIFT) => {
+                self.table.scroll(ScrollType::Bottom);
+                self.schedule_preview();
+                actions.push(Action::ClearKeys);
+                actions.push(Action::ForceRedraw);
+            }
+            (KeyCode::Char('H'), KeyModifiers::SHIFT)
             | (KeyCode::Char('M'), KeyModifiers::SHIFT)
             | (KeyCode::Char('L'), KeyModifiers::SHIFT) => {
                 let selected_idx = match key.code {
-                    KeyCode::Char('g') => 0,
-                    KeyCode::Char('G') => self.table.num_elements() - 1,
                     KeyCode::Char('H') => self.table.first_row(),
                     KeyCode::Char('M') => {
                         (self.table.first_row() + self.table.display_height()) / 2
@@ -217,25 +573,39 @@ impl<'a> Pane for TestListPane<'a> {
                     _ => unreachable!(),
                 };
                 self.table.set_selected_idx(selected_idx);
-                actions.extend(self.change_test_detail());
+                self.schedule_preview();
                 actions.push(Action::ClearKeys);
                 actions.push(Action::ForceRedraw);
             }
             (KeyCode::Up, KeyModifiers::NONE) | (KeyCode::Char('k'), KeyModifiers::NONE) => {
                 if self.table.navigate(-1) != 0 {
-                    actions.extend(self.change_test_detail());
+                    self.schedule_preview();
+                    actions.push(Action::ForceRedraw);
+                }
+                actions.push(Action::ClearKeys);
+            }
+            (KeyCode::Left, KeyModifiers::NONE) | (KeyCode::Char('h'), KeyModifiers::NONE) => {
+                if self.table.scroll_horizontal(-1) != 0 {
+                    actions.push(Action::ForceRedraw);
+                }
+                actions.push(Action::ClearKeys);
+            }
+            (KeyCode::Right, KeyModifiers::NONE) | (KeyCode::Char('l'), KeyModifiers::NONE) => {
+                if self.table.scroll_horizontal(1) != 0 {
                     actions.push(Action::ForceRedraw);
                 }
                 actions.push(Action::ClearKeys);
             }
             (KeyCode::Down, KeyModifiers::NONE) | (KeyCode::Char('j'), KeyModifiers::NONE) => {
                 if self.table.navigate(1) != 0 {
-                    actions.extend(self.change_test_detail());
+                    self.schedule_preview();
                     actions.push(Action::ForceRedraw);
                 }
                 actions.push(Action::ClearKeys);
             }
-            _ => {}
+            // Leave a first C-c pending, a second quits
+            (KeyCode::Char('c'), KeyModifiers::CONTROL) => {}
+            _ => actions.push(Action::ClearKeys),
         }
 
         Ok(actions)
@@ -247,7 +617,52 @@ impl<'a> Pane for TestListPane<'a> {
             HelpItem::new("C-n", "New Test Yaml", "Create new Test Yaml in Editor"),
             HelpItem::new("u", "Update Test", "Update Test"),
             HelpItem::new("e", "Edit Test Yaml", "Edit Test Yaml in Editor"),
+            HelpItem::new("r", "Run Test", "Run Test, following it in the console"),
+            HelpItem::new("c", "Clone Test", "Clone Test and its Steps"),
+            HelpItem::new(
+                "R",
+                "Rename Test",
+                "Rename Test, the name can't already be taken",
+            ),
             HelpItem::new("↑ | ↓ | j | k", "Navigate", "Navigate"),
+            HelpItem::new(
+                "p",
+                "Toggle Preview",
+                "Toggle showing the details of each test as it's selected",
+            ),
+            HelpItem::new("gg | G", "Top / Bottom", "Go to the first or last test"),
+            HelpItem::new(
+                "s",
+                "Sort",
+                "Sort by the next column of name, creation date, last used date and times used",
+            ),
+            HelpItem::new(
+                "S",
+                "Reverse Sort",
+                "Reverse the order the tests are sorted in",
+            ),
+            HelpItem::new(
+                "<name> | '<name>",
+                "Find",
+                "Go to the first test whose name starts with what's typed, use ' first when it \
+                 starts with a key above",
+            ),
+            HelpItem::new(":<row><Enter>", "Go To Row", "Go to the numbered test row"),
+            HelpItem::new(
+                "/<search>",
+                "Search",
+                "Show only the tests whose name or description contains what's typed",
+            ),
+            HelpItem::new(
+                "n | N",
+                "Next / Previous Match",
+                "Go to the next or previous test found once searching, <Esc> shows all tests",
+            ),
+            HelpItem::new(
+                "← | → | h | l",
+                "Scroll Columns",
+                "Scroll the columns left or right",
+            ),
             HelpItem::new(
                 "C-e | C-y",
                 "Line Forward / Backward",
@@ -284,4 +699,448 @@ impl<'a> Pane for TestListPane<'a> {
         tracing::trace!("Setting focus to {}", focus);
         self.table.set_focus(focus);
     }
+
+    fn set_compact(&mut self, is_compact: bool) {
+        self.is_compact = is_compact;
+        self.table.set_compact(is_compact);
+    }
+
+    fn set_ascii(&mut self, is_ascii: bool) {
+        self.table.set_ascii(is_ascii);
+    }
+
+    fn relayout(&mut self) {
+        self.table.ensure_visible();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use assertor::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tracing_test::traced_test;
+
+    use crate::{
+        db::PatuiTestDb,
+        tui::{
+            app::{Action, PopupMode, StatusChange, UpdateData},
+            keys::KeyBuffer,
+            panes::Pane,
+            widgets::ScrollType,
+        },
+    };
+
+    use super::TestListPane;
+
+    fn get_tests(num_tests: i64) -> Vec<PatuiTestDb> {
+        (1..=num_tests)
+            .map(|id| PatuiTestDb {
+                id: id.into(),
+                name: format!("test {}", id),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: "2024-01-01 00:00:00".to_string(),
+                last_updated: "2024-01-01 00:00:00".to_string(),
+                last_used_date: None,
+                times_used: 0,
+                steps: vec![],
+            })
+            .collect()
+    }
+
+    fn get_named_tests(names: &[&str]) -> Vec<PatuiTestDb> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| PatuiTestDb {
+                id: (id as i64).into(),
+                name: name.to_string(),
+                description: "test description".to_string(),
+                require_all_steps_produce_output: false,
+                default_timeout_ms: None,
+                creation_date: "2024-01-01 00:00:00".to_string(),
+                last_updated: "2024-01-01 00:00:00".to_string(),
+                last_used_date: None,
+                times_used: 0,
+                steps: vec![],
+            })
+            .collect()
+    }
+
+    fn get_pane() -> TestListPane<'static> {
+        let mut pane = TestListPane::new();
+        pane.update_tests(get_tests(5));
+        pane
+    }
+
+    /// Press `key` at `now` as the app would, clearing the buffer once the pane acts on it.
+    fn press(pane: &mut TestListPane, buffer: &mut KeyBuffer, key: char, now: Instant) {
+        buffer.expire(now);
+        buffer.push(KeyEvent::new(KeyCode::Char(key), KeyModifiers::NONE), now);
+        if pane
+            .input(buffer.keys())
+            .unwrap()
+            .contains(&Action::ClearKeys)
+        {
+            buffer.clear();
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn find_by_typed_prefix() {
+        let mut pane = TestListPane::new();
+        pane.update_tests(get_named_tests(&[
+            "signup",
+            "order",
+            "onboard",
+            "order again",
+        ]));
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        press(&mut pane, &mut buffer, 'o', start);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(1));
+
+        // `n` would otherwise create a test
+        press(
+            &mut pane,
+            &mut buffer,
+            'n',
+            start + Duration::from_millis(100),
+        );
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(2));
+        assert_that!(buffer.keys().len()).is_equal_to(2);
+
+        // The prefix has been forgotten so this starts a new one
+        let later = start + Duration::from_secs(2);
+        for key in "ORDER A".chars() {
+            press(&mut pane, &mut buffer, key, later);
+        }
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(3));
+
+        // Nothing matches so nothing changes
+        press(&mut pane, &mut buffer, 'x', later);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(3));
+
+        // A key that isn't handled ends it, after which `n` creates a test as usual
+        buffer.push(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE), later);
+        assert_that!(pane.input(buffer.keys()).unwrap()).contains(Action::ClearKeys);
+        buffer.clear();
+        buffer.push(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE), later);
+        assert_that!(pane.input(buffer.keys()).unwrap())
+            .contains(Action::PopupCreate(PopupMode::CreateTest));
+    }
+
+    #[traced_test]
+    #[test]
+    fn find_by_prefix_starting_with_bound_key() {
+        let mut pane = TestListPane::new();
+        pane.update_tests(get_named_tests(&["signup", "login", "logout"]));
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        // Alone `l` scrolls the columns right
+        press(&mut pane, &mut buffer, '\'', start);
+        assert_that!(pane.table.selected_idx()).is_equal_to(None);
+        for key in "lo".chars() {
+            press(&mut pane, &mut buffer, key, start);
+        }
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(1));
+
+        let later = start + Duration::from_secs(2);
+        for key in "'logo".chars() {
+            press(&mut pane, &mut buffer, key, later);
+        }
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(2));
+    }
+
+    #[traced_test]
+    #[test]
+    fn search_filters_tests() {
+        let mut pane = TestListPane::new();
+        pane.update_tests(get_named_tests(&[
+            "signup",
+            "login",
+            "logout",
+            "checkout",
+            "login again",
+        ]));
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let now = Instant::now();
+        let key = |code| [KeyEvent::new(code, KeyModifiers::NONE)];
+
+        // The first test found is selected
+        for key in "/log".chars() {
+            press(&mut pane, &mut buffer, key, now);
+        }
+        assert_that!(pane.table.num_elements()).is_equal_to(3);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(1));
+
+        // Taking `n` as part of the search rather than creating a test
+        press(&mut pane, &mut buffer, 'i', now);
+        assert_that!(pane.input(&key(KeyCode::Char('n'))).unwrap())
+            .does_not_contain(Action::PopupCreate(PopupMode::CreateTest));
+        assert_that!(pane.table.num_elements()).is_equal_to(2);
+        pane.input(&key(KeyCode::Backspace)).unwrap();
+        pane.input(&key(KeyCode::Backspace)).unwrap();
+        assert_that!(pane.table.num_elements()).is_equal_to(3);
+
+        // Once it's kept `n` and `N` go between the tests found
+        pane.input(&key(KeyCode::Enter)).unwrap();
+        for expected in [2, 4, 1] {
+            press(&mut pane, &mut buffer, 'n', now);
+            assert_that!(pane.table.selected_idx()).is_equal_to(Some(expected));
+        }
+        pane.input(&[KeyEvent::new(KeyCode::Char('N'), KeyModifiers::SHIFT)])
+            .unwrap();
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(4));
+        assert_that!(pane.get_selected_test_id()).is_equal_to(Some(4.into()));
+
+        // All the tests are shown again keeping the selection
+        pane.input(&key(KeyCode::Esc)).unwrap();
+        assert_that!(pane.table.num_elements()).is_equal_to(5);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(4));
+
+        // Nothing found after the selected test, the last test found is selected
+        for key in "/CK".chars() {
+            press(&mut pane, &mut buffer, key, now);
+        }
+        assert_that!(pane.table.num_elements()).is_equal_to(1);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(3));
+
+        pane.input(&key(KeyCode::Esc)).unwrap();
+        assert_that!(pane.table.num_elements()).is_equal_to(5);
+        assert_that!(pane.input(&key(KeyCode::Char('n'))).unwrap())
+            .contains(Action::PopupCreate(PopupMode::CreateTest));
+    }
+
+    #[traced_test]
+    #[test]
+    fn sort_tests() {
+        let mut tests = get_named_tests(&["b", "C", "a", "d"]);
+        for (test, (times_used, creation_date, last_used_date)) in tests.iter_mut().zip([
+            (
+                10,
+                "2024-08-31 10:30:00.25 +00:00",
+                Some("2024-09-01 00:00:00 +00:00"),
+            ),
+            (2, "2024-08-31 11:00:00.5 +01:00", None),
+            (
+                100,
+                "2024-01-01 00:00:00 +00:00",
+                Some("2024-08-31 00:00:00 +00:00"),
+            ),
+            (
+                9,
+                "2024-08-31 08:00:00 -05:00",
+                Some("2024-09-02 00:00:00 +00:00"),
+            ),
+        ]) {
+            test.times_used = times_used;
+            test.creation_date = creation_date.to_string();
+            test.last_used_date = last_used_date.map(str::to_string);
+        }
+        let mut pane = TestListPane::new();
+        pane.update_tests(tests.clone());
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let now = Instant::now();
+        let order = |pane: &mut TestListPane| {
+            (0..4)
+                .map(|row| pane.table.go_to(row).unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        // Name ignoring case, creation date by the time rather than how it reads, last used with
+        // those never used first and times used as a number
+        for expected in [[2, 0, 1, 3], [2, 1, 0, 3], [1, 2, 0, 3], [1, 3, 0, 2]] {
+            press(&mut pane, &mut buffer, 's', now);
+            assert_that!(order(&mut pane)).is_equal_to(expected.to_vec());
+        }
+
+        pane.input(&[KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT)])
+            .unwrap();
+        assert_that!(order(&mut pane)).is_equal_to(vec![2, 0, 3, 1]);
+
+        // Back round to the name, the sort's kept when the tests are read again
+        press(&mut pane, &mut buffer, 's', now);
+        pane.update_tests(tests);
+        assert_that!(pane.table.sort()).is_equal_to(Some((0, true)));
+        assert_that!(order(&mut pane)).is_equal_to(vec![2, 0, 1, 3]);
+    }
+
+    #[traced_test]
+    #[test]
+    fn selection_kept_across_focus() {
+        let mut pane = TestListPane::new();
+        pane.update_tests(get_tests(8));
+        pane.set_focus(true);
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        for i in 0..6 {
+            press(
+                &mut pane,
+                &mut buffer,
+                'j',
+                start + Duration::from_millis(100 * i),
+            );
+        }
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(5));
+
+        // The tests are read again while another pane has focus, e.g. after a run
+        pane.set_focus(false);
+        pane.update(&Action::UpdateData(UpdateData::Tests(get_tests(8))))
+            .unwrap();
+        pane.set_focus(true);
+
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(5));
+        assert_that!(pane.table.is_focussed()).is_true();
+
+        // Tests going leaves the last one selected
+        pane.update_tests(get_tests(3));
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(2));
+    }
+
+    #[traced_test]
+    #[test]
+    fn selection_follows_test() {
+        let mut pane = TestListPane::new();
+        pane.update_tests(get_tests(30));
+        pane.set_focus(true);
+        pane.table.go_to(20);
+        pane.table.scroll(ScrollType::Single(-3));
+        let first_row = pane.table.first_row();
+        let id = pane.get_selected_test_id();
+
+        // The details are looked at and the tests read again with a new test at the start
+        pane.set_focus(false);
+        let mut tests = get_tests(31);
+        tests.rotate_right(1);
+        pane.update(&Action::UpdateData(UpdateData::Tests(tests)))
+            .unwrap();
+        pane.set_focus(true);
+
+        assert_that!(pane.get_selected_test_id()).is_equal_to(id);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(21));
+        assert_that!(pane.table.first_row()).is_equal_to(first_row);
+
+        // The same tests in a different order
+        let mut tests = get_tests(31);
+        tests.reverse();
+        pane.update_tests(tests);
+        assert_that!(pane.get_selected_test_id()).is_equal_to(id);
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(10));
+    }
+
+    #[traced_test]
+    #[test]
+    fn preview_after_navigating() {
+        let mut pane = get_pane();
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let now = Instant::now();
+        let settled = || Instant::now() + Duration::from_secs(1);
+
+        // Only the test the selection settles on is loaded
+        press(&mut pane, &mut buffer, 'j', now);
+        assert_that!(pane.poll_preview(now)).is_equal_to(vec![]);
+        press(&mut pane, &mut buffer, 'j', now);
+        assert_that!(pane.poll_preview(settled())).is_equal_to(vec![Action::StatusChange(
+            StatusChange::ModeChangeTestListWithDetails(2.into()),
+        )]);
+        assert_that!(pane.poll_preview(settled())).is_equal_to(vec![]);
+
+        press(&mut pane, &mut buffer, 'p', now);
+        press(&mut pane, &mut buffer, 'j', now);
+        assert_that!(pane.poll_preview(settled())).is_equal_to(vec![]);
+    }
+
+    #[traced_test]
+    #[test]
+    fn go_to_top_sequence() {
+        let mut pane = get_pane();
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let start = Instant::now();
+
+        for _ in 0..3 {
+            press(&mut pane, &mut buffer, 'j', start);
+        }
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(2));
+
+        // Too long between the two presses, neither is taken as part of `gg`
+        press(&mut pane, &mut buffer, 'g', start);
+        press(&mut pane, &mut buffer, 'g', start + Duration::from_secs(1));
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(2));
+        assert_that!(buffer.keys().len()).is_equal_to(1);
+
+        press(
+            &mut pane,
+            &mut buffer,
+            'g',
+            start + Duration::from_millis(1200),
+        );
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(0));
+        assert_that!(buffer.keys().len()).is_equal_to(0);
+
+        pane.input(&[KeyEvent::new(KeyCode::Char('G'), KeyModifiers::SHIFT)])
+            .unwrap();
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(4));
+    }
+
+    #[traced_test]
+    #[test]
+    fn go_to_typed_row() {
+        let mut pane = get_pane();
+        let num_tests = pane.table.num_elements();
+        let mut buffer = KeyBuffer::new(Duration::from_millis(500));
+        let now = Instant::now();
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+
+        for key in ":3".chars() {
+            press(&mut pane, &mut buffer, key, now);
+        }
+        assert_that!(pane.table.selected_idx()).is_equal_to(None);
+        assert_that!(buffer.keys().len()).is_equal_to(2);
+
+        buffer.push(enter, now);
+        assert_that!(pane.input(buffer.keys()).unwrap()).contains(Action::ClearKeys);
+        buffer.clear();
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(2));
+
+        // Past the end goes to the last row
+        for key in ":999".chars() {
+            press(&mut pane, &mut buffer, key, now);
+        }
+        buffer.push(enter, now);
+        pane.input(buffer.keys()).unwrap();
+        buffer.clear();
+        assert_that!(pane.table.selected_idx()).is_equal_to(Some(num_tests - 1));
+    }
+
+    #[traced_test]
+    #[test]
+    fn run_selected() {
+        let mut pane = get_pane();
+
+        // Nothing to run until a test is selected
+        let r = [KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE)];
+        assert_that!(pane
+            .input(&r)
+            .unwrap()
+            .iter()
+            .any(|action| matches!(action, Action::RunTest(_))))
+        .is_false();
+
+        pane.table.set_selected_idx(2);
+        let id = pane.get_selected_test_id().unwrap();
+
+        let actions = pane.input(&r).unwrap();
+        assert_that!(actions).contains(Action::RunTest(id));
+        assert_that!(actions).contains(Action::StatusChange(
+            StatusChange::ModeChangeTestListWithDetails(id),
+        ));
+    }
 }
@@ -1,5 +1,8 @@
+mod diagnostics;
+mod diff;
 mod error;
 mod help;
+mod rename;
 mod test_edit;
 
 use crossterm::event::KeyEvent;
@@ -12,8 +15,11 @@ use ratatui::{
 
 use super::app::{Action, HelpItem, PaneType};
 
+pub(crate) use diagnostics::DiagnosticsComponent;
+pub(crate) use diff::DiffComponent;
 pub(crate) use error::ErrorComponent;
 pub(crate) use help::HelpComponent;
+pub(crate) use rename::RenameComponent;
 pub(crate) use test_edit::TestEditComponent;
 
 pub(crate) trait PopupComponent: std::fmt::Debug {
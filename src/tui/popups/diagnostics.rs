@@ -0,0 +1,99 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
+use ratatui::{layout::Rect, Frame};
+
+use crate::tui::{
+    app::{Action, HelpItem, PaneType},
+    widgets::{Text, TextDisplay},
+};
+
+use super::PopupComponent;
+
+/// Lists the diagnostics plugins have reported during test runs, oldest first.
+#[derive(Debug)]
+pub(crate) struct DiagnosticsComponent {
+    diagnostics: TextDisplay,
+}
+
+impl DiagnosticsComponent {
+    pub(crate) fn new(diagnostics: &[String]) -> Self {
+        let text = if diagnostics.is_empty() {
+            vec![Text::new("No diagnostics reported".to_string(), true)]
+        } else {
+            diagnostics
+                .iter()
+                .map(|diagnostic| Text::new(diagnostic.clone(), true))
+                .collect::<Vec<_>>()
+        };
+
+        let mut diagnostics = TextDisplay::new_with_text(text, None, true);
+        diagnostics.set_focus(true);
+
+        Self { diagnostics }
+    }
+}
+
+impl PopupComponent for DiagnosticsComponent {
+    fn render_inner(&self, f: &mut Frame, rect: Rect) {
+        f.render_widget(&self.diagnostics, rect);
+    }
+
+    fn input(&mut self, key: &KeyEvent, _mode: &PaneType) -> Result<Vec<Action>> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Down, KeyModifiers::NONE) | (KeyCode::Char('j'), KeyModifiers::NONE) => {
+                self.diagnostics.navigate(1);
+                Ok(vec![Action::ClearKeys, Action::ForceRedraw])
+            }
+            (KeyCode::Up, KeyModifiers::NONE) | (KeyCode::Char('k'), KeyModifiers::NONE) => {
+                self.diagnostics.navigate(-1);
+                Ok(vec![Action::ClearKeys, Action::ForceRedraw])
+            }
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), KeyModifiers::NONE) => {
+                Ok(vec![Action::PopupClose, Action::ClearKeys])
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn keys(&self, _mode: &PaneType) -> Vec<HelpItem> {
+        vec![
+            HelpItem::new("↑ | ↓", "Scroll", "Scroll through the diagnostics"),
+            HelpItem::new("<Esc> | q", "Close", "Close the diagnostics"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tracing_test::traced_test;
+
+    use crate::tui::popups::PopupComponent;
+
+    use super::DiagnosticsComponent;
+
+    #[traced_test]
+    #[test]
+    fn diagnostics_listed() {
+        let component = DiagnosticsComponent::new(&[
+            "plugin: Warning: Slow start: took 3s".to_string(),
+            "plugin: Info: Ready".to_string(),
+        ]);
+
+        let mut terminal = Terminal::new(TestBackend::new(50, 8)).unwrap();
+        terminal
+            .draw(|f| component.render(f, f.area(), "Diagnostics"))
+            .unwrap();
+
+        insta::assert_debug_snapshot!(terminal.backend().buffer());
+    }
+
+    #[traced_test]
+    #[test]
+    fn diagnostics_none() {
+        let component = DiagnosticsComponent::new(&[]);
+
+        assert_that!(component.diagnostics.num_elements()).is_equal_to(1);
+    }
+}
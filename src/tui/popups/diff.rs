@@ -0,0 +1,199 @@
+use std::{
+    fs,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::{eyre, Result};
+use ratatui::{layout::Rect, Frame};
+
+use crate::tui::{
+    app::{Action, HelpItem, PaneType},
+    widgets::{Text, TextDisplay},
+};
+
+use super::PopupComponent;
+
+/// Shows the unified diff between an expected and actual value, e.g. for a failed assertion.
+#[derive(Debug)]
+pub(crate) struct DiffComponent {
+    diff: TextDisplay,
+}
+
+impl DiffComponent {
+    pub(crate) fn new(expected: &str, actual: &str) -> Self {
+        // Not having `diff` available shouldn't be fatal, say so in place of the diff
+        let diff = get_diff(expected, actual).unwrap_or_else(|e| e.to_string());
+
+        let text = diff
+            .lines()
+            .map(|line| Text::new(line.to_string(), true))
+            .collect::<Vec<_>>();
+
+        let mut diff =
+            TextDisplay::new_with_text(text, Some("Expected vs Actual".to_string()), true);
+        diff.set_focus(true);
+
+        Self { diff }
+    }
+}
+
+impl PopupComponent for DiffComponent {
+    fn render_inner(&self, f: &mut Frame, rect: Rect) {
+        f.render_widget(&self.diff, rect);
+    }
+
+    fn input(&mut self, key: &KeyEvent, _mode: &PaneType) -> Result<Vec<Action>> {
+        match (key.code, key.modifiers) {
+            (KeyCode::Down, KeyModifiers::NONE) | (KeyCode::Char('j'), KeyModifiers::NONE) => {
+                self.diff.navigate(1);
+                Ok(vec![Action::ClearKeys, Action::ForceRedraw])
+            }
+            (KeyCode::Up, KeyModifiers::NONE) | (KeyCode::Char('k'), KeyModifiers::NONE) => {
+                self.diff.navigate(-1);
+                Ok(vec![Action::ClearKeys, Action::ForceRedraw])
+            }
+            (KeyCode::Char('d'), KeyModifiers::CONTROL) => {
+                self.diff
+                    .navigate(self.diff.num_display_lines() as isize / 2);
+                Ok(vec![Action::ClearKeys, Action::ForceRedraw])
+            }
+            (KeyCode::Char('u'), KeyModifiers::CONTROL) => {
+                self.diff
+                    .navigate(-(self.diff.num_display_lines() as isize / 2));
+                Ok(vec![Action::ClearKeys, Action::ForceRedraw])
+            }
+            (KeyCode::Esc, _) | (KeyCode::Char('q'), KeyModifiers::NONE) => {
+                Ok(vec![Action::PopupClose, Action::ClearKeys])
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn keys(&self, _mode: &PaneType) -> Vec<HelpItem> {
+        vec![
+            HelpItem::new("↑ | ↓", "Scroll", "Scroll the diff a line at a time"),
+            HelpItem::new("C-u | C-d", "Page", "Scroll the diff half a page at a time"),
+            HelpItem::new("<Esc> | q", "Close", "Close the diff"),
+        ]
+    }
+}
+
+/// Run the `diff` utility over `expected` and `actual`, returning the unified diff.
+fn get_diff(expected: &str, actual: &str) -> Result<String> {
+    static DIFF_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let dir = std::env::temp_dir().join(format!(
+        "patui-diff-{}-{}",
+        std::process::id(),
+        DIFF_COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir)?;
+
+    let expected_path = dir.join("expected");
+    let actual_path = dir.join("actual");
+
+    let output = fs::write(&expected_path, with_trailing_newline(expected))
+        .and_then(|_| fs::write(&actual_path, with_trailing_newline(actual)))
+        .and_then(|_| {
+            Command::new("diff")
+                .args(["-u", "--label", "expected", "--label", "actual"])
+                .arg(&expected_path)
+                .arg(&actual_path)
+                .output()
+        });
+
+    fs::remove_dir_all(&dir)?;
+
+    let output = output.map_err(|e| eyre!("Failed to run `diff`: {}", e))?;
+
+    // `diff` exits with 1 when the inputs differ, anything above that is trouble
+    match output.status.code() {
+        Some(0) => Ok("No differences".to_string()),
+        Some(1) => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+        _ => Err(eyre!(
+            "`diff` failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )),
+    }
+}
+
+fn with_trailing_newline(value: &str) -> String {
+    if value.ends_with('\n') {
+        value.to_string()
+    } else {
+        format!("{}\n", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, Terminal};
+    use tracing_test::traced_test;
+
+    use crate::tui::{
+        app::{Action, PaneType},
+        popups::PopupComponent,
+    };
+
+    use super::DiffComponent;
+
+    const EXPECTED_MAP: &str = r#"{
+  "name": "foo",
+  "count": 3,
+  "tags": ["a", "b"]
+}"#;
+
+    const ACTUAL_MAP: &str = r#"{
+  "name": "foo",
+  "count": 4,
+  "tags": ["a", "b"],
+  "extra": true
+}"#;
+
+    #[traced_test]
+    #[test]
+    fn diff_map() {
+        let component = DiffComponent::new(EXPECTED_MAP, ACTUAL_MAP);
+
+        let mut terminal = Terminal::new(TestBackend::new(50, 20)).unwrap();
+        terminal
+            .draw(|f| component.render(f, f.area(), "Diff"))
+            .unwrap();
+
+        insta::assert_debug_snapshot!(terminal.backend().buffer());
+    }
+
+    #[traced_test]
+    #[test]
+    fn diff_no_differences() {
+        let component = DiffComponent::new(EXPECTED_MAP, EXPECTED_MAP);
+
+        assert_that!(component.diff.num_elements()).is_equal_to(1);
+    }
+
+    #[traced_test]
+    #[test]
+    fn diff_close() {
+        let mut component = DiffComponent::new(EXPECTED_MAP, ACTUAL_MAP);
+
+        let actions = component
+            .input(
+                &KeyEvent::new(KeyCode::Down, KeyModifiers::NONE),
+                &PaneType::TestDetail,
+            )
+            .unwrap();
+        assert_that!(actions).does_not_contain(Action::PopupClose);
+
+        let actions = component
+            .input(
+                &KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+                &PaneType::TestDetail,
+            )
+            .unwrap();
+        assert_that!(actions).contains(Action::PopupClose);
+    }
+}
@@ -0,0 +1,132 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use eyre::Result;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    Frame,
+};
+
+use super::PopupComponent;
+use crate::{
+    db::PatuiTestId,
+    tui::{
+        app::{Action, DbUpdate, HelpItem, PaneType},
+        widgets::TextArea,
+    },
+};
+
+#[derive(Debug)]
+pub(crate) struct RenameComponent<'a> {
+    id: PatuiTestId,
+    name_component: TextArea<'a>,
+}
+
+impl<'a> RenameComponent<'a> {
+    pub(crate) fn new(id: PatuiTestId, name: String) -> Self {
+        let mut name_component = TextArea::new(
+            "Name".to_string(),
+            vec![Box::new(|x| !x.get_text().trim().is_empty())],
+        );
+        name_component.set_text(name);
+        name_component.selected(true);
+
+        Self { id, name_component }
+    }
+}
+
+impl<'a> PopupComponent for RenameComponent<'a> {
+    fn render_inner(&self, f: &mut Frame, rect: Rect) {
+        let inner = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Max(self.name_component.height()),
+                    Constraint::Min(0),
+                ]
+                .as_ref(),
+            )
+            .split(rect);
+
+        f.render_widget(&self.name_component, inner[0]);
+    }
+
+    fn input(&mut self, key: &KeyEvent, _mode: &PaneType) -> Result<Vec<Action>> {
+        let mut ret = vec![];
+
+        match (key.code, key.modifiers) {
+            (KeyCode::Enter, KeyModifiers::NONE) => {
+                let name = self.name_component.get_text().trim().to_string();
+                // Nothing to do with a blank name, leave the popup up to fix it
+                if !name.is_empty() {
+                    ret.push(Action::PopupClose);
+                    ret.push(Action::DbUpdate(DbUpdate::RenameTest(self.id, name)));
+                }
+                ret.push(Action::ClearKeys);
+            }
+            (KeyCode::Esc, KeyModifiers::NONE) => {
+                ret.push(Action::PopupClose);
+                ret.push(Action::ClearKeys);
+            }
+            _ => {
+                if self.name_component.input(key) {
+                    ret.push(Action::ClearKeys);
+                    ret.push(Action::ForceRedraw);
+                }
+            }
+        }
+
+        Ok(ret)
+    }
+
+    fn keys(&self, _mode: &PaneType) -> Vec<HelpItem> {
+        vec![
+            HelpItem::new("<Esc>", "Cancel", "Cancel"),
+            HelpItem::new("<Enter>", "Rename", "Rename the test"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn press(component: &mut RenameComponent, code: KeyCode) -> Vec<Action> {
+        component
+            .input(
+                &KeyEvent::new(code, KeyModifiers::NONE),
+                &PaneType::TestList,
+            )
+            .unwrap()
+    }
+
+    #[traced_test]
+    #[test]
+    fn rename_from_current_name() {
+        let mut component = RenameComponent::new(3.into(), "old".to_string());
+        for _ in 0..3 {
+            press(&mut component, KeyCode::Backspace);
+        }
+        for c in "new name".chars() {
+            press(&mut component, KeyCode::Char(c));
+        }
+
+        assert_that!(press(&mut component, KeyCode::Enter)).is_equal_to(vec![
+            Action::PopupClose,
+            Action::DbUpdate(DbUpdate::RenameTest(3.into(), "new name".to_string())),
+            Action::ClearKeys,
+        ]);
+    }
+
+    #[traced_test]
+    #[test]
+    fn rename_blank_name() {
+        let mut component = RenameComponent::new(3.into(), "old".to_string());
+        for _ in 0..3 {
+            press(&mut component, KeyCode::Backspace);
+        }
+
+        assert_that!(press(&mut component, KeyCode::Enter)).is_equal_to(vec![Action::ClearKeys]);
+    }
+}
@@ -7,9 +7,12 @@ use ratatui::{
 };
 
 use super::PopupComponent;
-use crate::tui::{
-    app::{Action, HelpItem, PaneType},
-    widgets::{Button, TextArea},
+use crate::{
+    tui::{
+        app::{Action, HelpItem, PaneType},
+        widgets::{Button, TextArea},
+    },
+    types::expr::matching_bracket,
 };
 
 #[derive(Debug)]
@@ -21,6 +24,9 @@ pub(crate) struct TestEditComponent<'a> {
     extra_components: IndexMap<String, TextArea<'a>>,
     edit_button: Button,
     cancel_button: Button,
+    /// The bracket under the cursor in the selected field and the one it matches, as (row,
+    /// column) in the field's text.
+    matched_brackets: Option<((usize, usize), (usize, usize))>,
 }
 
 impl<'a> TestEditComponent<'a> {
@@ -50,6 +56,7 @@ impl<'a> TestEditComponent<'a> {
             selected_component_idx: 0,
             edit_button,
             cancel_button,
+            matched_brackets: None,
         }
     }
 
@@ -130,6 +137,30 @@ impl<'a> TestEditComponent<'a> {
     //     true
     // }
 
+    /// Highlight the bracket under the cursor in the selected field along with its match.
+    fn match_brackets(&mut self) {
+        self.matched_brackets = None;
+        self.name_component.set_highlights(vec![]);
+        self.desc_component.set_highlights(vec![]);
+
+        if self.selected_component_idx >= self.num_components() - 2 {
+            return;
+        }
+        let Some(component) = self.selected_component() else {
+            return;
+        };
+
+        let text = component.get_text();
+        let cursor = component.cursor();
+        let Some(matched) = matching_bracket(&text, position_to_offset(&text, cursor)) else {
+            return;
+        };
+        let matched = offset_to_position(&text, matched);
+
+        component.set_highlights(vec![cursor, matched]);
+        self.matched_brackets = Some((cursor, matched));
+    }
+
     fn is_ok_button(&self) -> bool {
         self.selected_component_idx == self.num_components() - 2
     }
@@ -289,6 +320,9 @@ impl<'a> PopupComponent for TestEditComponent<'a> {
             }
         }
 
+        // The cursor or the text may have moved on from the brackets matched before
+        self.match_brackets();
+
         Ok(ret)
     }
 
@@ -309,3 +343,76 @@ impl<'a> PopupComponent for TestEditComponent<'a> {
         ret
     }
 }
+
+/// The byte offset in `text` of a (row, column) position in a text area.
+fn position_to_offset(text: &str, (row, col): (usize, usize)) -> usize {
+    let mut offset = 0;
+
+    for (idx, line) in text.split('\n').enumerate() {
+        if idx == row {
+            return offset + line.char_indices().nth(col).map_or(line.len(), |(i, _)| i);
+        }
+        offset += line.len() + 1;
+    }
+
+    text.len()
+}
+
+/// The (row, column) position in a text area of the byte `offset` in `text`.
+fn offset_to_position(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let row = before.matches('\n').count();
+    let col = before
+        .rsplit('\n')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .count();
+
+    (row, col)
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn press(component: &mut TestEditComponent, code: KeyCode) {
+        component
+            .input(
+                &KeyEvent::new(code, KeyModifiers::NONE),
+                &PaneType::TestList,
+            )
+            .unwrap();
+    }
+
+    #[traced_test]
+    #[test]
+    fn match_nested_brackets() {
+        let mut component = TestEditComponent::new();
+        for c in "foo((1 + 2), [3, \")\", (4)])".chars() {
+            press(&mut component, KeyCode::Char(c));
+        }
+        // The cursor's past the end so not on a bracket
+        assert_that!(component.matched_brackets).is_none();
+
+        for (cursor, expected) in [(3, 26), (4, 10), (13, 25), (22, 24), (26, 3)] {
+            press(&mut component, KeyCode::Home);
+            for _ in 0..cursor {
+                press(&mut component, KeyCode::Right);
+            }
+            assert_that!(component.matched_brackets)
+                .is_equal_to(Some(((0, cursor), (0, expected))));
+        }
+
+        // The bracket in the string isn't one to match
+        press(&mut component, KeyCode::Home);
+        for _ in 0..18 {
+            press(&mut component, KeyCode::Right);
+        }
+        assert_that!(component.matched_brackets).is_none();
+    }
+}
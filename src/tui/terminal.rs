@@ -33,6 +33,21 @@ fn stdout() -> IO {
     std::io::stdout()
 }
 
+/// Whether the terminal can be expected to show unicode, going by the locale in the same order
+/// of precedence as the C library. No locale at all is taken as unicode being fine.
+pub(crate) fn supports_unicode() -> bool {
+    let Some(locale) = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+    else {
+        return true;
+    };
+
+    let locale = locale.to_lowercase();
+    locale.contains("utf-8") || locale.contains("utf8")
+}
+
 pub(crate) struct Tui {
     terminal: ratatui::Terminal<Backend<IO>>,
     task: JoinHandle<()>,
@@ -1,5 +1,5 @@
 use crate::db::{PatuiTestDb, PatuiTestId};
-use crate::types::{PatuiTest, PatuiTestDetails};
+use crate::types::{PatuiEvent, PatuiTest, PatuiTestDetails};
 
 use super::{error::PatuiError, popups::PopupComponent};
 
@@ -23,6 +23,7 @@ pub(crate) enum PaneType {
     #[default]
     TestList,
     TestDetail,
+    Console,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,11 +35,13 @@ pub(crate) enum DbRead {
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum DbCreate {
     Test(PatuiTestDetails),
+    CloneTest(PatuiTestId),
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum DbUpdate {
     Test(PatuiTest),
+    RenameTest(PatuiTestId, String),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -52,8 +55,11 @@ pub(crate) enum UpdateData {
 pub(crate) enum PopupMode {
     CreateTest,
     UpdateTest(PatuiTestId),
+    RenameTest(PatuiTestId),
     Help,
     Error,
+    Diff { expected: String, actual: String },
+    Diagnostics(Vec<String>),
 }
 
 impl PopupMode {
@@ -61,8 +67,11 @@ impl PopupMode {
         match self {
             PopupMode::CreateTest => "Create Test",
             PopupMode::UpdateTest(_) => "Update Test",
+            PopupMode::RenameTest(_) => "Rename Test",
             PopupMode::Help => "Help",
             PopupMode::Error => "Error",
+            PopupMode::Diff { .. } => "Diff",
+            PopupMode::Diagnostics(_) => "Diagnostics",
         }
     }
 }
@@ -124,6 +133,11 @@ pub(crate) enum Action {
     PaneChange(PaneType),
     PopupCreate(PopupMode),
     PopupClose,
+    ToggleCompact,
+    ToggleConsole,
+    PatuiEvent(PatuiEvent),
+    /// Start a run of the test in the background, its events arrive as `PatuiEvent`.
+    RunTest(PatuiTestId),
     EditorMode(EditorMode),
     DbRead(DbRead),
     DbCreate(DbCreate),
@@ -1,11 +1,15 @@
 mod button;
 mod patui_widget;
+mod step_graph;
 mod table;
+mod tabs;
 mod text_display;
 mod textarea;
 
 pub(crate) use button::Button;
-pub(crate) use patui_widget::ScrollType;
-pub(crate) use table::{Table, TableHeader};
+pub(crate) use patui_widget::{scrollbar, scrollbar_area, titled_block, ScrollType};
+pub(crate) use step_graph::StepGraph;
+pub(crate) use table::{SortValue, Table, TableHeader};
+pub(crate) use tabs::Tabs;
 pub(crate) use text_display::{Text, TextDisplay};
 pub(crate) use textarea::TextArea;
@@ -1,3 +1,30 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::Style,
+    symbols::{border, scrollbar},
+    widgets::{Block, Borders, Padding, Scrollbar, ScrollbarOrientation},
+};
+
+/// Borders drawn with plain ASCII for terminals that can't show the box drawing characters.
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+/// A scrollbar drawn with plain ASCII for terminals that can't show the arrows and blocks.
+const ASCII_SCROLLBAR: scrollbar::Set = scrollbar::Set {
+    track: "|",
+    thumb: "#",
+    begin: "^",
+    end: "v",
+};
+
 #[derive(Debug)]
 pub(crate) enum ScrollType {
     Single(isize),
@@ -7,4 +34,59 @@ pub(crate) enum ScrollType {
     // HalfPageRight,
     FullPageUp,
     FullPageDown,
+    /// Select the first element, scrolled to the top.
+    Top,
+    /// Select the last element, scrolled to the bottom.
+    Bottom,
+}
+
+/// The block around a titled widget, compact blocks drop the borders and padding so only the
+/// title row is used up. ASCII blocks draw their borders without any unicode.
+pub(crate) fn titled_block<'a>(
+    title: String,
+    style: Style,
+    is_compact: bool,
+    is_ascii: bool,
+) -> Block<'a> {
+    let block = Block::new()
+        .title_alignment(Alignment::Center)
+        .title(title)
+        .style(style);
+
+    if is_compact {
+        block
+    } else if is_ascii {
+        block
+            .borders(Borders::ALL)
+            .border_set(ASCII_BORDER)
+            .padding(Padding::symmetric(2, 1))
+    } else {
+        block
+            .borders(Borders::ALL)
+            .padding(Padding::symmetric(2, 1))
+    }
+}
+
+/// The scrollbar of a titled widget, see `scrollbar_area` for where it goes.
+pub(crate) fn scrollbar<'a>(is_ascii: bool) -> Scrollbar<'a> {
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight);
+
+    if is_ascii {
+        scrollbar.symbols(ASCII_SCROLLBAR)
+    } else {
+        scrollbar.begin_symbol(Some("↑")).end_symbol(Some("↓"))
+    }
+}
+
+/// Where the scrollbar of a titled widget goes, inside the right border or just below the title
+/// when compact.
+pub(crate) fn scrollbar_area(area: Rect, is_compact: bool) -> Rect {
+    let border = if is_compact { 0 } else { 1 };
+
+    Rect {
+        x: area.x + area.width.saturating_sub(1),
+        y: area.y + 1,
+        width: 1,
+        height: area.height.saturating_sub(1 + border),
+    }
 }
@@ -0,0 +1,386 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::WidgetRef,
+};
+
+use crate::types::{expr::get_step_refs, PatuiStep, PatuiStepOutcome};
+
+/// Gap left between columns that have no arrows between them.
+const EMPTY_GAP_WIDTH: usize = 2;
+
+/// What's drawn in a column, placeholders carry arrows past columns to steps further right.
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    Step(usize),
+    Through,
+}
+
+/// A small ASCII drawing of how steps depend on each other, steps are laid out left to right by
+/// how many steps they're downstream of with arrows from the steps they reference. Steps are
+/// coloured by how they last finished and the selected step is highlighted.
+#[derive(Clone, Debug)]
+pub(crate) struct StepGraph {
+    names: Vec<String>,
+    columns: Vec<Vec<Node>>,
+    /// Arrows from a node in one column to a node in the next, as (column, from row, to row).
+    edges: Vec<(usize, usize, usize)>,
+    outcomes: Vec<Option<PatuiStepOutcome>>,
+    selected_idx: Option<usize>,
+}
+
+impl StepGraph {
+    pub(crate) fn new(steps: &[PatuiStep]) -> Self {
+        let idxs = steps
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| (step.name.as_str(), idx))
+            .collect::<HashMap<_, _>>();
+
+        let deps = steps
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| {
+                step.details
+                    .exprs()
+                    .into_iter()
+                    .flat_map(|expr| get_step_refs(expr).unwrap_or_default())
+                    .filter_map(|step_ref| idxs.get(step_ref.step.as_str()).copied())
+                    .filter(|dep| *dep != idx)
+                    .collect::<BTreeSet<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut depths = vec![None; steps.len()];
+        for idx in 0..steps.len() {
+            depth(idx, &deps, &mut depths, &mut vec![false; steps.len()]);
+        }
+        let depths = depths
+            .into_iter()
+            .map(|depth| depth.unwrap_or(0))
+            .collect::<Vec<_>>();
+
+        let num_columns = depths.iter().max().map_or(0, |max| max + 1);
+        let mut columns = vec![vec![]; num_columns];
+        let mut rows = vec![0; steps.len()];
+        for (idx, depth) in depths.iter().enumerate() {
+            rows[idx] = columns[*depth].len();
+            columns[*depth].push(Node::Step(idx));
+        }
+
+        // Arrows skipping columns go through a placeholder in each column they pass
+        let mut edges = vec![];
+        for (idx, step_deps) in deps.iter().enumerate() {
+            for dep in step_deps {
+                if depths[*dep] >= depths[idx] {
+                    continue;
+                }
+
+                let mut from_row = rows[*dep];
+                let passed = columns
+                    .iter_mut()
+                    .enumerate()
+                    .take(depths[idx])
+                    .skip(depths[*dep] + 1);
+                for (column, nodes) in passed {
+                    nodes.push(Node::Through);
+                    edges.push((column - 1, from_row, nodes.len() - 1));
+                    from_row = nodes.len() - 1;
+                }
+                edges.push((depths[idx] - 1, from_row, rows[idx]));
+            }
+        }
+
+        Self {
+            names: steps.iter().map(|step| step.name.clone()).collect(),
+            columns,
+            edges,
+            outcomes: vec![None; steps.len()],
+            selected_idx: None,
+        }
+    }
+
+    /// Record how the step called `name` finished, does nothing for steps not in the graph.
+    pub(crate) fn set_outcome(&mut self, name: &str, outcome: PatuiStepOutcome) {
+        if let Some(idx) = self.names.iter().position(|x| x == name) {
+            self.outcomes[idx] = Some(outcome);
+        }
+    }
+
+    pub(crate) fn set_selected_idx(&mut self, selected_idx: Option<usize>) {
+        self.selected_idx = selected_idx;
+    }
+
+    pub(crate) fn height(&self) -> u16 {
+        let rows = self.columns.iter().map(|nodes| nodes.len()).max();
+
+        rows.map_or(0, |rows| rows * 2 - 1) as u16
+    }
+
+    /// The rows of targets the arrows in the gap after `column` go into, top to bottom.
+    fn targets(&self, column: usize) -> Vec<usize> {
+        self.edges
+            .iter()
+            .filter(|(edge_column, _, _)| *edge_column == column)
+            .map(|(_, _, to_row)| *to_row)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    fn node_text(&self, node: &Node) -> String {
+        match node {
+            Node::Step(idx) => format!("[{}]", self.names[*idx]),
+            Node::Through => "-".to_string(),
+        }
+    }
+
+    fn node_style(&self, node: &Node) -> Style {
+        let Node::Step(idx) = node else {
+            return Style::default();
+        };
+
+        let style = match self.outcomes[*idx] {
+            Some(PatuiStepOutcome::Passed) => Style::default().fg(Color::Green),
+            Some(PatuiStepOutcome::Failed(_)) => Style::default().fg(Color::Red),
+            None => Style::default(),
+        };
+
+        if self.selected_idx == Some(*idx) {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
+    }
+
+    /// Every cell of the drawing by row.
+    fn draw(&self) -> Vec<Vec<(char, Style)>> {
+        let column_widths = self
+            .columns
+            .iter()
+            .map(|nodes| {
+                nodes
+                    .iter()
+                    .map(|node| self.node_text(node).chars().count())
+                    .max()
+                    .unwrap_or(1)
+            })
+            .collect::<Vec<_>>();
+        let gap_widths = (0..self.columns.len().saturating_sub(1))
+            .map(|column| match self.targets(column).len() {
+                0 => EMPTY_GAP_WIDTH,
+                num_targets => num_targets * 2 + 2,
+            })
+            .collect::<Vec<_>>();
+
+        let width = column_widths.iter().sum::<usize>() + gap_widths.iter().sum::<usize>();
+        let mut cells = vec![vec![(' ', Style::default()); width]; self.height() as usize];
+
+        let mut x = 0;
+        for (column, nodes) in self.columns.iter().enumerate() {
+            let column_width = column_widths[column];
+
+            for (row, node) in nodes.iter().enumerate() {
+                let text = self.node_text(node);
+                let style = self.node_style(node);
+                let has_edges = self
+                    .edges
+                    .iter()
+                    .any(|(edge_column, from_row, _)| *edge_column == column && *from_row == row);
+
+                // Lines run on to the gap from anything with arrows leaving it
+                let text = match (node, has_edges) {
+                    (Node::Through, _) => "-".repeat(column_width),
+                    (_, true) => format!("{:-<1$}", text, column_width),
+                    (_, false) => text,
+                };
+                for (offset, c) in text.chars().enumerate() {
+                    let is_label = offset < self.node_text(node).chars().count();
+                    cells[row * 2][x + offset] =
+                        (c, if is_label { style } else { Style::default() });
+                }
+            }
+
+            x += column_width;
+            if let Some(gap_width) = gap_widths.get(column) {
+                self.draw_gap(&mut cells, column, x, *gap_width);
+                x += gap_width;
+            }
+        }
+
+        cells
+    }
+
+    /// Draw the arrows in the gap after `column` starting at `x`. Each target gets its own
+    /// vertical line for arrows into it to join, lines crossing one that isn't theirs hop it.
+    fn draw_gap(&self, cells: &mut [Vec<(char, Style)>], column: usize, x: usize, width: usize) {
+        let targets = self.targets(column);
+        let edges = self
+            .edges
+            .iter()
+            .filter(|(edge_column, _, _)| *edge_column == column)
+            .collect::<Vec<_>>();
+        let line_x = |to_row: &usize| x + 1 + 2 * targets.iter().position(|t| t == to_row).unwrap();
+
+        for (_, from_row, to_row) in edges.iter() {
+            for cell in cells[from_row * 2][x..line_x(to_row)].iter_mut() {
+                cell.0 = '-';
+            }
+        }
+        for to_row in targets.iter() {
+            for cell in cells[to_row * 2][line_x(to_row) + 1..x + width - 1].iter_mut() {
+                cell.0 = '-';
+            }
+            cells[to_row * 2][x + width - 1].0 = '>';
+        }
+
+        for to_row in targets.iter() {
+            let joins = edges
+                .iter()
+                .filter(|(_, _, edge_to_row)| edge_to_row == to_row)
+                .map(|(_, from_row, _)| *from_row)
+                .chain([*to_row])
+                .collect::<BTreeSet<_>>();
+            let top = *joins.first().unwrap();
+            let bottom = *joins.last().unwrap();
+
+            let line = cells
+                .iter_mut()
+                .enumerate()
+                .take(bottom * 2 + 1)
+                .skip(top * 2);
+            for (y, row) in line {
+                row[line_x(to_row)].0 = if y % 2 == 0 && joins.contains(&(y / 2)) {
+                    '+'
+                } else {
+                    '|'
+                };
+            }
+        }
+    }
+}
+
+/// How many steps deep `idx` is, references back to a step already being looked at are ignored
+/// so a cycle can't recurse forever.
+fn depth(
+    idx: usize,
+    deps: &[BTreeSet<usize>],
+    depths: &mut [Option<usize>],
+    visiting: &mut [bool],
+) -> usize {
+    if let Some(depth) = depths[idx] {
+        return depth;
+    }
+
+    visiting[idx] = true;
+    let mut ret = 0;
+    for dep in deps[idx].iter() {
+        if !visiting[*dep] {
+            ret = ret.max(depth(*dep, deps, depths, visiting) + 1);
+        }
+    }
+    visiting[idx] = false;
+
+    depths[idx] = Some(ret);
+
+    ret
+}
+
+impl WidgetRef for StepGraph {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        for (y, row) in self.draw().iter().enumerate().take(area.height as usize) {
+            for (x, (c, style)) in row.iter().enumerate().take(area.width as usize) {
+                buf[(area.x + x as u16, area.y + y as u16)]
+                    .set_char(*c)
+                    .set_style(*style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use ratatui::{backend::TestBackend, Terminal};
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::types::{PatuiStepAssertion, PatuiStepDetails, PatuiStepRead};
+
+    fn step(name: &str, details: PatuiStepDetails) -> PatuiStep {
+        PatuiStep {
+            name: name.to_string(),
+            when: None,
+            depends_on: vec![],
+            timeout_ms: None,
+            details,
+        }
+    }
+
+    fn assertion(name: &str, expr: &str) -> PatuiStep {
+        step(
+            name,
+            PatuiStepDetails::Assertion(PatuiStepAssertion {
+                expr: expr.try_into().unwrap(),
+                sample: None,
+            }),
+        )
+    }
+
+    fn read(name: &str, r#in: &str) -> PatuiStep {
+        step(
+            name,
+            PatuiStepDetails::Read(PatuiStepRead {
+                r#in: r#in.try_into().unwrap(),
+            }),
+        )
+    }
+
+    #[traced_test]
+    #[test]
+    fn diamond() {
+        let mut graph = StepGraph::new(&[
+            read("src", "\"file.txt\""),
+            read("left", "steps.src.out"),
+            read("right", "steps.src.out"),
+            assertion("check", "steps.left.out == steps.right.out"),
+        ]);
+        graph.set_outcome("left", PatuiStepOutcome::Passed);
+        graph.set_outcome("right", PatuiStepOutcome::Failed("oops".to_string()));
+        graph.set_selected_idx(Some(3));
+
+        assert_that!(graph.height()).is_equal_to(3);
+
+        let mut terminal = Terminal::new(TestBackend::new(32, 3)).unwrap();
+        terminal
+            .draw(|f| f.render_widget(&graph, f.area()))
+            .unwrap();
+
+        insta::assert_debug_snapshot!(terminal.backend().buffer());
+    }
+
+    #[traced_test]
+    #[test]
+    fn skipped_column() {
+        let graph = StepGraph::new(&[
+            read("first", "\"file.txt\""),
+            read("middle", "steps.first.out"),
+            assertion("last", "steps.first.out == steps.middle.out"),
+        ]);
+
+        let rows = graph
+            .draw()
+            .into_iter()
+            .map(|row| row.into_iter().map(|(c, _)| c).collect::<String>())
+            .collect::<Vec<_>>();
+
+        assert_that!(rows).is_equal_to(vec![
+            "[first]-+-+->[middle]-+->[last]".to_string(),
+            "          |           |        ".to_string(),
+            "          +->---------+        ".to_string(),
+        ]);
+    }
+}
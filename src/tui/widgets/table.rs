@@ -1,20 +1,23 @@
-use std::{cell::Cell, cmp};
+use std::{cell::Cell, cmp, collections::VecDeque, ops::Range};
 
+use chrono::NaiveDateTime;
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Constraint, Rect},
+    layout::{Constraint, Flex, Layout, Rect},
     style::{Color, Style},
-    text::Text,
+    text::{Line, Span, Text},
     widgets::{
-        Block, Borders, Cell as RatatuiCell, Padding, Row, Scrollbar, ScrollbarOrientation,
-        ScrollbarState, StatefulWidget, Table as RatatuiTable, WidgetRef,
+        Cell as RatatuiCell, Row, ScrollbarState, StatefulWidget, Table as RatatuiTable, WidgetRef,
     },
 };
 
-use super::patui_widget::ScrollType;
+use super::patui_widget::{scrollbar, scrollbar_area, titled_block, ScrollType};
 
 const SHORT_WIDTH_DISPLAY: u16 = 60;
 
+/// How the parts of cells matching the filter are picked out.
+const MATCH_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
 #[derive(Debug, Clone)]
 pub(crate) struct SelectedData {
     selectable: bool,
@@ -150,14 +153,42 @@ impl SelectedData {
         self.selected_idx = cmp::max(self.first_row as isize, self.selected_idx);
         self.selected_idx = cmp::min(
             self.selected_idx,
-            (self.first_row + self.num_display_elements.get() - 1) as isize,
+            (self.first_row + self.num_display_elements.get()).saturating_sub(1) as isize,
         );
     }
 
+    /// The number of elements has changed, keep the selection and first row within them.
+    pub(crate) fn set_num_elements(&mut self, num_elements: usize) {
+        self.num_elements = num_elements;
+        if num_elements == 0 {
+            self.set_unselected();
+            self.first_row = 0;
+            return;
+        }
+
+        if self.selected_idx >= num_elements as isize {
+            self.selected_idx = num_elements as isize - 1;
+        }
+        self.ensure_visible();
+    }
+
     pub(crate) fn set_display_height(&self, height: usize) {
         self.num_display_elements.set(height);
     }
 
+    /// Move the first row so the selected element is in view and the rows shown don't run past
+    /// the end, for when the display height has changed under us.
+    pub(crate) fn ensure_visible(&mut self) {
+        let max_first_row = self
+            .num_elements
+            .saturating_sub(self.num_display_elements.get());
+        self.first_row = cmp::min(self.first_row, max_first_row);
+
+        if self.selected_idx().is_some() {
+            self.calculate_first_row_from_selected_idx();
+        }
+    }
+
     fn calculate_first_row_from_selected_idx(&mut self) {
         let min_first_row = cmp::max(
             0,
@@ -187,23 +218,53 @@ impl<'a> TableHeader<'a> {
     }
 }
 
+/// What a cell is sorted by in place of its text, e.g. so `10` comes after `9`. Missing values
+/// come before everything else.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SortValue {
+    Missing,
+    Number(i64),
+    Date(NaiveDateTime),
+    Text(String),
+}
+
 /// For the table widget you need to pass in:
 /// * The `short_header` that will be the elements displayed along with their index in the
 ///   `elements` vector when the width of the table is less than `SHORT_WIDTH_DISPLAY`.
 /// * The `long_header` that will be the elements displayed along with their index in the
 ///   `elements` vector when the width of the table is greater than `SHORT_WIDTH_DISPLAY`.
 /// * The `elements` that will be the data displayed in the table.
+///
+/// The rows can be sorted by a column and a filter set to only show the rows with a cell
+/// containing it. Neither reorders `elements`, positions like those passed to `go_to` count the
+/// rows as shown whereas `selected_idx` is always an index into `elements`.
 #[derive(Clone, Debug)]
 pub(crate) struct Table<'a> {
     short_header: Vec<TableHeader<'a>>,
     long_header: Vec<TableHeader<'a>>,
     elements: Vec<Vec<Text<'a>>>,
+    /// Sorted by in place of the text of the cell in the same row and column of `elements`.
+    sort_values: Vec<Vec<SortValue>>,
+
+    /// The column to sort by and whether it's ascending.
+    sort: Option<(usize, bool)>,
+    /// Indexes into `elements` in the order they're shown.
+    order: Vec<usize>,
+    /// Of `order` the rows shown, all of them unless there's a filter.
+    visible: Vec<usize>,
+    filter: String,
+    /// The columns the filter looks in, empty for all of them.
+    filter_columns: Vec<usize>,
 
     block_title: Option<&'a str>,
 
     selected_data: SelectedData,
+    first_col: usize,
 
     is_focussed: bool,
+    is_compact: bool,
+    is_ascii: bool,
+    wrap: bool,
 }
 
 impl<'a> Table<'a> {
@@ -220,17 +281,161 @@ impl<'a> Table<'a> {
             short_header,
             long_header,
             elements,
+            sort_values: vec![],
+
+            sort: None,
+            order: (0..elements_len).collect(),
+            visible: (0..elements_len).collect(),
+            filter: String::new(),
+            filter_columns: vec![],
 
             block_title,
 
             selected_data: SelectedData::new(is_selectable, false, elements_len, 24),
+            first_col: 0,
 
             is_focussed: false,
+            is_compact: false,
+            is_ascii: false,
+            wrap: false,
         }
     }
 
+    /// The number of rows shown, only those matching the filter if there is one.
     pub(crate) fn num_elements(&self) -> usize {
-        self.elements.len()
+        self.visible.len()
+    }
+
+    /// Replace the rows of the table keeping what's selected and scrolled to, both are pulled
+    /// back in if there are fewer rows now.
+    pub(crate) fn set_elements(&mut self, elements: Vec<Vec<Text<'a>>>) {
+        self.elements = elements;
+        self.update_rows();
+    }
+
+    /// Values to sort cells by in place of their text, by row and column as in `elements`, they're
+    /// used from the next time the table's sorted or its elements are set.
+    pub(crate) fn set_sort_values(&mut self, sort_values: Vec<Vec<SortValue>>) {
+        self.sort_values = sort_values;
+    }
+
+    /// The column sorted by, as an index into each row of `elements`, and whether it's ascending.
+    pub(crate) fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    /// Order the rows by their cells in `column`, an index into each row of `elements`, rows that
+    /// are equal stay in the order of `elements`. The selected row stays selected wherever it's
+    /// moved to.
+    pub(crate) fn sort_by(&mut self, column: usize, ascending: bool) {
+        let selected_idx = self.selected_idx();
+
+        self.sort = Some((column, ascending));
+        self.update_rows();
+
+        let position = selected_idx
+            .and_then(|selected_idx| self.visible.iter().position(|idx| *idx == selected_idx));
+        if let Some(position) = position {
+            self.selected_data.set_selected_idx(position);
+        }
+    }
+
+    pub(crate) fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Only show the rows with a cell containing `filter`, ignoring case, an empty filter shows
+    /// every row again. The selected row stays selected if it still matches, otherwise the next
+    /// row that does is selected.
+    pub(crate) fn set_filter(&mut self, filter: &str) {
+        let selected_idx = self.selected_idx();
+
+        self.filter = filter.to_string();
+        self.update_rows();
+
+        if let Some(selected_idx) = selected_idx {
+            if !self.visible.is_empty() {
+                let mut ranks = vec![0; self.elements.len()];
+                for (rank, idx) in self.order.iter().enumerate() {
+                    ranks[*idx] = rank;
+                }
+                let position = self
+                    .visible
+                    .partition_point(|idx| ranks[*idx] < ranks[selected_idx]);
+                self.selected_data
+                    .set_selected_idx(cmp::min(position, self.visible.len() - 1));
+            }
+        }
+    }
+
+    /// Which columns the filter looks in, by their index in each row of `elements`.
+    pub(crate) fn set_filter_columns(&mut self, columns: Vec<usize>) {
+        self.filter_columns = columns;
+        self.update_rows();
+    }
+
+    /// Move the selection on by `count` rows matching the filter, wrapping around at either end.
+    /// With nothing selected going forwards starts at the first match and backwards at the last.
+    ///
+    /// Returns the index in `elements` selected, `None` implies there's no filter or nothing
+    /// matches it.
+    pub(crate) fn next_match(&mut self, count: isize) -> Option<usize> {
+        if !self.selected_data.is_selectable() || self.filter.is_empty() || self.visible.is_empty()
+        {
+            return None;
+        }
+
+        let num_matches = self.visible.len() as isize;
+        let position = match self.selected_data.selected_idx() {
+            Some(position) => (position as isize + count).rem_euclid(num_matches),
+            None if count < 0 => num_matches - 1,
+            None => 0,
+        };
+        self.selected_data.set_selected_idx(position as usize);
+
+        self.selected_idx()
+    }
+
+    /// Work out the order of the rows and which of them are shown once the elements, sort or
+    /// filter have changed.
+    fn update_rows(&mut self) {
+        let mut order = (0..self.elements.len()).collect::<Vec<_>>();
+        match self.sort {
+            Some((column, true)) => order.sort_by_cached_key(|idx| self.sort_value(*idx, column)),
+            Some((column, false)) => {
+                order.sort_by_cached_key(|idx| cmp::Reverse(self.sort_value(*idx, column)))
+            }
+            None => {}
+        }
+
+        self.visible = order
+            .iter()
+            .copied()
+            .filter(|idx| self.is_match(*idx))
+            .collect();
+        self.order = order;
+        self.selected_data.set_num_elements(self.visible.len());
+    }
+
+    fn sort_value(&self, idx: usize, column: usize) -> SortValue {
+        match self.sort_values.get(idx).and_then(|row| row.get(column)) {
+            Some(sort_value) => sort_value.clone(),
+            None => self.elements[idx]
+                .get(column)
+                .map_or(SortValue::Missing, |cell| SortValue::Text(cell.to_string())),
+        }
+    }
+
+    fn is_match(&self, idx: usize) -> bool {
+        self.filter.is_empty()
+            || self.elements[idx].iter().enumerate().any(|(column, cell)| {
+                self.is_filtered_column(column)
+                    && !find_ignoring_case(&cell.to_string(), &self.filter).is_empty()
+            })
+    }
+
+    fn is_filtered_column(&self, column: usize) -> bool {
+        self.filter_columns.is_empty() || self.filter_columns.contains(&column)
     }
 
     /// Get the first row we're displaying
@@ -238,9 +443,11 @@ impl<'a> Table<'a> {
         self.selected_data.first_row()
     }
 
-    /// Get the selected index if we've selected something.
+    /// Get the index in `elements` of the selected row if we've selected something.
     pub(crate) fn selected_idx(&self) -> Option<usize> {
-        self.selected_data.selected_idx()
+        self.selected_data
+            .selected_idx()
+            .map(|position| self.visible[position])
     }
 
     /// Get the display height
@@ -254,20 +461,80 @@ impl<'a> Table<'a> {
     ///
     /// Returns the number of elements altered by, 0 implies nothing was changed.
     pub(crate) fn navigate(&mut self, count: isize) -> isize {
-        if !self.selected_data.is_selectable() || self.elements.is_empty() || count == 0 {
+        if !self.selected_data.is_selectable() || self.visible.is_empty() || count == 0 {
             return 0;
         }
 
         self.selected_data.add_selected_idx(count)
     }
 
+    /// Select the row at `selected_idx`, counting the rows shown, anything past the end selects
+    /// the last row, e.g. for `L` when there are fewer rows than fit.
     pub(crate) fn set_selected_idx(&mut self, selected_idx: usize) {
-        if !self.selected_data.is_selectable() || self.elements.is_empty() {
+        if !self.selected_data.is_selectable() || self.visible.is_empty() {
             return;
         }
+        let selected_idx = cmp::min(selected_idx, self.visible.len() - 1);
         self.selected_data.set_selected_idx(selected_idx);
     }
 
+    /// Select the row at `idx` and scroll it into view, an `idx` past the end selects the last row.
+    ///
+    /// Returns the index in `elements` selected, `None` implies there's nothing to select.
+    pub(crate) fn go_to(&mut self, idx: usize) -> Option<usize> {
+        if !self.selected_data.is_selectable() || self.visible.is_empty() {
+            return None;
+        }
+
+        let idx = cmp::min(idx, self.visible.len() - 1);
+        self.selected_data.set_selected_idx(idx);
+
+        self.selected_idx()
+    }
+
+    /// Bring the selected row back into view after the display height has changed, e.g. the
+    /// terminal was resized, the height is only known once the table has been rendered at its new
+    /// size.
+    pub(crate) fn ensure_visible(&mut self) {
+        self.selected_data.ensure_visible();
+    }
+
+    /// Select the row showing `elements[idx]` and scroll it into view.
+    ///
+    /// Returns the position of the row as shown, `None` implies it isn't shown, e.g. it's
+    /// filtered out, and the selection is unchanged.
+    pub(crate) fn select_element(&mut self, idx: usize) -> Option<usize> {
+        if !self.selected_data.is_selectable() {
+            return None;
+        }
+
+        let position = self.visible.iter().position(|visible| *visible == idx)?;
+        self.selected_data.set_selected_idx(position);
+
+        Some(position)
+    }
+
+    /// Select the first row that `predicate` accepts, given the row's cells.
+    ///
+    /// Returns the index in `elements` selected, `None` implies nothing matched and the selection
+    /// is unchanged.
+    pub(crate) fn select_by_predicate<F>(&mut self, predicate: F) -> Option<usize>
+    where
+        F: Fn(&[Text<'a>]) -> bool,
+    {
+        if !self.selected_data.is_selectable() {
+            return None;
+        }
+
+        let position = self
+            .visible
+            .iter()
+            .position(|idx| predicate(&self.elements[*idx]))?;
+        self.selected_data.set_selected_idx(position);
+
+        self.selected_idx()
+    }
+
     pub(crate) fn scroll(&mut self, scroll_type: ScrollType) {
         let display_height = self.selected_data.num_display_elements();
         match scroll_type {
@@ -280,9 +547,32 @@ impl<'a> Table<'a> {
             ScrollType::HalfPageUp => self
                 .selected_data
                 .add_first_row(-((display_height / 2) as isize)),
+            ScrollType::Top | ScrollType::Bottom => {
+                let count = match scroll_type {
+                    ScrollType::Top => -(self.visible.len() as isize),
+                    _ => self.visible.len() as isize,
+                };
+                self.selected_data.add_first_row(count);
+                self.navigate(count);
+            }
         };
     }
 
+    /// Shift the displayed columns by `count` so columns clipped on the right can be seen, we
+    /// always leave at least one column displayed.
+    ///
+    /// Returns the number of columns shifted by, 0 implies nothing was changed.
+    pub(crate) fn scroll_horizontal(&mut self, count: isize) -> isize {
+        let num_cols = cmp::max(self.short_header.len(), self.long_header.len());
+        let max_first_col = num_cols.saturating_sub(1) as isize;
+
+        let old_first_col = self.first_col as isize;
+        let first_col = (old_first_col + count).clamp(0, max_first_col);
+        self.first_col = first_col as usize;
+
+        first_col - old_first_col
+    }
+
     pub(crate) fn is_focussed(&self) -> bool {
         self.is_focussed
     }
@@ -291,6 +581,24 @@ impl<'a> Table<'a> {
         self.is_focussed = focus;
     }
 
+    /// Compact tables drop the borders and padding around the table to save space on small
+    /// terminals.
+    pub(crate) fn set_compact(&mut self, is_compact: bool) {
+        self.is_compact = is_compact;
+    }
+
+    /// ASCII tables draw their borders and scrollbar without unicode for terminals that can't
+    /// show it.
+    pub(crate) fn set_ascii(&mut self, is_ascii: bool) {
+        self.is_ascii = is_ascii;
+    }
+
+    /// Wrap cells too wide for their column onto more lines, making their row taller, rather than
+    /// cutting them off.
+    pub(crate) fn set_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
     pub(crate) fn reset(&mut self) {
         self.selected_data.set_unselected();
     }
@@ -304,55 +612,133 @@ impl<'a> Table<'a> {
 
         let is_short = area.width < SHORT_WIDTH_DISPLAY;
 
-        let header = if is_short {
-            self.short_header.iter()
+        let headers = if is_short {
+            &self.short_header
         } else {
-            self.long_header.iter()
-        }
-        .map(|header| RatatuiCell::from(header.text.clone()).style(style))
-        .collect::<Row>()
-        .height(2);
+            &self.long_header
+        };
+        let first_col = cmp::min(self.first_col, headers.len().saturating_sub(1));
+        let headers = &headers[first_col..];
 
-        let elements_display_height = if self.block_title.is_some() {
-            // -6 for block and title
-            area.height as usize - 6
+        let header = headers
+            .iter()
+            .map(|header| {
+                let mut text = header.text.clone();
+                if let Some((_, ascending)) =
+                    self.sort.filter(|(column, _)| *column == header.index)
+                {
+                    text.push_span(match (ascending, self.is_ascii) {
+                        (true, false) => " ▲",
+                        (false, false) => " ▼",
+                        (true, true) => " ^",
+                        (false, true) => " v",
+                    });
+                }
+                RatatuiCell::from(text).style(style)
+            })
+            .collect::<Row>()
+            .height(2);
+
+        let elements_display_height = match (self.block_title, self.is_compact) {
+            // -6 for block and header
+            (Some(_), false) => area.height.saturating_sub(6),
+            // -3 for block title and header
+            (Some(_), true) => area.height.saturating_sub(3),
+            // -2 for header
+            (None, _) => area.height.saturating_sub(2),
+        } as usize;
+
+        let block = self.block_title.map(|block_title| {
+            titled_block(
+                block_title.to_string(),
+                style,
+                self.is_compact,
+                self.is_ascii,
+            )
+        });
+        let constraints = headers
+            .iter()
+            .map(|header| header.constraint)
+            .collect::<Vec<_>>();
+
+        // Only needed for wrapping, worked out the same way the table lays out its columns
+        let column_widths = if self.wrap {
+            let table_area = block.as_ref().map_or(area, |block| block.inner(area));
+            Layout::horizontal(constraints.clone())
+                .flex(Flex::Start)
+                .spacing(1)
+                .split(Rect::new(0, 0, table_area.width, 1))
+                .iter()
+                .map(|column| column.width)
+                .collect::<Vec<_>>()
         } else {
-            // -2 for title
-            area.height as usize - 2
+            vec![]
         };
-        self.selected_data
-            .set_display_height(elements_display_height);
-
-        let num_elems_to_display = cmp::min(elements_display_height, self.elements.len());
 
-        let elems = self
-            .elements
+        let rows = self
+            .visible
             .iter()
             .enumerate()
-            .filter(|(i, _)| *i >= self.selected_data.first_row() as usize)
-            .take(num_elems_to_display)
-            .map(|(i, row)| {
-                (
-                    i,
-                    row.iter()
-                        .enumerate()
-                        .filter_map(|(j, elem)| {
-                            if (is_short
-                                && self.short_header.iter().any(|header| j == header.index))
-                                || (!is_short
-                                    && self.long_header.iter().any(|header| j == header.index))
-                            {
-                                Some(RatatuiCell::from(elem.clone()))
-                            } else {
-                                None
-                            }
+            .skip(self.selected_data.first_row())
+            .map(|(i, idx)| {
+                let cells = self.elements[*idx]
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(j, elem)| {
+                        let column = headers.iter().position(|header| j == header.index)?;
+                        let elem = if !self.filter.is_empty() && self.is_filtered_column(j) {
+                            highlight_matches(elem, &self.filter)
+                        } else {
+                            elem.clone()
+                        };
+                        Some(match column_widths.get(column) {
+                            Some(width) => wrap_text(&elem, *width),
+                            None => elem,
                         })
-                        .collect::<Row>()
-                        .height(1),
-                )
-            })
-            .map(|(i, row)| {
-                if self.selected_data.selected_idx() == Some(i) {
+                    })
+                    .collect::<Vec<_>>();
+                let height = cells.iter().map(|cell| cell.height()).max().unwrap_or(1);
+
+                (i, cells, cmp::max(height, 1))
+            });
+
+        // Rows can be taller than a line when wrapping, so rows at the top make way for the
+        // selected row if it wouldn't otherwise fit
+        let selected_idx = self.selected_data.selected_idx();
+        let mut displayed = VecDeque::new();
+        let mut displayed_height = 0;
+        for (i, cells, height) in rows {
+            let is_before_selected = selected_idx.is_some_and(|selected_idx| i <= selected_idx);
+            if displayed_height + height > elements_display_height && !is_before_selected {
+                break;
+            }
+
+            displayed_height += height;
+            displayed.push_back((i, cells, height));
+
+            while displayed_height > elements_display_height && displayed.len() > 1 {
+                if let Some((_, _, height)) = displayed.pop_front() {
+                    displayed_height -= height;
+                }
+            }
+        }
+
+        self.selected_data.set_display_height(if self.wrap {
+            displayed.len()
+        } else {
+            elements_display_height
+        });
+
+        let elems = displayed
+            .into_iter()
+            .map(|(i, cells, height)| {
+                let row = cells
+                    .into_iter()
+                    .map(RatatuiCell::from)
+                    .collect::<Row>()
+                    .height(height as u16);
+
+                if selected_idx == Some(i) {
                     row.style(style.fg(Color::Black).bg(Color::White))
                 } else {
                     row.style(style)
@@ -360,34 +746,10 @@ impl<'a> Table<'a> {
             })
             .collect::<Vec<Row>>();
 
-        let table = if is_short {
-            RatatuiTable::new(
-                elems,
-                self.short_header
-                    .iter()
-                    .map(|header| header.constraint)
-                    .collect::<Vec<_>>(),
-            )
-        } else {
-            RatatuiTable::new(
-                elems,
-                self.long_header
-                    .iter()
-                    .map(|header| header.constraint)
-                    .collect::<Vec<_>>(),
-            )
-        }
-        .header(header);
+        let table = RatatuiTable::new(elems, constraints).header(header);
 
-        let table = if let Some(block_title) = self.block_title {
-            table.block(
-                Block::new()
-                    .borders(Borders::ALL)
-                    .padding(Padding::symmetric(2, 1))
-                    .title_alignment(Alignment::Center)
-                    .title(block_title)
-                    .style(style),
-            )
+        let table = if let Some(block) = block {
+            table.block(block)
         } else {
             table
         };
@@ -396,9 +758,7 @@ impl<'a> Table<'a> {
     }
 
     fn render_scrollbar(&self, area: Rect, buf: &mut Buffer) {
-        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓"));
+        let scrollbar = scrollbar(self.is_ascii);
 
         let num_elements = self.num_elements();
         let display_height = self.selected_data.num_display_elements();
@@ -416,17 +776,143 @@ impl<'a> Table<'a> {
     }
 }
 
+/// The byte ranges of `haystack` where `needle` is found ignoring case, none for an empty
+/// `needle`.
+fn find_ignoring_case(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    let mut found = vec![];
+    if needle.is_empty() {
+        return found;
+    }
+
+    let mut start = 0;
+    while let Some(c) = haystack[start..].chars().next() {
+        let mut chars = haystack[start..].char_indices();
+        let is_match = needle.chars().all(|n| {
+            chars
+                .next()
+                .is_some_and(|(_, h)| h.to_lowercase().eq(n.to_lowercase()))
+        });
+
+        if is_match {
+            let len = chars.next().map_or(haystack.len() - start, |(idx, _)| idx);
+            found.push(start..start + len);
+            start += len;
+        } else {
+            start += c.len_utf8();
+        }
+    }
+
+    found
+}
+
+/// Pick out wherever `filter` is found in `text`, a match split across spans isn't picked out.
+fn highlight_matches<'a>(text: &Text<'a>, filter: &str) -> Text<'a> {
+    let mut highlighted = text.clone();
+
+    for line in highlighted.lines.iter_mut() {
+        line.spans = line
+            .spans
+            .iter()
+            .flat_map(|span| {
+                let mut spans = vec![];
+                let mut end = 0;
+                for found in find_ignoring_case(&span.content, filter) {
+                    if found.start > end {
+                        spans.push(Span::styled(
+                            span.content[end..found.start].to_string(),
+                            span.style,
+                        ));
+                    }
+                    spans.push(Span::styled(
+                        span.content[found.clone()].to_string(),
+                        span.style.patch(MATCH_STYLE),
+                    ));
+                    end = found.end;
+                }
+                if end < span.content.len() {
+                    spans.push(Span::styled(span.content[end..].to_string(), span.style));
+                }
+                spans
+            })
+            .collect();
+    }
+
+    highlighted
+}
+
+/// Word wrap `text` to fit in `width` columns keeping its styles, a word too long for a line by
+/// itself is broken wherever it reaches the end of the line.
+fn wrap_text<'a>(text: &Text<'a>, width: u16) -> Text<'a> {
+    let width = width as usize;
+    if width == 0 || text.width() <= width {
+        return text.clone();
+    }
+
+    let mut wrapped = Text::default().style(text.style);
+    wrapped.alignment = text.alignment;
+
+    for line in text.lines.iter() {
+        if line.width() <= width {
+            wrapped.lines.push(line.clone());
+            continue;
+        }
+
+        let new_line = || {
+            let mut new_line = Line::default().style(line.style);
+            new_line.alignment = line.alignment;
+            new_line
+        };
+        let mut current = new_line();
+
+        for span in line.spans.iter() {
+            for word in span.content.split_inclusive(' ') {
+                let mut word = word;
+
+                while !word.is_empty() {
+                    let room = width - cmp::min(current.width(), width);
+
+                    // Trailing spaces are allowed to hang off the end of the line
+                    if Span::raw(word.trim_end()).width() <= room {
+                        current.push_span(Span::styled(word.to_string(), span.style));
+                        break;
+                    }
+
+                    if current.width() > 0 {
+                        wrapped
+                            .lines
+                            .push(std::mem::replace(&mut current, new_line()));
+                        continue;
+                    }
+
+                    let split_at = word
+                        .char_indices()
+                        .map(|(idx, _)| idx)
+                        .skip(1)
+                        .take_while(|idx| Span::raw(&word[..*idx]).width() <= room)
+                        .last()
+                        .unwrap_or_else(|| word.chars().next().map_or(0, char::len_utf8));
+                    current.push_span(Span::styled(word[..split_at].to_string(), span.style));
+                    wrapped
+                        .lines
+                        .push(std::mem::replace(&mut current, new_line()));
+                    word = &word[split_at..];
+                }
+            }
+        }
+
+        if current.width() > 0 {
+            wrapped.lines.push(current);
+        }
+    }
+
+    wrapped
+}
+
 impl<'a> WidgetRef for Table<'a> {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         self.render_table(area, buf);
         if self.block_title.is_some() {
-            let scrollbar_area = Rect {
-                x: area.x + area.width - 1,
-                y: area.y + 1,
-                width: 1,
-                height: area.height - 2,
-            };
-            self.render_scrollbar(scrollbar_area, buf);
+            self.render_scrollbar(scrollbar_area(area, self.is_compact), buf);
         }
     }
 }
@@ -436,9 +922,11 @@ mod tests {
     use std::cmp;
 
     use assertor::*;
+    use chrono::NaiveDateTime;
     use ratatui::{
         buffer::Buffer,
         layout::{Alignment, Constraint, Rect},
+        style::Color,
         text::Text,
         widgets::WidgetRef,
     };
@@ -446,7 +934,7 @@ mod tests {
 
     use crate::tui::widgets::patui_widget::ScrollType;
 
-    use super::{SelectedData, Table, TableHeader};
+    use super::{find_ignoring_case, wrap_text, SelectedData, SortValue, Table, TableHeader};
 
     fn create_tests_table<'a>(
         num_tests: usize,
@@ -666,6 +1154,58 @@ mod tests {
         insta::assert_debug_snapshot!(buffer);
     }
 
+    #[traced_test]
+    #[test]
+    fn test_scroll_horizontal_table() {
+        let mut table = create_tests_table(40, None, true);
+        table.navigate(3);
+
+        assert_that!(table.scroll_horizontal(-1)).is_equal_to(0);
+        assert_that!(table.scroll_horizontal(2)).is_equal_to(2);
+        assert_that!(table.first_col).is_equal_to(2);
+
+        let rect = Rect::new(0, 0, 120, 20);
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+
+        insta::assert_debug_snapshot!(buffer);
+
+        assert_that!(table.scroll_horizontal(10)).is_equal_to(2);
+        assert_that!(table.first_col).is_equal_to(4);
+        assert_that!(table.scroll_horizontal(-10)).is_equal_to(-4);
+        assert_that!(table.first_col).is_equal_to(0);
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_scroll_horizontal_narrow_table() {
+        let mut table = create_tests_table(10, None, true);
+        let rect = Rect::new(0, 0, 64, 6);
+        let render = |table: &Table| {
+            let mut buffer = Buffer::empty(rect);
+            table.render_ref(rect, &mut buffer);
+            buffer
+        };
+        let rendered = |buffer: &Buffer| {
+            buffer
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        // Too narrow for all the long header's columns, the dates are cut off
+        let buffer = render(&table);
+        assert_that!(rendered(&buffer)).does_not_contain("2024-08-31 11:00:00");
+
+        assert_that!(table.scroll_horizontal(2)).is_equal_to(2);
+        let buffer = render(&table);
+        assert_that!(rendered(&buffer)).contains("2024-08-31 11:00:00");
+        assert_that!(rendered(&buffer)).does_not_contain("test0");
+
+        insta::assert_debug_snapshot!(buffer);
+    }
+
     #[traced_test]
     #[test]
     fn test_scroll_table() {
@@ -718,6 +1258,51 @@ mod tests {
         assert_that!(table.selected_data.is_selected()).is_equal_to(false);
     }
 
+    #[traced_test]
+    #[test]
+    fn test_scroll_top_bottom_table() {
+        let mut table = create_tests_table(40, None, true);
+        let rect = Rect::new(0, 0, 120, 24);
+        table.render_ref(rect, &mut Buffer::empty(rect));
+        assert_that!(table.display_height()).is_equal_to(22);
+
+        // From nothing selected
+        table.scroll(ScrollType::Bottom);
+        assert_that!(table.selected_idx()).is_equal_to(Some(39));
+        assert_that!(table.first_row()).is_equal_to(18);
+
+        table.scroll(ScrollType::Top);
+        assert_that!(table.selected_idx()).is_equal_to(Some(0));
+        assert_that!(table.first_row()).is_equal_to(0);
+
+        // From part way through
+        table.navigate(10);
+        table.scroll(ScrollType::Single(5));
+        table.scroll(ScrollType::Bottom);
+        assert_that!(table.selected_idx()).is_equal_to(Some(39));
+        assert_that!(table.first_row()).is_equal_to(18);
+        table.scroll(ScrollType::Bottom);
+        assert_that!(table.selected_idx()).is_equal_to(Some(39));
+        assert_that!(table.first_row()).is_equal_to(18);
+
+        table.navigate(-10);
+        table.scroll(ScrollType::Top);
+        assert_that!(table.selected_idx()).is_equal_to(Some(0));
+        assert_that!(table.first_row()).is_equal_to(0);
+
+        // Fewer rows than fit leave the first row at the top
+        table.set_filter("test3");
+        table.scroll(ScrollType::Bottom);
+        assert_that!(table.selected_idx()).is_equal_to(Some(39));
+        assert_that!(table.first_row()).is_equal_to(0);
+
+        // Nothing to go to
+        table.set_filter("nothing");
+        table.scroll(ScrollType::Bottom);
+        assert_that!(table.selected_idx()).is_equal_to(None);
+        assert_that!(table.first_row()).is_equal_to(0);
+    }
+
     #[traced_test]
     #[test]
     fn test_navigate_table() {
@@ -841,6 +1426,268 @@ mod tests {
         insta::assert_debug_snapshot!(buffer);
     }
 
+    #[traced_test]
+    #[test]
+    fn test_resize_table() {
+        let mut table = create_tests_table(40, None, true);
+        let render = |table: &Table, height: u16| {
+            let rect = Rect::new(0, 0, 120, height);
+            let mut buffer = Buffer::empty(rect);
+            table.render_ref(rect, &mut buffer);
+            buffer
+                .content()
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        render(&table, 24);
+        table.navigate(21);
+        assert_that!(table.selected_data.first_row()).is_equal_to(0);
+
+        // Shrinking leaves the selected row below what fits until it's brought back into view
+        render(&table, 12);
+        table.ensure_visible();
+
+        assert_that!(table.selected_data.first_row()).is_equal_to(11);
+        assert_that!(table.selected_data.selected_idx()).is_equal_to(Some(20));
+        assert_that!(render(&table, 12)).contains("test20");
+
+        // Growing again shows as many rows as fit rather than leaving space at the end
+        table.navigate(19);
+        assert_that!(table.selected_data.first_row()).is_equal_to(30);
+
+        render(&table, 24);
+        table.ensure_visible();
+
+        assert_that!(table.selected_data.first_row()).is_equal_to(18);
+        assert_that!(table.selected_data.selected_idx()).is_equal_to(Some(39));
+        let rendered = render(&table, 24);
+        assert_that!(rendered).contains("test18");
+        assert_that!(rendered).contains("test39");
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_go_to_index() {
+        let mut table = create_tests_table(40, None, true);
+        let rect = Rect::new(0, 0, 120, 12);
+        table.render_ref(rect, &mut Buffer::empty(rect));
+
+        assert_that!(table.go_to(25)).is_equal_to(Some(25));
+        assert_that!(table.selected_idx()).is_equal_to(Some(25));
+        assert_that!(table.first_row()).is_equal_to(16);
+
+        // Past the end goes to the last row
+        assert_that!(table.go_to(100)).is_equal_to(Some(39));
+        assert_that!(table.selected_idx()).is_equal_to(Some(39));
+        assert_that!(table.first_row()).is_equal_to(30);
+
+        // Back up above what's shown scrolls up to it
+        assert_that!(table.go_to(3)).is_equal_to(Some(3));
+        assert_that!(table.first_row()).is_equal_to(3);
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_filter_table() {
+        let mut table = create_tests_table(40, None, true);
+        table.set_filter_columns(vec![0, 1]);
+        let rect = Rect::new(0, 0, 120, 12);
+        table.render_ref(rect, &mut Buffer::empty(rect));
+        table.go_to(25);
+
+        // Nothing after test25 matches so the last match is selected
+        table.set_filter("TEST1");
+        assert_that!(table.num_elements()).is_equal_to(11);
+        assert_that!(table.selected_idx()).is_equal_to(Some(19));
+
+        // Otherwise the next match along
+        table.set_filter("test3");
+        assert_that!(table.num_elements()).is_equal_to(11);
+        assert_that!(table.selected_idx()).is_equal_to(Some(30));
+
+        // A match stays selected
+        table.set_filter("test30");
+        assert_that!(table.num_elements()).is_equal_to(1);
+        assert_that!(table.selected_idx()).is_equal_to(Some(30));
+        assert_that!(table.first_row()).is_equal_to(0);
+
+        // Only the name and description are looked in
+        table.set_filter("2024");
+        assert_that!(table.num_elements()).is_equal_to(0);
+        assert_that!(table.selected_idx()).is_equal_to(None);
+
+        table.set_filter("");
+        assert_that!(table.num_elements()).is_equal_to(40);
+        assert_that!(table.selected_idx()).is_equal_to(None);
+
+        // What matches is picked out
+        table.set_filter("st1");
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+        assert_that!(buffer[(0, 2)].symbol()).is_equal_to("t");
+        assert_that!(buffer[(0, 2)].bg).is_not_equal_to(Color::Yellow);
+        assert_that!(buffer[(2, 2)].bg).is_equal_to(Color::Yellow);
+        assert_that!(buffer[(4, 2)].bg).is_equal_to(Color::Yellow);
+        assert_that!(buffer[(5, 2)].bg).is_not_equal_to(Color::Yellow);
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_filter_next_match() {
+        let mut table = create_tests_table(40, None, true);
+
+        // Without a filter there are no matches
+        assert_that!(table.next_match(1)).is_equal_to(None);
+
+        table.set_filter("test2");
+        assert_that!(table.selected_idx()).is_equal_to(None);
+        assert_that!(table.next_match(1)).is_equal_to(Some(2));
+        assert_that!(table.next_match(1)).is_equal_to(Some(20));
+        assert_that!(table.next_match(-1)).is_equal_to(Some(2));
+        assert_that!(table.next_match(-1)).is_equal_to(Some(29));
+        assert_that!(table.next_match(1)).is_equal_to(Some(2));
+
+        // Navigating only goes through the matches
+        assert_that!(table.navigate(3)).is_equal_to(3);
+        assert_that!(table.selected_idx()).is_equal_to(Some(22));
+        assert_that!(table.go_to(100)).is_equal_to(Some(29));
+        assert_that!(table.selected_idx()).is_equal_to(Some(29));
+
+        // Dropping the filter keeps the selection
+        table.set_filter("");
+        assert_that!(table.selected_idx()).is_equal_to(Some(29));
+        assert_that!(table.next_match(1)).is_equal_to(None);
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_sort_table() {
+        let mut table = create_tests_table(5, None, true);
+        let date = |date| NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap();
+        let times_used = [9, 10, 2, 100, 0];
+        let last_used = [
+            Some("2024-08-31 11:00:00"),
+            None,
+            Some("2023-12-01 09:30:00"),
+            Some("2024-08-31 09:00:00"),
+            Some("2024-01-15 00:00:00"),
+        ];
+        table.set_sort_values(
+            (0..5)
+                .map(|i| {
+                    vec![
+                        SortValue::Missing,
+                        SortValue::Missing,
+                        SortValue::Missing,
+                        last_used[i].map_or(SortValue::Missing, |last_used| {
+                            SortValue::Date(date(last_used))
+                        }),
+                        SortValue::Missing,
+                        SortValue::Number(times_used[i]),
+                    ]
+                })
+                .collect(),
+        );
+        table.go_to(3);
+
+        // By value rather than as text, which would put 10 and 100 before 2
+        table.sort_by(5, true);
+        assert_that!(table.visible).is_equal_to(vec![4, 2, 0, 1, 3]);
+        table.sort_by(5, false);
+        assert_that!(table.visible).is_equal_to(vec![3, 1, 0, 2, 4]);
+
+        // Missing dates come first
+        table.sort_by(3, true);
+        assert_that!(table.visible).is_equal_to(vec![1, 2, 4, 3, 0]);
+        table.sort_by(3, false);
+        assert_that!(table.visible).is_equal_to(vec![0, 3, 4, 2, 1]);
+
+        // The same row stays selected throughout
+        assert_that!(table.selected_idx()).is_equal_to(Some(3));
+        assert_that!(table.selected_data.selected_idx()).is_equal_to(Some(1));
+
+        // Equal values keep the order of the elements
+        table.sort_by(1, false);
+        assert_that!(table.visible).is_equal_to(vec![0, 1, 2, 3, 4]);
+
+        // Filtering keeps the sort, once the selected row's gone the next one along as sorted is
+        // selected
+        table.sort_by(5, true);
+        assert_that!(table.go_to(0)).is_equal_to(Some(4));
+        table.set_filter("test1");
+        assert_that!(table.selected_idx()).is_equal_to(Some(1));
+        table.set_filter("");
+        assert_that!(table.visible).is_equal_to(vec![4, 2, 0, 1, 3]);
+
+        // Without sort values the text is sorted by, refreshed elements are sorted the same
+        table.set_sort_values(vec![]);
+        table.sort_by(0, false);
+        assert_that!(table.visible).is_equal_to(vec![4, 3, 2, 1, 0]);
+        table.set_elements(create_tests_table(3, None, true).elements);
+        assert_that!(table.visible).is_equal_to(vec![2, 1, 0]);
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_select_element() {
+        let mut table = create_tests_table(40, None, true);
+        let rect = Rect::new(0, 0, 120, 12);
+        table.render_ref(rect, &mut Buffer::empty(rect));
+
+        assert_that!(table.select_element(25)).is_equal_to(Some(25));
+        assert_that!(table.selected_idx()).is_equal_to(Some(25));
+        assert_that!(table.first_row()).is_equal_to(16);
+
+        // Sorted as text test9 comes first
+        table.sort_by(0, false);
+        assert_that!(table.select_element(9)).is_equal_to(Some(0));
+        assert_that!(table.first_row()).is_equal_to(0);
+
+        // Filtered out
+        table.set_filter("test3");
+        assert_that!(table.select_element(25)).is_equal_to(None);
+        assert_that!(table.selected_idx()).is_equal_to(Some(39));
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_sorted_header() {
+        let mut table = create_tests_table(5, None, true);
+        let rect = Rect::new(0, 0, 120, 4);
+        let header = |table: &Table| {
+            let mut buffer = Buffer::empty(rect);
+            table.render_ref(rect, &mut buffer);
+            buffer.content()[..120]
+                .iter()
+                .map(|cell| cell.symbol())
+                .collect::<String>()
+        };
+
+        table.sort_by(1, true);
+        assert_that!(header(&table)).contains("Description ▲");
+        table.sort_by(1, false);
+        assert_that!(header(&table)).contains("Description ▼");
+
+        table.set_ascii(true);
+        assert_that!(header(&table)).contains("Description v");
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_find_ignoring_case() {
+        assert_that!(find_ignoring_case("Test test TEST", "test")).is_equal_to(vec![
+            0..4,
+            5..9,
+            10..14,
+        ]);
+        assert_that!(find_ignoring_case("aaaa", "aa")).is_equal_to(vec![0..2, 2..4]);
+        assert_that!(find_ignoring_case("ÉTÉ été", "été")).is_equal_to(vec![0..5, 6..11]);
+        assert_that!(find_ignoring_case("test", "")).is_equal_to(vec![]);
+        assert_that!(find_ignoring_case("te", "test")).is_equal_to(vec![]);
+    }
+
     #[traced_test]
     #[test]
     fn test_bordered_table() {
@@ -886,4 +1733,112 @@ mod tests {
 
         insta::assert_debug_snapshot!(buffer);
     }
+
+    #[traced_test]
+    #[test]
+    fn test_compact_table() {
+        let mut table = create_tests_table(40, Some("My Table"), true);
+        table.navigate(3);
+
+        let rect = Rect::new(0, 0, 80, 12);
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+
+        assert_that!(table.display_height()).is_equal_to(6);
+        insta::assert_debug_snapshot!(buffer);
+
+        table.set_compact(true);
+
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+
+        assert_that!(table.display_height()).is_equal_to(9);
+        insta::assert_debug_snapshot!(buffer);
+
+        // Too small to show anything but mustn't panic either way
+        for is_compact in [false, true] {
+            table.set_compact(is_compact);
+
+            let rect = Rect::new(0, 0, 80, 2);
+            let mut buffer = Buffer::empty(rect);
+            table.render_ref(rect, &mut buffer);
+
+            assert_that!(table.display_height()).is_equal_to(0);
+            table.scroll(ScrollType::HalfPageDown);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_ascii_table() {
+        let mut table = create_tests_table(40, Some("My Table"), true);
+        table.set_ascii(true);
+        let rect = Rect::new(0, 0, 80, 12);
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+
+        assert_that!(buffer[(0, 0)].symbol()).is_equal_to("+");
+        assert_that!(buffer[(0, 5)].symbol()).is_equal_to("|");
+        assert_that!(buffer[(0, 11)].symbol()).is_equal_to("+");
+        assert_that!(buffer[(79, 1)].symbol()).is_equal_to("^");
+        assert_that!(buffer[(79, 10)].symbol()).is_equal_to("v");
+        assert_that!(buffer.content().iter().all(|cell| cell.symbol().is_ascii())).is_true();
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_wrapped_table() {
+        let mut table = create_tests_table(10, Some("My Table"), true);
+        for i in [1, 4] {
+            table.elements[i][1] = Text::from(format!(
+                "a much longer description of test {} that needs wrapping",
+                i
+            ));
+        }
+        table.set_wrap(true);
+        table.navigate(2);
+
+        let rect = Rect::new(0, 0, 100, 16);
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+
+        // The wrapped description of test4 doesn't fit below test3
+        assert_that!(table.display_height()).is_equal_to(4);
+        insta::assert_debug_snapshot!(buffer);
+
+        // The selected row stays in view
+        table.navigate(3);
+        let mut buffer = Buffer::empty(rect);
+        table.render_ref(rect, &mut buffer);
+
+        let rendered = buffer
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect::<String>();
+        assert_that!(rendered).contains("test4");
+        assert_that!(rendered).does_not_contain("test0");
+    }
+
+    #[traced_test]
+    #[test]
+    fn test_wrap_text() {
+        let wrapped = wrap_text(&Text::from("wrap these words"), 10);
+        assert_that!(wrapped.lines.len()).is_equal_to(2);
+        assert_that!(wrapped.lines[0].to_string()).is_equal_to("wrap these ".to_string());
+        assert_that!(wrapped.lines[1].to_string()).is_equal_to("words".to_string());
+
+        let wrapped = wrap_text(&Text::from("abcdefghij klm"), 4);
+        assert_that!(wrapped
+            .lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![
+            "abcd".to_string(),
+            "efgh".to_string(),
+            "ij ".to_string(),
+            "klm".to_string(),
+        ]);
+    }
 }
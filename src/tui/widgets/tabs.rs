@@ -0,0 +1,152 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Tabs as RatatuiTabs, Widget, WidgetRef},
+};
+
+/// A row of tab titles where exactly one tab is active, the owner is responsible for rendering
+/// the view that corresponds to `selected_idx`.
+#[derive(Clone, Debug)]
+pub(crate) struct Tabs {
+    titles: Vec<String>,
+    selected_idx: usize,
+
+    is_focussed: bool,
+}
+
+impl Tabs {
+    pub(crate) fn new(titles: Vec<String>) -> Self {
+        Self {
+            titles,
+            selected_idx: 0,
+
+            is_focussed: false,
+        }
+    }
+
+    pub(crate) fn selected_idx(&self) -> usize {
+        self.selected_idx
+    }
+
+    /// Select the tab at `idx`, returns whether the selected tab changed.
+    pub(crate) fn select(&mut self, idx: usize) -> bool {
+        if idx >= self.titles.len() || idx == self.selected_idx {
+            return false;
+        }
+
+        self.selected_idx = idx;
+
+        true
+    }
+
+    /// Move the selected tab by `count`, wrapping around at either end. Returns whether the
+    /// selected tab changed.
+    pub(crate) fn navigate(&mut self, count: isize) -> bool {
+        if self.titles.is_empty() {
+            return false;
+        }
+
+        let num_tabs = self.titles.len() as isize;
+        let idx = (self.selected_idx as isize + count).rem_euclid(num_tabs) as usize;
+
+        self.select(idx)
+    }
+
+    pub(crate) fn set_focus(&mut self, is_focussed: bool) {
+        self.is_focussed = is_focussed;
+    }
+}
+
+impl WidgetRef for Tabs {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let style = if self.is_focussed {
+            Style::default().fg(Color::White).bg(Color::Black)
+        } else {
+            Style::default().fg(Color::DarkGray).bg(Color::Black)
+        };
+
+        RatatuiTabs::new(
+            self.titles
+                .iter()
+                .enumerate()
+                .map(|(i, title)| Line::from(format!("{} {}", i + 1, title))),
+        )
+        .style(style)
+        .highlight_style(
+            style
+                .fg(Color::Black)
+                .bg(Color::White)
+                .add_modifier(Modifier::BOLD),
+        )
+        .select(self.selected_idx)
+        .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use ratatui::{buffer::Buffer, layout::Rect, widgets::WidgetRef};
+    use tracing_test::traced_test;
+
+    use super::Tabs;
+
+    fn get_tabs() -> Tabs {
+        Tabs::new(vec![
+            "Overview".to_string(),
+            "Steps".to_string(),
+            "Results".to_string(),
+        ])
+    }
+
+    #[traced_test]
+    #[test]
+    fn tabs_new() {
+        let tabs = get_tabs();
+
+        assert_that!(tabs.selected_idx()).is_equal_to(0);
+
+        let rect = Rect::new(0, 0, 40, 1);
+        let mut buffer = Buffer::empty(rect);
+        tabs.render_ref(rect, &mut buffer);
+
+        insta::assert_debug_snapshot!(buffer);
+    }
+
+    #[traced_test]
+    #[test]
+    fn tabs_navigate() {
+        let mut tabs = get_tabs();
+
+        assert_that!(tabs.navigate(1)).is_true();
+        assert_that!(tabs.selected_idx()).is_equal_to(1);
+        assert_that!(tabs.navigate(1)).is_true();
+        assert_that!(tabs.selected_idx()).is_equal_to(2);
+        assert_that!(tabs.navigate(1)).is_true();
+        assert_that!(tabs.selected_idx()).is_equal_to(0);
+        assert_that!(tabs.navigate(-1)).is_true();
+        assert_that!(tabs.selected_idx()).is_equal_to(2);
+        assert_that!(tabs.navigate(3)).is_false();
+        assert_that!(tabs.selected_idx()).is_equal_to(2);
+    }
+
+    #[traced_test]
+    #[test]
+    fn tabs_select() {
+        let mut tabs = get_tabs();
+
+        assert_that!(tabs.select(2)).is_true();
+        assert_that!(tabs.selected_idx()).is_equal_to(2);
+        assert_that!(tabs.select(2)).is_false();
+        assert_that!(tabs.select(3)).is_false();
+        assert_that!(tabs.selected_idx()).is_equal_to(2);
+
+        let rect = Rect::new(0, 0, 40, 1);
+        let mut buffer = Buffer::empty(rect);
+        tabs.render_ref(rect, &mut buffer);
+
+        insta::assert_debug_snapshot!(buffer);
+    }
+}
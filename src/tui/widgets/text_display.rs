@@ -2,15 +2,14 @@ use std::{cell::Cell, cmp};
 
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Rect},
+    layout::Rect,
     style::{Color, Style},
     text::{Line, Text as RatatuiText},
-    widgets::{
-        Block, Borders, Padding, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState,
-        StatefulWidget, WidgetRef,
-    },
+    widgets::{Paragraph, ScrollbarState, StatefulWidget, WidgetRef},
 };
 
+use super::patui_widget::{scrollbar, scrollbar_area, titled_block};
+
 #[derive(Clone, Debug)]
 pub(crate) struct Text {
     text: String,
@@ -30,6 +29,8 @@ pub(crate) struct TextDisplay {
     block_title: Option<String>,
 
     is_focussed: bool,
+    is_compact: bool,
+    is_ascii: bool,
     is_selectable: bool,
     first_row: usize,
     selected_idx: Option<usize>,
@@ -51,6 +52,8 @@ impl TextDisplay {
             block_title,
 
             is_focussed: false,
+            is_compact: false,
+            is_ascii: false,
             is_selectable,
             first_row: 0,
             selected_idx: None,
@@ -67,6 +70,11 @@ impl TextDisplay {
         }
     }
 
+    /// The index of the selected text, see `new_with_text`.
+    pub(crate) fn selected_idx(&self) -> Option<usize> {
+        self.selected_idx.filter(|_| self.is_selectable)
+    }
+
     pub(crate) fn set_unselected(&mut self) {
         self.selected_idx = None;
     }
@@ -157,6 +165,16 @@ impl TextDisplay {
         self.is_focussed = is_focussed;
     }
 
+    /// Compact displays drop the borders and padding around the text, leaving just the title.
+    pub(crate) fn set_compact(&mut self, is_compact: bool) {
+        self.is_compact = is_compact;
+    }
+
+    /// ASCII displays draw their borders and scrollbar without unicode.
+    pub(crate) fn set_ascii(&mut self, is_ascii: bool) {
+        self.is_ascii = is_ascii;
+    }
+
     fn get_selected_idx_range(&self) -> Option<(usize, usize)> {
         let Some(selected_idx) = self.selected_idx else {
             return None;
@@ -182,12 +200,13 @@ impl TextDisplay {
             Style::default().fg(Color::White).bg(Color::Black)
         };
 
-        let elements_display_height = if self.block_title.is_some() {
+        let elements_display_height = match (&self.block_title, self.is_compact) {
             // -4 for block
-            area.height as usize - 4
-        } else {
-            area.height as usize
-        };
+            (Some(_), false) => area.height.saturating_sub(4),
+            // -1 for block title
+            (Some(_), true) => area.height.saturating_sub(1),
+            (None, _) => area.height,
+        } as usize;
         self.num_display_lines.set(elements_display_height);
 
         let mut text = RatatuiText::default();
@@ -212,14 +231,12 @@ impl TextDisplay {
         let paragraph = Paragraph::new(text);
 
         let paragraph = if let Some(block_title) = self.block_title.as_ref() {
-            paragraph.block(
-                Block::new()
-                    .borders(Borders::ALL)
-                    .padding(Padding::symmetric(2, 1))
-                    .title_alignment(Alignment::Center)
-                    .title(block_title.to_string())
-                    .style(style),
-            )
+            paragraph.block(titled_block(
+                block_title.to_string(),
+                style,
+                self.is_compact,
+                self.is_ascii,
+            ))
         } else {
             paragraph
         };
@@ -228,9 +245,7 @@ impl TextDisplay {
     }
 
     fn render_scrollbar(&self, area: Rect, buf: &mut Buffer) {
-        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
-            .begin_symbol(Some("↑"))
-            .end_symbol(Some("↓"));
+        let scrollbar = scrollbar(self.is_ascii);
 
         let num_elements = self.num_elements();
         let display_height = self.num_display_lines();
@@ -251,13 +266,7 @@ impl WidgetRef for TextDisplay {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         self.render_text(area, buf);
         if self.block_title.is_some() {
-            let scrollbar_area = Rect {
-                x: area.x + area.width - 1,
-                y: area.y + 1,
-                width: 1,
-                height: area.height - 2,
-            };
-            self.render_scrollbar(scrollbar_area, buf);
+            self.render_scrollbar(scrollbar_area(area, self.is_compact), buf);
         }
     }
 }
@@ -335,6 +344,38 @@ mod tests {
         assert_that!(text_display.num_display_lines.get()).is_equal_to(6);
     }
 
+    #[traced_test]
+    #[test]
+    fn compact_text_display() {
+        let mut text_display = get_big_text_display();
+
+        let rect = Rect::new(0, 0, 50, 10);
+        let mut buffer = Buffer::empty(rect);
+        text_display.render_ref(rect, &mut buffer);
+
+        assert_that!(text_display.num_display_lines()).is_equal_to(6);
+        insta::assert_debug_snapshot!(buffer);
+
+        text_display.set_compact(true);
+
+        let mut buffer = Buffer::empty(rect);
+        text_display.render_ref(rect, &mut buffer);
+
+        assert_that!(text_display.num_display_lines()).is_equal_to(9);
+        insta::assert_debug_snapshot!(buffer);
+
+        // Too small to show any text but mustn't panic either way
+        for is_compact in [false, true] {
+            text_display.set_compact(is_compact);
+
+            let rect = Rect::new(0, 0, 50, 1);
+            let mut buffer = Buffer::empty(rect);
+            text_display.render_ref(rect, &mut buffer);
+
+            assert_that!(text_display.num_display_lines()).is_equal_to(0);
+        }
+    }
+
     #[traced_test]
     #[test]
     fn select_text() {
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::{cell::Cell, fmt::Debug};
 
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
@@ -11,6 +11,8 @@ use ratatui::{
 
 type ValidateFn = Box<dyn Fn(&TextArea) -> bool>;
 
+const HIGHLIGHT_STYLE: Style = Style::new().fg(Color::Black).bg(Color::Yellow);
+
 pub(crate) struct TextArea<'a> {
     inner: tui_textarea::TextArea<'a>,
     name: String,
@@ -19,6 +21,11 @@ pub(crate) struct TextArea<'a> {
     selected: bool,
     validate: Vec<ValidateFn>,
     valid_entries: Vec<String>,
+    /// Positions in the text to pick out, as (row, column).
+    highlights: Vec<(usize, usize)>,
+    /// Where the text was scrolled to when last rendered, worked out as `tui_textarea` does so
+    /// highlights land on the right cells.
+    scroll_top: Cell<(u16, u16)>,
 }
 
 impl Debug for TextArea<'_> {
@@ -30,6 +37,7 @@ impl Debug for TextArea<'_> {
             .field("is_valid", &self.is_valid)
             .field("selected", &self.selected)
             .field("valid_entries", &self.valid_entries)
+            .field("highlights", &self.highlights)
             .finish()
     }
 }
@@ -55,6 +63,8 @@ impl<'a> TextArea<'a> {
             selected: false,
             validate,
             valid_entries: vec![],
+            highlights: vec![],
+            scroll_top: Cell::new((0, 0)),
         }
     }
 
@@ -67,10 +77,19 @@ impl<'a> TextArea<'a> {
     //     }
     // }
 
-    pub(crate) fn get_text(&'a self) -> String {
+    pub(crate) fn get_text(&self) -> String {
         self.inner.lines().join("\n")
     }
 
+    /// The cursor as (row, column) in the text.
+    pub(crate) fn cursor(&self) -> (usize, usize) {
+        self.inner.cursor()
+    }
+
+    pub(crate) fn set_highlights(&mut self, highlights: Vec<(usize, usize)>) {
+        self.highlights = highlights;
+    }
+
     pub(crate) fn height(&'a self) -> u16 {
         self.height
     }
@@ -134,7 +153,7 @@ impl<'a> TextArea<'a> {
         self.setup_widget();
     }
 
-    fn set_text(&mut self, text: String) {
+    pub(crate) fn set_text(&mut self, text: String) {
         self.inner.select_all();
         self.inner.delete_line_by_head();
         self.inner.set_yank_text(text);
@@ -173,5 +192,35 @@ impl<'a> TextArea<'a> {
 impl WidgetRef for TextArea<'_> {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
         self.inner.render(area, buf);
+
+        let text_area = self.inner.block().map_or(area, |block| block.inner(area));
+        let (cursor_row, cursor_col) = self.inner.cursor();
+        let (top_row, top_col) = self.scroll_top.get();
+        let top_row = next_scroll_top(top_row, cursor_row as u16, text_area.height);
+        let top_col = next_scroll_top(top_col, cursor_col as u16, text_area.width);
+        self.scroll_top.set((top_row, top_col));
+
+        for &(row, col) in &self.highlights {
+            let (Some(y), Some(x)) = (
+                (row as u16).checked_sub(top_row),
+                (col as u16).checked_sub(top_col),
+            ) else {
+                continue;
+            };
+            if y < text_area.height && x < text_area.width {
+                buf[(text_area.x + x, text_area.y + y)].set_style(HIGHLIGHT_STYLE);
+            }
+        }
+    }
+}
+
+/// Scroll only as far as needed to keep the cursor in view, as `tui_textarea` does.
+fn next_scroll_top(prev_top: u16, cursor: u16, len: u16) -> u16 {
+    if cursor < prev_top {
+        cursor
+    } else if prev_top + len <= cursor {
+        cursor + 1 - len
+    } else {
+        prev_top
     }
 }
@@ -4,27 +4,31 @@
 pub(crate) mod expr;
 pub(crate) mod steps;
 
-use std::io::Read;
+use std::{fmt, io::Read, ops::Range, time::Duration};
 
 use bytes::Bytes;
+use convert_case::{Case, Casing};
 use edit::edit;
-use eyre::Result;
+use eyre::{eyre, Result};
 use rusqlite::{
     types::{ToSqlOutput, Value},
     ToSql,
 };
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    db::{PatuiInstance, PatuiRun, PatuiTestDb, PatuiTestId},
+    db::{PatuiInstance, PatuiRun, PatuiRunOutput, PatuiTestDb, PatuiTestId},
     utils::{get_current_time_string, get_current_timestamp},
 };
 
 pub(crate) use expr::PatuiExpr;
+use expr::{get_step_refs, lint_expr};
 use steps::PatuiStepEditable;
 pub(crate) use steps::{
-    PatuiStep, PatuiStepAssertion, PatuiStepData, PatuiStepDataFlavour, PatuiStepDetails,
-    PatuiStepRead, PatuiStepSender, PatuiStepTransformStream, PatuiStepWrite,
+    PatuiStep, PatuiStepAssertion, PatuiStepAssertionSample, PatuiStepData, PatuiStepDataFlavour,
+    PatuiStepDetails, PatuiStepFileSink, PatuiStepFileSinkFormat, PatuiStepRead, PatuiStepSender,
+    PatuiStepTransform, PatuiStepTransformStream, PatuiStepWrite, DEFAULT_CHANNEL,
 };
 
 #[cfg(test)]
@@ -40,10 +44,18 @@ pub mod ptplugin {
 ///
 /// We often label things as optional when they have defaults for the
 /// `Editable` types.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiTestEditable {
     pub(crate) name: String,
     pub(crate) description: Option<String>,
+    /// Fail the run if any step that publishes output finishes without having published
+    /// anything.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) require_all_steps_produce_output: bool,
+    /// Abandon runs of the test that take longer than this many milliseconds unless the run is
+    /// given a timeout of its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) default_timeout_ms: Option<u64>,
     pub(crate) steps: Option<Vec<PatuiStepEditable>>,
 }
 
@@ -52,6 +64,8 @@ impl From<&PatuiTestDb> for PatuiTestEditable {
         PatuiTestEditable {
             name: test.name.clone(),
             description: Some(test.description.clone()),
+            require_all_steps_produce_output: test.require_all_steps_produce_output,
+            default_timeout_ms: test.default_timeout_ms,
             steps: Some(test.steps.iter().map(|x| x.into()).collect()),
         }
     }
@@ -64,6 +78,8 @@ pub(crate) struct PatuiTest {
     pub(crate) id: PatuiTestId,
     pub(crate) name: String,
     pub(crate) description: String,
+    pub(crate) require_all_steps_produce_output: bool,
+    pub(crate) default_timeout_ms: Option<u64>,
     pub(crate) steps: Vec<PatuiStep>,
 }
 
@@ -73,6 +89,8 @@ impl PatuiTest {
             id: test_id,
             name: details.name,
             description: details.description,
+            require_all_steps_produce_output: details.require_all_steps_produce_output,
+            default_timeout_ms: details.default_timeout_ms,
             steps: details.steps,
         }
     }
@@ -84,6 +102,8 @@ impl From<PatuiTestDb> for PatuiTest {
             id: value.id,
             name: value.name,
             description: value.description,
+            require_all_steps_produce_output: value.require_all_steps_produce_output,
+            default_timeout_ms: value.default_timeout_ms,
             steps: value.steps,
         }
     }
@@ -95,6 +115,8 @@ impl From<&PatuiTestDb> for PatuiTest {
             id: value.id.clone(),
             name: value.name.clone(),
             description: value.description.clone(),
+            require_all_steps_produce_output: value.require_all_steps_produce_output,
+            default_timeout_ms: value.default_timeout_ms,
             steps: value.steps.clone(),
         }
     }
@@ -104,6 +126,8 @@ impl From<&PatuiTestDb> for PatuiTest {
 pub(crate) struct PatuiTestDetails {
     pub(crate) name: String,
     pub(crate) description: String,
+    pub(crate) require_all_steps_produce_output: bool,
+    pub(crate) default_timeout_ms: Option<u64>,
     pub(crate) creation_date: String,
     pub(crate) steps: Vec<PatuiStep>,
 }
@@ -115,11 +139,14 @@ impl Default for PatuiTestDetails {
         PatuiTestDetails {
             name: "Default".to_string(),
             description: "Default template".to_string(),
+            require_all_steps_produce_output: false,
+            default_timeout_ms: None,
             creation_date: now.clone(),
             steps: vec![PatuiStep {
                 name: "DefaultProcess".to_string(),
                 when: None,
                 depends_on: vec![],
+                timeout_ms: None,
                 details: PatuiStepDetails::Read(PatuiStepRead {
                     r#in: "\"dir/file.txt\"".try_into().unwrap(),
                 }),
@@ -130,13 +157,23 @@ impl Default for PatuiTestDetails {
 
 impl PatuiTestDetails {
     pub(crate) fn from_yaml_str(yaml: &str) -> Result<Self> {
-        let yaml_test = serde_yaml::from_str::<PatuiTestEditable>(yaml)?;
+        Self::from_editable(serde_yaml::from_str::<PatuiTestEditable>(yaml)?)
+    }
+
+    /// As `from_yaml_str` but with step kinds given as JSON objects, e.g. `{"Read": {...}}`,
+    /// rather than YAML tags.
+    pub(crate) fn from_json_str(json: &str) -> Result<Self> {
+        Self::from_editable(serde_json::from_str::<PatuiTestEditable>(json)?)
+    }
 
+    fn from_editable(yaml_test: PatuiTestEditable) -> Result<Self> {
         let now = get_current_time_string();
 
         let test = PatuiTestDetails {
             name: yaml_test.name,
-            description: yaml_test.description.unwrap_or_else(|| "".to_string()),
+            description: yaml_test.description.unwrap_or_default(),
+            require_all_steps_produce_output: yaml_test.require_all_steps_produce_output,
+            default_timeout_ms: yaml_test.default_timeout_ms,
             creation_date: now,
             steps: yaml_test
                 .steps
@@ -144,9 +181,122 @@ impl PatuiTestDetails {
                 .unwrap_or_else(|| Ok(Vec::new()))?,
         };
 
+        test.validate()?;
+
         Ok(test)
     }
 
+    /// Check that every `steps.<step>.<channel>` reference is to a step in this test that
+    /// publishes that channel.
+    pub(crate) fn validate(&self) -> Result<()> {
+        for step in self.steps.iter() {
+            for expr in step.details.exprs() {
+                for step_ref in get_step_refs(expr)? {
+                    let publishers = self
+                        .steps
+                        .iter()
+                        .filter(|other| other.name == step_ref.step)
+                        .collect::<Vec<_>>();
+
+                    if publishers.is_empty() {
+                        return Err(eyre!(
+                            "Step `{}` references `{}` but there's no step `{}`",
+                            step.name,
+                            step_ref.expr,
+                            step_ref.step,
+                        ));
+                    }
+
+                    for publisher in publishers {
+                        let Some(channels) = publisher.details.channels() else {
+                            continue;
+                        };
+                        if channels.contains(&step_ref.channel) {
+                            continue;
+                        }
+
+                        let kind: &'static str = (&publisher.details).into();
+                        return Err(if channels.is_empty() {
+                            eyre!(
+                                "Step `{}` references `{}` but the {} step `{}` doesn't publish any channels",
+                                step.name,
+                                step_ref.expr,
+                                kind.to_case(Case::Pascal),
+                                step_ref.step,
+                            )
+                        } else {
+                            eyre!(
+                                "Step `{}` references `{}` but `{}` only publishes: [{}]",
+                                step.name,
+                                step_ref.expr,
+                                step_ref.step,
+                                channels.join(", "),
+                            )
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look for likely mistakes in a test that is otherwise valid, see `PatuiLint`.
+    pub(crate) fn lint(&self) -> Result<Vec<PatuiLint>> {
+        let mut lints = vec![];
+
+        for step in self.steps.iter() {
+            if let PatuiStepDetails::Assertion(assertion) = &step.details {
+                if get_step_refs(&assertion.expr)?.is_empty() {
+                    lints.push(PatuiLint {
+                        step: step.name.clone(),
+                        message: "assertion doesn't reference any `steps` so is always the same"
+                            .to_string(),
+                        expr: Some(assertion.expr.to_string()),
+                        span: Some(0..assertion.expr.raw.len()),
+                    });
+                }
+            }
+
+            for expr in step.details.exprs() {
+                for lint in lint_expr(expr)? {
+                    lints.push(PatuiLint {
+                        step: step.name.clone(),
+                        message: lint.message,
+                        expr: Some(expr.to_string()),
+                        span: Some(lint.span),
+                    });
+                }
+            }
+
+            if step
+                .details
+                .channels()
+                .is_some_and(|channels| channels.is_empty())
+            {
+                continue;
+            }
+            let mut subscribed = false;
+            for other in self.steps.iter() {
+                for expr in other.details.exprs() {
+                    subscribed |= get_step_refs(expr)?
+                        .iter()
+                        .any(|step_ref| step_ref.step == step.name);
+                }
+            }
+            if !subscribed {
+                lints.push(PatuiLint {
+                    step: step.name.clone(),
+                    message: "nothing subscribes to the output of this step".to_string(),
+                    expr: None,
+                    span: None,
+                });
+            }
+        }
+
+        Ok(lints)
+    }
+
     pub(crate) fn edit_yaml(mut yaml_str: String) -> Result<Self> {
         loop {
             yaml_str = edit(&yaml_str)?;
@@ -167,6 +317,8 @@ impl PatuiTestDetails {
         let yaml_test = PatuiTestEditable {
             name: self.name.clone(),
             description: Some(self.description.clone()),
+            require_all_steps_produce_output: self.require_all_steps_produce_output,
+            default_timeout_ms: self.default_timeout_ms,
             steps: Some(self.steps.iter().map(|step| step.into()).collect()),
         };
 
@@ -196,10 +348,36 @@ impl PatuiTestDetails {
     }
 }
 
+/// A warning about something in a test that's probably a mistake but isn't invalid, `span` is
+/// where in `expr` the mistake is when it's down to a single expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PatuiLint {
+    pub(crate) step: String,
+    pub(crate) message: String,
+    pub(crate) expr: Option<String>,
+    pub(crate) span: Option<Range<usize>>,
+}
+
+impl fmt::Display for PatuiLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "step `{}`: {}", self.step, self.message)?;
+        if let (Some(expr), Some(span)) = (&self.expr, &self.span) {
+            write!(f, " at {}..{} in `{}`", span.start, span.end, expr)?;
+        }
+
+        Ok(())
+    }
+}
+
 // Test runs
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub(crate) enum PatuiRunError {}
+pub(crate) enum PatuiRunError {
+    /// The whole run took longer than it was allowed.
+    Timeout(Duration),
+    /// A step of the run failed with the given error.
+    StepFailed(String),
+}
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) enum PatuiRunStatus {
@@ -220,9 +398,48 @@ impl ToSql for PatuiRunStatus {
 
 // Result details
 
+/// How a step ended, see `PatuiEventKind::StepFinished`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) enum PatuiStepOutcome {
+    Passed,
+    Failed(String),
+}
+
+impl From<PatuiStepOutcome> for PatuiRunStatus {
+    fn from(value: PatuiStepOutcome) -> Self {
+        match value {
+            PatuiStepOutcome::Passed => PatuiRunStatus::Passed,
+            PatuiStepOutcome::Failed(e) => PatuiRunStatus::Error(PatuiRunError::StepFailed(e)),
+        }
+    }
+}
+
+/// How much a diagnostic reported by a plugin matters, see `PatuiEventKind::Diagnostic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub(crate) enum PatuiDiagnosticSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) enum PatuiEventKind {
     Bytes(Bytes),
+    /// Sent by the runner as a step is started.
+    StepStarted {
+        name: String,
+    },
+    /// Sent by the runner once a step has finished, whether it passed or not.
+    StepFinished {
+        name: String,
+        outcome: PatuiStepOutcome,
+    },
+    /// Something a plugin reported about how it's getting on.
+    Diagnostic {
+        severity: PatuiDiagnosticSeverity,
+        summary: String,
+        detail: String,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -247,19 +464,96 @@ impl PatuiEvent {
         PatuiEvent::new(PatuiEventKind::Bytes(value), step_name)
     }
 
-    #[cfg(test)]
+    pub(crate) fn step_started(step_name: String) -> Self {
+        PatuiEvent::new(
+            PatuiEventKind::StepStarted {
+                name: step_name.clone(),
+            },
+            step_name,
+        )
+    }
+
+    pub(crate) fn step_finished(step_name: String, outcome: PatuiStepOutcome) -> Self {
+        PatuiEvent::new(
+            PatuiEventKind::StepFinished {
+                name: step_name.clone(),
+                outcome,
+            },
+            step_name,
+        )
+    }
+
+    pub(crate) fn diagnostic(
+        step_name: String,
+        severity: PatuiDiagnosticSeverity,
+        summary: String,
+        detail: String,
+    ) -> Self {
+        PatuiEvent::new(
+            PatuiEventKind::Diagnostic {
+                severity,
+                summary,
+                detail,
+            },
+            step_name,
+        )
+    }
+
     pub(crate) fn value(&self) -> &PatuiEventKind {
         &self.value
     }
+
+    pub(crate) fn step_name(&self) -> &str {
+        &self.step_name
+    }
+
+    /// Whether this is a step finishing without passing.
+    pub(crate) fn is_failure(&self) -> bool {
+        matches!(
+            self.value,
+            PatuiEventKind::StepFinished {
+                outcome: PatuiStepOutcome::Failed(_),
+                ..
+            }
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct PatuiRunStepResult {
-    status: PatuiRunStatus,
+    pub(crate) status: PatuiRunStatus,
+    /// How a failed assertion evaluated, only kept when asked for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) explanation: Option<PatuiEvalTrace>,
+}
+
+/// How far an expression could be evaluated from what had been received.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PatuiEvalState {
+    Known,
+    Predictable,
+    Unknown,
+    Error,
+}
+
+/// What an expression and each of the sub-expressions it's made of evaluated to.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiEvalTrace {
+    pub(crate) expr: String,
+    pub(crate) state: PatuiEvalState,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) value: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) children: Vec<PatuiEvalTrace>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct PatuiRunStep {
+    #[serde(default)]
+    pub(crate) name: String,
     pub(crate) start_time: String,
     pub(crate) end_time: Option<String>,
     pub(crate) result: PatuiRunStepResult,
@@ -267,9 +561,13 @@ pub(crate) struct PatuiRunStep {
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct PatuiRunStepDisplay {
+    pub(crate) name: String,
     pub(crate) start_time: String,
     pub(crate) end_time: Option<String>,
     pub(crate) result: PatuiRunStepResult,
+    /// What the step output, when the run captured outputs and the step had one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) output: Option<PatuiRunOutput>,
 }
 
 impl TryFrom<PatuiRunStep> for PatuiRunStepDisplay {
@@ -277,9 +575,11 @@ impl TryFrom<PatuiRunStep> for PatuiRunStepDisplay {
 
     fn try_from(value: PatuiRunStep) -> std::result::Result<Self, Self::Error> {
         Ok(PatuiRunStepDisplay {
+            name: value.name,
             start_time: value.start_time,
             end_time: value.end_time,
             result: value.result,
+            output: None,
         })
     }
 }
@@ -313,6 +613,49 @@ impl TryFrom<PatuiRun> for PatuiRunDisplay {
     }
 }
 
+/// What a plugin reports about itself, along with where it was found.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiPluginDisplay {
+    pub(crate) path: String,
+    pub(crate) name: String,
+    pub(crate) description: String,
+    pub(crate) version: String,
+    pub(crate) r#type: String,
+    pub(crate) subscriptions: Vec<String>,
+}
+
+/// A kind of step a test can use, `plugin` is set when it's provided by a plugin rather than
+/// built in.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiStepKindDisplay {
+    pub(crate) kind: String,
+    pub(crate) fields: Vec<String>,
+    pub(crate) plugin: Option<PatuiPluginDisplay>,
+}
+
+impl From<PatuiPluginDisplay> for PatuiStepKindDisplay {
+    fn from(plugin: PatuiPluginDisplay) -> Self {
+        Self {
+            kind: plugin.r#type.clone(),
+            fields: vec![],
+            plugin: Some(plugin),
+        }
+    }
+}
+
+impl PatuiPluginDisplay {
+    pub(crate) fn new(path: String, step_runner: ptplugin::StepRunner) -> Self {
+        Self {
+            path,
+            name: step_runner.name,
+            description: step_runner.description,
+            version: step_runner.version,
+            r#type: step_runner.r#type,
+            subscriptions: step_runner.subscriptions,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -365,10 +708,218 @@ mod tests {
         assert_that!(details.steps[1].details).is_equal_to(PatuiStepDetails::Assertion(
             PatuiStepAssertion {
                 expr: "foo == \"bar\"".try_into().unwrap(),
+                sample: None,
             },
         ));
     }
 
+    #[test]
+    fn test_from_yaml_str_with_empty_when() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            steps:
+              - name: foo
+                when: "  "
+                details: !Read
+                  in: "\"dir/file.txt\""
+              - name: bar
+                when: ""
+                details: !Assertion
+                  expr: foo == "bar"
+            "#,
+        );
+
+        let details = PatuiTestDetails::from_yaml_str(&yaml).unwrap();
+
+        assert_that!(details.steps).has_length(2);
+        assert_that!(details.steps[0].when).is_none();
+        assert_that!(details.steps[1].when).is_none();
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_sampled_assertion() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            steps:
+              - name: every_n
+                details: !Assertion
+                  expr: foo == "bar"
+                  sample: !every_n 100
+              - name: every_ms
+                details: !Assertion
+                  expr: foo == "bar"
+                  sample: !every_ms 250
+            "#,
+        );
+
+        let details = PatuiTestDetails::from_yaml_str(&yaml).unwrap();
+
+        assert_that!(details
+            .steps
+            .iter()
+            .map(|step| match &step.details {
+                PatuiStepDetails::Assertion(assertion) => assertion.sample.clone(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![
+            Some(PatuiStepAssertionSample::EveryN(100)),
+            Some(PatuiStepAssertionSample::EveryMs(250)),
+        ]);
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_default_timeout_ms() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            default_timeout_ms: 30000
+            steps: []
+            "#,
+        );
+
+        let details = PatuiTestDetails::from_yaml_str(&yaml).unwrap();
+        assert_that!(details.default_timeout_ms).is_equal_to(Some(30000));
+        assert_that!(details.to_editable_yaml_string().unwrap())
+            .contains("default_timeout_ms: 30000");
+
+        let details = PatuiTestDetails::from_yaml_str("name: test name\nsteps: []\n").unwrap();
+        assert_that!(details.default_timeout_ms).is_none();
+        assert_that!(details.to_editable_yaml_string().unwrap())
+            .does_not_contain("default_timeout_ms");
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_step_timeout_ms() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            steps:
+              - name: foo
+                timeout_ms: 500
+                details: !Read
+                  in: "\"dir/file.txt\""
+              - name: bar
+                details: !Read
+                  in: "\"dir/file.txt\""
+            "#,
+        );
+
+        let details = PatuiTestDetails::from_yaml_str(&yaml).unwrap();
+        assert_that!(details.steps[0].timeout_ms).is_equal_to(Some(500));
+        assert_that!(details.steps[1].timeout_ms).is_none();
+
+        let yaml = details.to_editable_yaml_string().unwrap();
+        assert_that!(yaml).contains("timeout_ms: 500");
+        assert_that!(yaml.matches("timeout_ms").count()).is_equal_to(1);
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_require_all_steps_produce_output() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            require_all_steps_produce_output: true
+            steps: []
+            "#,
+        );
+
+        let details = PatuiTestDetails::from_yaml_str(&yaml).unwrap();
+        assert_that!(details.require_all_steps_produce_output).is_true();
+        assert_that!(details.to_editable_yaml_string().unwrap())
+            .contains("require_all_steps_produce_output: true");
+
+        let details = PatuiTestDetails::from_yaml_str("name: test name").unwrap();
+        assert_that!(details.require_all_steps_produce_output).is_false();
+        assert_that!(details.to_editable_yaml_string().unwrap())
+            .does_not_contain("require_all_steps_produce_output");
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_empty_expr_errors() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            steps:
+              - name: bar
+                details: !Assertion
+                  expr: "  "
+            "#,
+        );
+
+        let test = PatuiTestDetails::from_yaml_str(&yaml);
+
+        assert_that!(test).is_err();
+        assert_that!(test.unwrap_err().to_string()).contains("Empty expression");
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_publisher_refs() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            steps:
+              - name: foo
+                details: !Read
+                  in: "\"dir/file.txt\""
+              - name: plugin
+                details: !Plugin
+                  path: ./plugin
+                  config: {}
+                  in: {}
+              - name: bar
+                details: !Assertion
+                  expr: steps.foo.out[0] == steps.plugin.anything[0]
+            "#,
+        );
+
+        let test = PatuiTestDetails::from_yaml_str(&yaml);
+
+        assert_that!(test).is_ok();
+    }
+
+    #[test]
+    fn test_from_yaml_str_with_bad_publisher_refs_errors() {
+        for (expr, expected_err) in [
+            (
+                "steps.check.out.len() == 0",
+                "Step `bar` references `steps.check.out` but the Assertion step `check` doesn't publish any channels",
+            ),
+            (
+                "steps.foo.stdout.len() == 0",
+                "Step `bar` references `steps.foo.stdout` but `foo` only publishes: [out]",
+            ),
+            (
+                "steps.missing.out.len() == 0",
+                "Step `bar` references `steps.missing.out` but there's no step `missing`",
+            ),
+        ] {
+            let yaml = dedent(&format!(
+                r#"
+                name: test name
+                steps:
+                  - name: foo
+                    details: !Read
+                      in: "\"dir/file.txt\""
+                  - name: check
+                    details: !Assertion
+                      expr: "true"
+                  - name: bar
+                    details: !Assertion
+                      expr: {}
+                "#,
+                expr
+            ));
+
+            let test = PatuiTestDetails::from_yaml_str(&yaml);
+
+            assert_that!(test).is_err();
+            assert_that!(test.unwrap_err().to_string()).is_equal_to(expected_err.to_string());
+        }
+    }
+
     #[test]
     fn test_from_bad_yaml_str_errors() {
         let yaml = dedent(
@@ -397,4 +948,41 @@ mod tests {
         assert_that!(details.name).is_equal_to("streaming_process".to_string());
         assert_that!(details.steps).has_length(9);
     }
+
+    #[test]
+    fn test_lint() {
+        let yaml = dedent(
+            r#"
+            name: test name
+            steps:
+              - name: foo
+                details: !Read
+                  in: "\"dir/file.txt\""
+              - name: unused
+                details: !Read
+                  in: "\"dir/other.txt\""
+              - name: constant
+                details: !Assertion
+                  expr: "\"1\" == 1"
+              - name: check
+                details: !Assertion
+                  expr: steps.foo.out == [1, 2][2]
+            "#,
+        );
+
+        let lints = PatuiTestDetails::from_yaml_str(&yaml)
+            .unwrap()
+            .lint()
+            .unwrap()
+            .into_iter()
+            .map(|lint| lint.to_string())
+            .collect::<Vec<_>>();
+
+        assert_that!(lints).is_equal_to(vec![
+            "step `unused`: nothing subscribes to the output of this step".to_string(),
+            "step `constant`: assertion doesn't reference any `steps` so is always the same at 0..8 in `\"1\" == 1`".to_string(),
+            "step `constant`: `\"1\" == 1` compares a string with a number so never holds at 0..8 in `\"1\" == 1`".to_string(),
+            "step `check`: `[1, 2][2]` is out of range, `[1, 2]` only has 2 items at 17..26 in `steps.foo.out == [1, 2][2]`".to_string(),
+        ]);
+    }
 }
@@ -1,8 +1,13 @@
 pub(crate) mod ast;
+mod error;
 mod lexer;
+mod lint;
 mod parser;
+mod printer;
 mod query;
 mod visitor;
 
 pub(crate) use ast::PatuiExpr;
-pub(crate) use query::get_all_idents;
+pub(crate) use lexer::matching_bracket;
+pub(crate) use lint::lint_expr;
+pub(crate) use query::{get_step_refs, StepRef};
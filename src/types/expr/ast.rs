@@ -34,6 +34,17 @@ pub(crate) enum LitKind {
     Token(String),
 }
 
+/// The value of an integer literal as lexed, i.e. decimal or lowercased `0x` hex or `0b` binary.
+pub(crate) fn integer_value(int: &str) -> Option<i128> {
+    if let Some(hex) = int.strip_prefix("0x") {
+        i128::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = int.strip_prefix("0b") {
+        i128::from_str_radix(bin, 2).ok()
+    } else {
+        int.parse().ok()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub(crate) struct Lit {
     pub(crate) kind: LitKind,
@@ -76,7 +87,9 @@ pub(crate) enum BinOp {
     LessThanEqual,
     GreaterThan,
     GreaterThanEqual,
+    /// `lhs in rhs`
     Contains,
+    /// `lhs not in rhs`
     NotContains,
 }
 
@@ -92,6 +105,8 @@ pub(crate) enum ExprKind {
     Call(P<PatuiExpr>, Vec<P<PatuiExpr>>),
     /// Index operation (e.g., `foo[2]`).
     Index(P<PatuiExpr>, P<PatuiExpr>),
+    /// Slice operation (e.g., `foo[2:5]`), either end can be left out.
+    Slice(P<PatuiExpr>, Option<P<PatuiExpr>>, Option<P<PatuiExpr>>),
     /// If: expr2 if expr1 else expr3
     If(P<PatuiExpr>, P<PatuiExpr>, P<PatuiExpr>),
     /// List: [expr1, expr2, ...]
@@ -106,17 +121,26 @@ pub(crate) enum ExprKind {
     BinOp(BinOp, P<PatuiExpr>, P<PatuiExpr>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
 pub(crate) struct PatuiExpr {
     pub(crate) raw: String,
     pub(crate) kind: ExprKind,
 }
 
+/// What's read back of a serialized `PatuiExpr`, `raw` is the source of truth and the `kind`
+/// stored alongside it is ignored. It's parsed again from `raw` instead, as it may have been
+/// stored by an older parser.
+#[derive(Deserialize)]
+#[serde(rename = "PatuiExpr")]
+struct PatuiExprStored {
+    raw: String,
+}
+
 impl PatuiExpr {
     // Oh so naive right now, need to beef this up to be a full parser at some point but this
     // suffices for our basic use cases right now.
     fn try_from_str(value: &str) -> Result<Self> {
-        parser::parse(value)
+        Ok(parser::parse(value)?)
     }
 
     pub(crate) fn kind(&self) -> &ExprKind {
@@ -144,9 +168,28 @@ impl PartialOrd for PatuiExpr {
     }
 }
 
+impl<'de> Deserialize<'de> for PatuiExpr {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let stored = PatuiExprStored::deserialize(deserializer)?;
+
+        PatuiExpr::try_from_str(&stored.raw).map_err(|e| {
+            serde::de::Error::custom(format!("invalid expression `{}`: {}", stored.raw, e))
+        })
+    }
+}
+
 impl fmt::Display for PatuiExpr {
+    /// As written, or when there's no source, e.g. an expression built up in code, written out
+    /// from the AST.
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.write_str(&self.raw)
+        if self.raw.is_empty() {
+            f.write_str(&self.to_canonical())
+        } else {
+            f.write_str(&self.raw)
+        }
     }
 }
 
@@ -195,6 +238,7 @@ mod tests {
     use tracing_test::traced_test;
 
     use super::*;
+    use crate::types::expr::error::ParseError;
 
     #[traced_test]
     #[test]
@@ -245,6 +289,15 @@ mod tests {
                     }),
                 },
             ),
+            (
+                r#""say \"hi\"\n""#,
+                PatuiExpr {
+                    raw: r#""say \"hi\"\n""#.to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Str("say \"hi\"\n".to_string()),
+                    }),
+                },
+            ),
             (
                 "b\"hello\"",
                 PatuiExpr {
@@ -272,24 +325,24 @@ mod tests {
                     }),
                 },
             ),
-            // (
-            //     "b[0x6c]",
-            //     PatuiExpr {
-            //         raw: "b[0x6c]".to_string(),
-            //         kind: ExprKind::Lit(Lit {
-            //         kind: LitKind::Bytes(Bytes::from("l")),
-            //     }),
-            //     },
-            // ),
-            // (
-            //     "b[0x6C]",
-            //     PatuiExpr {
-            //     raw: "b[0x6C]".to_string(),
-            //      kind: ExprKind::Lit(Lit {
-            //         kind: LitKind::Bytes(Bytes::from("l")),
-            //     }),
-            //     },
-            // ),
+            (
+                "b[0x6c]",
+                PatuiExpr {
+                    raw: "b[0x6c]".to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Bytes(Bytes::from("l")),
+                    }),
+                },
+            ),
+            (
+                "b[0x6C, 0b1101100, 255]",
+                PatuiExpr {
+                    raw: "b[0x6C, 0b1101100, 255]".to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Bytes(Bytes::from(&b"ll\xff"[..])),
+                    }),
+                },
+            ),
             (
                 "b['o']",
                 PatuiExpr {
@@ -317,34 +370,42 @@ mod tests {
                     }),
                 },
             ),
-            // (
-            //     "b[104, 0x65, 0x6c, 0x6C, 'o']",
-            //     PatuiExpr {
-            //     raw: "b[104, 0x65, 0x6c, 0x6C, 'o']".to_string(),
-            //      kind: ExprKind::Lit(Lit {
-            //         kind: LitKind::Bytes(Bytes::from("hello")),
-            //     }),
-            //     },
-            // ),
-            // (
-            //     "b[104, 0x65, 0x6c, 0x6C, 'o',]",
-            //     PatuiExpr {
-            //     raw: //     "b[104, 0x65, 0x6c, 0x6C, 'o',]".to_string(),
-            //      kind: //     ExprKind::Lit(Lit {
-            //         kind: LitKind::Bytes(Bytes::from("hello")),
-            //     }),
-            //     },
-            // ),
-            // (
-            //     "b[      104    , 0x65      , 0x6c  , 0x6C   , 'o'  , ]",
-            //     PatuiExpr {
-            //     raw: //     "b[      104    , 0x65      , 0x6c  , 0x6C   , 'o'  ,
-            //     ]".to_string(),
-            //      kind: //     ExprKind::Lit(Lit {
-            //         kind: LitKind::Bytes(Bytes::from("hello")),
-            //     }),
-            //     },
-            // ),
+            (
+                "b[104, 0x65, 0x6c, 0x6C, 'o']",
+                PatuiExpr {
+                    raw: "b[104, 0x65, 0x6c, 0x6C, 'o']".to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Bytes(Bytes::from("hello")),
+                    }),
+                },
+            ),
+            (
+                "b[104, 0x65, 0x6c, 0x6C, 'o',]",
+                PatuiExpr {
+                    raw: "b[104, 0x65, 0x6c, 0x6C, 'o',]".to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Bytes(Bytes::from("hello")),
+                    }),
+                },
+            ),
+            (
+                "b[      104    , 0x65      , 0x6c  , 0x6C   , 'o'  , ]",
+                PatuiExpr {
+                    raw: "b[      104    , 0x65      , 0x6c  , 0x6C   , 'o'  , ]".to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Bytes(Bytes::from("hello")),
+                    }),
+                },
+            ),
+            (
+                "b[0b00000001]",
+                PatuiExpr {
+                    raw: "b[0b00000001]".to_string(),
+                    kind: ExprKind::Lit(Lit {
+                        kind: LitKind::Bytes(Bytes::from(&b"\x01"[..])),
+                    }),
+                },
+            ),
         ] {
             let res = PatuiExpr::try_from(*expr_string);
             assert_that!(res).is_ok();
@@ -513,21 +574,308 @@ mod tests {
         }
     }
 
+    fn parse_err(expr_string: &str) -> ParseError {
+        let res = PatuiExpr::try_from(expr_string);
+        assert_that!(res).is_err();
+        res.unwrap_err().downcast::<ParseError>().unwrap()
+    }
+
     #[traced_test]
     #[test]
     fn bad_lits() {
-        // TODO: Proper error reporting, errors trivially pass currently
-        for (expr_string, expected_err) in &[
-            ("\"test", ""),
-            ("b\"test", ""),
-            // ("b[104, 0x65, 0x6c, 0x6C, 'o'", ""),
+        for (expr_string, expected_err, expected_span) in [
+            (
+                "\"test",
+                "Unterminated string or bad escape, strings are in matching `\"` quotes",
+                0..5,
+            ),
+            (
+                "b\"test",
+                "Unterminated string or bad escape, strings are in matching `\"` quotes",
+                1..6,
+            ),
+            (
+                "b[104, 0x65, 0x6c, 0x6C, 'o'",
+                "Expected `]` to close the bytes list",
+                28..28,
+            ),
+            ("b 1", "Expected a string or `[` after `b`", 2..3),
         ] {
-            let res = PatuiExpr::try_from(*expr_string);
-            assert_that!(res).is_err();
-            assert_that!(res.unwrap_err().to_string()).contains(*expected_err);
+            let err = parse_err(expr_string);
+            assert_that!(err.message()).is_equal_to(expected_err);
+            assert_that!(err.span()).is_equal_to(expected_span);
         }
     }
 
+    #[traced_test]
+    #[test]
+    fn bad_not_in() {
+        let err = parse_err("x not y");
+        assert_that!(err.message()).is_equal_to("Expected `in` after `not`");
+        assert_that!(err.span()).is_equal_to(6..7);
+        assert_that!(err.token()).is_equal_to(Some("y"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn bad_bytes() {
+        for (expr_string, expected_err, expected_span) in [
+            ("b[256]", "Byte value 256 out of range 0-255", 2..5),
+            ("b[1, -1]", "Byte value -1 out of range 0-255", 5..7),
+            ("b[0x100]", "Byte value 0x100 out of range 0-255", 2..7),
+            (
+                "b[0b100000000]",
+                "Byte value 0b100000000 out of range 0-255",
+                2..13,
+            ),
+            (
+                "b['é']",
+                "String \"é\" in bytes list must be a single byte, got 2",
+                2..6,
+            ),
+            (
+                "b[\"\"]",
+                "String \"\" in bytes list must be a single byte, got 0",
+                2..4,
+            ),
+            ("b[1, foo]", "Unexpected `foo` in bytes list", 5..8),
+        ] {
+            let err = parse_err(expr_string);
+            assert_that!(err.message()).is_equal_to(expected_err);
+            assert_that!(err.span()).is_equal_to(expected_span);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn assignment_typo() {
+        for (expr_string, expected_span) in [("a = b", 2..3), ("steps.x.out[0] = 5", 15..16)] {
+            let err = parse_err(expr_string);
+            assert_that!(err.message()).is_equal_to("Unexpected `=`, did you mean `==`?");
+            assert_that!(err.span()).is_equal_to(expected_span);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn bad_map_keys() {
+        for (expr_string, expected_err, expected_span) in [
+            (
+                "{1: \"one\"}",
+                "Map keys must be string literals, got `1`",
+                1..2,
+            ),
+            (
+                "{\"a\": 1, 2: 2}",
+                "Map keys must be string literals, got `2`",
+                9..10,
+            ),
+            (
+                "{1 + 1: 2}",
+                "Map keys must be string literals, got `1 + 1`",
+                1..6,
+            ),
+            (
+                "{\"a\" + \"b\": 2}",
+                "Map keys must be string literals, got `\"a\" + \"b\"`",
+                1..10,
+            ),
+            (
+                "{ steps.foo.out : 2}",
+                "Map keys must be string literals, got `steps.foo.out`",
+                2..15,
+            ),
+        ] {
+            let err = parse_err(expr_string);
+            assert_that!(err.message()).is_equal_to(expected_err);
+            assert_that!(err.span()).is_equal_to(expected_span);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn bad_syntax() {
+        for (expr_string, expected_err, expected_span) in [
+            ("1 +", "Expected an expression", 3..3),
+            ("== 1", "Expected an expression before `==`", 0..2),
+            ("foo(1", "Expected `)` to close the call", 5..5),
+            ("(1", "Expected `)`", 2..2),
+            ("[1, 2", "Expected `,` or `]` in list", 5..5),
+            ("{1, 2", "Expected `,` or `}` in map or set", 5..5),
+            ("foo[1:2", "Expected `]` to close the slice", 7..7),
+            ("foo[3..1]", "Slice bounds reversed, 3 is after 1", 0..9),
+            ("x if y", "Expected `else` after the condition", 6..6),
+            ("foo.1", "Expected a field name after `.`", 4..5),
+            ("foo.", "Expected a field name after `.`", 4..4),
+            ("1 ]", "Unexpected `]`", 2..3),
+            ("1 @ 2", "Unrecognised `@`", 2..3),
+        ] {
+            let err = parse_err(expr_string);
+            assert_that!(err.message()).is_equal_to(expected_err);
+            assert_that!(err.span()).is_equal_to(expected_span);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn malformed_exprs_dont_panic() {
+        let err = parse_err(")");
+        assert_that!(err.message()).is_equal_to("Unexpected `)`");
+        assert_that!(err.token()).is_equal_to(Some(")"));
+        assert_that!(err.span()).is_equal_to(0..1);
+
+        let err = parse_err("+ +");
+        assert_that!(err.message()).is_equal_to("Expected an expression");
+        assert_that!(err.token()).is_none();
+        assert_that!(err.span()).is_equal_to(3..3);
+
+        // Half typed expressions, as seen while editing a test
+        for expr_string in [
+            "]",
+            "}",
+            ",",
+            ":",
+            "..",
+            "(",
+            "[",
+            "{",
+            "b",
+            "b[",
+            "b[1,",
+            "foo.",
+            "foo[",
+            "foo[1:",
+            "foo(",
+            "foo(1,",
+            "{\"a\":",
+            "{\"a\": 1,",
+            "x if",
+            "x if y else",
+            "if",
+            "else",
+            "not",
+            "x not",
+            "x in",
+            "1 ==",
+            "!",
+            "-",
+            "(1))",
+            "[1]]",
+            "1 @",
+            "\"a\\q\"",
+        ] {
+            assert_that!(PatuiExpr::try_from(expr_string)).is_err();
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn parse_error_display() {
+        assert_that!(parse_err("1 +").to_string())
+            .is_equal_to("Expected an expression\n  1 +\n     ^".to_string());
+        assert_that!(parse_err("\"test").to_string()).is_equal_to(
+            "Unterminated string or bad escape, strings are in matching `\"` quotes\n  \"test\n  ^^^^^"
+                .to_string(),
+        );
+        // Only the line at fault is shown, with the caret counted in characters not bytes
+        assert_that!(parse_err("\"é\" ==\n  [1, 2 = 3]").to_string()).is_equal_to(
+            "Unexpected `=`, did you mean `==`?\n    [1, 2 = 3]\n          ^".to_string(),
+        );
+    }
+
+    #[traced_test]
+    #[test]
+    fn empty_exprs() {
+        for expr_string in [
+            "",
+            "   ",
+            "\n\t ",
+            "# just a comment",
+            "  # comment\n# another",
+        ] {
+            let err = parse_err(expr_string);
+            assert_that!(err.message()).is_equal_to("Empty expression");
+            assert_that!(err.span()).is_equal_to(expr_string.len()..expr_string.len());
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn comments() {
+        let res = PatuiExpr::try_from("foo # trailing comment");
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap().kind).is_equal_to(ExprKind::Ident(Ident {
+            value: "foo".to_string(),
+        }));
+    }
+
+    #[traced_test]
+    #[test]
+    fn string_escapes() {
+        for (expr_string, expected) in [
+            (r#""a\tb""#, "a\tb"),
+            (r#"'line\n'"#, "line\n"),
+            (r#""\u{1F600}""#, "\u{1F600}"),
+            (r#""say \"hi\"""#, "say \"hi\""),
+        ] {
+            let expr = PatuiExpr::try_from(expr_string).unwrap();
+            // The source keeps its escapes, only the value is decoded
+            assert_that!(expr.raw.as_str()).is_equal_to(expr_string);
+            assert_that!(expr.kind).is_equal_to(ExprKind::Lit(Lit {
+                kind: LitKind::Str(expected.to_string()),
+            }));
+
+            let serialized = serde_json::to_string(&expr).unwrap();
+            let res = serde_json::from_str::<PatuiExpr>(&serialized).unwrap();
+            assert_that!(res.kind).is_equal_to(expr.kind);
+        }
+
+        for expr_string in [r#""a\qb""#, r#""\u{110000}""#, r#""\u{41""#] {
+            assert_that!(PatuiExpr::try_from(expr_string)).is_err();
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn serde_round_trip() {
+        for expr_string in ["foo == bar", "[b\"abc\", b[1, 2]]", "steps.foo.out[0]"] {
+            let expr = PatuiExpr::try_from(expr_string).unwrap();
+
+            let serialized = serde_json::to_string(&expr).unwrap();
+            let res = serde_json::from_str::<PatuiExpr>(&serialized);
+
+            assert_that!(res).is_ok();
+            let res = res.unwrap();
+            assert_that!(res.raw).is_equal_to(expr.raw);
+            assert_that!(res.kind).is_equal_to(expr.kind);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn serde_tampered() {
+        let expr = PatuiExpr::try_from("123").unwrap();
+        let serialized = serde_json::to_string(&expr).unwrap();
+
+        // Whatever kind's stored it's what the source parses to that's used
+        let tampered = serialized.replace(r#""Integer":"123""#, r#""Integer":"456""#);
+        assert_that!(tampered).is_not_equal_to(serialized.clone());
+        let res = serde_json::from_str::<PatuiExpr>(&tampered);
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap().kind).is_equal_to(expr.kind.clone());
+
+        let tampered = serialized.replace(r#""kind":{"Lit""#, r#""kind":{"NoSuchKind""#);
+        assert_that!(tampered).is_not_equal_to(serialized.clone());
+        let res = serde_json::from_str::<PatuiExpr>(&tampered);
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap().kind).is_equal_to(expr.kind);
+
+        let tampered = serialized.replace(r#""raw":"123""#, r#""raw":"123 +""#);
+        let res = serde_json::from_str::<PatuiExpr>(&tampered);
+        assert_that!(res).is_err();
+        assert_that!(res.unwrap_err().to_string()).starts_with("invalid expression `123 +`");
+    }
+
     #[traced_test]
     #[test]
     fn idents() {
@@ -709,6 +1057,37 @@ mod tests {
         }
     }
 
+    #[traced_test]
+    #[test]
+    fn slicing() {
+        for (expr_string, from, to) in [
+            ("foo[1:3]", Some("1"), Some("3")),
+            ("foo[1:]", Some("1"), None),
+            ("foo[:bar.len()]", None, Some("bar.len()")),
+            ("foo[:]", None, None),
+            ("foo[0][2 : 5]", Some("2"), Some("5")),
+            ("foo[1..3]", Some("1"), Some("3")),
+            ("foo[..3]", None, Some("3")),
+            ("foo[2..]", Some("2"), None),
+            ("foo[..]", None, None),
+        ] {
+            let expr = PatuiExpr::try_from(expr_string).unwrap();
+            assert_that!(expr.raw.as_str()).is_equal_to(expr_string);
+
+            let ExprKind::Slice(_, expr_from, expr_to) = expr.kind else {
+                panic!("`{}` should parse to a slice", expr_string);
+            };
+            assert_that!(expr_from.map(|x| x.raw.clone())).is_equal_to(from.map(|x| x.to_string()));
+            assert_that!(expr_to.map(|x| x.raw.clone())).is_equal_to(to.map(|x| x.to_string()));
+        }
+
+        assert_that!(PatuiExpr::try_from("foo[1:2:3]")).is_err();
+        assert_that!(PatuiExpr::try_from("foo[1:2")).is_err();
+        assert_that!(PatuiExpr::try_from("foo[3..1]")).is_err();
+        assert_that!(PatuiExpr::try_from("foo[3:1]")).is_err();
+        assert_that!(PatuiExpr::try_from("foo[1..2..3]")).is_err();
+    }
+
     #[traced_test]
     #[test]
     fn maths() {
@@ -879,6 +1258,69 @@ mod tests {
         }
     }
 
+    #[traced_test]
+    #[test]
+    fn unary_ops() {
+        let lit = |raw: &str, kind: LitKind| PatuiExpr {
+            raw: raw.to_string(),
+            kind: ExprKind::Lit(Lit { kind }),
+        };
+        let ident = |value: &str| PatuiExpr {
+            raw: value.to_string(),
+            kind: ExprKind::Ident(Ident {
+                value: value.to_string(),
+            }),
+        };
+        let un_op = |raw: &str, op: UnOp, expr: PatuiExpr| PatuiExpr {
+            raw: raw.to_string(),
+            kind: ExprKind::UnOp(
+                op,
+                P {
+                    ptr: Box::new(expr),
+                },
+            ),
+        };
+
+        for (expr_string, expected) in &[
+            (
+                "!!true",
+                un_op(
+                    "!!true",
+                    UnOp::Not,
+                    un_op("!true", UnOp::Not, lit("true", LitKind::Bool(true))),
+                ),
+            ),
+            (
+                "not !true",
+                un_op(
+                    "not !true",
+                    UnOp::Not,
+                    un_op("!true", UnOp::Not, lit("true", LitKind::Bool(true))),
+                ),
+            ),
+            // The lexer takes `-5` as a negative integer so only the first `-` is an operator
+            (
+                "--5",
+                un_op(
+                    "--5",
+                    UnOp::Neg,
+                    lit("-5", LitKind::Integer("-5".to_string())),
+                ),
+            ),
+            (
+                "- -x",
+                un_op("- -x", UnOp::Neg, un_op("-x", UnOp::Neg, ident("x"))),
+            ),
+            ("+5", lit("+5", LitKind::Integer("5".to_string()))),
+            ("++5", lit("++5", LitKind::Integer("5".to_string()))),
+            ("+-x", un_op("+-x", UnOp::Neg, ident("x"))),
+        ] {
+            let res = PatuiExpr::try_from(*expr_string);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(expected);
+        }
+    }
+
     #[traced_test]
     #[test]
     fn comparison() {
@@ -908,6 +1350,81 @@ mod tests {
                     ),
                 },
             ),
+            (
+                "x in y",
+                PatuiExpr {
+                    raw: "x in y".to_string(),
+                    kind: ExprKind::BinOp(
+                        BinOp::Contains,
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "x".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "x".to_string(),
+                                }),
+                            }),
+                        },
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "y".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "y".to_string(),
+                                }),
+                            }),
+                        },
+                    ),
+                },
+            ),
+            (
+                "x not in y",
+                PatuiExpr {
+                    raw: "x not in y".to_string(),
+                    kind: ExprKind::BinOp(
+                        BinOp::NotContains,
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "x".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "x".to_string(),
+                                }),
+                            }),
+                        },
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "y".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "y".to_string(),
+                                }),
+                            }),
+                        },
+                    ),
+                },
+            ),
+            (
+                "x NOT IN y",
+                PatuiExpr {
+                    raw: "x NOT IN y".to_string(),
+                    kind: ExprKind::BinOp(
+                        BinOp::NotContains,
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "x".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "x".to_string(),
+                                }),
+                            }),
+                        },
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "y".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "y".to_string(),
+                                }),
+                            }),
+                        },
+                    ),
+                },
+            ),
             (
                 "1 != 2",
                 PatuiExpr {
@@ -1040,6 +1557,105 @@ mod tests {
         }
     }
 
+    #[traced_test]
+    #[test]
+    fn if_else() {
+        fn ident(name: &str) -> P<PatuiExpr> {
+            P {
+                ptr: Box::new(PatuiExpr {
+                    raw: name.to_string(),
+                    kind: ExprKind::Ident(Ident {
+                        value: name.to_string(),
+                    }),
+                }),
+            }
+        }
+
+        for (expr_string, expected) in &[
+            (
+                "a if k else c",
+                PatuiExpr {
+                    raw: "a if k else c".to_string(),
+                    kind: ExprKind::If(ident("k"), ident("a"), ident("c")),
+                },
+            ),
+            (
+                "1 if x == 2 else y",
+                PatuiExpr {
+                    raw: "1 if x == 2 else y".to_string(),
+                    kind: ExprKind::If(
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "x == 2".to_string(),
+                                kind: ExprKind::BinOp(
+                                    BinOp::Equal,
+                                    ident("x"),
+                                    P {
+                                        ptr: Box::new(PatuiExpr {
+                                            raw: "2".to_string(),
+                                            kind: ExprKind::Lit(Lit {
+                                                kind: LitKind::Integer("2".to_string()),
+                                            }),
+                                        }),
+                                    },
+                                ),
+                            }),
+                        },
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "1".to_string(),
+                                kind: ExprKind::Lit(Lit {
+                                    kind: LitKind::Integer("1".to_string()),
+                                }),
+                            }),
+                        },
+                        ident("y"),
+                    ),
+                },
+            ),
+            (
+                "a if k else (c if d else e)",
+                PatuiExpr {
+                    raw: "a if k else (c if d else e)".to_string(),
+                    kind: ExprKind::If(
+                        ident("k"),
+                        ident("a"),
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "c if d else e".to_string(),
+                                kind: ExprKind::If(ident("d"), ident("c"), ident("e")),
+                            }),
+                        },
+                    ),
+                },
+            ),
+            (
+                "a if k else c if d else e",
+                PatuiExpr {
+                    raw: "a if k else c if d else e".to_string(),
+                    kind: ExprKind::If(
+                        ident("k"),
+                        ident("a"),
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "c if d else e".to_string(),
+                                kind: ExprKind::If(ident("d"), ident("c"), ident("e")),
+                            }),
+                        },
+                    ),
+                },
+            ),
+        ] {
+            let res = PatuiExpr::try_from(*expr_string);
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(expected.clone());
+        }
+
+        for expr_string in ["a if k", "if k else c"] {
+            assert_that!(PatuiExpr::try_from(expr_string)).is_err();
+        }
+    }
+
     #[traced_test]
     #[test]
     fn boolean_logic() {
@@ -1519,6 +2135,72 @@ mod tests {
         }
     }
 
+    #[traced_test]
+    #[test]
+    fn nested_brackets() {
+        let lit = |value: &str| P {
+            ptr: Box::new(PatuiExpr {
+                raw: value.to_string(),
+                kind: ExprKind::Lit(Lit {
+                    kind: LitKind::Integer(value.to_string()),
+                }),
+            }),
+        };
+
+        for (expr_string, expected) in &[
+            (
+                "foo((1+2), 3)",
+                PatuiExpr {
+                    raw: "foo((1+2), 3)".to_string(),
+                    kind: ExprKind::Call(
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "foo".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "foo".to_string(),
+                                }),
+                            }),
+                        },
+                        vec![
+                            P {
+                                ptr: Box::new(PatuiExpr {
+                                    raw: "1+2".to_string(),
+                                    kind: ExprKind::BinOp(BinOp::Add, lit("1"), lit("2")),
+                                }),
+                            },
+                            lit("3"),
+                        ],
+                    ),
+                },
+            ),
+            (
+                "foo(1, (2))",
+                PatuiExpr {
+                    raw: "foo(1, (2))".to_string(),
+                    kind: ExprKind::Call(
+                        P {
+                            ptr: Box::new(PatuiExpr {
+                                raw: "foo".to_string(),
+                                kind: ExprKind::Ident(Ident {
+                                    value: "foo".to_string(),
+                                }),
+                            }),
+                        },
+                        vec![lit("1"), lit("2")],
+                    ),
+                },
+            ),
+            ("((1))", *lit("1").ptr),
+        ] {
+            let res = PatuiExpr::try_from(*expr_string);
+            assert_that!(res).is_ok();
+            // Equality only looks at `raw` so compare the whole tree too
+            let res = res.unwrap();
+            assert_that!(format!("{:?}", res)).is_equal_to(format!("{:?}", expected));
+            assert_that!(res).is_equal_to(expected);
+        }
+    }
+
     #[traced_test]
     #[test]
     fn complex() {
@@ -0,0 +1,67 @@
+use std::{fmt, ops::Range};
+
+/// An error parsing an expression, keeps the input and the span of it at fault so it can be
+/// pointed out.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParseError {
+    input: String,
+    span: Range<usize>,
+    message: String,
+}
+
+impl ParseError {
+    /// `span` is the byte range of `input` at fault, an empty span at the end of `input` is for
+    /// something missing after it, e.g. the right hand side of `1 +`.
+    pub(crate) fn new(input: &str, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            input: input.to_string(),
+            span,
+            message: message.into(),
+        }
+    }
+
+    #[cfg(test)]
+    pub(crate) fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The text of the offending token, `None` when the input ran out.
+    #[cfg(test)]
+    pub(crate) fn token(&self) -> Option<&str> {
+        (!self.span.is_empty()).then(|| &self.input[self.span.clone()])
+    }
+
+    #[cfg(test)]
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ParseError {
+    /// The message followed by the line of input at fault with carets under the span, e.g.
+    ///
+    /// ```text
+    /// Expected an expression at the end
+    ///   1 +
+    ///      ^
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = self.span.start;
+        let line_start = self.input[..start].rfind('\n').map_or(0, |idx| idx + 1);
+        let line_end = self.input[start..]
+            .find('\n')
+            .map_or(self.input.len(), |idx| start + idx);
+
+        let indent = self.input[line_start..start].chars().count();
+        let width = self.input[start..self.span.end.min(line_end)]
+            .chars()
+            .count()
+            .max(1);
+
+        writeln!(f, "{}", self.message)?;
+        writeln!(f, "  {}", &self.input[line_start..line_end])?;
+        write!(f, "  {}{}", " ".repeat(indent), "^".repeat(width))
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -4,6 +4,7 @@ use logos::{Lexer, Logos};
 
 #[derive(Logos, Clone, Debug, PartialEq, Eq)]
 #[logos(skip r"[ \t\r\n\f]+")]
+#[logos(skip r"#[^\n]*")]
 pub(crate) enum Token {
     #[token("false", |_| false, ignore(case))]
     #[token("true", |_| true, ignore(case))]
@@ -15,8 +16,7 @@ pub(crate) enum Token {
     #[regex(r"-?(?:0|[1-9]\d*)(?:\.\d+)?(?:[eE][+-]?\d+)?", priority = 4, callback = |lex| lex.slice().to_string())]
     Decimal(String),
 
-    #[regex(r#""([^"\\]|\\["\\bnfrt]|u[a-fA-F0-9]{4})*""#, |lex| lex.slice()[1..lex.slice().len() - 1].to_string())]
-    #[regex(r#"'([^'\\]|\\['\\bnfrt]|u[a-fA-F0-9]{4})*'"#, |lex| lex.slice()[1..lex.slice().len() - 1].to_string())]
+    #[regex(r#""([^"\\]|\\.)*"|'([^'\\]|\\.)*'"#, lex_string)]
     String(String),
 
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*", priority = 1, callback = |lex| lex.slice().to_string())]
@@ -55,6 +55,9 @@ pub(crate) enum Token {
     #[token(".")]
     Period,
 
+    #[token("..")]
+    DotDot,
+
     #[token(",")]
     Comma,
 
@@ -94,6 +97,10 @@ pub(crate) enum Token {
     #[token("==")]
     Equal,
 
+    /// Not part of the language, only lexed to point out a likely typo of `==`.
+    #[token("=")]
+    Assign,
+
     #[token("!=")]
     NotEqual,
 
@@ -108,6 +115,86 @@ pub(crate) enum Token {
 
     #[token(">=")]
     GreaterThanEqual,
+
+    #[token("in", ignore(case))]
+    In,
+}
+
+/// Strip the quotes from a string literal and decode its escapes, an unknown escape is a lexing
+/// error.
+fn lex_string(lex: &mut Lexer<Token>) -> Option<String> {
+    let slice = lex.slice();
+    let mut chars = slice[1..slice.len() - 1].chars();
+    let mut ret = String::new();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+
+        let decoded = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '0' => '\0',
+            '\\' => '\\',
+            '"' => '"',
+            '\'' => '\'',
+            'u' => {
+                if chars.next()? != '{' {
+                    return None;
+                }
+                let mut hex = String::new();
+                loop {
+                    match chars.next()? {
+                        '}' => break,
+                        c if c.is_ascii_hexdigit() && hex.len() < 6 => hex.push(c),
+                        _ => return None,
+                    }
+                }
+                char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+            }
+            _ => return None,
+        };
+        ret.push(decoded);
+    }
+
+    Some(ret)
+}
+
+/// Where the bracket matching the one at byte `pos` of `input` is. Working from the tokens means
+/// brackets in strings and comments are left alone, brackets of a different kind don't match.
+pub(crate) fn matching_bracket(input: &str, pos: usize) -> Option<usize> {
+    let mut open: Vec<(Token, usize)> = vec![];
+
+    for (token, span) in Token::lexer(input).spanned() {
+        let (opener, start) = match token {
+            Ok(token @ (Token::LeftBracket | Token::LeftSquareBrace | Token::LeftCurlyBrace)) => {
+                open.push((token, span.start));
+                continue;
+            }
+            Ok(Token::RightBracket) => (Token::LeftBracket, span.start),
+            Ok(Token::RightSquareBrace) => (Token::LeftSquareBrace, span.start),
+            Ok(Token::RightCurlyBrace) => (Token::LeftCurlyBrace, span.start),
+            _ => continue,
+        };
+
+        let (open_token, open_start) = open.pop()?;
+        if open_token != opener {
+            return None;
+        }
+        if open_start == pos {
+            return Some(start);
+        }
+        if start == pos {
+            return Some(open_start);
+        }
+    }
+
+    None
 }
 
 // Wrapper around Logos Lexer, needs to be peekable and inspectable at the
@@ -146,18 +233,16 @@ impl<'a> LexerPeekable<'a> {
 
     /// Check if next token matches, if it does eat it, if not leave it as next
     /// edible token.
+    /// A token that couldn't be lexed never matches, it's left for `next` to report.
     pub(crate) fn next_if_match(&mut self, match_token: Token) -> bool {
         if let Some(peek_next) = self.peek() {
             tracing::trace!("Peeking next: {:?}", peek_next);
-            match peek_next {
-                Ok(next_tok) => {
-                    if *next_tok == match_token {
-                        tracing::trace!("Matched");
-                        self.next().unwrap().unwrap();
-                        return true;
-                    }
+            if let Ok(next_tok) = peek_next {
+                if *next_tok == match_token {
+                    tracing::trace!("Matched");
+                    self.next().unwrap().unwrap();
+                    return true;
                 }
-                Err(e) => panic!("TODO: Handle: {:?}", e),
             }
         }
 
@@ -171,4 +256,11 @@ impl<'a> LexerPeekable<'a> {
     pub(crate) fn span(&self) -> Range<usize> {
         self.lexer.span()
     }
+
+    /// The span of the token `peek` gives, or of the last token if there are none left.
+    pub(crate) fn peek_span(&self) -> Range<usize> {
+        let mut lexer = self.lexer.clone();
+        lexer.next();
+        lexer.span()
+    }
 }
@@ -0,0 +1,159 @@
+//! Lints for likely mistakes in an expression that still parses fine.
+
+use std::ops::Range;
+
+use eyre::Result;
+
+use super::ast::*;
+use super::visitor::Visitor;
+
+/// A likely mistake in an expression, `span` is where in the expression it was found.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ExprLint {
+    pub(crate) message: String,
+    pub(crate) span: Range<usize>,
+}
+
+/// Find the likely mistakes in `expr` that can be seen without running anything: comparisons
+/// that can never be true because both sides are literals of different types and indexing a list
+/// literal past its end.
+pub(crate) fn lint_expr(expr: &PatuiExpr) -> Result<Vec<ExprLint>> {
+    struct LintVisitor<'a> {
+        root: &'a PatuiExpr,
+        lints: Vec<ExprLint>,
+    }
+
+    impl LintVisitor<'_> {
+        fn push(&mut self, expr: &PatuiExpr, message: String) {
+            // Sub-expressions keep the text they were parsed from so can be found in the root
+            let start = self.root.raw.find(&expr.raw).unwrap_or(0);
+            self.lints.push(ExprLint {
+                message,
+                span: start..start + expr.raw.len(),
+            });
+        }
+    }
+
+    impl Visitor for LintVisitor<'_> {
+        fn visit_expr(&mut self, expr: &PatuiExpr) -> Result<()> {
+            match expr.kind() {
+                ExprKind::BinOp(
+                    BinOp::Equal
+                    | BinOp::NotEqual
+                    | BinOp::LessThan
+                    | BinOp::LessThanEqual
+                    | BinOp::GreaterThan
+                    | BinOp::GreaterThanEqual,
+                    lhs,
+                    rhs,
+                ) => {
+                    if let (Some(lhs_type), Some(rhs_type)) = (literal_type(lhs), literal_type(rhs))
+                    {
+                        if lhs_type != rhs_type {
+                            self.push(
+                                expr,
+                                format!(
+                                    "`{}` compares a {} with a {} so never holds",
+                                    expr, lhs_type, rhs_type
+                                ),
+                            );
+                        }
+                    }
+                }
+                ExprKind::Index(receiver, index) => {
+                    let (
+                        ExprKind::List(items),
+                        ExprKind::Lit(Lit {
+                            kind: LitKind::Integer(idx),
+                        }),
+                    ) = (receiver.kind(), index.kind())
+                    else {
+                        return Ok(());
+                    };
+                    if integer_value(idx)
+                        .and_then(|idx| usize::try_from(idx).ok())
+                        .is_some_and(|idx| idx >= items.len())
+                    {
+                        self.push(
+                            expr,
+                            format!(
+                                "`{}` is out of range, `{}` only has {} items",
+                                expr,
+                                **receiver,
+                                items.len()
+                            ),
+                        );
+                    }
+                }
+                _ => {}
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut visitor = LintVisitor {
+        root: expr,
+        lints: vec![],
+    };
+    expr.visit(&mut visitor)?;
+
+    Ok(visitor.lints)
+}
+
+/// The type of `expr` when it's a literal, integers and decimals are both numbers.
+fn literal_type(expr: &PatuiExpr) -> Option<&'static str> {
+    match expr.kind() {
+        ExprKind::Lit(lit) => match lit.kind {
+            LitKind::Bool(_) => Some("bool"),
+            LitKind::Bytes(_) => Some("bytes"),
+            LitKind::Integer(_) | LitKind::Decimal(_) => Some("number"),
+            LitKind::Str(_) => Some("string"),
+            LitKind::Token(_) => None,
+        },
+        ExprKind::List(_) => Some("list"),
+        ExprKind::Map(_) => Some("map"),
+        ExprKind::Set(_) => Some("set"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn lint(input: &str) -> Vec<ExprLint> {
+        lint_expr(&input.try_into().unwrap()).unwrap()
+    }
+
+    #[traced_test]
+    #[test]
+    fn incompatible_comparison() {
+        let lints = lint(r#"steps.foo.out == 1 && "1" == 1"#);
+
+        assert_that!(lints).is_equal_to(vec![ExprLint {
+            message: r#"`"1" == 1` compares a string with a number so never holds"#.to_string(),
+            span: 22..30,
+        }]);
+
+        assert_that!(lint("1 == 1.5")).is_empty();
+        assert_that!(lint(r#"[1] != {"a": 1}"#)).has_length(1);
+    }
+
+    #[traced_test]
+    #[test]
+    fn index_out_of_range() {
+        let lints = lint("steps.foo.out == [1, 2][2]");
+
+        assert_that!(lints).is_equal_to(vec![ExprLint {
+            message: "`[1, 2][2]` is out of range, `[1, 2]` only has 2 items".to_string(),
+            span: 17..26,
+        }]);
+
+        assert_that!(lint("steps.foo.out == [1, 2][1]")).is_empty();
+        assert_that!(lint("steps.foo.out[5]")).is_empty();
+    }
+}
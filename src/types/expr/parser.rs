@@ -1,27 +1,60 @@
+use std::ops::Range;
+
 use bytes::Bytes;
-use eyre::{eyre, Result};
 use logos::Logos;
 
 use super::{
     ast::*,
+    error::ParseError,
     lexer::{LexerPeekable, Token},
 };
 
+type Result<T> = std::result::Result<T, ParseError>;
+
 pub(crate) fn parse(input: &str) -> Result<PatuiExpr> {
     let mut lexer = LexerPeekable::new(Token::lexer(input));
 
-    let expr = parse_expr(input, &mut lexer, vec![]);
+    if lexer.peek().is_none() {
+        return Err(ParseError::new(
+            input,
+            input.len()..input.len(),
+            "Empty expression",
+        ));
+    }
+
+    let expr = parse_expr(input, &mut lexer, vec![])?;
 
     if lexer.peek().is_some() {
-        let span = lexer.span();
-        let rest = &input[span.start..];
-        return Err(eyre!(
-            "More tokens left to parse after parsing full expression: '{}'",
-            rest,
+        return Err(ParseError::new(
+            input,
+            lexer.peek_span(),
+            "Expected the end of the expression",
         ));
     }
 
-    expr
+    Ok(expr)
+}
+
+/// Where the next token is, or the end of `input` if there isn't one, for errors about what
+/// should have come next.
+fn next_span(input: &str, lexer: &mut LexerPeekable<'_>) -> Range<usize> {
+    if lexer.peek().is_some() {
+        lexer.peek_span()
+    } else {
+        input.len()..input.len()
+    }
+}
+
+/// The lexer couldn't make a token from what's at `span`.
+fn lex_error(input: &str, span: Range<usize>) -> ParseError {
+    let message = match input[span.clone()].chars().next() {
+        Some(quote @ ('"' | '\'')) => format!(
+            "Unterminated string or bad escape, strings are in matching `{}` quotes",
+            quote
+        ),
+        _ => format!("Unrecognised `{}`", &input[span.clone()]),
+    };
+    ParseError::new(input, span, message)
 }
 
 pub(crate) fn parse_expr(
@@ -35,7 +68,7 @@ pub(crate) fn parse_expr(
     while let Some(token) = lexer.next() {
         let token = match token {
             Ok(token) => token,
-            Err(e) => return Err(eyre!("Error parsing token: {:?}", e)),
+            Err(()) => return Err(lex_error(input, lexer.span())),
         };
 
         if expr_start.is_none() {
@@ -80,7 +113,7 @@ pub(crate) fn parse_expr(
                     }),
                 });
             }
-            Token::BytesPrefix => expr = Some(parse_bytes(input, lexer)?),
+            Token::BytesPrefix => expr = Some(parse_bytes(input, lexer, start)?),
             Token::Ident(id) => {
                 expr = Some(parse_ident(input, lexer, id)?);
             }
@@ -129,11 +162,41 @@ pub(crate) fn parse_expr(
                 };
             }
             Token::Not => {
-                expr = Some(parse_un_op(
+                expr = match expr.take() {
+                    None => Some(parse_un_op(
+                        input,
+                        lexer,
+                        expr_start.unwrap(),
+                        UnOp::Not,
+                        parse_until.clone(),
+                    )?),
+                    // Following an expression `not` can only be the start of `not in`
+                    Some(lhs) => {
+                        if !lexer.next_if_match(Token::In) {
+                            return Err(ParseError::new(
+                                input,
+                                next_span(input, lexer),
+                                "Expected `in` after `not`",
+                            ));
+                        }
+                        Some(parse_bin_op(
+                            input,
+                            lexer,
+                            Some(lhs),
+                            expr_start.unwrap(),
+                            BinOp::NotContains,
+                            parse_until.clone(),
+                        )?)
+                    }
+                };
+            }
+            Token::In => {
+                expr = Some(parse_bin_op(
                     input,
                     lexer,
+                    expr,
                     expr_start.unwrap(),
-                    UnOp::Not,
+                    BinOp::Contains,
                     parse_until.clone(),
                 )?);
             }
@@ -147,6 +210,13 @@ pub(crate) fn parse_expr(
                     parse_until.clone(),
                 )?);
             }
+            Token::Assign => {
+                return Err(ParseError::new(
+                    input,
+                    start..end,
+                    "Unexpected `=`, did you mean `==`?",
+                ));
+            }
             Token::NotEqual => {
                 expr = Some(parse_bin_op(
                     input,
@@ -218,14 +288,24 @@ pub(crate) fn parse_expr(
                 )?);
             }
             Token::Add => {
-                expr = Some(parse_bin_op(
-                    input,
-                    lexer,
-                    expr,
-                    expr_start.unwrap(),
-                    BinOp::Add,
-                    parse_until.clone(),
-                )?);
+                expr = match expr.take() {
+                    // Unary plus is a no-op, we keep the `+` in `raw` but otherwise drop it
+                    None => {
+                        let inner = parse_expr(input, lexer, parse_until.clone())?;
+                        Some(PatuiExpr {
+                            raw: input[expr_start.unwrap()..lexer.span().end].to_string(),
+                            kind: inner.kind,
+                        })
+                    }
+                    Some(lhs) => Some(parse_bin_op(
+                        input,
+                        lexer,
+                        Some(lhs),
+                        expr_start.unwrap(),
+                        BinOp::Add,
+                        parse_until.clone(),
+                    )?),
+                };
             }
             Token::Star => {
                 if expr.is_some() {
@@ -267,7 +347,22 @@ pub(crate) fn parse_expr(
                     parse_until.clone(),
                 )?);
             }
-            tok => panic!("Unexpectedly reached token: {:?}", tok),
+            Token::If => {
+                expr = Some(parse_if(
+                    input,
+                    lexer,
+                    expr,
+                    expr_start.unwrap(),
+                    parse_until.clone(),
+                )?);
+            }
+            _ => {
+                return Err(ParseError::new(
+                    input,
+                    start..end,
+                    format!("Unexpected `{}`", &input[start..end]),
+                ));
+            }
         }
 
         if let Some(Ok(ref peek_token)) = lexer.peek() {
@@ -279,63 +374,100 @@ pub(crate) fn parse_expr(
         }
     }
 
-    expr.ok_or_else(|| eyre!("Couldn't parse expression"))
+    expr.ok_or_else(|| ParseError::new(input, next_span(input, lexer), "Expected an expression"))
 }
 
-fn parse_bytes(input: &str, lexer: &mut LexerPeekable<'_>) -> Result<PatuiExpr> {
+fn parse_bytes(input: &str, lexer: &mut LexerPeekable<'_>, start: usize) -> Result<PatuiExpr> {
     while let Some(token) = lexer.next() {
         match token {
             Ok(Token::String(s)) => {
                 return Ok(PatuiExpr {
-                    raw: input.to_string(),
+                    raw: input[start..lexer.span().end].to_string(),
                     kind: ExprKind::Lit(Lit {
                         kind: LitKind::Bytes(Bytes::from(s)),
                     }),
                 });
             }
             Ok(Token::LeftSquareBrace) => {
-                let bytes = parse_bytes_list(lexer)?;
+                let bytes = parse_bytes_list(input, lexer)?;
                 return Ok(PatuiExpr {
-                    raw: input.to_string(),
+                    raw: input[start..lexer.span().end].to_string(),
                     kind: ExprKind::Lit(Lit {
                         kind: LitKind::Bytes(bytes),
                     }),
                 });
             }
-            Ok(tok) => panic!("Unexpectedly reached token: {:?}", tok),
-            Err(e) => return Err(eyre!("Error parsing token: {:?}", e)),
+            Ok(_) => {
+                return Err(ParseError::new(
+                    input,
+                    lexer.span(),
+                    "Expected a string or `[` after `b`",
+                ));
+            }
+            Err(()) => return Err(lex_error(input, lexer.span())),
         }
     }
 
-    Err(eyre!("Error, ran out of tokens while parsing bytes",))
+    Err(ParseError::new(
+        input,
+        input.len()..input.len(),
+        "Expected a string or `[` after `b`",
+    ))
 }
 
-fn parse_bytes_list(lexer: &mut LexerPeekable<'_>) -> Result<Bytes> {
+/// Parse the entries of `b[...]`, each either an integer from 0 to 255, in decimal, hex or
+/// binary, or a string of exactly one byte. Strings are taken as UTF-8 so only ASCII characters
+/// are a single byte, anything else, e.g. `'é'`, has to be given as its separate bytes.
+fn parse_bytes_list(input: &str, lexer: &mut LexerPeekable<'_>) -> Result<Bytes> {
     let mut bytes = Vec::new();
 
     while let Some(token) = lexer.next() {
         match token {
             Ok(Token::Integer(int)) => {
-                let byte = int.parse::<u8>()?;
+                let byte = integer_value(&int)
+                    .and_then(|value| u8::try_from(value).ok())
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            input,
+                            lexer.span(),
+                            format!("Byte value {} out of range 0-255", int),
+                        )
+                    })?;
                 bytes.push(byte);
             }
             Ok(Token::String(s)) => {
                 if s.len() != 1 {
-                    return Err(eyre!(
-                        "Error, string in bytes list must be a single character"
+                    return Err(ParseError::new(
+                        input,
+                        lexer.span(),
+                        format!(
+                            "String {:?} in bytes list must be a single byte, got {}",
+                            s,
+                            s.len()
+                        ),
                     ));
                 }
-                let byte = s.chars().next().unwrap() as u8;
-                bytes.push(byte);
+                bytes.push(s.as_bytes()[0]);
             }
             Ok(Token::Comma) => {}
             Ok(Token::RightSquareBrace) => return Ok(Bytes::from(bytes)),
-            Ok(tok) => panic!("Unexpectedly reached token: {:?}", tok),
-            Err(e) => return Err(eyre!("Error parsing token: {:?}", e)),
+            Ok(_) => {
+                let span = lexer.span();
+                return Err(ParseError::new(
+                    input,
+                    span.clone(),
+                    format!("Unexpected `{}` in bytes list", &input[span]),
+                ));
+            }
+            Err(()) => return Err(lex_error(input, lexer.span())),
         }
     }
 
-    Err(eyre!("Error, ran out of tokens while parsing bytes list",))
+    Err(ParseError::new(
+        input,
+        input.len()..input.len(),
+        "Expected `]` to close the bytes list",
+    ))
 }
 
 fn parse_ident(input: &str, lexer: &mut LexerPeekable<'_>, id: String) -> Result<PatuiExpr> {
@@ -357,12 +489,24 @@ fn parse_field(
 ) -> Result<PatuiExpr> {
     let expr = expr
         .take()
-        .ok_or_else(|| eyre!("Expected expression before field access"))?;
+        .ok_or_else(|| ParseError::new(input, lexer.span(), "Expected an expression before `.`"))?;
     let field_ident = match lexer.next() {
         Some(Ok(Token::Ident(id))) => id,
-        Some(Ok(tok)) => return Err(eyre!("Unexpected token: {:?}", tok)),
-        Some(Err(e)) => return Err(eyre!("Error parsing token: {:?}", e)),
-        None => return Err(eyre!("Ran out of tokens while parsing field")),
+        Some(Ok(_)) => {
+            return Err(ParseError::new(
+                input,
+                lexer.span(),
+                "Expected a field name after `.`",
+            ));
+        }
+        Some(Err(())) => return Err(lex_error(input, lexer.span())),
+        None => {
+            return Err(ParseError::new(
+                input,
+                input.len()..input.len(),
+                "Expected a field name after `.`",
+            ));
+        }
     };
 
     let end = lexer.span().end;
@@ -396,7 +540,11 @@ fn parse_list(input: &str, lexer: &mut LexerPeekable<'_>) -> Result<PatuiExpr> {
             tracing::trace!("Parsed list elements: {:?}", elements);
             break;
         } else if !lexer.next_if_match(Token::Comma) {
-            return Err(eyre!("Couldn't parse list from string"));
+            return Err(ParseError::new(
+                input,
+                next_span(input, lexer),
+                "Expected `,` or `]` in list",
+            ));
         }
     }
 
@@ -414,24 +562,82 @@ fn parse_index(
 ) -> Result<PatuiExpr> {
     tracing::trace!("Parsing index: {:?}", &input[start..]);
 
-    let expr = parse_expr(input, lexer, vec![Token::RightSquareBrace])?;
+    let separators = vec![Token::RightSquareBrace, Token::Colon, Token::DotDot];
 
-    if !lexer.next_if_match(Token::RightSquareBrace) {
-        return Err(eyre!("Couldn't parse list from string"));
-    }
+    let from = if lexer.next_if_match(Token::Colon) || lexer.next_if_match(Token::DotDot) {
+        None
+    } else {
+        let expr = parse_expr(input, lexer, separators.clone())?;
+        if lexer.next_if_match(Token::RightSquareBrace) {
+            return Ok(PatuiExpr {
+                raw: input[start..lexer.span().end].to_string(),
+                kind: ExprKind::Index(
+                    P {
+                        ptr: Box::new(ident),
+                    },
+                    P {
+                        ptr: Box::new(expr),
+                    },
+                ),
+            });
+        }
+        if !lexer.next_if_match(Token::Colon) && !lexer.next_if_match(Token::DotDot) {
+            return Err(ParseError::new(
+                input,
+                next_span(input, lexer),
+                "Expected `]`, `:` or `..` in index",
+            ));
+        }
+        Some(P {
+            ptr: Box::new(expr),
+        })
+    };
 
-    #[allow(unused)]
-    let end = lexer.span().end;
+    // Past the `:` or `..` this is a slice
+    let to = if lexer.next_if_match(Token::RightSquareBrace) {
+        None
+    } else {
+        let expr = parse_expr(input, lexer, separators)?;
+        if !lexer.next_if_match(Token::RightSquareBrace) {
+            return Err(ParseError::new(
+                input,
+                next_span(input, lexer),
+                "Expected `]` to close the slice",
+            ));
+        }
+        Some(P {
+            ptr: Box::new(expr),
+        })
+    };
+
+    if let (Some(from), Some(to)) = (&from, &to) {
+        if let (
+            ExprKind::Lit(Lit {
+                kind: LitKind::Integer(from),
+            }),
+            ExprKind::Lit(Lit {
+                kind: LitKind::Integer(to),
+            }),
+        ) = (from.kind(), to.kind())
+        {
+            if integer_value(from) > integer_value(to) {
+                return Err(ParseError::new(
+                    input,
+                    start..lexer.span().end,
+                    format!("Slice bounds reversed, {} is after {}", from, to),
+                ));
+            }
+        }
+    }
 
     Ok(PatuiExpr {
-        raw: input[start..end].to_string(),
-        kind: ExprKind::Index(
+        raw: input[start..lexer.span().end].to_string(),
+        kind: ExprKind::Slice(
             P {
                 ptr: Box::new(ident),
             },
-            P {
-                ptr: Box::new(expr),
-            },
+            from,
+            to,
         ),
     })
 }
@@ -452,6 +658,22 @@ fn parse_set_or_map(input: &str, lexer: &mut LexerPeekable<'_>) -> Result<PatuiE
         )?;
 
         if lexer.next_if_match(Token::Colon) {
+            // Map data is keyed by strings so only string literals make sense as keys
+            if !matches!(
+                key.kind(),
+                ExprKind::Lit(Lit {
+                    kind: LitKind::Str(_)
+                })
+            ) {
+                let key_end = lexer.span().start;
+                let key_start = input[..key_end].trim_end().len() - key.raw.len();
+                return Err(ParseError::new(
+                    input,
+                    key_start..key_start + key.raw.len(),
+                    format!("Map keys must be string literals, got `{}`", key.raw),
+                ));
+            }
+
             let value = parse_expr(input, lexer, vec![Token::Comma, Token::RightCurlyBrace])?;
             tracing::trace!("Parsed dict element: {:?}={:?}", key, value);
             map_elements.push(P {
@@ -471,16 +693,19 @@ fn parse_set_or_map(input: &str, lexer: &mut LexerPeekable<'_>) -> Result<PatuiE
             tracing::trace!("Peek after set/map: {:?}", lexer.peek());
             break;
         } else if !lexer.next_if_match(Token::Comma) {
-            return Err(eyre!(
-                "Couldn't parse map or set from string: {}",
-                &input[start..]
+            return Err(ParseError::new(
+                input,
+                next_span(input, lexer),
+                "Expected `,` or `}` in map or set",
             ));
         }
     }
 
     if set_elements.len() != 0 && map_elements.len() != 0 {
-        Err(eyre!(
-            "Parsed set and map elements, must be one or the other"
+        Err(ParseError::new(
+            input,
+            start..end,
+            "Parsed set and map elements, must be one or the other",
         ))
     } else if set_elements.len() != 0 {
         Ok(PatuiExpr {
@@ -529,9 +754,14 @@ fn parse_bin_op(
     op: BinOp,
     parse_until: Vec<Token>,
 ) -> Result<PatuiExpr> {
-    let lhs = lhs
-        .take()
-        .ok_or_else(|| eyre!("Expected left hand side of binary operation"))?;
+    let lhs = lhs.take().ok_or_else(|| {
+        let span = lexer.span();
+        ParseError::new(
+            input,
+            span.clone(),
+            format!("Expected an expression before `{}`", &input[span]),
+        )
+    })?;
 
     let rhs = parse_expr(input, lexer, parse_until)?;
 
@@ -545,10 +775,53 @@ fn parse_bin_op(
     Ok(expr)
 }
 
+/// Parse the rest of `then if cond else otherwise` having just passed the `if`.
+fn parse_if(
+    input: &str,
+    lexer: &mut LexerPeekable<'_>,
+    mut then: Option<PatuiExpr>,
+    start: usize,
+    parse_until: Vec<Token>,
+) -> Result<PatuiExpr> {
+    let then = then.take().ok_or_else(|| {
+        ParseError::new(input, lexer.span(), "Expected an expression before `if`")
+    })?;
+
+    let cond = parse_expr(input, lexer, vec![Token::Else])?;
+    if !lexer.next_if_match(Token::Else) {
+        return Err(ParseError::new(
+            input,
+            next_span(input, lexer),
+            "Expected `else` after the condition",
+        ));
+    }
+
+    let otherwise = parse_expr(input, lexer, parse_until)?;
+
+    Ok(PatuiExpr {
+        raw: input[start..lexer.span().end].to_string(),
+        kind: ExprKind::If(
+            P {
+                ptr: Box::new(cond),
+            },
+            P {
+                ptr: Box::new(then),
+            },
+            P {
+                ptr: Box::new(otherwise),
+            },
+        ),
+    })
+}
+
 fn parse_bracket_ordering(input: &str, lexer: &mut LexerPeekable<'_>) -> Result<PatuiExpr> {
     let expr = parse_expr(input, lexer, vec![Token::RightBracket])?;
     if !lexer.next_if_match(Token::RightBracket) {
-        return Err(eyre!("Couldn't parse bracket ordering from string"));
+        return Err(ParseError::new(
+            input,
+            next_span(input, lexer),
+            "Expected `)`",
+        ));
     }
     Ok(expr)
 }
@@ -561,10 +834,20 @@ fn parse_function_call(
 ) -> Result<PatuiExpr> {
     let mut args = Vec::new();
 
-    while let Some(peek_token) = lexer.peek() {
-        if let Ok(Token::RightBracket) = peek_token {
-            lexer.next();
-            break;
+    loop {
+        match lexer.peek() {
+            Some(Ok(Token::RightBracket)) => {
+                lexer.next();
+                break;
+            }
+            Some(_) => {}
+            None => {
+                return Err(ParseError::new(
+                    input,
+                    input.len()..input.len(),
+                    "Expected `)` to close the call",
+                ));
+            }
         }
         let arg = parse_expr(input, lexer, vec![Token::Comma, Token::RightBracket])?;
         args.push(P { ptr: Box::new(arg) });
@@ -664,19 +947,55 @@ mod tests {
         );
         single_successful_lex(
             r#""foo\"bar\"boo""#,
-            Token::String("foo\\\"bar\\\"boo".to_string()),
+            Token::String("foo\"bar\"boo".to_string()),
             0..15,
             r#""foo\"bar\"boo""#,
         );
     }
 
+    #[test]
+    fn lex_string_escapes() {
+        for (input, expected) in [
+            (r#""a\nb""#, "a\nb"),
+            (r#""a\tb""#, "a\tb"),
+            (r#""a\rb""#, "a\rb"),
+            (r#""a\bb""#, "a\u{8}b"),
+            (r#""a\fb""#, "a\u{c}b"),
+            (r#""a\0b""#, "a\0b"),
+            (r#""a\\b""#, "a\\b"),
+            (r#""a\"b""#, "a\"b"),
+            (r#"'a\'b'"#, "a'b"),
+            (r#""a\u{41}b""#, "aAb"),
+            (r#""\u{1F600}""#, "\u{1F600}"),
+            (r#""\\n""#, "\\n"),
+        ] {
+            single_successful_lex(
+                input,
+                Token::String(expected.to_string()),
+                0..input.len(),
+                input,
+            );
+        }
+    }
+
     #[test]
     fn lex_string_errors() {
-        let mut lex = Token::lexer("\"foo bar boo");
-        let tok = lex.next();
-        assert_that!(tok).is_some();
-        let tok = tok.unwrap();
-        assert_that!(tok).is_err();
+        for input in [
+            "\"foo bar boo",
+            r#""foo\qbar""#,
+            r#""\u0041""#,
+            r#""\u{}""#,
+            r#""\u{zz}""#,
+            r#""\u{41""#,
+            r#""\u{1234567}""#,
+            r#""\u{d800}""#,
+        ] {
+            let mut lex = Token::lexer(input);
+            let tok = lex.next();
+            assert_that!(tok).is_some();
+            let tok = tok.unwrap();
+            assert_that!(tok).is_err();
+        }
     }
 
     #[test]
@@ -705,6 +1024,7 @@ mod tests {
         single_successful_lex("(", Token::LeftBracket, 0..1, "(");
         single_successful_lex(")", Token::RightBracket, 0..1, ")");
         single_successful_lex(".", Token::Period, 0..1, ".");
+        single_successful_lex("..", Token::DotDot, 0..2, "..");
         single_successful_lex(",", Token::Comma, 0..1, ",");
         single_successful_lex(":", Token::Colon, 0..1, ":");
         single_successful_lex(";", Token::Semicolon, 0..1, ";");
@@ -729,11 +1049,14 @@ mod tests {
     #[test]
     fn comparison_tokens() {
         single_successful_lex("==", Token::Equal, 0..2, "==");
+        single_successful_lex("=", Token::Assign, 0..1, "=");
         single_successful_lex("!=", Token::NotEqual, 0..2, "!=");
         single_successful_lex("<", Token::LessThan, 0..1, "<");
         single_successful_lex("<=", Token::LessThanEqual, 0..2, "<=");
         single_successful_lex(">", Token::GreaterThan, 0..1, ">");
         single_successful_lex(">=", Token::GreaterThanEqual, 0..2, ">=");
+        single_successful_lex("in", Token::In, 0..2, "in");
+        single_successful_lex("index", Token::Ident("index".to_string()), 0..5, "index");
     }
 
     #[test]
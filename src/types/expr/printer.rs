@@ -0,0 +1,354 @@
+//! Printer
+
+use std::fmt::Write;
+
+use super::ast::*;
+
+/// How tightly an expression binds as a reader would expect, higher binds tighter. The parser has
+/// no precedence, a binary operator takes everything after it as its right hand side, so
+/// parentheses go in wherever either the parser or a reader would otherwise group differently.
+fn precedence(kind: &ExprKind) -> u8 {
+    match kind {
+        ExprKind::If(_, _, _) => 0,
+        ExprKind::BinOp(BinOp::Or, _, _) => 1,
+        ExprKind::BinOp(BinOp::And, _, _) => 2,
+        ExprKind::BinOp(
+            BinOp::Equal
+            | BinOp::NotEqual
+            | BinOp::LessThan
+            | BinOp::LessThanEqual
+            | BinOp::GreaterThan
+            | BinOp::GreaterThanEqual
+            | BinOp::Contains
+            | BinOp::NotContains,
+            _,
+            _,
+        ) => 3,
+        ExprKind::BinOp(BinOp::Add | BinOp::Subtract, _, _) => 4,
+        ExprKind::BinOp(BinOp::Multiply | BinOp::Divide | BinOp::Modulo, _, _) => 5,
+        ExprKind::UnOp(_, _) => 6,
+        _ => 7,
+    }
+}
+
+/// Binary and unary operators and `if` take in whatever follows them, so they need parentheses
+/// before anything else that would otherwise be taken in too.
+fn is_open_ended(kind: &ExprKind) -> bool {
+    matches!(
+        kind,
+        ExprKind::BinOp(_, _, _) | ExprKind::UnOp(_, _) | ExprKind::If(_, _, _)
+    )
+}
+
+fn bin_op_str(op: &BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Subtract => "-",
+        BinOp::Multiply => "*",
+        BinOp::Divide => "/",
+        BinOp::Modulo => "%",
+        BinOp::And => "&&",
+        BinOp::Or => "||",
+        BinOp::Equal => "==",
+        BinOp::NotEqual => "!=",
+        BinOp::LessThan => "<",
+        BinOp::LessThanEqual => "<=",
+        BinOp::GreaterThan => ">",
+        BinOp::GreaterThanEqual => ">=",
+        BinOp::Contains => "in",
+        BinOp::NotContains => "not in",
+    }
+}
+
+fn write_str_lit(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            '\0' => out.push_str("\\0"),
+            c if c.is_control() => write!(out, "\\u{{{:x}}}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn write_lit(out: &mut String, lit: &Lit) {
+    match &lit.kind {
+        LitKind::Bool(b) => write!(out, "{}", b).unwrap(),
+        LitKind::Integer(s) | LitKind::Decimal(s) | LitKind::Token(s) => out.push_str(s),
+        LitKind::Str(s) => write_str_lit(out, s),
+        LitKind::Bytes(bytes) => match std::str::from_utf8(bytes) {
+            Ok(s) => {
+                out.push('b');
+                write_str_lit(out, s);
+            }
+            Err(_) => {
+                let bytes = bytes
+                    .iter()
+                    .map(|byte| byte.to_string())
+                    .collect::<Vec<_>>();
+                write!(out, "b[{}]", bytes.join(", ")).unwrap();
+            }
+        },
+    }
+}
+
+impl PatuiExpr {
+    /// The expression written out from its AST in a standard form, e.g. `(a+b)  ==c` becomes
+    /// `a + b == c`, regardless of how it was originally written. Parsing it again gives the same
+    /// AST.
+    pub(crate) fn to_canonical(&self) -> String {
+        let mut out = String::new();
+        self.write_canonical(&mut out);
+        out
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        match &self.kind {
+            ExprKind::Lit(lit) => write_lit(out, lit),
+            ExprKind::Ident(ident) => out.push_str(&ident.value),
+            ExprKind::Field(receiver, field) => {
+                receiver.write_receiver(out);
+                out.push('.');
+                out.push_str(&field.value);
+            }
+            ExprKind::Call(func, args) => {
+                func.write_receiver(out);
+                out.push('(');
+                write_joined(out, args.iter().map(|arg| &**arg));
+                out.push(')');
+            }
+            ExprKind::Index(receiver, index) => {
+                receiver.write_receiver(out);
+                out.push('[');
+                index.write_canonical(out);
+                out.push(']');
+            }
+            ExprKind::Slice(receiver, from, to) => {
+                receiver.write_receiver(out);
+                out.push('[');
+                if let Some(from) = from {
+                    from.write_canonical(out);
+                }
+                out.push(':');
+                if let Some(to) = to {
+                    to.write_canonical(out);
+                }
+                out.push(']');
+            }
+            ExprKind::If(cond, then, otherwise) => {
+                then.write_bracketed(out, is_open_ended(then.kind()));
+                out.push_str(" if ");
+                cond.write_bracketed(out, matches!(cond.kind(), ExprKind::If(_, _, _)));
+                out.push_str(" else ");
+                otherwise.write_canonical(out);
+            }
+            ExprKind::List(elems) => {
+                out.push('[');
+                write_joined(out, elems.iter().map(|elem| &**elem));
+                out.push(']');
+            }
+            ExprKind::Set(elems) => {
+                out.push('{');
+                write_joined(out, elems.iter().map(|elem| &**elem));
+                out.push('}');
+            }
+            ExprKind::Map(entries) => {
+                out.push('{');
+                for (idx, entry) in entries.iter().enumerate() {
+                    if idx > 0 {
+                        out.push_str(", ");
+                    }
+                    let (key, value) = &**entry;
+                    key.write_canonical(out);
+                    out.push_str(": ");
+                    value.write_canonical(out);
+                }
+                out.push('}');
+            }
+            ExprKind::UnOp(op, inner) => {
+                out.push(match op {
+                    UnOp::Neg => '-',
+                    UnOp::Not => '!',
+                });
+                // `-1` is lexed as a negative literal rather than negating `1`
+                let is_number = matches!(
+                    inner.kind(),
+                    ExprKind::Lit(Lit {
+                        kind: LitKind::Integer(_) | LitKind::Decimal(_)
+                    })
+                );
+                inner.write_bracketed(out, is_number || precedence(inner.kind()) < 6);
+            }
+            ExprKind::BinOp(op, lhs, rhs) => {
+                lhs.write_bracketed(out, is_open_ended(lhs.kind()));
+                out.push(' ');
+                out.push_str(bin_op_str(op));
+                out.push(' ');
+                // Binary operators group to the right when parsed but a reader would take
+                // `a - b - c` as `(a - b) - c`, so even the same precedence is bracketed
+                rhs.write_bracketed(
+                    out,
+                    is_open_ended(rhs.kind()) && precedence(rhs.kind()) <= precedence(&self.kind),
+                );
+            }
+        }
+    }
+
+    /// Write something a field, index, slice or call applies to.
+    fn write_receiver(&self, out: &mut String) {
+        self.write_bracketed(out, is_open_ended(self.kind()));
+    }
+
+    fn write_bracketed(&self, out: &mut String, is_bracketed: bool) {
+        if is_bracketed {
+            out.push('(');
+            self.write_canonical(out);
+            out.push(')');
+        } else {
+            self.write_canonical(out);
+        }
+    }
+}
+
+fn write_joined<'a>(out: &mut String, exprs: impl Iterator<Item = &'a PatuiExpr>) {
+    for (idx, expr) in exprs.enumerate() {
+        if idx > 0 {
+            out.push_str(", ");
+        }
+        expr.write_canonical(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use assertor::*;
+    use bytes::Bytes;
+    use tracing_test::traced_test;
+
+    use super::*;
+    use crate::types::expr::visitor::Visitor;
+
+    fn canonical(expr_string: &str) -> String {
+        PatuiExpr::try_from(expr_string).unwrap().to_canonical()
+    }
+
+    #[traced_test]
+    #[test]
+    fn canonical_forms() {
+        for (expr_string, expected) in [
+            ("foo", "foo"),
+            ("foo . bar [ 2 ]", "foo.bar[2]"),
+            ("foo.bar[bar.foo]", "foo.bar[bar.foo]"),
+            ("foo[ 1 : ]", "foo[1:]"),
+            ("foo[..3]", "foo[:3]"),
+            ("baz( 1,2 , 3 )", "baz(1, 2, 3)"),
+            ("0X1F", "0x1f"),
+            ("TRUE", "true"),
+            ("'a\"b'", "\"a\\\"b\""),
+            ("\"tab\\there\"", "\"tab\\there\""),
+            ("b'abc'", "b\"abc\""),
+            ("b[104, 0x69]", "b\"hi\""),
+            ("b[255, 0]", "b[255, 0]"),
+            ("[1,2]", "[1, 2]"),
+            ("{\"a\" : 1}", "{\"a\": 1}"),
+            ("{1,2}", "{1, 2}"),
+            ("x AND y", "x && y"),
+            ("x NOT IN y", "x not in y"),
+            ("not x", "!x"),
+            ("-(1)", "-(1)"),
+            ("-x", "-x"),
+            ("-(a + c)", "-(a + c)"),
+            ("!(a == c)", "!(a == c)"),
+            ("(a == c) == d", "(a == c) == d"),
+            ("a - (c - d)", "a - (c - d)"),
+            ("a - (c * d)", "a - c * d"),
+            ("a * (c - d)", "a * (c - d)"),
+            ("(a + c).len()", "(a + c).len()"),
+            ("(-a)[0]", "(-a)[0]"),
+            ("a if k else c if d else e", "a if k else c if d else e"),
+            ("(a + c) if k else e", "(a + c) if k else e"),
+            ("a if (x if y else z) else e", "a if (x if y else z) else e"),
+            ("# comment\nfoo   ==   1 # trailing", "foo == 1"),
+        ] {
+            assert_that!(canonical(expr_string)).is_equal_to(expected.to_string());
+        }
+    }
+
+    /// Every subexpression in canonical form, `raw` differs between the original and the reparsed
+    /// canonical form so the trees can't be compared directly.
+    fn subexprs(expr: &PatuiExpr) -> Vec<String> {
+        struct Subexprs(Vec<String>);
+
+        impl Visitor for Subexprs {
+            fn visit_expr(&mut self, expr: &PatuiExpr) -> eyre::Result<()> {
+                self.0.push(expr.to_canonical());
+                Ok(())
+            }
+        }
+
+        let mut visitor = Subexprs(vec![]);
+        expr.visit(&mut visitor).unwrap();
+        visitor.0
+    }
+
+    #[traced_test]
+    #[test]
+    fn canonical_reparses_identically() {
+        for expr_string in [
+            "((foo.bar[2].baz(1, 2, 3) + 5) == 123) && foobar[\"abc\"]",
+            "(steps.foo.out.len() == 1) && (steps.foo.out[0].lower() == \"ready\")",
+            "steps.foo.out[0][2:5] == b[0x00, 0x01]",
+            "a * c + d - e / f % g",
+            "((a * c) + d) - ((e / f) % g)",
+            "!(x in [1, 2, 3]) || (-y <= -(2.5))",
+            "{\"a\": [1, {2, 3}], \"c\": b\"\\n\"}",
+            "zip(steps.foo.out, steps.bar.out)[1:]",
+            "1 if x == 2 else (3 if y else 4)",
+            "\"\\u{1F600} \\u{7}\" == '\\0'",
+        ] {
+            let expr = PatuiExpr::try_from(expr_string).unwrap();
+            let canonical = expr.to_canonical();
+
+            let reparsed = PatuiExpr::try_from(&canonical[..]).unwrap();
+            assert_that!(subexprs(&reparsed)).is_equal_to(subexprs(&expr));
+            assert_that!(reparsed.to_canonical()).is_equal_to(canonical);
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn display_without_raw() {
+        let expr = PatuiExpr {
+            raw: "".to_string(),
+            kind: ExprKind::Index(
+                P {
+                    ptr: Box::new(PatuiExpr {
+                        raw: "".to_string(),
+                        kind: ExprKind::Ident(Ident {
+                            value: "foo".to_string(),
+                        }),
+                    }),
+                },
+                P {
+                    ptr: Box::new(PatuiExpr {
+                        raw: "".to_string(),
+                        kind: ExprKind::Lit(Lit {
+                            kind: LitKind::Bytes(Bytes::from_static(&[0xfe])),
+                        }),
+                    }),
+                },
+            ),
+        };
+        assert_that!(expr.to_string()).is_equal_to("foo[b[254]]".to_string());
+
+        // What was written is kept when there is something
+        let expr = PatuiExpr::try_from("foo [ 1 ]").unwrap();
+        assert_that!(expr.to_string()).is_equal_to("foo [ 1 ]".to_string());
+    }
+}
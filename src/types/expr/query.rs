@@ -16,6 +16,7 @@ pub(crate) fn get_all_idents(expr: &PatuiExpr) -> Result<Vec<PatuiExpr>> {
                 ExprKind::Ident(_)
                 | ExprKind::Field(_, _)
                 | ExprKind::Index(_, _)
+                | ExprKind::Slice(_, _, _)
                 | ExprKind::Call(_, _) => {
                     self.idents.push(expr.clone());
                 }
@@ -31,6 +32,42 @@ pub(crate) fn get_all_idents(expr: &PatuiExpr) -> Result<Vec<PatuiExpr>> {
     Ok(visitor.idents)
 }
 
+/// A reference to a channel of another step, `steps.<step>.<channel>`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct StepRef {
+    pub(crate) expr: PatuiExpr,
+    pub(crate) step: String,
+    pub(crate) channel: String,
+}
+
+/// Find all the references to other steps' channels in `expr`.
+pub(crate) fn get_step_refs(expr: &PatuiExpr) -> Result<Vec<StepRef>> {
+    let mut step_refs = vec![];
+
+    for ident in get_all_idents(expr)? {
+        let ExprKind::Field(root_expr, channel) = ident.kind() else {
+            continue;
+        };
+        let ExprKind::Field(root_expr, step) = root_expr.kind() else {
+            continue;
+        };
+        let ExprKind::Ident(root_ident) = root_expr.kind() else {
+            continue;
+        };
+        if root_ident.value != "steps" {
+            continue;
+        }
+
+        step_refs.push(StepRef {
+            step: step.value.clone(),
+            channel: channel.value.clone(),
+            expr: ident,
+        });
+    }
+
+    Ok(step_refs)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -269,4 +306,22 @@ mod tests {
             ),
         });
     }
+
+    #[traced_test]
+    #[test]
+    fn step_refs() {
+        let expr = "steps.foo.out[0] == steps.bar.echo.len() && foo.bar.baz && steps.baz"
+            .try_into()
+            .unwrap();
+        let step_refs = get_step_refs(&expr).unwrap();
+
+        assert_that!(step_refs
+            .iter()
+            .map(|x| (&x.expr.raw[..], &x.step[..], &x.channel[..]))
+            .collect::<Vec<_>>())
+        .is_equal_to(vec![
+            ("steps.foo.out", "foo", "out"),
+            ("steps.bar.echo", "bar", "echo"),
+        ]);
+    }
 }
@@ -44,6 +44,12 @@ impl PatuiExpr {
                 p.visit(visitor)?;
                 p1.visit(visitor)?;
             }
+            ExprKind::Slice(p, p1, p2) => {
+                p.visit(visitor)?;
+                for expr in [p1, p2].into_iter().flatten() {
+                    expr.visit(visitor)?;
+                }
+            }
             ExprKind::If(p, p1, p2) => {
                 p.visit(visitor)?;
                 p1.visit(visitor)?;
@@ -1,33 +1,46 @@
+mod data;
 mod other;
 mod transform_stream;
 
 use std::collections::HashMap;
 
-use bytes::Bytes;
 use convert_case::{Case, Casing};
 use eyre::{eyre, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use strum::{EnumDiscriminants, IntoStaticStr, VariantNames};
 
+pub(crate) use data::{PatuiStepData, PatuiStepDataFlavour};
 pub(crate) use other::{
-    PatuiStepAssertion, PatuiStepAssertionEditable, PatuiStepPlugin, PatuiStepPluginEditable,
-    PatuiStepRead, PatuiStepReadEditable, PatuiStepSender, PatuiStepSenderEditable, PatuiStepWrite,
-    PatuiStepWriteEditable,
+    PatuiStepAssertion, PatuiStepAssertionEditable, PatuiStepAssertionSample, PatuiStepFileSink,
+    PatuiStepFileSinkEditable, PatuiStepFileSinkFormat, PatuiStepPlugin, PatuiStepPluginEditable,
+    PatuiStepPluginTransport, PatuiStepRead, PatuiStepReadEditable, PatuiStepSender,
+    PatuiStepSenderEditable, PatuiStepWrite, PatuiStepWriteEditable,
+};
+pub(crate) use transform_stream::{
+    PatuiStepTransform, PatuiStepTransformEditable, PatuiStepTransformStream,
+    PatuiStepTransformStreamEditable,
 };
-pub(crate) use transform_stream::{PatuiStepTransformStream, PatuiStepTransformStreamEditable};
 
 #[cfg(test)]
 pub(crate) use transform_stream::PatuiStepTransformStreamFlavour;
 
 use super::PatuiExpr;
 
+/// The channel a step publishes its output on when it doesn't declare any others, referenced in
+/// expressions as `steps.<name>.out`.
+pub(crate) const DEFAULT_CHANNEL: &str = "out";
+
 /// PatuiStepEditable is to endable users ability to edit steps before they
 /// are saved to the database, similar to PatuiTestEditable.
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepEditable {
     pub(crate) name: String,
     pub(crate) when: Option<Option<String>>,
     pub(crate) depends_on: Option<Vec<PatuiStepEditable>>,
+    /// Fail the step if it's still going this many milliseconds after it started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) timeout_ms: Option<u64>,
     pub(crate) details: PatuiStepDetailsEditable,
 }
 
@@ -37,6 +50,7 @@ impl From<PatuiStep> for PatuiStepEditable {
             name: step.name,
             when: Some(step.when),
             depends_on: Some(step.depends_on.into_iter().map(|x| x.into()).collect()),
+            timeout_ms: step.timeout_ms,
             details: match step.details {
                 PatuiStepDetails::TransformStream(stream) => {
                     PatuiStepDetailsEditable::TransformStream(PatuiStepTransformStreamEditable {
@@ -44,9 +58,16 @@ impl From<PatuiStep> for PatuiStepEditable {
                         flavour: stream.flavour,
                     })
                 }
+                PatuiStepDetails::Transform(transform) => {
+                    PatuiStepDetailsEditable::Transform(PatuiStepTransformEditable {
+                        input: transform.input.into(),
+                        expr: transform.expr.into(),
+                    })
+                }
                 PatuiStepDetails::Assertion(assertion) => {
                     PatuiStepDetailsEditable::Assertion(PatuiStepAssertionEditable {
                         expr: assertion.expr.into(),
+                        sample: assertion.sample,
                     })
                 }
                 PatuiStepDetails::Read(patui_step_read) => {
@@ -59,6 +80,13 @@ impl From<PatuiStep> for PatuiStepEditable {
                         out: patui_step_write.out.into(),
                     })
                 }
+                PatuiStepDetails::FileSink(sink) => {
+                    PatuiStepDetailsEditable::FileSink(PatuiStepFileSinkEditable {
+                        input: sink.input.into(),
+                        path: sink.path,
+                        format: sink.format,
+                    })
+                }
                 PatuiStepDetails::Sender(patui_step_sender) => {
                     PatuiStepDetailsEditable::Sender(PatuiStepSenderEditable {
                         expr: patui_step_sender.expr.into(),
@@ -81,6 +109,9 @@ impl From<PatuiStep> for PatuiStepEditable {
                                 .map(|(k, v)| (k, v.into()))
                                 .collect(),
                         ),
+                        channels: Some(patui_step_plugin.channels),
+                        shutdown_grace_ms: patui_step_plugin.shutdown_grace_ms,
+                        transport: patui_step_plugin.transport,
                     })
                 }
             },
@@ -94,6 +125,7 @@ impl From<&PatuiStep> for PatuiStepEditable {
             name: value.name.clone(),
             when: Some(value.when.clone()),
             depends_on: Some(value.depends_on.iter().map(|x| x.into()).collect()),
+            timeout_ms: value.timeout_ms,
             details: match &value.details {
                 PatuiStepDetails::TransformStream(stream) => {
                     PatuiStepDetailsEditable::TransformStream(PatuiStepTransformStreamEditable {
@@ -101,9 +133,16 @@ impl From<&PatuiStep> for PatuiStepEditable {
                         flavour: stream.flavour.clone(),
                     })
                 }
+                PatuiStepDetails::Transform(transform) => {
+                    PatuiStepDetailsEditable::Transform(PatuiStepTransformEditable {
+                        input: (&transform.input).into(),
+                        expr: (&transform.expr).into(),
+                    })
+                }
                 PatuiStepDetails::Assertion(assertion) => {
                     PatuiStepDetailsEditable::Assertion(PatuiStepAssertionEditable {
                         expr: (&assertion.expr).into(),
+                        sample: assertion.sample.clone(),
                     })
                 }
                 PatuiStepDetails::Read(patui_step_read) => {
@@ -116,6 +155,13 @@ impl From<&PatuiStep> for PatuiStepEditable {
                         out: (&patui_step_write.out).into(),
                     })
                 }
+                PatuiStepDetails::FileSink(sink) => {
+                    PatuiStepDetailsEditable::FileSink(PatuiStepFileSinkEditable {
+                        input: (&sink.input).into(),
+                        path: sink.path.clone(),
+                        format: sink.format.clone(),
+                    })
+                }
                 PatuiStepDetails::Sender(patui_step_sender) => {
                     PatuiStepDetailsEditable::Sender(PatuiStepSenderEditable {
                         expr: (&patui_step_sender.expr).into(),
@@ -136,6 +182,9 @@ impl From<&PatuiStep> for PatuiStepEditable {
                                 .map(|(k, v)| (k.clone(), v.into()))
                                 .collect(),
                         ),
+                        channels: Some(patui_step_plugin.channels.clone()),
+                        shutdown_grace_ms: patui_step_plugin.shutdown_grace_ms,
+                        transport: patui_step_plugin.transport,
                     })
                 }
             },
@@ -150,6 +199,8 @@ pub(crate) struct PatuiStep {
     pub(crate) name: String,
     pub(crate) when: Option<String>,
     pub(crate) depends_on: Vec<PatuiStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) timeout_ms: Option<u64>,
     pub(crate) details: PatuiStepDetails,
 }
 
@@ -159,12 +210,18 @@ impl TryFrom<&PatuiStepEditable> for PatuiStep {
     fn try_from(value: &PatuiStepEditable) -> Result<Self, Self::Error> {
         Ok(PatuiStep {
             name: value.name.clone(),
-            when: value.when.clone().unwrap_or(None),
+            // An empty `when` is the same as not having a condition at all
+            when: value
+                .when
+                .clone()
+                .flatten()
+                .filter(|when| !when.trim().is_empty()),
             depends_on: value
                 .depends_on
                 .as_ref()
                 .map(|x| x.iter().map(|x| x.try_into()).collect())
                 .unwrap_or_else(|| Ok(Vec::new()))?,
+            timeout_ms: value.timeout_ms,
             details: match &value.details {
                 PatuiStepDetailsEditable::TransformStream(stream) => {
                     PatuiStepDetails::TransformStream(PatuiStepTransformStream {
@@ -172,9 +229,16 @@ impl TryFrom<&PatuiStepEditable> for PatuiStep {
                         flavour: stream.flavour.clone(),
                     })
                 }
+                PatuiStepDetailsEditable::Transform(transform) => {
+                    PatuiStepDetails::Transform(PatuiStepTransform {
+                        input: (&transform.input[..]).try_into()?,
+                        expr: (&transform.expr[..]).try_into()?,
+                    })
+                }
                 PatuiStepDetailsEditable::Assertion(assertion) => {
                     PatuiStepDetails::Assertion(PatuiStepAssertion {
                         expr: (&assertion.expr[..]).try_into()?,
+                        sample: assertion.sample.clone(),
                     })
                 }
                 PatuiStepDetailsEditable::Read(patui_step_read_editable) => {
@@ -187,6 +251,13 @@ impl TryFrom<&PatuiStepEditable> for PatuiStep {
                         out: (&patui_step_write_editable.out[..]).try_into()?,
                     })
                 }
+                PatuiStepDetailsEditable::FileSink(sink) => {
+                    PatuiStepDetails::FileSink(PatuiStepFileSink {
+                        input: (&sink.input[..]).try_into()?,
+                        path: sink.path.clone(),
+                        format: sink.format.clone(),
+                    })
+                }
                 PatuiStepDetailsEditable::Sender(patui_step_sender_editable) => {
                     PatuiStepDetails::Sender(PatuiStepSender {
                         expr: (&patui_step_sender_editable.expr[..]).try_into()?,
@@ -215,6 +286,12 @@ impl TryFrom<&PatuiStepEditable> for PatuiStep {
                                 .collect::<Result<_>>()?,
                             None => HashMap::new(),
                         },
+                        channels: patui_step_plugin_editable
+                            .channels
+                            .clone()
+                            .unwrap_or_default(),
+                        shutdown_grace_ms: patui_step_plugin_editable.shutdown_grace_ms,
+                        transport: patui_step_plugin_editable.transport,
                     })
                 }
             },
@@ -222,11 +299,13 @@ impl TryFrom<&PatuiStepEditable> for PatuiStep {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) enum PatuiStepDetailsEditable {
     TransformStream(PatuiStepTransformStreamEditable),
+    Transform(PatuiStepTransformEditable),
     Read(PatuiStepReadEditable),
     Write(PatuiStepWriteEditable),
+    FileSink(PatuiStepFileSinkEditable),
     Assertion(PatuiStepAssertionEditable),
     Sender(PatuiStepSenderEditable),
     Plugin(PatuiStepPluginEditable),
@@ -239,13 +318,52 @@ pub(crate) enum PatuiStepDetailsEditable {
 pub(crate) enum PatuiStepDetails {
     Read(PatuiStepRead),
     Write(PatuiStepWrite),
+    FileSink(PatuiStepFileSink),
     Sender(PatuiStepSender),
     TransformStream(PatuiStepTransformStream),
+    Transform(PatuiStepTransform),
     Assertion(PatuiStepAssertion),
     Plugin(PatuiStepPlugin),
 }
 
 impl PatuiStepDetails {
+    /// The channels that other steps can subscribe to on this step. `None` means the step hasn't
+    /// declared its channels and any channel name is accepted.
+    pub(crate) fn channels(&self) -> Option<Vec<String>> {
+        match self {
+            PatuiStepDetails::Read(_)
+            | PatuiStepDetails::Sender(_)
+            | PatuiStepDetails::TransformStream(_)
+            | PatuiStepDetails::Transform(_) => Some(vec![DEFAULT_CHANNEL.to_string()]),
+            PatuiStepDetails::Write(_)
+            | PatuiStepDetails::FileSink(_)
+            | PatuiStepDetails::Assertion(_) => Some(vec![]),
+            PatuiStepDetails::Plugin(plugin) => {
+                if plugin.channels.is_empty() {
+                    None
+                } else {
+                    Some(plugin.channels.clone())
+                }
+            }
+        }
+    }
+
+    /// All the expressions in the step that are evaluated when it runs.
+    pub(crate) fn exprs(&self) -> Vec<&PatuiExpr> {
+        match self {
+            PatuiStepDetails::Read(read) => vec![&read.r#in],
+            PatuiStepDetails::Write(write) => vec![&write.out],
+            PatuiStepDetails::FileSink(sink) => vec![&sink.input],
+            PatuiStepDetails::Sender(sender) => vec![&sender.expr],
+            PatuiStepDetails::TransformStream(stream) => vec![&stream.r#in],
+            PatuiStepDetails::Transform(transform) => vec![&transform.input, &transform.expr],
+            PatuiStepDetails::Assertion(assertion) => vec![&assertion.expr],
+            PatuiStepDetails::Plugin(plugin) => {
+                plugin.config.values().chain(plugin.r#in.values()).collect()
+            }
+        }
+    }
+
     pub(crate) fn get_display_yaml(&self) -> Result<String> {
         let mut ret = String::new();
 
@@ -262,9 +380,11 @@ impl PatuiStepDetails {
     pub(crate) fn inner_yaml(&self) -> Result<String> {
         Ok(match self {
             PatuiStepDetails::TransformStream(stream) => serde_yaml::to_string(stream)?,
+            PatuiStepDetails::Transform(transform) => serde_yaml::to_string(transform)?,
             PatuiStepDetails::Assertion(assertion) => serde_yaml::to_string(assertion)?,
             PatuiStepDetails::Read(reader) => serde_yaml::to_string(reader)?,
             PatuiStepDetails::Write(writer) => serde_yaml::to_string(writer)?,
+            PatuiStepDetails::FileSink(sink) => serde_yaml::to_string(sink)?,
             PatuiStepDetails::Sender(sender) => serde_yaml::to_string(sender)?,
             PatuiStepDetails::Plugin(plugin) => serde_yaml::to_string(plugin)?,
         })
@@ -293,33 +413,13 @@ impl PatuiStepDetails {
     // }
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub(crate) struct PatuiStepData {
-    pub(crate) timestamp: chrono::DateTime<chrono::Utc>,
-    pub(crate) data: PatuiStepDataFlavour,
-}
-
-impl PatuiStepData {
-    pub(crate) fn new(data: PatuiStepDataFlavour) -> Self {
-        let timestamp = chrono::Utc::now();
-        Self { timestamp, data }
-    }
-
-    // pub(crate) fn into_data(self) -> PatuiStepDataFlavour {
-    //     self.data
-    // }
-
-    #[cfg(test)]
-    pub(crate) fn data(&self) -> &PatuiStepDataFlavour {
-        &self.data
-    }
-}
-
 impl TryFrom<super::ptplugin::PatuiStepData> for PatuiStepData {
     type Error = eyre::Error;
 
     fn try_from(value: super::ptplugin::PatuiStepData) -> Result<Self, Self::Error> {
-        Ok(PatuiStepData::new(rmp_serde::from_slice(&value.bytes)?))
+        Ok(PatuiStepData::new(PatuiStepDataFlavour::from_bytes(
+            &value.bytes,
+        )?))
     }
 }
 
@@ -328,91 +428,11 @@ impl TryFrom<PatuiStepData> for super::ptplugin::PatuiStepData {
 
     fn try_from(value: PatuiStepData) -> Result<Self, Self::Error> {
         Ok(super::ptplugin::PatuiStepData {
-            bytes: rmp_serde::to_vec(&value.data)?,
+            bytes: value.data.to_bytes()?,
         })
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
-pub(crate) enum PatuiStepDataFlavour {
-    Null,
-    Bool(bool),
-    Bytes(Bytes),
-    String(String),
-    Integer(String),
-    Float(String),
-    Array(Vec<PatuiStepDataFlavour>),
-    Map(HashMap<String, PatuiStepDataFlavour>),
-    Set(Vec<PatuiStepDataFlavour>),
-}
-
-impl PatuiStepDataFlavour {
-    pub(crate) fn as_bytes(&self) -> Result<&Bytes> {
-        match self {
-            Self::Bytes(bytes) => Ok(bytes),
-            _ => Err(eyre!("not bytes")),
-        }
-    }
-
-    // pub(crate) fn as_number(&self) -> Result<i64> {
-    //     match self {
-    //         Self::Number(number) => Ok(*number),
-    //         _ => Err(eyre!("not number")),
-    //     }
-    // }
-
-    // pub(crate) fn is_bytes(&self) -> bool {
-    //     matches!(self, Self::Bytes(_))
-    // }
-
-    // pub(crate) fn is_string(&self) -> bool {
-    //     matches!(self, Self::String(_))
-    // }
-
-    // pub(crate) fn is_number(&self) -> bool {
-    //     matches!(self, Self::Number(_))
-    // }
-
-    #[cfg(test)]
-    pub(crate) fn is_object(&self) -> bool {
-        matches!(self, Self::Map(_))
-    }
-
-    // pub(crate) fn is_yaml(&self) -> bool {
-    //     matches!(self, Self::Yaml(_))
-    // }
-}
-
-impl From<bool> for PatuiStepDataFlavour {
-    fn from(value: bool) -> Self {
-        Self::Bool(value)
-    }
-}
-
-impl From<Bytes> for PatuiStepDataFlavour {
-    fn from(value: Bytes) -> Self {
-        Self::Bytes(value)
-    }
-}
-
-impl From<String> for PatuiStepDataFlavour {
-    fn from(value: String) -> Self {
-        Self::String(value)
-    }
-}
-
-impl From<i64> for PatuiStepDataFlavour {
-    fn from(value: i64) -> Self {
-        Self::Integer(format!("{}", value))
-    }
-}
-
-impl From<f64> for PatuiStepDataFlavour {
-    fn from(value: f64) -> Self {
-        Self::Float(format!("{}", value))
-    }
-}
-
 impl TryFrom<serde_json::Value> for PatuiStepDataFlavour {
     type Error = eyre::Error;
 
@@ -448,6 +468,35 @@ impl TryFrom<serde_json::Value> for PatuiStepDataFlavour {
     }
 }
 
+/// Bytes that aren't valid UTF-8 become a list of the byte values and sets become lists, numbers
+/// too big for JSON are kept as strings.
+impl From<&PatuiStepDataFlavour> for serde_json::Value {
+    fn from(value: &PatuiStepDataFlavour) -> Self {
+        match value {
+            PatuiStepDataFlavour::Null => serde_json::Value::Null,
+            PatuiStepDataFlavour::Bool(value) => serde_json::Value::Bool(*value),
+            PatuiStepDataFlavour::Bytes(bytes) => match std::str::from_utf8(bytes) {
+                Ok(value) => serde_json::Value::String(value.to_string()),
+                Err(_) => serde_json::Value::Array(bytes.iter().map(|b| (*b).into()).collect()),
+            },
+            PatuiStepDataFlavour::String(value) => serde_json::Value::String(value.clone()),
+            PatuiStepDataFlavour::Integer(value) | PatuiStepDataFlavour::Float(value) => {
+                serde_json::from_str::<serde_json::Number>(value)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.clone()))
+            }
+            PatuiStepDataFlavour::Array(values) | PatuiStepDataFlavour::Set(values) => {
+                serde_json::Value::Array(values.iter().map(|value| value.into()).collect())
+            }
+            PatuiStepDataFlavour::Map(map) => serde_json::Value::Object(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
 pub(crate) enum PatuiStepDataTransfer {
     #[default]
@@ -455,3 +504,190 @@ pub(crate) enum PatuiStepDataTransfer {
     Fixed(PatuiStepDataFlavour),
     Ref(Box<(PatuiStep, String)>),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{Hash, Hasher};
+
+    use assertor::*;
+    use bytes::Bytes;
+
+    use super::*;
+
+    const FLAVOUR_SIZE: usize = std::mem::size_of::<PatuiStepDataFlavour>();
+
+    #[test]
+    fn estimated_size_scalars() {
+        assert_that!(PatuiStepDataFlavour::Null.estimated_size()).is_equal_to(FLAVOUR_SIZE);
+        assert_that!(PatuiStepDataFlavour::Bool(true).estimated_size()).is_equal_to(FLAVOUR_SIZE);
+        assert_that!(PatuiStepDataFlavour::from("hello".to_string()).estimated_size())
+            .is_equal_to(FLAVOUR_SIZE + 5);
+        assert_that!(PatuiStepDataFlavour::from(12345).estimated_size())
+            .is_equal_to(FLAVOUR_SIZE + 5);
+    }
+
+    #[test]
+    fn estimated_size_large_bytes() {
+        let blob = PatuiStepDataFlavour::Bytes(Bytes::from(vec![0u8; 10 * 1024 * 1024]));
+
+        assert_that!(blob.estimated_size()).is_equal_to(FLAVOUR_SIZE + 10 * 1024 * 1024);
+    }
+
+    #[test]
+    fn estimated_size_nested() {
+        let nested = PatuiStepDataFlavour::Map(HashMap::from([
+            (
+                "list".to_string(),
+                PatuiStepDataFlavour::Array(vec![
+                    PatuiStepDataFlavour::from(Bytes::from("abc")),
+                    PatuiStepDataFlavour::Set(vec![PatuiStepDataFlavour::from("de".to_string())]),
+                ]),
+            ),
+            ("flag".to_string(), PatuiStepDataFlavour::from(false)),
+        ]));
+
+        let list = FLAVOUR_SIZE + (FLAVOUR_SIZE + 3) + (FLAVOUR_SIZE + (FLAVOUR_SIZE + 2));
+        let flag = FLAVOUR_SIZE;
+        let keys = 2 * std::mem::size_of::<String>() + "list".len() + "flag".len();
+
+        assert_that!(nested.estimated_size()).is_equal_to(FLAVOUR_SIZE + keys + list + flag);
+    }
+
+    fn all_flavours() -> Vec<PatuiStepDataFlavour> {
+        vec![
+            PatuiStepDataFlavour::Null,
+            PatuiStepDataFlavour::Bool(false),
+            PatuiStepDataFlavour::Bytes(Bytes::from(vec![0, 1, 0xff])),
+            PatuiStepDataFlavour::String("test".to_string()),
+            PatuiStepDataFlavour::Integer("-12".to_string()),
+            PatuiStepDataFlavour::Float("1.5".to_string()),
+            PatuiStepDataFlavour::Array(vec![
+                PatuiStepDataFlavour::Integer("1".to_string()),
+                PatuiStepDataFlavour::Array(vec![]),
+            ]),
+            PatuiStepDataFlavour::Map(HashMap::from([
+                ("a".to_string(), PatuiStepDataFlavour::Null),
+                (
+                    "b".to_string(),
+                    PatuiStepDataFlavour::Set(vec![PatuiStepDataFlavour::Bool(true)]),
+                ),
+            ])),
+            PatuiStepDataFlavour::Set(vec![]),
+        ]
+    }
+
+    #[test]
+    fn flavour_bytes_round_trip() {
+        for flavour in all_flavours() {
+            let bytes = flavour.to_bytes();
+            assert_that!(bytes).is_ok();
+            let res = PatuiStepDataFlavour::from_bytes(&bytes.unwrap());
+            assert_that!(res).is_ok();
+            assert_that!(res.unwrap()).is_equal_to(flavour);
+        }
+
+        let step_data = PatuiStepData::new(PatuiStepDataFlavour::String("test".to_string()));
+        let plugin_data = super::super::ptplugin::PatuiStepData::try_from(step_data.clone());
+        assert_that!(plugin_data).is_ok();
+        let res = PatuiStepData::try_from(plugin_data.unwrap());
+        assert_that!(res).is_ok();
+        assert_that!(res.unwrap().data).is_equal_to(step_data.data);
+
+        assert_that!(PatuiStepDataFlavour::from_bytes(b"not data")).is_err();
+    }
+
+    fn hash_of(flavour: &PatuiStepDataFlavour) -> u64 {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        flavour.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn flavour_equal_values_hash_equal() {
+        for (lhs, rhs) in all_flavours().into_iter().zip(all_flavours()) {
+            assert_that!(lhs).is_equal_to(rhs.clone());
+            assert_that!(hash_of(&lhs)).is_equal_to(hash_of(&rhs));
+        }
+
+        // Maps are equal however their entries were inserted
+        let entries = (0..20)
+            .map(|i| (i.to_string(), PatuiStepDataFlavour::from(i)))
+            .collect::<Vec<_>>();
+        let map = PatuiStepDataFlavour::Map(entries.iter().cloned().collect());
+        let reversed = PatuiStepDataFlavour::Map(entries.into_iter().rev().collect());
+        assert_that!(map).is_equal_to(reversed.clone());
+        assert_that!(hash_of(&map)).is_equal_to(hash_of(&reversed));
+
+        // The same text as a different type isn't the same value
+        let string = PatuiStepDataFlavour::String("1".to_string());
+        let integer = PatuiStepDataFlavour::Integer("1".to_string());
+        assert_that!(string).is_not_equal_to(integer.clone());
+        assert_that!(hash_of(&string)).is_not_equal_to(hash_of(&integer));
+    }
+
+    #[test]
+    fn flavour_set_order_ignored_list_order_kept() {
+        let items = vec![
+            PatuiStepDataFlavour::from(1),
+            PatuiStepDataFlavour::from("two".to_string()),
+            PatuiStepDataFlavour::Array(vec![PatuiStepDataFlavour::Null]),
+        ];
+        let mut reversed = items.clone();
+        reversed.reverse();
+
+        let set = PatuiStepDataFlavour::Set(items.clone());
+        let reversed_set = PatuiStepDataFlavour::Set(reversed.clone());
+        assert_that!(set).is_equal_to(reversed_set.clone());
+        assert_that!(hash_of(&set)).is_equal_to(hash_of(&reversed_set));
+
+        let list = PatuiStepDataFlavour::Array(items.clone());
+        let reversed_list = PatuiStepDataFlavour::Array(reversed);
+        assert_that!(list).is_not_equal_to(reversed_list.clone());
+        assert_that!(hash_of(&list)).is_not_equal_to(hash_of(&reversed_list));
+
+        // Sets with the same items but different numbers of them differ
+        let doubled = PatuiStepDataFlavour::Set(vec![
+            PatuiStepDataFlavour::from(1),
+            PatuiStepDataFlavour::from(1),
+            PatuiStepDataFlavour::from(2),
+        ]);
+        let other = PatuiStepDataFlavour::Set(vec![
+            PatuiStepDataFlavour::from(1),
+            PatuiStepDataFlavour::from(2),
+            PatuiStepDataFlavour::from(2),
+        ]);
+        assert_that!(doubled).is_not_equal_to(other.clone());
+        assert_that!(hash_of(&doubled)).is_not_equal_to(hash_of(&other));
+        assert_that!(set).is_not_equal_to(list);
+    }
+
+    /// The encoding is fixed, plugins written in anything other than Rust depend on it, so these
+    /// are the bytes the test plugin sends.
+    #[test]
+    fn flavour_bytes_match_plugin() {
+        for (flavour, expected) in [
+            (PatuiStepDataFlavour::Null, &b"\xa4Null"[..]),
+            (PatuiStepDataFlavour::Bool(true), b"\x81\xa4Bool\xc3"),
+            (
+                PatuiStepDataFlavour::String("test".to_string()),
+                b"\x81\xa6String\xa4test",
+            ),
+            (
+                PatuiStepDataFlavour::Array(vec![
+                    PatuiStepDataFlavour::Integer("1".to_string()),
+                    PatuiStepDataFlavour::Integer("2".to_string()),
+                ]),
+                b"\x81\xa5Array\x92\x81\xa7Integer\xa11\x81\xa7Integer\xa12",
+            ),
+            (
+                PatuiStepDataFlavour::Map(HashMap::from([(
+                    "a".to_string(),
+                    PatuiStepDataFlavour::Integer("1".to_string()),
+                )])),
+                b"\x81\xa3Map\x81\xa1a\x81\xa7Integer\xa11",
+            ),
+        ] {
+            assert_that!(flavour.to_bytes().unwrap()).is_equal_to(expected.to_vec());
+        }
+    }
+}
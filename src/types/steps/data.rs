@@ -0,0 +1,267 @@
+//! The data passed between steps. This file is also pulled into plugins by path, e.g. the test
+//! plugin, so that both ends of the wire share one definition, it mustn't refer to anything else
+//! in the crate.
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fmt,
+    hash::{Hash, Hasher},
+};
+
+use bytes::Bytes;
+use eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+/// Values compare and hash by what they hold, so can be used as keys. Arrays are ordered whereas
+/// the items of sets and the entries of maps can be in any order.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) enum PatuiStepDataFlavour {
+    Null,
+    Bool(bool),
+    Bytes(Bytes),
+    String(String),
+    Integer(String),
+    Float(String),
+    Array(Vec<PatuiStepDataFlavour>),
+    Map(HashMap<String, PatuiStepDataFlavour>),
+    Set(Vec<PatuiStepDataFlavour>),
+}
+
+impl PatuiStepDataFlavour {
+    /// Encode for sending to or from a plugin, `from_bytes` reverses this.
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec(self)?)
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(rmp_serde::from_slice(bytes)?)
+    }
+
+    pub(crate) fn as_bytes(&self) -> Result<&Bytes> {
+        match self {
+            Self::Bytes(bytes) => Ok(bytes),
+            _ => Err(eyre!("not bytes")),
+        }
+    }
+
+    // pub(crate) fn as_number(&self) -> Result<i64> {
+    //     match self {
+    //         Self::Number(number) => Ok(*number),
+    //         _ => Err(eyre!("not number")),
+    //     }
+    // }
+
+    // pub(crate) fn is_bytes(&self) -> bool {
+    //     matches!(self, Self::Bytes(_))
+    // }
+
+    // pub(crate) fn is_string(&self) -> bool {
+    //     matches!(self, Self::String(_))
+    // }
+
+    // pub(crate) fn is_number(&self) -> bool {
+    //     matches!(self, Self::Number(_))
+    // }
+
+    /// Whether `lhs` and `rhs` hold the same items in any order, as sets are compared, going by
+    /// `eq`. Each item on one side pairs off with a different equal item on the other, so an item
+    /// has to appear as many times on both.
+    pub(crate) fn same_items(
+        lhs: &[Self],
+        rhs: &[Self],
+        eq: impl Fn(&Self, &Self) -> bool,
+    ) -> bool {
+        let mut is_paired = vec![false; rhs.len()];
+        lhs.len() == rhs.len()
+            && lhs.iter().all(|item| {
+                let pair = (0..rhs.len()).find(|&idx| !is_paired[idx] && eq(item, &rhs[idx]));
+                if let Some(idx) = pair {
+                    is_paired[idx] = true;
+                }
+                pair.is_some()
+            })
+    }
+
+    /// Rough number of bytes this value holds in memory, including everything nested inside
+    /// it. Allocator overhead and spare capacity aren't counted.
+    pub(crate) fn estimated_size(&self) -> usize {
+        let heap_size = match self {
+            Self::Null | Self::Bool(_) => 0,
+            Self::Bytes(bytes) => bytes.len(),
+            Self::String(s) | Self::Integer(s) | Self::Float(s) => s.len(),
+            Self::Array(values) | Self::Set(values) => {
+                values.iter().map(|value| value.estimated_size()).sum()
+            }
+            Self::Map(map) => map
+                .iter()
+                .map(|(key, value)| {
+                    std::mem::size_of::<String>() + key.len() + value.estimated_size()
+                })
+                .sum(),
+        };
+
+        std::mem::size_of::<Self>() + heap_size
+    }
+
+    #[cfg(test)]
+    pub(crate) fn is_object(&self) -> bool {
+        matches!(self, Self::Map(_))
+    }
+
+    // pub(crate) fn is_yaml(&self) -> bool {
+    //     matches!(self, Self::Yaml(_))
+    // }
+}
+
+impl PartialEq for PatuiStepDataFlavour {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Null, Self::Null) => true,
+            (Self::Bool(lhs), Self::Bool(rhs)) => lhs == rhs,
+            (Self::Bytes(lhs), Self::Bytes(rhs)) => lhs == rhs,
+            (Self::String(lhs), Self::String(rhs))
+            | (Self::Integer(lhs), Self::Integer(rhs))
+            | (Self::Float(lhs), Self::Float(rhs)) => lhs == rhs,
+            (Self::Array(lhs), Self::Array(rhs)) => lhs == rhs,
+            (Self::Map(lhs), Self::Map(rhs)) => lhs == rhs,
+            (Self::Set(lhs), Self::Set(rhs)) => Self::same_items(lhs, rhs, |lhs, rhs| lhs == rhs),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for PatuiStepDataFlavour {}
+
+impl Hash for PatuiStepDataFlavour {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        /// Hash items that can be in any order by hashing each alone and then their hashes in
+        /// sorted order.
+        fn hash_unordered<H: Hasher, T: Hash>(items: impl Iterator<Item = T>, state: &mut H) {
+            let mut hashes = items
+                .map(|item| {
+                    let mut hasher = DefaultHasher::new();
+                    item.hash(&mut hasher);
+                    hasher.finish()
+                })
+                .collect::<Vec<_>>();
+            hashes.sort_unstable();
+            hashes.hash(state);
+        }
+
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::Null => {}
+            Self::Bool(value) => value.hash(state),
+            Self::Bytes(bytes) => bytes.hash(state),
+            Self::String(value) | Self::Integer(value) | Self::Float(value) => value.hash(state),
+            Self::Array(values) => values.hash(state),
+            Self::Set(values) => hash_unordered(values.iter(), state),
+            Self::Map(map) => hash_unordered(map.iter(), state),
+        }
+    }
+}
+
+/// Writes the value the way it would be written in an expression, map keys are sorted.
+impl fmt::Display for PatuiStepDataFlavour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write_all(
+            f: &mut fmt::Formatter<'_>,
+            values: &[PatuiStepDataFlavour],
+            open: &str,
+            close: &str,
+        ) -> fmt::Result {
+            f.write_str(open)?;
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            f.write_str(close)
+        }
+
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Bytes(bytes) => {
+                f.write_str("b[")?;
+                for (i, byte) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", byte)?;
+                }
+                f.write_str("]")
+            }
+            Self::String(value) => write!(f, "{:?}", value),
+            Self::Integer(value) | Self::Float(value) => f.write_str(value),
+            Self::Array(values) => write_all(f, values, "[", "]"),
+            Self::Set(values) => write_all(f, values, "{", "}"),
+            Self::Map(map) => {
+                let mut entries = map.iter().collect::<Vec<_>>();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{:?}: {}", key, value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+impl From<bool> for PatuiStepDataFlavour {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<Bytes> for PatuiStepDataFlavour {
+    fn from(value: Bytes) -> Self {
+        Self::Bytes(value)
+    }
+}
+
+impl From<String> for PatuiStepDataFlavour {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<i64> for PatuiStepDataFlavour {
+    fn from(value: i64) -> Self {
+        Self::Integer(format!("{}", value))
+    }
+}
+
+impl From<f64> for PatuiStepDataFlavour {
+    fn from(value: f64) -> Self {
+        Self::Float(format!("{}", value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiStepData {
+    pub(crate) timestamp: chrono::DateTime<chrono::Utc>,
+    pub(crate) data: PatuiStepDataFlavour,
+}
+
+impl PatuiStepData {
+    pub(crate) fn new(data: PatuiStepDataFlavour) -> Self {
+        let timestamp = chrono::Utc::now();
+        Self { timestamp, data }
+    }
+
+    // pub(crate) fn into_data(self) -> PatuiStepDataFlavour {
+    //     self.data
+    // }
+
+    #[cfg(test)]
+    pub(crate) fn data(&self) -> &PatuiStepDataFlavour {
+        &self.data
+    }
+}
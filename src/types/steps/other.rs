@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::types::expr::PatuiExpr;
 
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepReadEditable {
     pub(crate) r#in: String,
 }
@@ -14,7 +15,7 @@ pub(crate) struct PatuiStepRead {
     pub(crate) r#in: PatuiExpr,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepWriteEditable {
     pub(crate) out: String,
 }
@@ -24,17 +25,59 @@ pub(crate) struct PatuiStepWrite {
     pub(crate) out: PatuiExpr,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+/// How a file sink writes each item it receives.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub(crate) enum PatuiStepFileSinkFormat {
+    /// Bytes and strings as they are, anything else as it's displayed.
+    #[default]
+    Raw,
+    /// Each item as JSON on a line of its own.
+    JsonLines,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct PatuiStepFileSinkEditable {
+    pub(crate) input: String,
+    pub(crate) path: String,
+    #[serde(default)]
+    pub(crate) format: PatuiStepFileSinkFormat,
+}
+
+/// Writes everything received from `input` to the file at `path`, e.g. to keep hold of a step's
+/// output to compare against later.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiStepFileSink {
+    pub(crate) input: PatuiExpr,
+    pub(crate) path: String,
+    pub(crate) format: PatuiStepFileSinkFormat,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepAssertionEditable {
     pub(crate) expr: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sample: Option<PatuiStepAssertionSample>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub(crate) struct PatuiStepAssertion {
     pub(crate) expr: PatuiExpr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) sample: Option<PatuiStepAssertionSample>,
 }
 
-#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+/// How often an assertion is evaluated as data arrives, by default it's evaluated for every
+/// item. Everything received is kept either way so indexing still sees every item.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PatuiStepAssertionSample {
+    /// Evaluate once every this many items.
+    EveryN(u64),
+    /// Evaluate at most once every this many milliseconds.
+    EveryMs(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepSenderEditable {
     pub(crate) expr: String,
 }
@@ -44,11 +87,19 @@ pub(crate) struct PatuiStepSender {
     pub(crate) expr: PatuiExpr,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepPluginEditable {
     pub(crate) path: String, // TODO: Find a better solution when we're publishing plugins
     pub(crate) config: Option<HashMap<String, String>>,
     pub(crate) r#in: Option<HashMap<String, String>>,
+    pub(crate) channels: Option<Vec<String>>,
+    /// How many milliseconds the plugin has to exit by itself once it's finished before it's
+    /// killed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) shutdown_grace_ms: Option<u64>,
+    /// How to connect to the plugin, IPv4 loopback when not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) transport: Option<PatuiStepPluginTransport>,
 }
 
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -56,4 +107,23 @@ pub(crate) struct PatuiStepPlugin {
     pub(crate) path: String, // TODO: Find a better solution when we're publishing plugins
     pub(crate) config: HashMap<String, PatuiExpr>,
     pub(crate) r#in: HashMap<String, PatuiExpr>,
+    /// Channels the plugin publishes on, when empty any channel may be subscribed to.
+    #[serde(default)]
+    pub(crate) channels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) shutdown_grace_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) transport: Option<PatuiStepPluginTransport>,
+}
+
+/// How Patui connects to a plugin it's started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub(crate) enum PatuiStepPluginTransport {
+    /// TCP on the IPv4 loopback address.
+    #[default]
+    Ipv4,
+    /// TCP on the IPv6 loopback address.
+    Ipv6,
+    /// A Unix domain socket.
+    Unix,
 }
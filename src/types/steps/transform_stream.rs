@@ -1,10 +1,11 @@
 //! Types related to streaming and transforming.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::types::expr::PatuiExpr;
 
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) enum PatuiStepTransformStreamFlavour {
     Utf8,
     #[default]
@@ -14,7 +15,7 @@ pub(crate) enum PatuiStepTransformStreamFlavour {
     Toml,
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
 pub(crate) struct PatuiStepTransformStreamEditable {
     pub(crate) r#in: String,
     pub(crate) flavour: PatuiStepTransformStreamFlavour,
@@ -25,3 +26,17 @@ pub(crate) struct PatuiStepTransformStream {
     pub(crate) r#in: PatuiExpr,
     pub(crate) flavour: PatuiStepTransformStreamFlavour,
 }
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub(crate) struct PatuiStepTransformEditable {
+    pub(crate) input: String,
+    pub(crate) expr: String,
+}
+
+/// Publishes the result of `expr` for each item arriving on `input`, where `input` in `expr`
+/// stands for that one item, e.g. `steps.foo.out * 2` doubles everything from `steps.foo.out`.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub(crate) struct PatuiStepTransform {
+    pub(crate) input: PatuiExpr,
+    pub(crate) expr: PatuiExpr,
+}
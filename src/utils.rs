@@ -1,6 +1,11 @@
+use std::{path::PathBuf, time::Duration};
+
 use chrono::Local;
-use eyre::Result;
-use tokio::net::TcpListener;
+use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
+use eyre::{eyre, Result};
+
+/// Where data is kept, relative to the current directory, when the platform has no usual place.
+const FALLBACK_DATA_DIR: &str = ".patui";
 
 pub(crate) fn get_current_time_string() -> String {
     Local::now().to_string()
@@ -10,7 +15,98 @@ pub(crate) fn get_current_timestamp() -> i64 {
     Local::now().timestamp_millis()
 }
 
-pub(crate) async fn get_unused_localhost_port() -> Result<u16> {
-    let listener = TcpListener::bind(format!("127.0.0.1:0")).await?;
-    Ok(listener.local_addr()?.port())
+/// Parse a duration such as `500ms`, `30s`, `2m` or `1h`, a plain number is taken as seconds.
+pub(crate) fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (amount, unit) = value.split_at(unit_start);
+
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| eyre!("Invalid duration `{}`, expected e.g. `30s` or `2m`", value))?;
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(amount)),
+        "" | "s" => Ok(Duration::from_secs(amount)),
+        "m" => Ok(Duration::from_secs(amount * 60)),
+        "h" => Ok(Duration::from_secs(amount * 60 * 60)),
+        _ => Err(eyre!(
+            "Invalid duration unit `{}` in `{}`, expected one of ms, s, m or h",
+            unit,
+            value
+        )),
+    }
+}
+
+fn get_app_strategy() -> Result<impl AppStrategy> {
+    Ok(choose_app_strategy(AppStrategyArgs {
+        top_level_domain: "rs".to_string(),
+        author: "strottos".to_string(),
+        app_name: "patui".to_string(),
+    })?)
+}
+
+pub(crate) fn get_data_dir() -> Result<PathBuf> {
+    Ok(data_dir_or_fallback(
+        get_app_strategy().map(|strategy| strategy.data_dir()),
+    ))
+}
+
+/// The data dir found or, when there isn't one such as when `HOME` isn't set, `./.patui` with a
+/// warning rather than giving up before anything's started.
+fn data_dir_or_fallback(data_dir: Result<PathBuf>) -> PathBuf {
+    data_dir.unwrap_or_else(|e| {
+        let fallback = PathBuf::from(".").join(FALLBACK_DATA_DIR);
+        eprintln!(
+            "warning: no data directory found ({}), using `{}` instead, pass `--db` to choose where the database goes",
+            e,
+            fallback.display()
+        );
+        fallback
+    })
+}
+
+/// Where plugins are looked for when not told otherwise.
+pub(crate) fn get_plugin_dir() -> Result<PathBuf> {
+    Ok(get_data_dir()?.join("plugins"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use assertor::*;
+    use eyre::eyre;
+
+    use super::*;
+
+    #[test]
+    fn parse_durations() {
+        for (input, expected) in [
+            ("500ms", Duration::from_millis(500)),
+            ("30s", Duration::from_secs(30)),
+            ("30", Duration::from_secs(30)),
+            ("2m", Duration::from_secs(120)),
+            ("1h", Duration::from_secs(3600)),
+        ] {
+            assert_that!(parse_duration(input).unwrap()).is_equal_to(expected);
+        }
+
+        for input in ["", "s", "-1s", "1.5s", "10d"] {
+            assert_that!(parse_duration(input)).is_err();
+        }
+    }
+
+    #[test]
+    fn data_dir_fallback() {
+        assert_that!(data_dir_or_fallback(Ok(PathBuf::from(
+            "/home/me/.local/share/patui"
+        ))))
+        .is_equal_to(PathBuf::from("/home/me/.local/share/patui"));
+
+        assert_that!(data_dir_or_fallback(Err(eyre!("home directory not found"))))
+            .is_equal_to(PathBuf::from("./.patui"));
+    }
 }
@@ -1,30 +1,42 @@
 use std::{
     collections::HashMap,
-    env,
+    env, fmt,
     fs::create_dir_all,
+    panic::Location,
     pin::Pin,
-    sync::{Arc, Mutex},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
 };
 
-use bytes::Bytes;
 use clap::Parser;
 use eyre::Result;
-use serde::Serialize;
+#[cfg(unix)]
+use tokio::net::UnixListener;
 use tokio::sync::oneshot;
 use tokio::{
+    net::TcpListener,
     sync::{mpsc, RwLock},
     time::sleep,
 };
-use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+#[cfg(unix)]
+use tokio_stream::wrappers::UnixListenerStream;
+use tokio_stream::{
+    wrappers::{ReceiverStream, TcpListenerStream},
+    Stream, StreamExt,
+};
 use tonic::{transport::Server, Code, Request, Response, Status};
 use tracing_subscriber::{
     fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Registry,
 };
 
+use self::data::PatuiStepDataFlavour;
 use self::ptplugin::{
-    get_info, init,
+    diagnostic::Severity,
+    get_info, health, init,
     plugin_service_server::{PluginService, PluginServiceServer},
-    publish, run, subscribe, wait, PatuiStepData, StepRunner,
+    publish, run, subscribe, wait, Diagnostic, PatuiStepData, StepRunner,
 };
 
 pub mod ptplugin {
@@ -40,21 +52,10 @@ const VERSION_MESSAGE: &str = concat!(
     ")"
 );
 
-// Copied from patui/src/types/steprs.rs, when we split out Patui into separate
-// crates this problem will go away.
+// Shared with Patui by path until Patui is split into separate crates
 #[allow(dead_code)]
-#[derive(Debug, Serialize)]
-pub(crate) enum PatuiStepDataFlavour {
-    Null,
-    Bool(bool),
-    Bytes(Bytes),
-    String(String),
-    Integer(String),
-    Float(String),
-    Array(Vec<PatuiStepDataFlavour>),
-    Map(HashMap<String, PatuiStepDataFlavour>),
-    Set(Vec<PatuiStepDataFlavour>),
-}
+#[path = "../../../src/types/steps/data.rs"]
+mod data;
 
 #[derive(Debug)]
 pub(crate) struct MyPlugin {
@@ -67,6 +68,8 @@ pub(crate) struct MyPlugin {
     shutdown_signal: Arc<Mutex<Option<oneshot::Sender<()>>>>,
     echo_tx: Mutex<Option<mpsc::Sender<PatuiStepData>>>,
     echo_rx: Mutex<Option<mpsc::Receiver<PatuiStepData>>>,
+    keep_alive: AtomicBool,
+    ignore_shutdown: AtomicBool,
 }
 
 impl MyPlugin {
@@ -81,6 +84,8 @@ impl MyPlugin {
             shutdown_signal: Arc::new(Mutex::new(Some(shutdown_signal))),
             echo_tx: Mutex::new(Some(echo_tx)),
             echo_rx: Mutex::new(Some(echo_rx)),
+            keep_alive: AtomicBool::new(false),
+            ignore_shutdown: AtomicBool::new(false),
         }
     }
 }
@@ -97,7 +102,9 @@ impl PluginService for MyPlugin {
             step_runner: Some(StepRunner {
                 name: "test_patui_plugin".to_string(),
                 description: "Test Patui Plugin, used for testing Patui only".to_string(),
-                version: "0.1.0".to_string(),
+                // Lets tests check how Patui copes with a plugin version it doesn't support
+                version: env::var("TEST_PATUI_PLUGIN_VERSION")
+                    .unwrap_or_else(|_| "0.1.0".to_string()),
                 r#type: "test".to_string(),
                 subscriptions: vec![],
             }),
@@ -111,7 +118,68 @@ impl PluginService for MyPlugin {
     ) -> std::result::Result<Response<init::Response>, Status> {
         tracing::info!("Request init: {:?}", request.remote_addr());
 
-        Ok(Response::new(init::Response {
+        let keep_alive = request.get_ref().keep_alive;
+        self.keep_alive.store(keep_alive, Ordering::SeqCst);
+
+        // We may be being reused from a previous run, start afresh
+        self.subscribers.write().await.clear();
+        let (echo_tx, echo_rx) = mpsc::channel(100);
+        *self.echo_tx.lock().unwrap() = Some(echo_tx);
+        *self.echo_rx.lock().unwrap() = Some(echo_rx);
+
+        // Lets tests check how Patui copes with a plugin that won't exit when it's finished
+        let ignore_shutdown = request
+            .get_ref()
+            .config
+            .get("ignore_shutdown")
+            .map(String::as_str)
+            == Some("true");
+        self.ignore_shutdown
+            .store(ignore_shutdown, Ordering::SeqCst);
+
+        let mut diagnostics = vec![];
+
+        // Lets tests check how Patui copes with a plugin that can't start
+        if request
+            .get_ref()
+            .config
+            .get("fail_init")
+            .map(String::as_str)
+            == Some("true")
+        {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error.into(),
+                summary: "Init failed".to_string(),
+                detail: "Asked to fail by `fail_init`".to_string(),
+            });
+        }
+
+        // Lets tests check Patui passes on diagnostics that don't stop the plugin
+        if request
+            .get_ref()
+            .config
+            .get("warn_init")
+            .map(String::as_str)
+            == Some("true")
+        {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Warning.into(),
+                summary: "Init warning".to_string(),
+                detail: "Asked to warn by `warn_init`".to_string(),
+            });
+        }
+
+        Ok(Response::new(init::Response { diagnostics }))
+    }
+
+    async fn health(
+        &self,
+        request: Request<health::Request>,
+    ) -> std::result::Result<Response<health::Response>, Status> {
+        tracing::info!("Request health: {:?}", request.remote_addr());
+
+        Ok(Response::new(health::Response {
+            ready: true,
             diagnostics: vec![],
         }))
     }
@@ -131,17 +199,19 @@ impl PluginService for MyPlugin {
                 for (name, subscribers) in lock.iter() {
                     if name == "out" {
                         for bytes in [
-                            rmp_serde::to_vec(&PatuiStepDataFlavour::Null).unwrap(),
-                            rmp_serde::to_vec(&PatuiStepDataFlavour::Bool(true)).unwrap(),
-                            rmp_serde::to_vec(&PatuiStepDataFlavour::String("test".to_string()))
+                            PatuiStepDataFlavour::Null.to_bytes().unwrap(),
+                            PatuiStepDataFlavour::Bool(true).to_bytes().unwrap(),
+                            PatuiStepDataFlavour::String("test".to_string())
+                                .to_bytes()
                                 .unwrap(),
-                            rmp_serde::to_vec(&PatuiStepDataFlavour::Array(vec![
+                            PatuiStepDataFlavour::Array(vec![
                                 PatuiStepDataFlavour::Integer("1".to_string()),
                                 PatuiStepDataFlavour::Integer("2".to_string()),
                                 PatuiStepDataFlavour::Integer("3".to_string()),
-                            ]))
+                            ])
+                            .to_bytes()
                             .unwrap(),
-                            rmp_serde::to_vec(&PatuiStepDataFlavour::Map(HashMap::from([
+                            PatuiStepDataFlavour::Map(HashMap::from([
                                 (
                                     "a".to_string(),
                                     PatuiStepDataFlavour::Integer("1".to_string()),
@@ -150,7 +220,8 @@ impl PluginService for MyPlugin {
                                     "b".to_string(),
                                     PatuiStepDataFlavour::Integer("2".to_string()),
                                 ),
-                            ])))
+                            ]))
+                            .to_bytes()
                             .unwrap(),
                         ] {
                             sleep(tokio::time::Duration::from_millis(10)).await;
@@ -272,11 +343,13 @@ impl PluginService for MyPlugin {
 
         tracing::info!("Done waiting");
 
-        let shutdown_tx = self.shutdown_signal.lock().unwrap().take().unwrap();
+        if !self.keep_alive.load(Ordering::SeqCst) && !self.ignore_shutdown.load(Ordering::SeqCst) {
+            let shutdown_tx = self.shutdown_signal.lock().unwrap().take().unwrap();
 
-        tokio::spawn(async {
-            let _ = shutdown_tx.send(());
-        });
+            tokio::spawn(async {
+                let _ = shutdown_tx.send(());
+            });
+        }
 
         Ok(Response::new(wait::Response {
             diagnostics: vec![],
@@ -287,8 +360,21 @@ impl PluginService for MyPlugin {
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
 pub(crate) struct Cli {
+    /// Listen on this port, 0 to choose a free one, which port is written to stdout once listening
     #[clap(short, long)]
     pub(crate) port: Option<String>,
+
+    /// Listen on IPv6 localhost rather than IPv4
+    #[clap(long)]
+    pub(crate) ipv6: bool,
+
+    /// Listen on a Unix domain socket at this path rather than a port
+    #[clap(long)]
+    pub(crate) socket: Option<String>,
+
+    /// Report panics and errors on a single line, as does setting `PATUI_PLAIN_PANIC`
+    #[clap(long)]
+    pub(crate) plain_panic: bool,
 }
 
 fn version() -> String {
@@ -382,29 +468,110 @@ fn initialise_panic_handler() -> Result<()> {
     Ok(())
 }
 
-async fn do_main() -> Result<()> {
-    tracing::info!("Starting Patui Test Plugin");
+#[derive(Debug, Default)]
+struct PlainEyreHandler {
+    location: Option<&'static Location<'static>>,
+}
 
-    let args = Cli::parse();
+impl eyre::EyreHandler for PlainEyreHandler {
+    fn debug(
+        &self,
+        error: &(dyn std::error::Error + 'static),
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        write!(f, "{}", error)?;
+
+        let mut source = error.source();
+        while let Some(cause) = source {
+            write!(f, ": {}", cause)?;
+            source = cause.source();
+        }
+
+        if let Some(location) = self.location {
+            write!(f, " at {}:{}", location.file(), location.line())?;
+        }
+
+        Ok(())
+    }
+
+    fn track_caller(&mut self, location: &'static Location<'static>) {
+        self.location = Some(location);
+    }
+}
+
+/// Same output as patui's own plain mode so the two read alike in one log.
+fn initialise_plain_panic_handler() -> Result<()> {
+    eyre::set_hook(Box::new(|_| Box::new(PlainEyreHandler::default())))?;
+
+    std::panic::set_hook(Box::new(|panic_info| {
+        let payload = panic_info.payload();
+        let msg = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(|s| &s[..]))
+            .unwrap_or("Box<dyn Any>");
+        let location = panic_info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()))
+            .unwrap_or_else(|| "unknown location".to_string());
+
+        eprintln!("Panic: {} at {}", msg, location);
+        tracing::error!("Panic: {} at {}", msg, location);
 
-    let Some(port) = args.port else {
-        tracing::error!("No port provided");
         std::process::exit(libc::EXIT_FAILURE);
-    };
-    let addr = format!("[::1]:{}", port);
-    let addr = addr.parse().unwrap();
+    }));
+
+    Ok(())
+}
+
+async fn do_main(args: Cli) -> Result<()> {
+    tracing::info!("Starting Patui Test Plugin");
 
     let (tx, rx) = oneshot::channel();
     let (echo_tx, echo_rx) = mpsc::channel(100);
 
     let plugin = MyPlugin::new(tx, echo_tx, echo_rx);
+    let shutdown = async {
+        rx.await.ok();
+        tracing::info!("Shutting down");
+    };
+
+    #[cfg(unix)]
+    if let Some(socket) = args.socket {
+        let incoming = UnixListenerStream::new(UnixListener::bind(&socket)?);
+
+        Server::builder()
+            .add_service(PluginServiceServer::new(plugin))
+            .serve_with_incoming_shutdown(incoming, shutdown)
+            .await?;
+
+        std::fs::remove_file(&socket)?;
+
+        return Ok(());
+    }
+    #[cfg(not(unix))]
+    if args.socket.is_some() {
+        tracing::error!("Unix domain sockets aren't supported on this platform");
+        std::process::exit(libc::EXIT_FAILURE);
+    }
+
+    let Some(port) = args.port else {
+        tracing::error!("No port or socket provided");
+        std::process::exit(libc::EXIT_FAILURE);
+    };
+    let addr = if args.ipv6 {
+        format!("[::1]:{}", port)
+    } else {
+        format!("127.0.0.1:{}", port)
+    };
+    let listener = TcpListener::bind(addr).await?;
+
+    // Patui waits on this to know where to connect
+    println!("{}", listener.local_addr()?.port());
 
     Server::builder()
         .add_service(PluginServiceServer::new(plugin))
-        .serve_with_shutdown(addr, async {
-            rx.await.ok();
-            tracing::info!("Shutting down");
-        })
+        .serve_with_incoming_shutdown(TcpListenerStream::new(listener), shutdown)
         .await?;
 
     Ok(())
@@ -413,7 +580,13 @@ async fn do_main() -> Result<()> {
 #[tokio::main]
 async fn main() -> Result<()> {
     initialise_logging()?;
-    initialise_panic_handler()?;
 
-    do_main().await
+    let args = Cli::parse();
+    if args.plain_panic || env::var("PATUI_PLAIN_PANIC").is_ok_and(|value| value != "0") {
+        initialise_plain_panic_handler()?;
+    } else {
+        initialise_panic_handler()?;
+    }
+
+    do_main(args).await
 }
@@ -0,0 +1,70 @@
+mod types;
+mod utils;
+
+use assertor::*;
+use tempfile::tempdir;
+
+use self::{
+    types::{PatuiTestEditStatus, PatuiTestMinDisplay},
+    utils::run_patui,
+};
+
+#[test]
+fn test_backup_and_restore() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+    let backup_path = tmpdir.path().join("suite.db");
+    let restored_path = tmpdir.path().join("restored.db");
+
+    for name in ["first test", "second test"] {
+        let output = run_patui(
+            &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+            Some(&format!(
+                "name: {}\ndescription: test description\nsteps: []\n",
+                name
+            )),
+        );
+        let insert_output: Vec<PatuiTestEditStatus> =
+            serde_json::from_slice(&output.stdout).unwrap();
+        assert_that!(insert_output[0].status).is_equal_to("ok".to_string());
+    }
+
+    run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "db",
+            "backup",
+            "--file",
+            backup_path.to_str().unwrap(),
+        ],
+        None,
+    );
+    assert_that!(backup_path.is_file()).is_true();
+
+    let restore_args = [
+        "--db",
+        restored_path.to_str().unwrap(),
+        "db",
+        "restore",
+        "--file",
+        backup_path.to_str().unwrap(),
+    ];
+    let get_tests_args = ["--db", restored_path.to_str().unwrap(), "get", "tests"];
+
+    // Not confirming leaves the database alone
+    let output = run_patui(&restore_args, Some("n\n"));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string()).contains("Restore cancelled");
+    let output = run_patui(&get_tests_args, None);
+    let tests: Vec<PatuiTestMinDisplay> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(tests.len()).is_equal_to(0);
+
+    run_patui(&restore_args, Some("y\n"));
+    let output = run_patui(&get_tests_args, None);
+    let tests: Vec<PatuiTestMinDisplay> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(tests
+        .iter()
+        .map(|test| test.name.clone())
+        .collect::<Vec<_>>())
+    .is_equal_to(vec!["first test".to_string(), "second test".to_string()]);
+}
@@ -0,0 +1,177 @@
+mod utils;
+
+use assertor::*;
+use tempfile::tempdir;
+
+use self::utils::run_patui_failure;
+
+#[test]
+fn test_plain_panic_errors() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+    // Not a mistake in how patui was used, the backup just can't be written
+    let backup_path = tmpdir.path().join("missing").join("backup.db");
+    let args = [
+        "--db",
+        db_path.to_str().unwrap(),
+        "db",
+        "backup",
+        "--file",
+        backup_path.to_str().unwrap(),
+    ];
+
+    let mut plain_args = vec!["--plain-panic"];
+    plain_args.extend(args);
+    let output = run_patui_failure(&plain_args, None);
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    assert_that!(output.status.code()).is_equal_to(Some(3));
+    assert_that!(stderr.lines().count()).is_equal_to(1);
+    assert_that!(stderr).starts_with("Error: ");
+    assert_that!(stderr).contains(" at src/");
+    assert_that!(stderr).does_not_contain("\x1b[");
+
+    // The default report goes on to show the location, spantrace and so on over several lines
+    let output = run_patui_failure(&args, None);
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+
+    assert_that!(stderr.lines().count()).is_greater_than(1);
+}
+
+#[test]
+fn test_exit_codes() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+    let db_args = ["--db", db_path.to_str().unwrap()];
+
+    // Bad arguments are a usage error
+    let output = run_patui_failure(
+        &[&db_args[..], &["new", "run", "--no-such-flag"]].concat(),
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+
+    // As is asking for a test that doesn't exist
+    let output = run_patui_failure(
+        &[&db_args[..], &["describe", "tests", "--id", "42"]].concat(),
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+
+    // Or a plugin, a backup or a subcommand that isn't there
+    let plugin_dir = tmpdir.path().join("plugins");
+    std::fs::create_dir(&plugin_dir).unwrap();
+    let output = run_patui_failure(
+        &[
+            &db_args[..],
+            &[
+                "describe",
+                "plugin",
+                "--type",
+                "no-such-plugin",
+                "--dir",
+                plugin_dir.to_str().unwrap(),
+            ],
+        ]
+        .concat(),
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .starts_with("No plugin of type `no-such-plugin` found");
+
+    let backup_path = tmpdir.path().join("missing.db");
+    let output = run_patui_failure(
+        &[
+            &db_args[..],
+            &[
+                "db",
+                "restore",
+                "--file",
+                backup_path.to_str().unwrap(),
+                "--yes",
+            ],
+        ]
+        .concat(),
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .is_equal_to(format!("no backup found at {}\n", backup_path.display()));
+
+    let output = run_patui_failure(&[&db_args[..], &["run"]].concat(), None);
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .is_equal_to("Expected a subcommand or `--steps-from-stdin`\n".to_string());
+
+    // A run with a failing step is a failure
+    let output = run_patui_failure(
+        &[&db_args[..], &["run", "--steps-from-stdin"]].concat(),
+        Some("name: Failing Test\nsteps:\n  - name: check\n    details: !Assertion\n      expr: 1 == 2\n"),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+
+    // As is a test that isn't valid, whether it's the YAML or an expression in it that's broken
+    let output = run_patui_failure(
+        &[&db_args[..], &["run", "--steps-from-stdin"]].concat(),
+        Some("name: Broken Test\nsteps: 42\n"),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+
+    let output = run_patui_failure(
+        &[&db_args[..], &["run", "--steps-from-stdin"]].concat(),
+        Some("name: Broken Test\nsteps:\n  - name: check\n    details: !Assertion\n      expr: 1 ==\n"),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+
+    let output = run_patui_failure(
+        &[&db_args[..], &["new", "test", "--no-edit", "-"]].concat(),
+        Some("name: Broken Test\nsteps: 42\n"),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_interrupted_run() {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+        thread::sleep,
+        time::Duration,
+    };
+
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let mut child = Command::new(assert_cmd::cargo::cargo_bin("patui"))
+        .args([
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "--steps-from-stdin",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // Each step reads from the other so this run never finishes by itself
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"name: Hanging Test\nsteps:\n  - name: ping\n    details: !Read\n      in: steps.pong.out\n  - name: pong\n    details: !Read\n      in: steps.ping.out\n")
+        .unwrap();
+
+    sleep(Duration::from_secs(1));
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert_that!(status.success()).is_true();
+
+    let output = child.wait_with_output().unwrap();
+    assert_that!(output.status.code()).is_equal_to(Some(130));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string()).contains("Interrupted");
+}
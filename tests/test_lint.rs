@@ -0,0 +1,78 @@
+mod utils;
+
+use assertor::*;
+use tempfile::tempdir;
+
+use self::utils::{run_patui, run_patui_failure};
+
+const LINTED_TEST: &str = r#"name: Linted Test
+steps:
+  - name: reader
+    details: !Read
+      in: "\"./tests/data/test.txt\""
+  - name: unused
+    details: !Read
+      in: "\"./tests/data/test.json\""
+  - name: constant
+    details: !Assertion
+      expr: "true == 1"
+  - name: check
+    details: !Assertion
+      expr: steps.reader.out == [1, 2, 3][3]
+"#;
+
+#[test]
+fn test_lint_test() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "lint", "test", "-"],
+        Some(LINTED_TEST),
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+
+    assert_that!(stdout.lines().collect::<Vec<_>>()).is_equal_to(vec![
+        "warning: test `Linted Test`: step `unused`: nothing subscribes to the output of this step",
+        "warning: test `Linted Test`: step `constant`: assertion doesn't reference any `steps` so is always the same at 0..9 in `true == 1`",
+        "warning: test `Linted Test`: step `constant`: `true == 1` compares a bool with a number so never holds at 0..9 in `true == 1`",
+        "warning: test `Linted Test`: step `check`: `[1, 2, 3][3]` is out of range, `[1, 2, 3]` only has 3 items at 20..32 in `steps.reader.out == [1, 2, 3][3]`",
+    ]);
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string()).contains("4 warning(s)");
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "lint",
+            "test",
+            "--deny-warnings",
+            "-",
+        ],
+        Some(LINTED_TEST),
+    );
+
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+}
+
+#[test]
+fn test_lint_clean_test() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "lint",
+            "test",
+            "--deny-warnings",
+            "-",
+        ],
+        Some(
+            "name: Clean Test\nsteps:\n  - name: reader\n    details: !Read\n      in: \"\\\"./tests/data/test.txt\\\"\"\n  - name: check\n    details: !Assertion\n      expr: steps.reader.out == b\"Hello\"\n",
+        ),
+    );
+
+    assert_that!(output.stdout).is_empty();
+}
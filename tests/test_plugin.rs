@@ -0,0 +1,122 @@
+mod types;
+mod utils;
+
+use std::{fs, process::Command};
+
+use assertor::*;
+use tempfile::tempdir;
+
+use self::{
+    types::{PatuiPluginDisplay, PatuiStepKindDisplay},
+    utils::run_patui,
+};
+
+fn compile_test_plugin() {
+    let output = Command::new("cargo")
+        .arg("build")
+        .current_dir("test_progs/test_plugin")
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_get_and_describe_plugins() {
+    compile_test_plugin();
+
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    // Only the plugin should be picked up, not other files alongside it
+    let plugin_dir = tmpdir.path().join("plugins");
+    fs::create_dir(&plugin_dir).unwrap();
+    fs::copy(
+        "test_progs/test_plugin/target/debug/test_patui_plugin",
+        plugin_dir.join("test_patui_plugin"),
+    )
+    .unwrap();
+    fs::write(plugin_dir.join("README"), "Not a plugin").unwrap();
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "get",
+            "plugins",
+            "--dir",
+            plugin_dir.to_str().unwrap(),
+        ],
+        None,
+    );
+    assert!(output.status.success());
+
+    let plugins: Vec<PatuiPluginDisplay> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(plugins.len()).is_equal_to(1);
+    assert_that!(plugins[0].name).is_equal_to("test_patui_plugin".to_string());
+    assert_that!(plugins[0].r#type).is_equal_to("test".to_string());
+    assert_that!(plugins[0].version).is_equal_to("0.1.0".to_string());
+    assert_that!(plugins[0].subscriptions).is_empty();
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "describe",
+            "plugin",
+            "--type",
+            "test",
+            "--dir",
+            plugin_dir.to_str().unwrap(),
+        ],
+        None,
+    );
+    assert!(output.status.success());
+
+    let plugin: PatuiPluginDisplay = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(plugin.name).is_equal_to("test_patui_plugin".to_string());
+    assert_that!(plugin.path).ends_with("test_patui_plugin");
+}
+
+#[test]
+fn test_list_steps() {
+    compile_test_plugin();
+
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let plugin_dir = tmpdir.path().join("plugins");
+    fs::create_dir(&plugin_dir).unwrap();
+    fs::copy(
+        "test_progs/test_plugin/target/debug/test_patui_plugin",
+        plugin_dir.join("test_patui_plugin"),
+    )
+    .unwrap();
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "steps",
+            "list",
+            "--dir",
+            plugin_dir.to_str().unwrap(),
+        ],
+        None,
+    );
+    assert!(output.status.success());
+
+    let kinds: Vec<PatuiStepKindDisplay> = serde_json::from_slice(&output.stdout).unwrap();
+
+    let read = kinds.iter().find(|kind| kind.kind == "Read").unwrap();
+    assert_that!(read.fields).is_equal_to(vec!["in".to_string()]);
+    assert_that!(read.plugin).is_none();
+
+    let assertion = kinds.iter().find(|kind| kind.kind == "Assertion").unwrap();
+    assert_that!(assertion.fields).contains("expr".to_string());
+
+    let plugin = kinds.iter().find(|kind| kind.kind == "test").unwrap();
+    assert_that!(plugin.plugin.as_ref().map(|plugin| plugin.name.clone()))
+        .is_equal_to(Some("test_patui_plugin".to_string()));
+}
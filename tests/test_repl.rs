@@ -0,0 +1,48 @@
+mod utils;
+
+use assertor::*;
+use tempfile::tempdir;
+
+use self::utils::run_patui;
+
+#[test]
+fn test_repl() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "repl"],
+        Some("1 + 2\ntrue && false\n\n1 +\nsteps.foo.out\n\"a\" in [\"a\"]\n"),
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_that!(stdout.lines().collect::<Vec<_>>()).is_equal_to(vec!["3", "false", "true"]);
+
+    // Errors are reported and the session carries on
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_that!(stderr
+        .lines()
+        .filter(|line| line.starts_with("Error: "))
+        .count())
+    .is_equal_to(2);
+    assert_that!(stderr).contains("Error: Step references can only be evaluated during a run");
+}
+
+#[test]
+fn test_repl_ast() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "repl"],
+        Some(":ast\n1 + 2\n:ast\n1 + 2\n"),
+    );
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_that!(stdout).starts_with("AST on\nBinOp(\n    Add,\n");
+    assert_that!(stdout).ends_with("3\nAST off\n3\n");
+}
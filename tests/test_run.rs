@@ -1,12 +1,15 @@
 mod types;
 mod utils;
 
+use std::time::{Duration, Instant};
+
+use assert_cmd::Command;
 use assertor::*;
 use tempfile::tempdir;
 
 use self::{
     types::{PatuiRunStatus, PatuiTestEditStatus},
-    utils::run_patui,
+    utils::{run_patui, run_patui_failure},
 };
 
 #[test]
@@ -66,3 +69,728 @@ fn test_run_test_instance() {
     let row = rows.next().unwrap();
     assert!(row.is_none());
 }
+
+#[test]
+fn test_run_timeout() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    // Each step reads from the other so without a timeout this run would never finish
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Hanging Test\ndescription: never finishes\nsteps:\n  - name: ping\n    details: !Read\n      in: steps.pong.out\n  - name: pong\n    details: !Read\n      in: steps.ping.out\n"),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id;
+
+    let start = Instant::now();
+    let output = Command::cargo_bin("patui")
+        .unwrap()
+        .args([
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--test-id",
+            &id.to_string(),
+            "--timeout",
+            "1s",
+        ])
+        .timeout(Duration::from_secs(10))
+        .output()
+        .unwrap();
+    let elapsed = start.elapsed();
+
+    assert_that!(output.status.code()).is_equal_to(Some(124));
+    assert_that!(elapsed).is_at_least(Duration::from_secs(1));
+    assert_that!(elapsed).is_less_than(Duration::from_secs(10));
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_that!(stdout).contains("Timeout");
+}
+
+#[test]
+fn test_run_default_timeout() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Hanging Test\ndescription: never finishes\ndefault_timeout_ms: 300\nsteps:\n  - name: ping\n    details: !Read\n      in: steps.pong.out\n  - name: pong\n    details: !Read\n      in: steps.ping.out\n"),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id;
+
+    let run = |extra_args: &[&str]| {
+        let start = Instant::now();
+        let output = Command::cargo_bin("patui")
+            .unwrap()
+            .args([
+                "--db",
+                db_path.to_str().unwrap(),
+                "new",
+                "run",
+                "--test-id",
+                &id.to_string(),
+            ])
+            .args(extra_args)
+            .timeout(Duration::from_secs(10))
+            .output()
+            .unwrap();
+        (output, start.elapsed())
+    };
+
+    // The timeout saved with the test is used when none is given
+    let (output, elapsed) = run(&[]);
+    assert_that!(output.status.code()).is_equal_to(Some(124));
+    assert_that!(elapsed).is_at_least(Duration::from_millis(300));
+    assert_that!(elapsed).is_less_than(Duration::from_secs(10));
+
+    // One given to the run wins
+    let (output, elapsed) = run(&["--timeout", "1500ms"]);
+    assert_that!(output.status.code()).is_equal_to(Some(124));
+    assert_that!(elapsed).is_at_least(Duration::from_millis(1500));
+    assert_that!(elapsed).is_less_than(Duration::from_secs(10));
+}
+
+#[test]
+fn test_run_step_timeout() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    // Only the step timing out lets `pong` finish, it has nothing to read after that
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Slow Test\ndescription: a step never finishes\nsteps:\n  - name: ping\n    timeout_ms: 300\n    details: !Read\n      in: steps.pong.out\n  - name: pong\n    details: !Read\n      in: steps.ping.out\n"),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id;
+
+    let start = Instant::now();
+    let output = Command::cargo_bin("patui")
+        .unwrap()
+        .args([
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--test-id",
+            &id.to_string(),
+        ])
+        .timeout(Duration::from_secs(10))
+        .output()
+        .unwrap();
+    let elapsed = start.elapsed();
+
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+    assert_that!(elapsed).is_at_least(Duration::from_millis(300));
+    assert_that!(elapsed).is_less_than(Duration::from_secs(10));
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_that!(stdout).contains("Step timed out after 300ms");
+    assert_that!(stdout).does_not_contain("panicked");
+}
+
+#[test]
+fn test_run_missing_test() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--test-id",
+            "42",
+        ],
+        None,
+    );
+
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .is_equal_to("test 42 not found\n".to_string());
+}
+
+#[test]
+fn test_run_fail_fast() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Failing Test\ndescription: two failing assertions\nsteps:\n  - name: first\n    details: !Assertion\n      expr: 1 == 2\n  - name: second\n    details: !Assertion\n      expr: 3 == 4\n"),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id.to_string();
+
+    let args = [
+        "--db",
+        db_path.to_str().unwrap(),
+        "new",
+        "run",
+        "--test-id",
+        &id,
+    ];
+
+    let output = run_patui_failure(&args, None);
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_that!(stdout).contains("Assertion `first` failed");
+    assert_that!(stdout).contains("Assertion `second` failed");
+
+    let output = run_patui_failure(&[&args[..], &["--fail-fast"]].concat(), None);
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_that!(stdout).contains("Assertion `first` failed");
+    assert_that!(stdout).does_not_contain("Assertion `second` failed");
+}
+
+#[test]
+fn test_run_show_outputs() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Read Test\ndescription: reads a file\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.json\"'\n  - name: json\n    details: !TransformStream\n      flavour: Json\n      in: steps.file.out\n"),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id.to_string();
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--test-id",
+            &id,
+            "--capture",
+        ],
+        None,
+    );
+    let run_insert_output: PatuiRunStatus = serde_json::from_slice(&output.stdout).unwrap();
+    let run_id = run_insert_output.id.to_string();
+
+    let show_args = |step: &str| {
+        [
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "show",
+            &run_id,
+            "--step",
+            step,
+        ]
+        .map(|arg| arg.to_string())
+    };
+
+    let output = run_patui(
+        &show_args("json")
+            .iter()
+            .map(|arg| &arg[..])
+            .collect::<Vec<_>>(),
+        None,
+    );
+    let run_output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(run_output["step_name"]).is_equal_to(serde_json::json!("json"));
+    assert_that!(run_output["dropped"]).is_equal_to(serde_json::json!(0));
+    assert_that!(run_output["outputs"].as_array().unwrap().len()).is_equal_to(1);
+    assert_that!(run_output["outputs"][0]["data"]["Map"]["foo"])
+        .is_equal_to(serde_json::json!({"String": "bar"}));
+
+    let output = run_patui_failure(
+        &show_args("missing")
+            .iter()
+            .map(|arg| &arg[..])
+            .collect::<Vec<_>>(),
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string()).is_equal_to(format!(
+        "output of step missing in run {} not found\n",
+        run_id
+    ));
+}
+
+/// Add a test reading `tests/data/test.json` and checking `expr` holds for it, giving its id.
+fn new_json_check_test(db_path: &str, expr: &str) -> String {
+    let output = run_patui(
+        &["--db", db_path, "new", "test", "-n", "-"],
+        Some(&format!("name: Json Test\ndescription: checks a json file\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.json\"'\n  - name: json\n    details: !TransformStream\n      flavour: Json\n      in: steps.file.out\n  - name: check\n    details: !Assertion\n      expr: '{}'\n", expr)),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    test_insert_output[0].id.to_string()
+}
+
+#[test]
+fn test_run_json_report() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+    let db_path = db_path.to_str().unwrap();
+    let id = new_json_check_test(db_path, "steps.json.out[0].foo == \"bar\"");
+
+    let output = run_patui(
+        &["--db", db_path, "new", "run", "--test-id", &id, "--capture"],
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(0));
+
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(run["id"].is_i64()).is_true();
+    assert_that!(run["instance"]["name"]).is_equal_to(serde_json::json!("Json Test"));
+    assert_that!(run["start_time"].is_string()).is_true();
+    assert_that!(run["status"]).is_equal_to(serde_json::json!("Passed"));
+
+    let steps = run["step_run_details"].as_array().unwrap();
+    assert_that!(steps
+        .iter()
+        .map(|step| step["name"].as_str().unwrap())
+        .collect::<Vec<_>>())
+    .is_equal_to(vec!["file", "json", "check"]);
+    for step in steps {
+        assert_that!(step["start_time"].is_string()).is_true();
+        assert_that!(step["end_time"].is_string()).is_true();
+        assert_that!(step["result"]["status"]).is_equal_to(serde_json::json!("Passed"));
+    }
+
+    // What each step output is in the report, assertions have nothing to output
+    assert_that!(steps[1]["output"]["dropped"]).is_equal_to(serde_json::json!(0));
+    assert_that!(steps[1]["output"]["outputs"][0]["data"]["Map"]["foo"])
+        .is_equal_to(serde_json::json!({"String": "bar"}));
+    assert_that!(steps[0]["output"]["outputs"].as_array().unwrap().len()).is_equal_to(1);
+    assert_that!(steps[2].get("output")).is_none();
+
+    // Without capturing there's nothing to report
+    let output = run_patui(&["--db", db_path, "new", "run", "--test-id", &id], None);
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    for step in run["step_run_details"].as_array().unwrap() {
+        assert_that!(step.get("output")).is_none();
+    }
+}
+
+#[test]
+fn test_run_json_report_failure() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+    let db_path = db_path.to_str().unwrap();
+    let id = new_json_check_test(db_path, "steps.json.out[0].foo == \"baz\"");
+
+    let output = run_patui_failure(
+        &["--db", db_path, "new", "run", "--test-id", &id, "--capture"],
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let failure = "Assertion `check` failed: steps.json.out[0].foo == \"baz\"";
+    assert_that!(run["status"]).is_equal_to(serde_json::json!({"Error": {"StepFailed": failure}}));
+
+    let steps = run["step_run_details"].as_array().unwrap();
+    assert_that!(steps[0]["result"]["status"]).is_equal_to(serde_json::json!("Passed"));
+    assert_that!(steps[1]["result"]["status"]).is_equal_to(serde_json::json!("Passed"));
+    assert_that!(steps[2]["result"]["status"])
+        .is_equal_to(serde_json::json!({"Error": {"StepFailed": failure}}));
+    // What was output is still reported to see why it failed
+    assert_that!(steps[1]["output"]["outputs"][0]["data"]["Map"]["foo"])
+        .is_equal_to(serde_json::json!({"String": "bar"}));
+}
+
+#[test]
+fn test_run_show_env() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Read Test\ndescription: reads a file\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.json\"'\n"),
+    );
+
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id.to_string();
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--test-id",
+            &id,
+        ],
+        None,
+    );
+    let run_insert_output: PatuiRunStatus = serde_json::from_slice(&output.stdout).unwrap();
+    let run_id = run_insert_output.id.to_string();
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "show",
+            &run_id,
+            "--env",
+        ],
+        None,
+    );
+    let env: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(env["patui_version"]).is_equal_to(serde_json::json!(env!("CARGO_PKG_VERSION")));
+    assert_that!(env["os"]).is_equal_to(serde_json::json!(std::env::consts::OS));
+    assert_that!(env["plugins"]).is_equal_to(serde_json::json!({}));
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "show",
+            "1000",
+            "--env",
+        ],
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .is_equal_to("run 1000 not found\n".to_string());
+}
+
+#[test]
+fn test_run_steps_from_stdin() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let args = [
+        "--db",
+        db_path.to_str().unwrap(),
+        "run",
+        "--steps-from-stdin",
+    ];
+
+    let output = run_patui(
+        &args,
+        Some("name: Adhoc Test\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.json\"'\n  - name: check\n    details: !Assertion\n      expr: 'b\"bar\" in steps.file.out[0]'\n"),
+    );
+    let run_output: PatuiRunStatus = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(run_output.status).is_equal_to("Passed".to_string());
+
+    let output = run_patui_failure(
+        &args,
+        Some(
+            r#"{"name": "Adhoc Test", "steps": [{"name": "file", "details": {"Read": {"in": "\"tests/data/test.json\""}}}, {"name": "check", "details": {"Assertion": {"expr": "b\"qux\" in steps.file.out[0]"}}}]}"#,
+        ),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    assert_that!(stdout).contains("Assertion `check` failed");
+
+    // Nothing about the run was saved, the database wasn't even created
+    assert_that!(db_path.exists()).is_false();
+}
+
+#[test]
+fn test_run_ndjson_output() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: Streamed Test\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.json\"'\n  - name: check\n    details: !Assertion\n      expr: 'b\"bar\" in steps.file.out[0]'\n"),
+    );
+    let test_insert_output: Vec<PatuiTestEditStatus> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let id = test_insert_output[0].id;
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--test-id",
+            &id.to_string(),
+            "--output",
+            "ndjson",
+        ],
+        None,
+    );
+
+    let lines = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+
+    for (seq, line) in lines.iter().enumerate() {
+        assert_that!(line["seq"]).is_equal_to(serde_json::json!(seq));
+    }
+
+    let lifecycle = lines
+        .iter()
+        .filter(|line| line["type"] == "event")
+        .filter_map(|line| {
+            let value = line["event"]["value"].as_object()?;
+            let (kind, details) = value.iter().next()?;
+            (kind == "StepStarted" || kind == "StepFinished")
+                .then(|| format!("{} {}", kind, details["name"].as_str().unwrap()))
+        })
+        .collect::<Vec<_>>();
+    let (started, finished) = lifecycle.split_at(2);
+    assert_that!(started.to_vec()).is_equal_to(
+        ["StepStarted file", "StepStarted check"]
+            .map(String::from)
+            .to_vec(),
+    );
+    // Steps are reported as they finish and the check can settle before the file's closed
+    let mut finished = finished.to_vec();
+    finished.sort();
+    assert_that!(finished).is_equal_to(
+        ["StepFinished check", "StepFinished file"]
+            .map(String::from)
+            .to_vec(),
+    );
+
+    let summary = lines.last().unwrap();
+    assert_that!(summary["type"]).is_equal_to(serde_json::json!("summary"));
+    assert_that!(summary["run"]["status"]).is_equal_to(serde_json::json!("Passed"));
+}
+
+#[test]
+fn test_run_explain_failure() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "--steps-from-stdin",
+            "--explain-failure",
+            "check",
+        ],
+        Some("name: Adhoc Test\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.txt\"'\n  - name: check\n    details: !Assertion\n      expr: '(steps.file.out.len() == 1) && (steps.file.out[0] == b\"Goodbye\")'\n"),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let check = run["step_run_details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|step| step["name"] == "check")
+        .unwrap();
+    let explanation = &check["result"]["explanation"];
+
+    assert_that!(explanation["expr"]).is_equal_to(serde_json::json!(
+        "(steps.file.out.len() == 1) && (steps.file.out[0] == b\"Goodbye\")"
+    ));
+    assert_that!(explanation["value"]).is_equal_to(serde_json::json!(false));
+    assert_that!(explanation["children"][0]["value"]).is_equal_to(serde_json::json!(true));
+    assert_that!(explanation["children"][1]["value"]).is_equal_to(serde_json::json!(false));
+    assert_that!(explanation["children"][1]["children"][1]["expr"])
+        .is_equal_to(serde_json::json!("b\"Goodbye\""));
+
+    // Only the step asked about is explained
+    let file = run["step_run_details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|step| step["name"] == "file")
+        .unwrap();
+    assert_that!(file["result"].get("explanation")).is_none();
+}
+
+#[test]
+fn test_run_only_failures() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+    let test = "name: Adhoc Test\nsteps:\n  - name: file\n    details: !Read\n      in: '\"tests/data/test.txt\"'\n  - name: good\n    details: !Assertion\n      expr: 'steps.file.out.len() == 1'\n  - name: bad\n    details: !Assertion\n      expr: 'steps.file.out[0] == b\"Goodbye\"'\n";
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "--steps-from-stdin",
+            "--only-failures",
+        ],
+        Some(test),
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+
+    let run: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let step_names = run["step_run_details"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_that!(step_names).is_equal_to(vec!["bad"]);
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "run",
+            "--steps-from-stdin",
+            "--only-failures",
+            "--output",
+            "ndjson",
+        ],
+        Some(test),
+    );
+
+    let lines = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_that!(lines).has_length(2);
+
+    assert_that!(lines[0]["seq"]).is_equal_to(serde_json::json!(0));
+    assert_that!(lines[0]["event"]["step_name"]).is_equal_to(serde_json::json!("bad"));
+    assert_that!(lines[0]["event"]["value"]["StepFinished"]["outcome"].get("Failed")).is_some();
+
+    assert_that!(lines[1]["seq"]).is_equal_to(serde_json::json!(1));
+    assert_that!(lines[1]["type"]).is_equal_to(serde_json::json!("summary"));
+}
+
+#[test]
+fn test_run_repeat() {
+    let tmpdir = tempdir().unwrap();
+    let db_path = tmpdir.path().join("test.db");
+
+    let new_test = |name: &str, expr: &str| {
+        let output = run_patui(
+            &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+            Some(&format!(
+                "name: {}\nsteps:\n  - name: check\n    details: !Assertion\n      expr: {}\n",
+                name, expr
+            )),
+        );
+        let test_insert_output: Vec<PatuiTestEditStatus> =
+            serde_json::from_slice(&output.stdout).unwrap();
+        test_insert_output[0].id.to_string()
+    };
+    let passing_id = new_test("Passing Test", "1 == 1");
+    let failing_id = new_test("Failing Test", "1 == 2");
+
+    let args = |id| {
+        [
+            "--db",
+            db_path.to_str().unwrap(),
+            "new",
+            "run",
+            "--repeat",
+            "3",
+            "--test-id",
+            id,
+        ]
+    };
+
+    let output = run_patui(&args(&passing_id), None);
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(summary["runs"].as_array().unwrap().len()).is_equal_to(3);
+    assert_that!(summary["passed"]).is_equal_to(serde_json::json!(3));
+    assert_that!(summary["pass_rate"]).is_equal_to(serde_json::json!(1.0));
+
+    let failing_args = args(&failing_id);
+    let output = run_patui_failure(&failing_args, None);
+    assert_that!(output.status.code()).is_equal_to(Some(1));
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(summary["runs"].as_array().unwrap().len()).is_equal_to(3);
+    assert_that!(summary["failed"]).is_equal_to(serde_json::json!(3));
+    assert_that!(summary["pass_rate"]).is_equal_to(serde_json::json!(0.0));
+
+    // Failing fast stops repeating after the first failed run
+    let output = run_patui_failure(&[&failing_args[..], &["--fail-fast"]].concat(), None);
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(summary["runs"].as_array().unwrap().len()).is_equal_to(1);
+
+    // A test passing and failing in turn. Each run reads what to check from a pipe that's fed a
+    // value per run, so which runs fail is fixed, and writes what it read back out to another once
+    // it's done reading.
+    #[cfg(unix)]
+    {
+        use std::io::{Read, Write};
+
+        let results_path = tmpdir.path().join("results");
+        let done_path = tmpdir.path().join("done");
+        for path in [&results_path, &done_path] {
+            let status = std::process::Command::new("mkfifo")
+                .arg(path)
+                .status()
+                .unwrap();
+            assert_that!(status.success()).is_true();
+        }
+
+        let output = run_patui(
+            &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+            Some(&format!(
+                "name: Alternating Test\nsteps:\n  - name: result\n    details: !Read\n      in: '\"{}\"'\n  - name: done\n    details: !FileSink\n      input: steps.result.out\n      path: {}\n  - name: check\n    details: !Assertion\n      expr: steps.result.out[0] == b\"pass\"\n",
+                results_path.display(),
+                done_path.display()
+            )),
+        );
+        let test_insert_output: Vec<PatuiTestEditStatus> =
+            serde_json::from_slice(&output.stdout).unwrap();
+        let alternating_id = test_insert_output[0].id.to_string();
+
+        // The next value's only written once the last run's finished with the pipe, otherwise
+        // it'd be read by the same run
+        let feeder = std::thread::spawn(move || {
+            for result in ["pass", "fail", "pass"] {
+                std::fs::OpenOptions::new()
+                    .write(true)
+                    .open(&results_path)
+                    .unwrap()
+                    .write_all(result.as_bytes())
+                    .unwrap();
+
+                let mut read_back = String::new();
+                std::fs::File::open(&done_path)
+                    .unwrap()
+                    .read_to_string(&mut read_back)
+                    .unwrap();
+                assert_that!(read_back).is_equal_to(result.to_string());
+            }
+        });
+
+        let output = run_patui_failure(&args(&alternating_id), None);
+        feeder.join().unwrap();
+        assert_that!(output.status.code()).is_equal_to(Some(1));
+        let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let statuses = summary["runs"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|run| run["status"] == "Passed")
+            .collect::<Vec<_>>();
+        assert_that!(statuses).is_equal_to(vec![true, false, true]);
+        assert_that!(summary["passed"]).is_equal_to(serde_json::json!(2));
+        assert_that!(summary["failed"]).is_equal_to(serde_json::json!(1));
+        assert_that!(summary["pass_rate"]).is_equal_to(serde_json::json!(2.0 / 3.0));
+    }
+}
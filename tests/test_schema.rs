@@ -0,0 +1,46 @@
+mod utils;
+
+use assertor::*;
+use tempfile::tempdir;
+
+use self::utils::run_patui;
+
+#[test]
+fn test_schema() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(&["--db", db_path.to_str().unwrap(), "schema"], None);
+    assert!(output.status.success());
+
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    assert_that!(schema["title"]).is_equal_to(serde_json::json!("PatuiTestEditable"));
+
+    let step_kinds = schema["definitions"]["PatuiStepDetailsEditable"]["oneOf"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .flat_map(|kind| kind["required"].as_array().unwrap())
+        .map(|kind| kind.as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_that!(step_kinds).is_equal_to(vec![
+        "TransformStream",
+        "Transform",
+        "Read",
+        "Write",
+        "FileSink",
+        "Assertion",
+        "Sender",
+        "Plugin",
+    ]);
+
+    assert_that!(
+        schema["definitions"]["PatuiStepTransformStreamFlavour"]["enum"]
+            .as_array()
+            .unwrap()
+            .len()
+    )
+    .is_equal_to(5);
+}
@@ -6,7 +6,7 @@ use tempfile::tempdir;
 
 use self::{
     types::{PatuiTest, PatuiTestEditStatus, PatuiTestMinDisplay},
-    utils::run_patui,
+    utils::{run_patui, run_patui_failure},
 };
 
 #[test]
@@ -146,6 +146,234 @@ fn test_get_tests() {
     ]);
 }
 
+#[test]
+fn test_clone_test() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    let output = run_patui(
+        &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+        Some("name: test name\ndescription: test description\nsteps: []\n"),
+    );
+    assert!(output.status.success());
+
+    for name in [None, Some("cloned name")] {
+        let mut args = vec![
+            "--db",
+            db_path.to_str().unwrap(),
+            "clone",
+            "test",
+            "--id",
+            "1",
+        ];
+        if let Some(name) = name {
+            args.extend(["--name", name]);
+        }
+        let output = run_patui(&args, None);
+        assert!(output.status.success());
+    }
+
+    let output = run_patui(&["--db", db_path.to_str().unwrap(), "get", "tests"], None);
+    assert!(output.status.success());
+
+    let tests: Vec<PatuiTestMinDisplay> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(tests.iter().map(|x| &x.name[..]).collect::<Vec<&str>>()).is_equal_to(vec![
+        "test name",
+        "test name (copy)",
+        "cloned name",
+    ]);
+    assert_that!(tests
+        .iter()
+        .map(|x| &x.description[..])
+        .collect::<Vec<&str>>())
+    .is_equal_to(vec!["test description"; 3]);
+}
+
+#[test]
+fn test_rename_test() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    for name in ["first", "second"] {
+        let output = run_patui(
+            &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+            Some(&format!(
+                "name: {}\ndescription: test description\nsteps: []\n",
+                name
+            )),
+        );
+        assert!(output.status.success());
+    }
+
+    let output = run_patui(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "rename",
+            "test",
+            "--id",
+            "1",
+            "--name",
+            "renamed",
+        ],
+        None,
+    );
+    assert!(output.status.success());
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "rename",
+            "test",
+            "--id",
+            "2",
+            "--name",
+            "renamed",
+        ],
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .is_equal_to("a test named `renamed` already exists\n".to_string());
+
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "clone",
+            "test",
+            "--id",
+            "2",
+            "--name",
+            "renamed",
+        ],
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+    assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+        .is_equal_to("a test named `renamed` already exists\n".to_string());
+
+    let output = run_patui(&["--db", db_path.to_str().unwrap(), "get", "tests"], None);
+    assert!(output.status.success());
+
+    let tests: Vec<PatuiTestMinDisplay> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_that!(tests.iter().map(|x| &x.name[..]).collect::<Vec<&str>>())
+        .is_equal_to(vec!["renamed", "second"]);
+}
+
+#[test]
+fn test_get_tests_filtered() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    for name in ["beta", "alphabet", "alpha"] {
+        let output = run_patui(
+            &["--db", db_path.to_str().unwrap(), "new", "test", "-n", "-"],
+            Some(&format!(
+                "name: {}\ndescription: {} description\nsteps: []\n",
+                name, name
+            )),
+        );
+        assert!(output.status.success());
+    }
+
+    let get_tests = |args: &[&str]| {
+        let mut all_args = vec!["--db", db_path.to_str().unwrap(), "get", "tests"];
+        all_args.extend(args);
+        let output = run_patui(&all_args, None);
+        assert!(output.status.success());
+
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+    let get_names = |args: &[&str]| {
+        let tests: Vec<PatuiTestMinDisplay> = serde_json::from_str(&get_tests(args)).unwrap();
+        tests.into_iter().map(|x| x.name).collect::<Vec<_>>()
+    };
+
+    assert_that!(get_names(&[])).is_equal_to(vec![
+        "beta".to_string(),
+        "alphabet".to_string(),
+        "alpha".to_string(),
+    ]);
+    assert_that!(get_names(&["--sort-by", "name"])).is_equal_to(vec![
+        "alpha".to_string(),
+        "alphabet".to_string(),
+        "beta".to_string(),
+    ]);
+    assert_that!(get_names(&["--name-contains", "alpha"]))
+        .is_equal_to(vec!["alphabet".to_string(), "alpha".to_string()]);
+    assert_that!(get_names(&[
+        "--name-contains",
+        "alpha",
+        "--sort-by",
+        "name",
+        "--limit",
+        "1"
+    ]))
+    .is_equal_to(vec!["alpha".to_string()]);
+    assert_that!(get_names(&["--name-contains", "gamma"])).is_empty();
+
+    assert_that!(get_tests(&["--sort-by", "name", "--format", "table"])).is_equal_to(
+        "ID  NAME      DESCRIPTION\n\
+         3   alpha     alpha description\n\
+         2   alphabet  alphabet description\n\
+         1   beta      beta description\n"
+            .to_string(),
+    );
+    assert_that!(get_tests(&[
+        "--name-contains",
+        "gamma",
+        "--format",
+        "table"
+    ]))
+    .is_equal_to("ID  NAME  DESCRIPTION\n".to_string());
+
+    // Filters only make sense when listing
+    let output = run_patui_failure(
+        &[
+            "--db",
+            db_path.to_str().unwrap(),
+            "get",
+            "tests",
+            "--id",
+            "1",
+            "--limit",
+            "1",
+        ],
+        None,
+    );
+    assert_that!(output.status.code()).is_equal_to(Some(2));
+}
+
+#[test]
+fn test_missing_test() {
+    let tmpdir = tempdir().unwrap();
+    let mut db_path = tmpdir.path().to_path_buf();
+    db_path.push("test.db");
+
+    for command in [
+        &["describe", "test"][..],
+        &["get", "test"],
+        &["edit", "test"],
+        &["clone", "test"],
+    ] {
+        let mut args = vec!["--db", db_path.to_str().unwrap()];
+        args.extend(command);
+        args.extend(["--id", "42"]);
+
+        let output = run_patui_failure(&args, None);
+
+        assert_that!(output.status.code()).is_equal_to(Some(2));
+        assert_that!(String::from_utf8_lossy(&output.stdout).to_string()).is_empty();
+        assert_that!(String::from_utf8_lossy(&output.stderr).to_string())
+            .is_equal_to("test 42 not found\n".to_string());
+    }
+}
+
 #[test]
 fn test_new_test_with_shell() {
     let tmpdir = tempdir().unwrap();
@@ -59,6 +59,23 @@ pub struct PatuiTestMinDisplay {
     pub description: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct PatuiPluginDisplay {
+    pub path: String,
+    pub name: String,
+    pub description: String,
+    pub version: String,
+    pub r#type: String,
+    pub subscriptions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PatuiStepKindDisplay {
+    pub kind: String,
+    pub fields: Vec<String>,
+    pub plugin: Option<PatuiPluginDisplay>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct PatuiRunStatus {
     pub id: i64,
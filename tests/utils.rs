@@ -25,3 +25,25 @@ pub(crate) fn run_patui(args: &[&str], stdin: Option<&str>) -> Output {
 
     output
 }
+
+/// Run patui expecting it to fail, returning the output to check the failure.
+pub(crate) fn run_patui_failure(args: &[&str], stdin: Option<&str>) -> Output {
+    let mut cmd = Command::cargo_bin("patui").unwrap();
+    if let Some(stdin) = stdin {
+        cmd.write_stdin(stdin);
+    }
+    let output = cmd
+        .args(args)
+        .env("PATUI_LOG", "trace")
+        .env("PATUI_LOG_FILE", "./target/test_logs/patui.log.${datetime}")
+        .output()
+        .unwrap();
+
+    assert!(
+        !output.status.success(),
+        "Unexpected success: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    output
+}